@@ -39,7 +39,9 @@ fn setup_vector_graphics(mut commands: Commands, asset_server: ResMut<AssetServe
                             ..default()
                         })
                         .theme(Theme::new().add("calendar", Color::BLUE))
-                        .transition(PlayerTransition::OnMouseEnter { state: "play" })
+                        .transition(PlayerTransition::OnMouseEnter {
+                            state: "play".into(),
+                        })
                         .reset_playhead_on_start()
                 })
                 .with_state(
@@ -50,7 +52,9 @@ fn setup_vector_graphics(mut commands: Commands, asset_server: ResMut<AssetServe
                             ..default()
                         })
                         .theme(Theme::new().add("calendar", Color::GREEN))
-                        .transition(PlayerTransition::OnMouseLeave { state: "rev" }),
+                        .transition(PlayerTransition::OnMouseLeave {
+                            state: "rev".into(),
+                        }),
                 )
                 .with_state(
                     PlayerState::new("rev")
@@ -61,8 +65,12 @@ fn setup_vector_graphics(mut commands: Commands, asset_server: ResMut<AssetServe
                             ..default()
                         })
                         .theme(Theme::new().add("calendar", Color::RED))
-                        .transition(PlayerTransition::OnMouseEnter { state: "play" })
-                        .transition(PlayerTransition::OnComplete { state: "stopped" }),
+                        .transition(PlayerTransition::OnMouseEnter {
+                            state: "play".into(),
+                        })
+                        .transition(PlayerTransition::OnComplete {
+                            state: "stopped".into(),
+                        }),
                 ),
         );
 }
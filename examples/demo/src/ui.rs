@@ -20,11 +20,18 @@ pub fn controls_ui(
     else {
         return;
     };
+    // The player has no state until its state machine is loaded
+    if player.try_state().is_none() {
+        return;
+    }
 
     let asset = assets.get(handle.id()).unwrap();
     let VectorFile::Lottie(composition) = &asset.file else {
         return;
     };
+    let Some(frames) = options.segments.frames(asset) else {
+        return;
+    };
 
     let window = egui::Window::new("Controls")
         .resizable(false)
@@ -39,8 +46,7 @@ pub fn controls_ui(
             if ui
                 .add(egui::Slider::new(
                     &mut frame,
-                    options.segments.start.max(composition.frames.start)
-                        ..=options.segments.end.min(composition.frames.end).prev(),
+                    frames.start..=frames.end.prev(),
                 ))
                 .changed()
             {
@@ -70,9 +76,9 @@ pub fn controls_ui(
         let mut transition = None;
         ui.horizontal_wrapped(|ui| {
             for state in player.states() {
-                let selected = player.state().id == state.id;
-                if ui.radio(selected, state.id).clicked() {
-                    transition.replace(state.id);
+                let selected = player.try_state().unwrap().id == state.id;
+                if ui.radio(selected, state.id.as_ref()).clicked() {
+                    transition.replace(state.id.clone());
                 }
             }
         });
@@ -81,7 +87,7 @@ pub fn controls_ui(
         }
 
         ui.heading("Current State");
-        ui.label(format!("Id: {}", player.state().id));
+        ui.label(format!("Id: {}", player.try_state().unwrap().id));
         ui.horizontal(|ui| {
             ui.label("Autoplay");
             let autoplaying = options.autoplay.to_string();
@@ -89,7 +95,13 @@ pub fn controls_ui(
                 .checkbox(&mut options.autoplay, autoplaying.to_string())
                 .changed()
             {
-                player.state_mut().options.as_mut().unwrap().autoplay = options.autoplay;
+                player
+                    .try_state_mut()
+                    .unwrap()
+                    .options
+                    .as_mut()
+                    .unwrap()
+                    .autoplay = options.autoplay;
             };
         });
         ui.vertical(|ui| {
@@ -100,7 +112,13 @@ pub fn controls_ui(
                     .radio_value(&mut options.direction, PlaybackDirection::Normal, "Normal")
                     .changed()
                 {
-                    player.state_mut().options.as_mut().unwrap().direction = options.direction;
+                    player
+                        .try_state_mut()
+                        .unwrap()
+                        .options
+                        .as_mut()
+                        .unwrap()
+                        .direction = options.direction;
                 }
             });
             ui.horizontal(|ui| {
@@ -113,7 +131,13 @@ pub fn controls_ui(
                     )
                     .changed()
                 {
-                    player.state_mut().options.as_mut().unwrap().direction = options.direction;
+                    player
+                        .try_state_mut()
+                        .unwrap()
+                        .options
+                        .as_mut()
+                        .unwrap()
+                        .direction = options.direction;
                 }
             });
         });
@@ -125,8 +149,13 @@ pub fn controls_ui(
                 .add(egui::Slider::new(&mut intermission, 0.0..=5.0))
                 .changed()
             {
-                player.state_mut().options.as_mut().unwrap().intermission =
-                    Duration::from_secs_f32(intermission);
+                player
+                    .try_state_mut()
+                    .unwrap()
+                    .options
+                    .as_mut()
+                    .unwrap()
+                    .intermission = Duration::from_secs_f32(intermission);
                 options.intermission = Duration::from_secs_f32(intermission);
             };
         });
@@ -136,8 +165,13 @@ pub fn controls_ui(
                 ui.separator();
                 let selected = matches!(options.play_mode, PlaybackPlayMode::Normal);
                 if ui.radio(selected, "Normal").clicked() {
-                    player.state_mut().options.as_mut().unwrap().play_mode =
-                        PlaybackPlayMode::Normal;
+                    player
+                        .try_state_mut()
+                        .unwrap()
+                        .options
+                        .as_mut()
+                        .unwrap()
+                        .play_mode = PlaybackPlayMode::Normal;
                     options.play_mode = PlaybackPlayMode::Normal;
                 }
             });
@@ -145,8 +179,13 @@ pub fn controls_ui(
                 ui.separator();
                 let selected = matches!(options.play_mode, PlaybackPlayMode::Bounce);
                 if ui.radio(selected, "Bounce").clicked() {
-                    player.state_mut().options.as_mut().unwrap().play_mode =
-                        PlaybackPlayMode::Bounce;
+                    player
+                        .try_state_mut()
+                        .unwrap()
+                        .options
+                        .as_mut()
+                        .unwrap()
+                        .play_mode = PlaybackPlayMode::Bounce;
                     options.play_mode = PlaybackPlayMode::Bounce;
                 }
             });
@@ -157,8 +196,13 @@ pub fn controls_ui(
                 ui.separator();
                 let selected = matches!(options.looping, PlaybackLoopBehavior::DoNotLoop);
                 if ui.radio(selected, "Do not loop").clicked() {
-                    player.state_mut().options.as_mut().unwrap().looping =
-                        PlaybackLoopBehavior::DoNotLoop;
+                    player
+                        .try_state_mut()
+                        .unwrap()
+                        .options
+                        .as_mut()
+                        .unwrap()
+                        .looping = PlaybackLoopBehavior::DoNotLoop;
                     options.looping = PlaybackLoopBehavior::DoNotLoop;
                 }
             });
@@ -175,8 +219,13 @@ pub fn controls_ui(
                     .changed()
                     || clicked
                 {
-                    player.state_mut().options.as_mut().unwrap().looping =
-                        PlaybackLoopBehavior::Amount(amt);
+                    player
+                        .try_state_mut()
+                        .unwrap()
+                        .options
+                        .as_mut()
+                        .unwrap()
+                        .looping = PlaybackLoopBehavior::Amount(amt);
                     options.looping = PlaybackLoopBehavior::Amount(amt);
                 };
             });
@@ -184,8 +233,13 @@ pub fn controls_ui(
                 ui.separator();
                 let selected = matches!(options.looping, PlaybackLoopBehavior::Loop);
                 if ui.radio(selected, "Loop").clicked() {
-                    player.state_mut().options.as_mut().unwrap().looping =
-                        PlaybackLoopBehavior::Loop;
+                    player
+                        .try_state_mut()
+                        .unwrap()
+                        .options
+                        .as_mut()
+                        .unwrap()
+                        .looping = PlaybackLoopBehavior::Loop;
                     options.looping = PlaybackLoopBehavior::Loop;
                 }
             });
@@ -196,39 +250,45 @@ pub fn controls_ui(
             ui.horizontal(|ui| {
                 ui.separator();
                 ui.label("Start");
-                let mut start = options.segments.start;
+                let mut start = frames.start;
                 if ui
                     .add(
-                        egui::Slider::new(
-                            &mut start,
-                            composition.frames.start
-                                ..=options.segments.end.min(composition.frames.end),
-                        )
-                        .integer(),
+                        egui::Slider::new(&mut start, composition.frames.start..=frames.end)
+                            .integer(),
                     )
                     .changed()
                 {
-                    player.state_mut().options.as_mut().unwrap().segments.start = start;
-                    options.segments.start = start;
+                    let segments = PlaybackSegments::Frames(start..frames.end);
+                    player
+                        .try_state_mut()
+                        .unwrap()
+                        .options
+                        .as_mut()
+                        .unwrap()
+                        .segments = segments.clone();
+                    options.segments = segments;
                 };
             });
             ui.horizontal(|ui| {
                 ui.separator();
                 ui.label("End");
-                let mut end = options.segments.end;
+                let mut end = frames.end;
                 if ui
                     .add(
-                        egui::Slider::new(
-                            &mut end,
-                            options.segments.start.max(composition.frames.start)
-                                ..=composition.frames.end,
-                        )
-                        .integer(),
+                        egui::Slider::new(&mut end, frames.start..=composition.frames.end)
+                            .integer(),
                     )
                     .changed()
                 {
-                    player.state_mut().options.as_mut().unwrap().segments.end = end;
-                    options.segments.end = end;
+                    let segments = PlaybackSegments::Frames(frames.start..end);
+                    player
+                        .try_state_mut()
+                        .unwrap()
+                        .options
+                        .as_mut()
+                        .unwrap()
+                        .segments = segments.clone();
+                    options.segments = segments;
                 };
             });
         });
@@ -236,7 +296,13 @@ pub fn controls_ui(
             ui.label("Speed");
             let mut speed = options.speed;
             if ui.add(egui::Slider::new(&mut speed, 0.05..=2.0)).changed() {
-                player.state_mut().options.as_mut().unwrap().speed = speed;
+                player
+                    .try_state_mut()
+                    .unwrap()
+                    .options
+                    .as_mut()
+                    .unwrap()
+                    .speed = speed;
                 options.speed = speed;
             };
         });
@@ -252,7 +318,8 @@ pub fn controls_ui(
                 {
                     let [r, g, b, a] = color_edit;
                     player
-                        .state_mut()
+                        .try_state_mut()
+                        .unwrap()
                         .theme
                         .as_mut()
                         .unwrap()
@@ -263,8 +330,11 @@ pub fn controls_ui(
             });
         }
 
-        ui.heading(format!("Transitions: {}", player.state().transitions.len()));
-        for transition in player.state().transitions.iter() {
+        ui.heading(format!(
+            "Transitions: {}",
+            player.try_state().unwrap().transitions.len()
+        ));
+        for transition in player.try_state().unwrap().transitions.iter() {
             ui.label(format!("{transition:?}"));
         }
     });
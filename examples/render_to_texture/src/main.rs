@@ -0,0 +1,114 @@
+use bevy::asset::AssetMetaCheck;
+use bevy::prelude::*;
+use bevy::render::camera::RenderTarget;
+use bevy::render::render_resource::{
+    Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy_vello::vello::{kurbo, peniko};
+use bevy_vello::{prelude::*, VelloPlugin};
+
+fn main() {
+    App::new()
+        .insert_resource(AssetMetaCheck::Never)
+        .add_plugins(DefaultPlugins)
+        .add_plugins(VelloPlugin)
+        .add_systems(Startup, setup)
+        .add_systems(Update, (simple_animation, rotate_cube))
+        .run()
+}
+
+#[derive(Component)]
+struct Cube;
+
+fn setup(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let size = Extent3d {
+        width: 512,
+        height: 512,
+        ..default()
+    };
+
+    // This is the texture that vello will render to.
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    let image_handle = images.add(image);
+
+    // A 2D camera rendering the vello scene into the image
+    commands.spawn(Camera2dBundle {
+        camera: Camera {
+            order: -1,
+            target: RenderTarget::Image(image_handle.clone()),
+            clear_color: ClearColorConfig::Custom(Color::WHITE),
+            ..default()
+        },
+        ..default()
+    });
+    commands.spawn(VelloSceneBundle::default());
+
+    // A 3D cube textured with the image
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Cuboid::default()),
+            material: materials.add(StandardMaterial {
+                base_color_texture: Some(image_handle),
+                reflectance: 0.02,
+                unlit: false,
+                ..default()
+            }),
+            ..default()
+        },
+        Cube,
+    ));
+    commands.spawn(PointLightBundle {
+        transform: Transform::from_translation(Vec3::new(0.0, 0.0, 10.0)),
+        ..default()
+    });
+    commands.spawn(Camera3dBundle {
+        transform: Transform::from_xyz(0.0, 0.0, 3.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+fn simple_animation(mut query_scene: Query<(&mut Transform, &mut VelloScene)>, time: Res<Time>) {
+    let sin_time = time.elapsed_seconds().sin().mul_add(0.5, 0.5);
+    let (mut transform, mut scene) = query_scene.single_mut();
+
+    // Reset scene every frame
+    *scene = VelloScene::default();
+
+    // Animate the corner radius
+    scene.fill(
+        peniko::Fill::NonZero,
+        kurbo::Affine::default(),
+        peniko::Color::rgb(0.8, 0.2, 0.4),
+        None,
+        &kurbo::RoundedRect::new(-150.0, -150.0, 150.0, 150.0, (sin_time as f64) * 150.0),
+    );
+
+    transform.rotation = Quat::from_rotation_z(-std::f32::consts::TAU * sin_time);
+}
+
+fn rotate_cube(mut query: Query<&mut Transform, With<Cube>>, time: Res<Time>) {
+    for mut transform in query.iter_mut() {
+        transform.rotate_x(0.5 * time.delta_seconds());
+        transform.rotate_y(0.7 * time.delta_seconds());
+    }
+}
@@ -8,7 +8,6 @@
 /// ```
 /// cargo run_wasm -p with_winit
 /// ```
-
 fn main() {
     cargo_run_wasm::run_wasm_with_css("body { margin: 0px; }");
 }
@@ -13,7 +13,8 @@ fn main() {
         .add_systems(
             Startup,
             (setup_camera, setup_screenspace_text, setup_worldspace_text),
-        );
+        )
+        .add_systems(Update, type_paragraph);
     embedded_asset!(app, "assets/Rubik-Medium.ttf");
     embedded_asset!(app, "assets/Rubik-Medium.ttf");
     app.run();
@@ -30,6 +31,7 @@ fn setup_worldspace_text(mut commands: Commands, asset_server: ResMut<AssetServe
             content: "This text is centered\non x and y axes".to_string(),
             size: 50.0,
             brush: None,
+            ..default()
         },
         alignment: VelloTextAlignment::Center,
         transform: Transform::from_xyz(100.0, 100.0, 0.0),
@@ -42,12 +44,61 @@ fn setup_worldspace_text(mut commands: Commands, asset_server: ResMut<AssetServe
         text: VelloText {
             content: "WXYZ".to_string(),
             size: 100.0,
-            brush: None,
+            brush: Some(
+                peniko::Gradient::new_linear((0.0, 0.0), (0.0, 1.0))
+                    .with_stops([peniko::Color::GOLD, peniko::Color::ORANGE_RED])
+                    .into(),
+            ),
+            ..default()
         },
         transform: Transform::from_xyz(-100.0, -100.0, 0.0),
         debug_visualizations: DebugVisualizations::Visible,
         ..default()
     });
+    commands.spawn((
+        VelloTextBundle {
+            font: asset_server.load("embedded://text/assets/Rubik-Medium.ttf"),
+            text: VelloText {
+                content: "This paragraph is wrapped to fit within its bounds, ".to_string(),
+                size: 30.0,
+                brush: None,
+                bounds: Some(Vec2::new(300.0, 200.0)),
+                align: VelloTextAlign::Justify,
+                sections: vec![
+                    VelloTextSection::new("and styled ")
+                        .with_brush(peniko::Color::ORANGE)
+                        .with_size(40.0),
+                    VelloTextSection::new("in sections"),
+                ],
+                ..default()
+            },
+            alignment: VelloTextAlignment::TopLeft,
+            transform: Transform::from_xyz(-400.0, 250.0, 0.0),
+            debug_visualizations: DebugVisualizations::Visible,
+            ..default()
+        },
+        VelloTextReveal::default(),
+    ));
+    commands.spawn(VelloTextBundle {
+        font: asset_server.load("embedded://text/assets/Rubik-Medium.ttf"),
+        text: VelloText {
+            content: "שלום, עולם! (Hello, world!)".to_string(),
+            size: 30.0,
+            direction: VelloTextDirection::RightToLeft,
+            ..default()
+        },
+        alignment: VelloTextAlignment::TopRight,
+        transform: Transform::from_xyz(400.0, 250.0, 0.0),
+        debug_visualizations: DebugVisualizations::Visible,
+        ..default()
+    });
+}
+
+/// Type the paragraph on, over and over
+fn type_paragraph(time: Res<Time>, mut reveals: Query<&mut VelloTextReveal>) {
+    for mut reveal in reveals.iter_mut() {
+        reveal.progress = time.elapsed_seconds() / 4.0 % 1.25;
+    }
 }
 
 fn setup_screenspace_text(mut commands: Commands, asset_server: ResMut<AssetServer>) {
@@ -58,6 +109,7 @@ fn setup_screenspace_text(mut commands: Commands, asset_server: ResMut<AssetServ
             content: "Text rendered by Vello!".to_string(),
             size: 15.0,
             brush: Some(peniko::Brush::Solid(peniko::Color::RED)),
+            ..default()
         },
         alignment: bevy_vello::text::VelloTextAlignment::TopLeft,
         transform: Transform::from_xyz(100.0, 85.0, 0.0),
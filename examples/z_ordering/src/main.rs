@@ -43,6 +43,7 @@ fn setup_worldspace_vectors(mut commands: Commands, asset_server: ResMut<AssetSe
                 content: label.into(),
                 brush: Some(Brush::Solid(Color::WHITE)),
                 size: 50.0 / SIZE,
+                ..default()
             },
             transform: Transform::from_scale(Vec3::splat(SIZE)).with_translation(Vec3::new(
                 -10.0 / SIZE,
@@ -60,6 +61,7 @@ fn setup_worldspace_vectors(mut commands: Commands, asset_server: ResMut<AssetSe
                 content: "Center".to_string(),
                 brush: Some(Brush::Solid(Color::WHITE)),
                 size: 50.0 / SIZE,
+                ..default()
             },
             transform: Transform::from_scale(Vec3::splat(SIZE)).with_translation(Vec3::new(
                 0.0,
@@ -76,6 +78,7 @@ fn setup_worldspace_vectors(mut commands: Commands, asset_server: ResMut<AssetSe
                 content: "Bottom".to_string(),
                 brush: Some(Brush::Solid(Color::WHITE)),
                 size: 50.0 / SIZE,
+                ..default()
             },
             transform: Transform::from_scale(Vec3::splat(SIZE)).with_translation(Vec3::new(
                 X_SPACING,
@@ -92,6 +95,7 @@ fn setup_worldspace_vectors(mut commands: Commands, asset_server: ResMut<AssetSe
                 content: "Top".to_string(),
                 brush: Some(Brush::Solid(Color::WHITE)),
                 size: 50.0 / SIZE,
+                ..default()
             },
             transform: Transform::from_scale(Vec3::splat(SIZE)).with_translation(Vec3::new(
                 X_SPACING * 2.0,
@@ -108,6 +112,7 @@ fn setup_worldspace_vectors(mut commands: Commands, asset_server: ResMut<AssetSe
                 content: "Right".to_string(),
                 brush: Some(Brush::Solid(Color::WHITE)),
                 size: 50.0 / SIZE,
+                ..default()
             },
             transform: Transform::from_scale(Vec3::splat(SIZE)).with_translation(Vec3::new(
                 X_SPACING * 3.0,
@@ -124,6 +129,7 @@ fn setup_worldspace_vectors(mut commands: Commands, asset_server: ResMut<AssetSe
                 content: "Left".to_string(),
                 brush: Some(Brush::Solid(Color::WHITE)),
                 size: 50.0 / SIZE,
+                ..default()
             },
             transform: Transform::from_scale(Vec3::splat(SIZE)).with_translation(Vec3::new(
                 X_SPACING * 4.0,
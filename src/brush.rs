@@ -0,0 +1,102 @@
+//! Brushes from Bevy colors, e.g. to fill a [`VelloScene`](crate::VelloScene)
+//! or a [`VelloText`](crate::VelloText).
+//!
+//! Bevy colors may be in linear RGB, HSL or LCH, while vello colors are sRGB,
+//! so colors are converted to sRGB rather than copied component by component.
+
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureFormat;
+use std::sync::Arc;
+use vello::peniko::{self, Blob, Brush, ColorStop, Extend, Gradient};
+
+/// The sRGB vello color of a Bevy color in any color space.
+pub fn to_peniko_color(color: Color) -> peniko::Color {
+    let [r, g, b, a] = color.as_rgba_u8();
+    peniko::Color::rgba8(r, g, b, a)
+}
+
+/// The Bevy color of an sRGB vello color.
+pub fn from_peniko_color(color: peniko::Color) -> Color {
+    Color::rgba_u8(color.r, color.g, color.b, color.a)
+}
+
+/// The vello image of a Bevy image, or `None` if its format can't be
+/// converted to sRGB RGBA.
+///
+/// Vello draws images from their data rather than GPU textures, so the image
+/// has to keep its data in the main world, with
+/// [`RenderAssetUsages::MAIN_WORLD`](bevy::render::render_asset::RenderAssetUsages::MAIN_WORLD).
+/// The data is copied, so convert images once rather than every frame.
+pub fn to_peniko_image(image: &Image) -> Option<peniko::Image> {
+    let rgba = image.convert(TextureFormat::Rgba8UnormSrgb)?;
+    let size = rgba.size();
+    Some(peniko::Image::new(
+        Blob::new(Arc::new(rgba.data)),
+        peniko::Format::Rgba8,
+        size.x,
+        size.y,
+    ))
+}
+
+/// A builder of gradient brushes with Bevy colors.
+///
+/// Points are in the coordinates of what the brush fills, e.g. with Y down in
+/// a scene, or from (0, 0) at the top left to (1, 1) at the bottom right of a
+/// text.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_vello::prelude::*;
+/// # use bevy_vello::vello::peniko::Brush;
+/// let brush: Brush = VelloGradient::linear(Vec2::ZERO, Vec2::X)
+///     .with_stops([(0.0, Color::RED), (1.0, Color::BLUE)])
+///     .into();
+/// ```
+#[derive(Clone, Debug)]
+pub struct VelloGradient(Gradient);
+
+impl VelloGradient {
+    /// A gradient along the line from a point to another.
+    pub fn linear(from: Vec2, to: Vec2) -> Self {
+        Self(Gradient::new_linear(point(from), point(to)))
+    }
+
+    /// A gradient from the center of a circle to its edge.
+    pub fn radial(center: Vec2, radius: f32) -> Self {
+        Self(Gradient::new_radial(point(center), radius))
+    }
+
+    /// Add color stops, by their offset along the gradient from 0.0 to 1.0.
+    pub fn with_stops(mut self, stops: impl IntoIterator<Item = (f32, Color)>) -> Self {
+        self.0
+            .stops
+            .extend(stops.into_iter().map(|(offset, color)| ColorStop {
+                offset,
+                color: to_peniko_color(color),
+            }));
+        self
+    }
+
+    /// How the gradient extends beyond its end points. Defaults to
+    /// [`Extend::Pad`], the colors of the end points.
+    pub fn with_extend(mut self, extend: Extend) -> Self {
+        self.0.extend = extend;
+        self
+    }
+}
+
+fn point(position: Vec2) -> vello::kurbo::Point {
+    vello::kurbo::Point::new(position.x as f64, position.y as f64)
+}
+
+impl From<VelloGradient> for Gradient {
+    fn from(gradient: VelloGradient) -> Self {
+        gradient.0
+    }
+}
+
+impl From<VelloGradient> for Brush {
+    fn from(gradient: VelloGradient) -> Self {
+        Brush::Gradient(gradient.0)
+    }
+}
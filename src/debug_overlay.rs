@@ -0,0 +1,153 @@
+//! An overlay of the bounds and anchors of every vello entity, drawn with
+//! vello.
+//!
+//! Unlike [`DebugVisualizations`](crate::debug::DebugVisualizations), which
+//! draws gizmos for opted-in entities, the overlay is drawn for every entity
+//! while [`VelloDebugOverlay::enabled`] is set, and shows the bounds which are
+//! computed for culling.
+
+use crate::brush::to_peniko_color;
+use crate::draw::DrawScene;
+use crate::{CoordinateSpace, VelloAsset, VelloScene, VelloSceneBundle, VelloText, VelloZIndex};
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use bevy::render::view::VisibilitySystems;
+use bevy::transform::TransformSystem;
+use vello::kurbo::{Affine, BezPath, Line, Point, Stroke};
+
+/// The half size of the cross drawn at the anchor of entities.
+const ANCHOR_SIZE: f64 = 6.0;
+
+/// Draws the overlay of the bounds and anchors of every vello entity.
+pub struct VelloDebugPlugin;
+
+impl Plugin for VelloDebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VelloDebugOverlay>()
+            .register_type::<VelloDebugOverlay>()
+            .add_systems(
+                PostUpdate,
+                draw_debug_overlay
+                    .after(TransformSystem::TransformPropagate)
+                    .after(VisibilitySystems::CalculateBounds),
+            );
+    }
+}
+
+/// Toggles and styles the overlay of [`VelloDebugPlugin`].
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct VelloDebugOverlay {
+    /// Whether the overlay is drawn.
+    pub enabled: bool,
+    /// The color of the bounds of entities.
+    pub bounds_color: Color,
+    /// The color of the anchors of entities.
+    pub anchor_color: Color,
+    /// The width of the lines of the overlay.
+    pub line_width: f64,
+}
+
+impl Default for VelloDebugOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            bounds_color: Color::FUCHSIA,
+            anchor_color: Color::RED,
+            line_width: 1.0,
+        }
+    }
+}
+
+/// The scene of the overlay, for entities in a coordinate space.
+#[derive(Component)]
+struct DebugOverlayScene(CoordinateSpace);
+
+/// A point of the overlay, with Y down.
+fn to_point(point: Vec3) -> Point {
+    Point::new(point.x as f64, -point.y as f64)
+}
+
+/// Redraw the overlay of the bounds and anchors of every vello entity.
+fn draw_debug_overlay(
+    mut commands: Commands,
+    overlay: Res<VelloDebugOverlay>,
+    mut overlay_scenes: Query<(&DebugOverlayScene, &mut VelloScene, &mut Visibility)>,
+    query: Query<
+        (
+            &GlobalTransform,
+            &CoordinateSpace,
+            &InheritedVisibility,
+            Option<&Aabb>,
+        ),
+        (
+            Or<(With<Handle<VelloAsset>>, With<VelloText>, With<VelloScene>)>,
+            Without<Node>,
+            Without<DebugOverlayScene>,
+            Without<DrawScene>,
+        ),
+    >,
+) {
+    if overlay_scenes.is_empty() {
+        for space in [CoordinateSpace::WorldSpace, CoordinateSpace::ScreenSpace] {
+            commands.spawn((
+                VelloSceneBundle {
+                    coordinate_space: space,
+                    // Above every other vector
+                    transform: Transform::from_xyz(0.0, 0.0, 999.0),
+                    z_index: VelloZIndex(i32::MAX),
+                    ..default()
+                },
+                DebugOverlayScene(space),
+            ));
+        }
+        return;
+    }
+
+    let stroke = Stroke::new(overlay.line_width);
+    let bounds_color = to_peniko_color(overlay.bounds_color);
+    let anchor_color = to_peniko_color(overlay.anchor_color);
+    for (DebugOverlayScene(overlay_space), mut scene, mut visibility) in overlay_scenes.iter_mut() {
+        *visibility = match overlay.enabled {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+        scene.reset();
+        if !overlay.enabled {
+            continue;
+        }
+        for (transform, space, inherited_visibility, aabb) in query.iter() {
+            if space != overlay_space || !inherited_visibility.get() {
+                continue;
+            }
+            if let Some(aabb) = aabb {
+                let (min, max) = (Vec3::from(aabb.min()), Vec3::from(aabb.max()));
+                let mut bounds = BezPath::new();
+                let corners = [
+                    Vec3::new(min.x, min.y, 0.0),
+                    Vec3::new(max.x, min.y, 0.0),
+                    Vec3::new(max.x, max.y, 0.0),
+                    Vec3::new(min.x, max.y, 0.0),
+                ];
+                for (i, corner) in corners.into_iter().enumerate() {
+                    let point = to_point(transform.transform_point(corner));
+                    match i {
+                        0 => bounds.move_to(point),
+                        _ => bounds.line_to(point),
+                    }
+                }
+                bounds.close_path();
+                scene.stroke(&stroke, Affine::IDENTITY, bounds_color, None, &bounds);
+            }
+            let anchor = to_point(transform.translation());
+            for (dx, dy) in [(1.0, 1.0), (1.0, -1.0)] {
+                let offset = (dx * ANCHOR_SIZE, dy * ANCHOR_SIZE);
+                let line = Line::new(
+                    (anchor.x - offset.0, anchor.y - offset.1),
+                    (anchor.x + offset.0, anchor.y + offset.1),
+                );
+                scene.stroke(&stroke, Affine::IDENTITY, anchor_color, None, &line);
+            }
+        }
+    }
+}
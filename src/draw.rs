@@ -0,0 +1,110 @@
+//! Immediate mode drawing with [`VelloDraw`], e.g. for debug overlays,
+//! selection rectangles and prototypes, without spawning entities.
+
+use crate::{CoordinateSpace, VelloScene, VelloSceneBundle, VelloZIndex};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// Draws the scenes of [`VelloDraw`] every frame.
+pub struct VelloDrawPlugin;
+
+impl Plugin for VelloDrawPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VelloDrawScenes>()
+            .add_systems(Startup, spawn_draw_scenes)
+            .add_systems(PostUpdate, present_draw_scenes);
+    }
+}
+
+/// The scenes drawn this frame, by coordinate space.
+#[derive(Resource, Default)]
+pub struct VelloDrawScenes {
+    world: VelloScene,
+    screen: VelloScene,
+}
+
+/// A system param to draw vectors for one frame, like Bevy's `Gizmos`.
+///
+/// It derefs to the scene in world space, so the shapes of the helpers of
+/// [`VelloScene`] are drawn with Y up in the world, and [`VelloDraw::screen`]
+/// is the scene in screen space, where the helpers draw in logical pixels
+/// from the top left of the window with Y down, like cursor positions.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_vello::prelude::*;
+/// fn draw(mut painter: VelloDraw) {
+///     painter.circle(Vec2::ZERO, 10.0, Color::RED);
+///     painter
+///         .screen()
+///         .rect(Vec2::new(100.0, 50.0), Vec2::new(80.0, 20.0), Color::GREEN);
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct VelloDraw<'w> {
+    scenes: ResMut<'w, VelloDrawScenes>,
+}
+
+impl VelloDraw<'_> {
+    /// The scene drawn in world space this frame.
+    pub fn world(&mut self) -> &mut VelloScene {
+        &mut self.scenes.world
+    }
+
+    /// The scene drawn in screen space this frame.
+    pub fn screen(&mut self) -> &mut VelloScene {
+        &mut self.scenes.screen
+    }
+}
+
+impl std::ops::Deref for VelloDraw<'_> {
+    type Target = VelloScene;
+
+    fn deref(&self) -> &Self::Target {
+        &self.scenes.world
+    }
+}
+
+impl std::ops::DerefMut for VelloDraw<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.scenes.world
+    }
+}
+
+/// The entity drawing the scene of [`VelloDraw`] in a coordinate space.
+#[derive(Component)]
+pub(crate) struct DrawScene(CoordinateSpace);
+
+fn spawn_draw_scenes(mut commands: Commands) {
+    for space in [CoordinateSpace::WorldSpace, CoordinateSpace::ScreenSpace] {
+        let mut transform = Transform::from_xyz(0.0, 0.0, 999.0);
+        // Flip the Y up helpers of the scene to Y down
+        if space == CoordinateSpace::ScreenSpace {
+            transform.scale.y = -1.0;
+        }
+        commands.spawn((
+            VelloSceneBundle {
+                coordinate_space: space,
+                transform,
+                // Above every other vector but the debug overlay
+                z_index: VelloZIndex(i32::MAX - 1),
+                ..default()
+            },
+            DrawScene(space),
+        ));
+    }
+}
+
+/// Move the scenes drawn this frame to their entities, which clears them for
+/// the next frame.
+fn present_draw_scenes(
+    mut scenes: ResMut<VelloDrawScenes>,
+    mut draw_scenes: Query<(&DrawScene, &mut VelloScene)>,
+) {
+    for (DrawScene(space), mut scene) in draw_scenes.iter_mut() {
+        *scene = match space {
+            CoordinateSpace::WorldSpace => std::mem::take(&mut scenes.world),
+            CoordinateSpace::ScreenSpace => std::mem::take(&mut scenes.screen),
+        };
+    }
+}
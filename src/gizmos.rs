@@ -0,0 +1,170 @@
+//! Gizmos drawn with vello, with anti-aliased and joined strokes of a width
+//! in pixels, instead of the lines of Bevy's gizmos.
+//!
+//! Bevy doesn't expose the lines submitted to `Gizmos`, so [`VelloGizmos`]
+//! mirrors the 2D methods of `Gizmos`, and draws them with [`VelloDraw`].
+
+use crate::brush::to_peniko_color;
+use crate::draw::VelloDraw;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+use std::f32::consts::FRAC_PI_2;
+use vello::kurbo::{self, Affine, BezPath, Cap, Join, Point, Shape, Stroke};
+
+/// Adds the [`VelloGizmoConfig`] of [`VelloGizmos`], which are drawn with the
+/// default config without it.
+pub struct VelloGizmosPlugin;
+
+impl Plugin for VelloGizmosPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VelloGizmoConfig>()
+            .register_type::<VelloGizmoConfig>();
+    }
+}
+
+/// The style of [`VelloGizmos`].
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Reflect)]
+#[reflect(Resource)]
+pub struct VelloGizmoConfig {
+    /// Whether gizmos are drawn.
+    pub enabled: bool,
+    /// The width of the lines of gizmos, in logical pixels.
+    pub line_width: f32,
+}
+
+impl Default for VelloGizmoConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            line_width: 2.0,
+        }
+    }
+}
+
+/// A system param to draw 2D gizmos in world space for one frame, with the
+/// methods of Bevy's `Gizmos`.
+///
+/// Lines are as wide in pixels as the [`VelloGizmoConfig`], or its default
+/// without the [`VelloGizmosPlugin`], at the zoom of the active 2D camera
+/// with the lowest order which renders the default render layer. Other
+/// cameras draw the same strokes, scaled by their own zoom.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_vello::prelude::*;
+/// fn draw(mut gizmos: VelloGizmos) {
+///     gizmos.circle_2d(Vec2::ZERO, 50.0, Color::GREEN);
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct VelloGizmos<'w, 's> {
+    draw: VelloDraw<'w>,
+    config: Option<Res<'w, VelloGizmoConfig>>,
+    cameras: Query<
+        'w,
+        's,
+        (
+            &'static Camera,
+            &'static OrthographicProjection,
+            Option<&'static RenderLayers>,
+        ),
+        With<Camera2d>,
+    >,
+}
+
+/// A point of the world, flipped so Y is down.
+fn point(position: Vec2) -> Point {
+    Point::new(position.x as f64, -position.y as f64)
+}
+
+impl VelloGizmos<'_, '_> {
+    /// Stroke a shape in the coordinates of the world, with Y down.
+    fn stroke(&mut self, transform: Affine, color: Color, shape: &impl Shape) {
+        let config = self.config.as_deref().copied().unwrap_or_default();
+        if !config.enabled {
+            return;
+        }
+        // The camera rendering the scene of `VelloDraw`, which has the
+        // default render layer
+        let scale = self
+            .cameras
+            .iter()
+            .filter(|(camera, _, layers)| {
+                camera.is_active
+                    && layers
+                        .copied()
+                        .unwrap_or_default()
+                        .intersects(&RenderLayers::default())
+            })
+            .min_by_key(|(camera, _, _)| camera.order)
+            .map_or(1.0, |(_, projection, _)| projection.scale);
+        let stroke = Stroke::new((config.line_width * scale) as f64)
+            .with_caps(Cap::Round)
+            .with_join(Join::Round);
+        self.draw
+            .stroke(&stroke, transform, to_peniko_color(color), None, shape);
+    }
+
+    /// Draw a line from `start` to `end`.
+    pub fn line_2d(&mut self, start: Vec2, end: Vec2, color: Color) {
+        let line = kurbo::Line::new(point(start), point(end));
+        self.stroke(Affine::IDENTITY, color, &line);
+    }
+
+    /// Draw lines between consecutive positions.
+    pub fn linestrip_2d(&mut self, positions: impl IntoIterator<Item = Vec2>, color: Color) {
+        let mut path = BezPath::new();
+        for (i, position) in positions.into_iter().enumerate() {
+            match i {
+                0 => path.move_to(point(position)),
+                _ => path.line_to(point(position)),
+            }
+        }
+        self.stroke(Affine::IDENTITY, color, &path);
+    }
+
+    /// Draw a line from `start` to `start + vector`.
+    pub fn ray_2d(&mut self, start: Vec2, vector: Vec2, color: Color) {
+        self.line_2d(start, start + vector, color);
+    }
+
+    /// Draw a circle.
+    pub fn circle_2d(&mut self, position: Vec2, radius: f32, color: Color) {
+        let circle = kurbo::Circle::new(point(position), radius as f64);
+        self.stroke(Affine::IDENTITY, color, &circle);
+    }
+
+    /// Draw an arc of a circle, whose midpoint is at the clockwise
+    /// `direction_angle` from `Vec2::Y`, and which is `arc_angle` long, in
+    /// radians.
+    pub fn arc_2d(
+        &mut self,
+        position: Vec2,
+        direction_angle: f32,
+        arc_angle: f32,
+        radius: f32,
+        color: Color,
+    ) {
+        // The start of the arc counterclockwise from the X axis, which is
+        // clockwise with Y down
+        let start_angle = FRAC_PI_2 - (direction_angle - arc_angle / 2.0);
+        let arc = kurbo::Arc {
+            center: point(position),
+            radii: kurbo::Vec2::new(radius as f64, radius as f64),
+            start_angle: -start_angle as f64,
+            sweep_angle: arc_angle as f64,
+            x_rotation: 0.0,
+        };
+        self.stroke(Affine::IDENTITY, color, &arc);
+    }
+
+    /// Draw a rectangle around `position`, rotated counterclockwise by
+    /// `rotation` in radians.
+    pub fn rect_2d(&mut self, position: Vec2, rotation: f32, size: Vec2, color: Color) {
+        let rect = kurbo::Rect::from_center_size(Point::ORIGIN, (size.x as f64, size.y as f64));
+        let transform =
+            Affine::translate(point(position).to_vec2()) * Affine::rotate(-rotation as f64);
+        self.stroke(transform, color, &rect);
+    }
+}
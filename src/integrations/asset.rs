@@ -1,6 +1,7 @@
 use crate::VectorFile;
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
+use bevy::render::render_asset::RenderAsset;
 
 #[derive(Asset, TypePath, Clone)]
 pub struct VelloAsset {
@@ -9,6 +10,48 @@ pub struct VelloAsset {
     pub width: f32,
     pub height: f32,
     pub alpha: f32,
+    /// The parsed tree of an SVG file. This is `None` for other files.
+    #[cfg(feature = "svg")]
+    pub tree: Option<std::sync::Arc<vello_svg::usvg::Tree>>,
+    /// The source of an SVG file, to override its elements per entity with
+    /// [`SvgElementOverrides`](crate::integrations::svg::SvgElementOverrides).
+    /// This is `None` for other files.
+    #[cfg(feature = "svg")]
+    pub svg_source: Option<std::sync::Arc<crate::integrations::svg::SvgSource>>,
+    /// The named segments (frames) of a lottie animation, defined by its
+    /// markers. This is empty for other files.
+    #[cfg(feature = "lottie")]
+    pub markers: bevy::utils::HashMap<String, std::ops::Range<f64>>,
+    /// The JSON of a lottie file with slots, to override them per entity with
+    /// [`LottieSlots`](crate::integrations::lottie::LottieSlots). This is
+    /// `None` for other files.
+    #[cfg(feature = "lottie")]
+    pub source: Option<std::sync::Arc<serde_json::Value>>,
+    /// The text layers of a lottie file by name, which velato doesn't render.
+    /// This is empty for other files.
+    #[cfg(feature = "lottie")]
+    pub text_layers: bevy::utils::HashMap<String, crate::integrations::lottie::LottieTextLayer>,
+    /// The image layers of a lottie file by name, which velato doesn't
+    /// render. This is empty for other files.
+    #[cfg(feature = "lottie")]
+    pub image_layers: bevy::utils::HashMap<String, crate::integrations::lottie::LottieImage>,
+}
+
+impl RenderAsset for VelloAsset {
+    type PreparedAsset = VelloAsset;
+
+    type Param = ();
+
+    fn asset_usage(&self) -> bevy::render::render_asset::RenderAssetUsages {
+        Default::default()
+    }
+
+    fn prepare_asset(
+        self,
+        _param: &mut bevy::ecs::system::SystemParamItem<Self::Param>,
+    ) -> Result<Self::PreparedAsset, bevy::render::render_asset::PrepareAssetError<Self>> {
+        Ok(self)
+    }
 }
 
 impl VelloAsset {
@@ -43,7 +86,8 @@ impl VelloAsset {
 }
 
 /// Describes how to position the asset from the origin
-#[derive(Component, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Component, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
 pub enum VelloAssetAlignment {
     /// Bounds start from the render position and advance up and to the right.
     BottomLeft,
@@ -74,9 +118,24 @@ impl VelloAssetAlignment {
         asset: &VelloAsset,
         transform: &GlobalTransform,
     ) -> GlobalTransform {
-        let (width, height) = (asset.width, asset.height);
-        // Apply alignment
-        let adjustment = match self {
+        self.compute_sized(Vec2::new(asset.width, asset.height), transform)
+    }
+
+    /// Align bounds of the given size, e.g. of an asset drawn at another size.
+    pub(crate) fn compute_sized(&self, size: Vec2, transform: &GlobalTransform) -> GlobalTransform {
+        let adjustment = self.offset(size);
+        let new_translation: Vec3 = (transform.compute_matrix() * adjustment.extend(1.0)).xyz();
+        GlobalTransform::from(
+            transform
+                .compute_transform()
+                .with_translation(new_translation),
+        )
+    }
+
+    /// The offset of the center of bounds of the given size from the origin.
+    pub(crate) fn offset(&self, size: Vec2) -> Vec3 {
+        let (width, height) = size.into();
+        match self {
             VelloAssetAlignment::TopLeft => Vec3::new(width / 2.0, -height / 2.0, 0.0),
             VelloAssetAlignment::Left => Vec3::new(width / 2.0, 0.0, 0.0),
             VelloAssetAlignment::BottomLeft => Vec3::new(width / 2.0, height / 2.0, 0.0),
@@ -86,12 +145,6 @@ impl VelloAssetAlignment {
             VelloAssetAlignment::TopRight => Vec3::new(-width / 2.0, -height / 2.0, 0.0),
             VelloAssetAlignment::Right => Vec3::new(-width / 2.0, 0.0, 0.0),
             VelloAssetAlignment::BottomRight => Vec3::new(-width / 2.0, height / 2.0, 0.0),
-        };
-        let new_translation: Vec3 = (transform.compute_matrix() * adjustment.extend(1.0)).xyz();
-        GlobalTransform::from(
-            transform
-                .compute_transform()
-                .with_translation(new_translation),
-        )
+        }
     }
 }
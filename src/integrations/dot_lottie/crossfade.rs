@@ -0,0 +1,46 @@
+use crate::{Theme, VelloAnimationTime, VelloAsset};
+use bevy::prelude::*;
+
+/// The animation a [`DotLottiePlayer`](super::DotLottiePlayer) is fading out
+/// of, drawn with the current animation until the crossfade completes.
+///
+/// Inserted by transitions with a crossfade, see
+/// [`PlayerTransition::with_crossfade`](super::PlayerTransition::with_crossfade).
+#[derive(Component, Clone, Debug)]
+pub struct PlayerCrossfade {
+    /// The asset being faded out.
+    pub asset: Handle<VelloAsset>,
+    /// The frame the faded out asset is held at.
+    pub frame: f64,
+    /// The theme of the faded out asset.
+    pub theme: Option<Theme>,
+    /// Seconds since the crossfade started.
+    pub elapsed: f32,
+    /// The duration of the crossfade, in seconds.
+    pub secs: f32,
+}
+
+impl PlayerCrossfade {
+    /// The progress of the crossfade, from `0.0` to `1.0`.
+    pub fn progress(&self) -> f32 {
+        if self.secs <= 0.0 {
+            return 1.0;
+        }
+        (self.elapsed / self.secs).clamp(0.0, 1.0)
+    }
+}
+
+/// Advance crossfades, and remove them once complete.
+pub fn advance_crossfades(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut PlayerCrossfade)>,
+    time: Res<Time>,
+    animation_time: Res<VelloAnimationTime>,
+) {
+    for (entity, mut crossfade) in query.iter_mut() {
+        crossfade.elapsed += animation_time.scale(time.delta()).as_secs_f32();
+        if crossfade.progress() >= 1.0 {
+            commands.entity(entity).remove::<PlayerCrossfade>();
+        }
+    }
+}
@@ -0,0 +1,15 @@
+//! Events sent by the dotLottie state machine.
+
+use bevy::prelude::*;
+use std::borrow::Cow;
+
+/// Sent when a [`DotLottiePlayer`](super::DotLottiePlayer) enters a state.
+#[derive(Event, Clone, Debug, PartialEq, Eq)]
+pub struct AnimationStateEntered {
+    /// The entity with the player.
+    pub entity: Entity,
+    /// The state which was left, or `None` if this is the initial state.
+    pub from: Option<Cow<'static, str>>,
+    /// The state which was entered.
+    pub to: Cow<'static, str>,
+}
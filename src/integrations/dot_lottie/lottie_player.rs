@@ -1,6 +1,10 @@
-use super::PlayerState;
+use super::{PlaybackOverrides, PlayerState, PlayerTransition};
 use bevy::prelude::*;
 use bevy::utils::hashbrown::HashMap;
+use bevy::utils::HashSet;
+use std::any::TypeId;
+use std::borrow::Cow;
+use std::time::Duration;
 
 /// A lottie player that closely mirrors the behavior and functionality for
 /// dotLottie Interactivity.
@@ -8,11 +12,16 @@ use bevy::utils::hashbrown::HashMap;
 /// Controls lottie playback and transitions with state machine support.
 ///
 /// See: <https://docs.lottiefiles.com/dotlottie-js-external/>
+///
+/// This component isn't reflected, as the guards of its transitions hold
+/// functions.
 #[derive(Component, Clone, Debug)]
 pub struct DotLottiePlayer {
-    pub(crate) current_state: Option<&'static str>,
-    pub(crate) next_state: Option<&'static str>,
-    pub(crate) states: HashMap<&'static str, PlayerState>,
+    pub(crate) current_state: Option<Cow<'static, str>>,
+    pub(crate) next_state: Option<Cow<'static, str>>,
+    /// The crossfade duration of the transition to the next state, in seconds.
+    pub(crate) next_crossfade: Option<f32>,
+    pub(crate) states: HashMap<Cow<'static, str>, PlayerState>,
     /// Whether the player has started.
     pub(crate) started: bool,
     /// Whether the player is playing. State machines will continue unless
@@ -20,29 +29,99 @@ pub struct DotLottiePlayer {
     pub(crate) playing: bool,
     /// Stopped. Doesn't run state machines.
     pub(crate) stopped: bool,
+    /// The triggers received this frame, for `OnEvent` transitions.
+    pub(crate) triggers: HashSet<TypeId>,
+    /// The transitions of the current state, by index, whose guards failed
+    /// this frame.
+    pub(crate) blocked_transitions: HashSet<usize>,
+    /// Playback options which take precedence over every state's options.
+    pub(crate) overrides: PlaybackOverrides,
 }
 
 impl DotLottiePlayer {
     /// Retrieve an immutable reference to the current state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the player has no state, or its state doesn't exist.
+    #[deprecated(note = "use `try_state`, which doesn't panic")]
     pub fn state(&self) -> &PlayerState {
+        let id = self
+            .current_state
+            .as_ref()
+            .or(self.next_state.as_ref())
+            .expect("expected state");
         self.states
-            .get(
-                self.current_state
-                    .or(self.next_state)
-                    .expect("expected state"),
-            )
-            .unwrap_or_else(|| panic!("state not found: '{}'", self.current_state.unwrap()))
+            .get(id)
+            .unwrap_or_else(|| panic!("state not found: '{id}'"))
     }
 
     /// Retrieve a mutable reference to the current state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the player has no state, or its state doesn't exist.
+    #[deprecated(note = "use `try_state_mut`, which doesn't panic")]
     pub fn state_mut(&mut self) -> &mut PlayerState {
+        let id = self
+            .current_state
+            .as_ref()
+            .or(self.next_state.as_ref())
+            .expect("expected state");
         self.states
-            .get_mut(
-                self.current_state
-                    .or(self.next_state)
-                    .expect("expected state"),
-            )
-            .unwrap_or_else(|| panic!("state not found: '{}'", self.current_state.unwrap()))
+            .get_mut(id)
+            .unwrap_or_else(|| panic!("state not found: '{id}'"))
+    }
+
+    /// The current state, or the state being transitioned to before the first
+    /// transition. Returns `None` if the state doesn't exist.
+    pub fn try_state(&self) -> Option<&PlayerState> {
+        self.current_state
+            .as_ref()
+            .or(self.next_state.as_ref())
+            .and_then(|id| self.states.get(id))
+    }
+
+    /// A mutable reference to the current state, or the state being
+    /// transitioned to before the first transition. Returns `None` if the
+    /// state doesn't exist.
+    pub fn try_state_mut(&mut self) -> Option<&mut PlayerState> {
+        self.current_state
+            .as_ref()
+            .or(self.next_state.as_ref())
+            .and_then(|id| self.states.get_mut(id))
+    }
+
+    /// The transitions of the current state, followed by those of its
+    /// ancestors.
+    pub(crate) fn active_transitions(&self) -> impl Iterator<Item = &PlayerTransition> {
+        let mut state = self.try_state();
+        // Bounded by the number of states, in case of cyclic parents
+        let ancestors = std::iter::from_fn(move || {
+            let current = state?;
+            state = current
+                .parent
+                .as_ref()
+                .and_then(|parent| self.states.get(parent));
+            Some(current)
+        })
+        .take(self.states.len());
+        ancestors.flat_map(|state| state.transitions.iter())
+    }
+
+    /// Follow the initial substates of a state to the state which is entered.
+    pub(crate) fn resolve_substate(&self, mut id: Cow<'static, str>) -> Cow<'static, str> {
+        for _ in 0..self.states.len() {
+            match self
+                .states
+                .get(&id)
+                .and_then(|state| state.initial_substate.as_ref())
+            {
+                Some(substate) => id = substate.clone(),
+                None => break,
+            }
+        }
+        id
     }
 
     /// Returns an immutable iterator of the states for this player.
@@ -56,8 +135,43 @@ impl DotLottiePlayer {
     }
 
     /// Transition to the named state.
-    pub fn transition(&mut self, state: &'static str) {
-        self.next_state.replace(state);
+    pub fn transition(&mut self, state: impl Into<Cow<'static, str>>) {
+        self.next_state.replace(state.into());
+        self.next_crossfade.take();
+    }
+
+    /// Transition to the named state, crossfading from the current animation
+    /// over a period of seconds.
+    pub fn transition_with_crossfade(&mut self, state: impl Into<Cow<'static, str>>, secs: f32) {
+        self.next_state.replace(state.into());
+        self.next_crossfade.replace(secs);
+    }
+
+    /// Override the speed of every state, until cleared.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.overrides.speed.replace(speed);
+    }
+
+    /// Override the intermission of every state, until cleared.
+    pub fn set_intermission(&mut self, intermission: Duration) {
+        self.overrides.intermission.replace(intermission);
+    }
+
+    /// Returns the playback options which take precedence over every state's
+    /// options.
+    pub fn overrides(&self) -> &PlaybackOverrides {
+        &self.overrides
+    }
+
+    /// Returns a mutable reference to the playback options which take
+    /// precedence over every state's options.
+    pub fn overrides_mut(&mut self) -> &mut PlaybackOverrides {
+        &mut self.overrides
+    }
+
+    /// Clear every override, restoring the options of each state.
+    pub fn clear_overrides(&mut self) {
+        self.overrides = PlaybackOverrides::default();
     }
 
     /// Toggle the play state.
@@ -95,19 +209,23 @@ impl DotLottiePlayer {
 }
 
 impl DotLottiePlayer {
-    pub fn new(initial_state: &'static str) -> DotLottiePlayer {
+    pub fn new(initial_state: impl Into<Cow<'static, str>>) -> DotLottiePlayer {
         DotLottiePlayer {
             current_state: None,
-            next_state: Some(initial_state),
+            next_state: Some(initial_state.into()),
+            next_crossfade: None,
             states: HashMap::new(),
             started: false,
             playing: false,
             stopped: false,
+            triggers: HashSet::new(),
+            blocked_transitions: HashSet::new(),
+            overrides: PlaybackOverrides::default(),
         }
     }
 
     pub fn with_state(mut self, state: PlayerState) -> Self {
-        self.states.insert(state.id, state);
+        self.states.insert(state.id.clone(), state);
         self
     }
 }
@@ -1,12 +1,33 @@
+mod crossfade;
+pub use crossfade::PlayerCrossfade;
+
+mod events;
+pub use events::AnimationStateEntered;
+
 mod lottie_player;
 pub use lottie_player::DotLottiePlayer;
 
+mod player_guard;
+pub use player_guard::TransitionGuard;
+
+mod player_overrides;
+pub use player_overrides::PlaybackOverrides;
+
 mod player_state;
 pub use player_state::PlayerState;
 
 mod player_transition;
 pub use player_transition::PlayerTransition;
 
+mod player_trigger;
+pub use player_trigger::DotLottieAppExt;
+
+mod player_warning;
+pub use player_warning::PlayerWarning;
+
+mod state_machine;
+pub use state_machine::PlayerStateMachine;
+
 mod plugin;
 pub use plugin::DotLottieIntegrationPlugin;
 
@@ -0,0 +1,25 @@
+use bevy::ecs::world::EntityRef;
+use std::fmt;
+use std::sync::Arc;
+
+/// A condition on the player's entity which must hold for a transition to
+/// fire. See [`PlayerTransition::with_guard`](super::PlayerTransition::with_guard).
+#[derive(Clone)]
+pub struct TransitionGuard(Arc<dyn Fn(EntityRef) -> bool + Send + Sync>);
+
+impl TransitionGuard {
+    pub fn new(guard: impl Fn(EntityRef) -> bool + Send + Sync + 'static) -> Self {
+        Self(Arc::new(guard))
+    }
+
+    /// Whether the guard allows the transition for this entity.
+    pub fn check(&self, entity: EntityRef) -> bool {
+        (self.0)(entity)
+    }
+}
+
+impl fmt::Debug for TransitionGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TransitionGuard(..)")
+    }
+}
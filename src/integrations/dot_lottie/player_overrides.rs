@@ -0,0 +1,36 @@
+use crate::PlaybackOptions;
+use std::borrow::Cow;
+use std::time::Duration;
+
+/// Playback options set on a [`DotLottiePlayer`](super::DotLottiePlayer)
+/// which take precedence over the options of every state. Clearing an
+/// override restores the values of the current state.
+#[derive(PartialEq, Default, Clone, Copy, Debug)]
+pub struct PlaybackOverrides {
+    /// Overrides [`PlaybackOptions::speed`].
+    pub speed: Option<f64>,
+    /// Overrides [`PlaybackOptions::intermission`].
+    pub intermission: Option<Duration>,
+}
+
+impl PlaybackOverrides {
+    /// Whether no options are overridden.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the options with the overrides applied.
+    pub fn apply<'a>(&self, options: &'a PlaybackOptions) -> Cow<'a, PlaybackOptions> {
+        if self.is_empty() {
+            return Cow::Borrowed(options);
+        }
+        let mut options = options.clone();
+        if let Some(speed) = self.speed {
+            options.speed = speed;
+        }
+        if let Some(intermission) = self.intermission {
+            options.intermission = intermission;
+        }
+        Cow::Owned(options)
+    }
+}
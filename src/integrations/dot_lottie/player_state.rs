@@ -1,10 +1,11 @@
 use super::PlayerTransition;
 use crate::{PlaybackOptions, Theme, VelloAsset};
 use bevy::prelude::*;
+use std::borrow::Cow;
 
 #[derive(Debug, Clone)]
 pub struct PlayerState {
-    pub id: &'static str,
+    pub id: Cow<'static, str>,
     pub asset: Option<Handle<VelloAsset>>,
     pub theme: Option<Theme>,
     pub options: Option<PlaybackOptions>,
@@ -13,18 +14,25 @@ pub struct PlayerState {
     pub reset_playhead_on_exit: bool,
     /// Whether to reset the playhead when a transition enters this state
     pub reset_playhead_on_start: bool,
+    /// The state this is a substate of. The transitions of the parent, and
+    /// its ancestors, also apply while in this state.
+    pub parent: Option<Cow<'static, str>>,
+    /// The substate entered when a transition enters this state.
+    pub initial_substate: Option<Cow<'static, str>>,
 }
 
 impl PlayerState {
-    pub fn new(id: &'static str) -> Self {
+    pub fn new(id: impl Into<Cow<'static, str>>) -> Self {
         Self {
-            id,
+            id: id.into(),
             asset: Default::default(),
             options: Default::default(),
             theme: Default::default(),
             transitions: vec![],
             reset_playhead_on_exit: false,
             reset_playhead_on_start: false,
+            parent: None,
+            initial_substate: None,
         }
     }
 
@@ -58,6 +66,16 @@ impl PlayerState {
         self
     }
 
+    pub fn parent(mut self, parent: impl Into<Cow<'static, str>>) -> Self {
+        self.parent.replace(parent.into());
+        self
+    }
+
+    pub fn initial_substate(mut self, substate: impl Into<Cow<'static, str>>) -> Self {
+        self.initial_substate.replace(substate.into());
+        self
+    }
+
     pub fn set_asset(mut self, asset: Option<Handle<VelloAsset>>) -> Self {
         self.asset = asset;
         self
@@ -88,6 +106,16 @@ impl PlayerState {
         self
     }
 
+    pub fn set_parent(mut self, parent: Option<Cow<'static, str>>) -> Self {
+        self.parent = parent;
+        self
+    }
+
+    pub fn set_initial_substate(mut self, substate: Option<Cow<'static, str>>) -> Self {
+        self.initial_substate = substate;
+        self
+    }
+
     pub fn get_asset(&self) -> Option<&Handle<VelloAsset>> {
         self.asset.as_ref()
     }
@@ -111,4 +139,12 @@ impl PlayerState {
     pub fn get_reset_playhead_on_start(&self) -> bool {
         self.reset_playhead_on_start
     }
+
+    pub fn get_parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+
+    pub fn get_initial_substate(&self) -> Option<&str> {
+        self.initial_substate.as_deref()
+    }
 }
@@ -1,16 +1,132 @@
+use super::TransitionGuard;
+use bevy::ecs::world::EntityRef;
+use bevy::input::gamepad::GamepadButtonType;
+use bevy::input::keyboard::KeyCode;
+use std::any::TypeId;
+use std::borrow::Cow;
+
 #[derive(Debug, Clone)]
 #[allow(clippy::enum_variant_names)]
 pub enum PlayerTransition {
-    /// Transitions to the given state after a period of seconds.
-    OnAfter { state: &'static str, secs: f32 },
+    /// Transitions to the given state after a period of seconds since the
+    /// state was first rendered, by the [`PlaybackClock`](crate::PlaybackClock).
+    OnAfter { state: Cow<'static, str>, secs: f32 },
     /// Transition to the given state after the animation finishes.
-    OnComplete { state: &'static str },
+    OnComplete { state: Cow<'static, str> },
+    /// Transition to the given state after a number of loops complete, which
+    /// also fires for animations that loop forever.
+    OnLoopCount {
+        state: Cow<'static, str>,
+        loops: usize,
+    },
     /// Transition to the given state when the mouse enters the image bounding box.
-    OnMouseEnter { state: &'static str },
-    /// Transition to the given state when the mouse clicks inside the image bounding box.
-    OnMouseClick { state: &'static str },
+    ///
+    /// Touches also enter the bounding box while pressed.
+    OnMouseEnter { state: Cow<'static, str> },
+    /// Transition to the given state when the mouse clicks, or a touch is
+    /// pressed, inside the image bounding box.
+    OnMouseClick { state: Cow<'static, str> },
     /// Transition to the given state when the mouse exits the image bounding box.
-    OnMouseLeave { state: &'static str },
+    OnMouseLeave { state: Cow<'static, str> },
+    /// Transition to the given state when the key is pressed.
+    OnKeyPress {
+        state: Cow<'static, str>,
+        key: KeyCode,
+    },
+    /// Transition to the given state when the button is pressed on any gamepad.
+    OnGamepadButtonPress {
+        state: Cow<'static, str>,
+        button: GamepadButtonType,
+    },
     /// Transition to the given state on first render of this state.
-    OnShow { state: &'static str },
+    OnShow { state: Cow<'static, str> },
+    /// Transition to the given state when a trigger of the given type is
+    /// received. Triggers are registered with
+    /// [`DotLottieAppExt`](super::DotLottieAppExt). See
+    /// [`PlayerTransition::on_event`].
+    OnEvent {
+        state: Cow<'static, str>,
+        trigger: TypeId,
+    },
+    /// Only allow the transition when the guard holds. See
+    /// [`PlayerTransition::with_guard`].
+    Guarded {
+        transition: Box<PlayerTransition>,
+        guard: TransitionGuard,
+    },
+    /// Crossfade from the current animation when the transition fires. See
+    /// [`PlayerTransition::with_crossfade`].
+    Crossfade {
+        transition: Box<PlayerTransition>,
+        secs: f32,
+    },
+}
+
+impl PlayerTransition {
+    /// Transition to the given state when a trigger of type `T` is received.
+    pub fn on_event<T: 'static>(state: impl Into<Cow<'static, str>>) -> Self {
+        Self::OnEvent {
+            state: state.into(),
+            trigger: TypeId::of::<T>(),
+        }
+    }
+
+    /// Only allow this transition when `guard` returns true for the player's
+    /// entity, e.g. `OnComplete` only when a `Health` component is low:
+    ///
+    /// ```no_run
+    /// # use bevy::prelude::*;
+    /// # use bevy_vello::prelude::*;
+    /// # #[derive(Component)]
+    /// # struct Health(f32);
+    /// # let transition =
+    /// PlayerTransition::OnComplete { state: "dying".into() }
+    ///     .with_guard(|entity| entity.get::<Health>().is_some_and(|h| h.0 < 10.0))
+    /// # ;
+    /// ```
+    pub fn with_guard(self, guard: impl Fn(EntityRef) -> bool + Send + Sync + 'static) -> Self {
+        Self::Guarded {
+            transition: Box::new(self),
+            guard: TransitionGuard::new(guard),
+        }
+    }
+
+    /// Fade out the current animation over a period of seconds while the
+    /// next state's animation fades in, rather than cutting to it.
+    pub fn with_crossfade(self, secs: f32) -> Self {
+        Self::Crossfade {
+            transition: Box::new(self),
+            secs,
+        }
+    }
+
+    /// The transition without any guards or crossfades.
+    pub fn inner(&self) -> &PlayerTransition {
+        match self {
+            Self::Guarded { transition, .. } | Self::Crossfade { transition, .. } => {
+                transition.inner()
+            }
+            transition => transition,
+        }
+    }
+
+    /// Whether every guard of this transition holds for the entity.
+    pub fn check_guards(&self, entity: EntityRef) -> bool {
+        match self {
+            Self::Guarded { transition, guard } => {
+                guard.check(entity) && transition.check_guards(entity)
+            }
+            Self::Crossfade { transition, .. } => transition.check_guards(entity),
+            _ => true,
+        }
+    }
+
+    /// The crossfade duration of this transition, in seconds.
+    pub fn crossfade(&self) -> Option<f32> {
+        match self {
+            Self::Crossfade { secs, .. } => Some(*secs),
+            Self::Guarded { transition, .. } => transition.crossfade(),
+            _ => None,
+        }
+    }
 }
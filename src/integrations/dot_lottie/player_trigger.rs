@@ -0,0 +1,53 @@
+use super::DotLottiePlayer;
+use bevy::prelude::*;
+use std::any::TypeId;
+
+/// Extends the [`App`] with the triggers of [`PlayerTransition::OnEvent`](super::PlayerTransition::OnEvent).
+pub trait DotLottieAppExt {
+    /// Fire `OnEvent` transitions of type `E` for every player when an event
+    /// of type `E` is sent.
+    fn add_player_event<E: Event>(&mut self) -> &mut Self;
+
+    /// Fire `OnEvent` transitions of type `C` for a player when a component of
+    /// type `C` is added to its entity. The component is removed afterwards, so
+    /// it can be inserted again to fire again.
+    fn add_player_trigger<C: Component>(&mut self) -> &mut Self;
+}
+
+impl DotLottieAppExt for App {
+    fn add_player_event<E: Event>(&mut self) -> &mut Self {
+        self.add_systems(
+            Last,
+            receive_player_events::<E>.before(super::systems::run_transitions),
+        )
+    }
+
+    fn add_player_trigger<C: Component>(&mut self) -> &mut Self {
+        self.add_systems(
+            Last,
+            receive_player_triggers::<C>.before(super::systems::run_transitions),
+        )
+    }
+}
+
+fn receive_player_events<E: Event>(
+    mut events: EventReader<E>,
+    mut query_player: Query<&mut DotLottiePlayer>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+    for mut player in query_player.iter_mut() {
+        player.triggers.insert(TypeId::of::<E>());
+    }
+}
+
+fn receive_player_triggers<C: Component>(
+    mut commands: Commands,
+    mut query_player: Query<(Entity, &mut DotLottiePlayer), Added<C>>,
+) {
+    for (entity, mut player) in query_player.iter_mut() {
+        player.triggers.insert(TypeId::of::<C>());
+        commands.entity(entity).remove::<C>();
+    }
+}
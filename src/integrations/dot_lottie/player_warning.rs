@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+use std::borrow::Cow;
+
+/// A recoverable problem encountered by a [`DotLottiePlayer`](super::DotLottiePlayer).
+///
+/// These are sent instead of panicking, and the player continues in its
+/// current state.
+#[derive(Event, Clone, Debug, PartialEq)]
+pub enum PlayerWarning {
+    /// A transition was requested to a state which doesn't exist.
+    StateNotFound {
+        /// The entity with the player.
+        entity: Entity,
+        /// The requested state.
+        state: Cow<'static, str>,
+    },
+}
@@ -1,4 +1,8 @@
-use super::systems;
+use super::crossfade;
+use super::state_machine::{self, PlayerStateMachineLoader};
+use super::{systems, AnimationStateEntered, PlayerStateMachine, PlayerWarning};
+use crate::PlaybackClock;
+use bevy::ecs::schedule::common_conditions::resource_equals;
 use bevy::prelude::*;
 
 pub struct DotLottieIntegrationPlugin;
@@ -6,12 +10,39 @@ pub struct DotLottieIntegrationPlugin;
 impl Plugin for DotLottieIntegrationPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         // TODO: Add .lottie loader
-        app.add_systems(PostUpdate, systems::advance_dot_lottie_playheads)
+        app.init_asset::<PlayerStateMachine>()
+            .init_asset_loader::<PlayerStateMachineLoader>()
+            .add_event::<PlayerWarning>()
+            .add_event::<AnimationStateEntered>()
+            .add_systems(
+                PostUpdate,
+                (
+                    systems::advance_dot_lottie_playheads,
+                    crossfade::advance_crossfades,
+                )
+                    .after(crate::integrations::lottie::mark_offscreen_playheads)
+                    .run_if(resource_equals(PlaybackClock::Virtual)),
+            )
+            .add_systems(
+                FixedPostUpdate,
+                (
+                    systems::advance_dot_lottie_playheads,
+                    crossfade::advance_crossfades,
+                )
+                    .run_if(resource_equals(PlaybackClock::Fixed)),
+            )
             .add_systems(
                 Last,
-                (systems::run_transitions, systems::transition_state)
+                (
+                    state_machine::apply_state_machines,
+                    systems::check_transition_guards,
+                    systems::run_transitions,
+                    systems::sync_group_states,
+                    systems::transition_state,
+                )
                     .chain()
-                    .after(crate::integrations::lottie::spawn_playheads),
+                    .after(crate::integrations::lottie::spawn_playheads)
+                    .before(crate::integrations::lottie::sync_animation_groups),
             );
     }
 }
@@ -0,0 +1,276 @@
+//! State machines for [`DotLottiePlayer`], loadable from `.statemachine.ron`
+//! files.
+
+use super::{DotLottiePlayer, PlayerState, PlayerTransition};
+use crate::integrations::VectorLoaderError;
+use crate::{PlaybackOptions, Theme};
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::input::gamepad::GamepadButtonType;
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::utils::{BoxedFuture, HashMap, HashSet};
+use serde::Deserialize;
+use std::borrow::Cow;
+
+/// The states and transitions of a [`DotLottiePlayer`].
+///
+/// Add a `Handle<PlayerStateMachine>` to a `VelloAssetBundle` entity to drive
+/// it with this state machine. The entity's player is created when the state
+/// machine loads, and its states are replaced whenever the asset is modified.
+///
+/// Asset paths are relative to the state machine file:
+///
+/// ```ron
+/// (
+///     initial_state: "stopped",
+///     states: [
+///         (
+///             id: "stopped",
+///             asset: Some("calendar.json"),
+///             options: Some((autoplay: false)),
+///             transitions: [OnMouseEnter(state: "play")],
+///             reset_playhead_on_start: true,
+///         ),
+///         (
+///             id: "play",
+///             theme: Some({"calendar": Rgba(red: 0.0, green: 1.0, blue: 0.0, alpha: 1.0)}),
+///             options: Some((looping: DoNotLoop, speed: 0.25)),
+///             transitions: [OnMouseLeave(state: "stopped"), OnKeyPress(state: "stopped", key: Escape)],
+///         ),
+///     ],
+/// )
+/// ```
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct PlayerStateMachine {
+    /// The state entered when the player is created.
+    pub initial_state: Cow<'static, str>,
+    pub states: Vec<PlayerState>,
+}
+
+impl PlayerStateMachine {
+    /// Create a player with these states.
+    pub fn player(&self) -> DotLottiePlayer {
+        self.states.iter().cloned().fold(
+            DotLottiePlayer::new(self.initial_state.clone()),
+            |player, state| player.with_state(state),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct StateMachineDefinition {
+    initial_state: String,
+    states: Vec<StateDefinition>,
+}
+
+#[derive(Deserialize)]
+struct StateDefinition {
+    id: String,
+    /// A path to the asset, relative to the state machine file.
+    #[serde(default)]
+    asset: Option<String>,
+    /// Colors to swap, by layer name.
+    #[serde(default)]
+    theme: Option<HashMap<String, Color>>,
+    #[serde(default)]
+    options: Option<PlaybackOptions>,
+    #[serde(default)]
+    transitions: Vec<TransitionDefinition>,
+    #[serde(default)]
+    reset_playhead_on_exit: bool,
+    #[serde(default)]
+    reset_playhead_on_start: bool,
+    /// The state this is a substate of.
+    #[serde(default)]
+    parent: Option<String>,
+    /// The substate entered when a transition enters this state.
+    #[serde(default)]
+    initial_substate: Option<String>,
+}
+
+/// The transitions of [`PlayerTransition`] which can be defined in a file.
+#[derive(Deserialize)]
+#[allow(clippy::enum_variant_names)]
+enum TransitionDefinition {
+    OnAfter {
+        state: String,
+        secs: f32,
+    },
+    OnComplete {
+        state: String,
+    },
+    OnLoopCount {
+        state: String,
+        loops: usize,
+    },
+    OnMouseEnter {
+        state: String,
+    },
+    OnMouseClick {
+        state: String,
+    },
+    OnMouseLeave {
+        state: String,
+    },
+    OnKeyPress {
+        state: String,
+        key: KeyCode,
+    },
+    OnGamepadButtonPress {
+        state: String,
+        button: GamepadButtonType,
+    },
+    OnShow {
+        state: String,
+    },
+}
+
+impl From<TransitionDefinition> for PlayerTransition {
+    fn from(transition: TransitionDefinition) -> Self {
+        match transition {
+            TransitionDefinition::OnAfter { state, secs } => PlayerTransition::OnAfter {
+                state: state.into(),
+                secs,
+            },
+            TransitionDefinition::OnComplete { state } => PlayerTransition::OnComplete {
+                state: state.into(),
+            },
+            TransitionDefinition::OnLoopCount { state, loops } => PlayerTransition::OnLoopCount {
+                state: state.into(),
+                loops,
+            },
+            TransitionDefinition::OnMouseEnter { state } => PlayerTransition::OnMouseEnter {
+                state: state.into(),
+            },
+            TransitionDefinition::OnMouseClick { state } => PlayerTransition::OnMouseClick {
+                state: state.into(),
+            },
+            TransitionDefinition::OnMouseLeave { state } => PlayerTransition::OnMouseLeave {
+                state: state.into(),
+            },
+            TransitionDefinition::OnKeyPress { state, key } => PlayerTransition::OnKeyPress {
+                state: state.into(),
+                key,
+            },
+            TransitionDefinition::OnGamepadButtonPress { state, button } => {
+                PlayerTransition::OnGamepadButtonPress {
+                    state: state.into(),
+                    button,
+                }
+            }
+            TransitionDefinition::OnShow { state } => PlayerTransition::OnShow {
+                state: state.into(),
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PlayerStateMachineLoader;
+
+impl AssetLoader for PlayerStateMachineLoader {
+    type Asset = PlayerStateMachine;
+
+    type Settings = ();
+
+    type Error = VectorLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            debug!("parsing {}...", load_context.path().display());
+            let definition: StateMachineDefinition = ron::de::from_bytes(&bytes)?;
+            let mut states = Vec::with_capacity(definition.states.len());
+            for state in definition.states {
+                let asset = match state.asset {
+                    Some(path) => {
+                        let path = load_context.asset_path().resolve_embed(&path)?;
+                        Some(load_context.load(path))
+                    }
+                    None => None,
+                };
+                states.push(PlayerState {
+                    id: state.id.into(),
+                    asset,
+                    theme: state.theme.map(|colors| Theme {
+                        colors,
+                        ..default()
+                    }),
+                    options: state.options,
+                    transitions: state.transitions.into_iter().map(Into::into).collect(),
+                    reset_playhead_on_exit: state.reset_playhead_on_exit,
+                    reset_playhead_on_start: state.reset_playhead_on_start,
+                    parent: state.parent.map(Into::into),
+                    initial_substate: state.initial_substate.map(Into::into),
+                });
+            }
+            info!(
+                path = format!("{}", load_context.path().display()),
+                states = states.len(),
+                "finished parsing state machine asset"
+            );
+            Ok(PlayerStateMachine {
+                initial_state: definition.initial_state.into(),
+                states,
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["statemachine.ron"]
+    }
+}
+
+/// Create and update the players of entities with a state machine.
+pub fn apply_state_machines(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<PlayerStateMachine>>,
+    machines: Res<Assets<PlayerStateMachine>>,
+    mut query: Query<(
+        Entity,
+        Ref<Handle<PlayerStateMachine>>,
+        Option<&mut DotLottiePlayer>,
+    )>,
+) {
+    let modified: HashSet<AssetId<PlayerStateMachine>> = asset_events
+        .read()
+        .filter_map(|ev| match ev {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    for (entity, handle, player) in query.iter_mut() {
+        if !handle.is_changed() && !modified.contains(&handle.id()) {
+            continue;
+        }
+        let Some(machine) = machines.get(handle.id()) else {
+            continue;
+        };
+        match player {
+            Some(mut player) => {
+                player.states = machine
+                    .states
+                    .iter()
+                    .map(|state| (state.id.clone(), state.clone()))
+                    .collect();
+                // Restart if the current state no longer exists
+                if player.try_state().is_none() {
+                    player.current_state.take();
+                    player.next_state.replace(machine.initial_state.clone());
+                }
+            }
+            None => {
+                commands.entity(entity).insert(machine.player());
+            }
+        }
+    }
+}
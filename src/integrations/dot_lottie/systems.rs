@@ -1,38 +1,45 @@
-use super::DotLottiePlayer;
-use crate::integrations::lottie::PlaybackPlayMode;
+use super::{AnimationStateEntered, DotLottiePlayer, PlayerCrossfade, PlayerWarning};
+use crate::integrations::lottie::{advance_playhead, PlaybackEventWriters};
 use crate::{
-    PlaybackDirection, PlaybackLoopBehavior, PlaybackOptions, PlayerTransition, Playhead,
-    VectorFile, VelloAsset,
+    AnimationGroup, PlaybackDirection, PlaybackLoopBehavior, PlaybackOptions, PlayerTransition,
+    Playhead, Theme, VectorFile, VelloAnimationTime, VelloAsset,
 };
 use bevy::prelude::*;
-use bevy::utils::Instant;
-use std::time::Duration;
-use vello_svg::usvg::strict_num::Ulps;
+use bevy::utils::HashSet;
 
 /// Advance all the dotLottie playheads in the scene
 pub fn advance_dot_lottie_playheads(
-    mut query: Query<(
-        &Handle<VelloAsset>,
-        &mut Playhead,
-        &mut DotLottiePlayer,
-        &PlaybackOptions,
-    )>,
-    mut assets: ResMut<Assets<VelloAsset>>,
+    mut query: Query<
+        (
+            Entity,
+            &Handle<VelloAsset>,
+            &mut Playhead,
+            &mut DotLottiePlayer,
+            &PlaybackOptions,
+        ),
+        Without<AnimationGroup>,
+    >,
+    assets: Res<Assets<VelloAsset>>,
     time: Res<Time>,
+    animation_time: Res<VelloAnimationTime>,
+    mut events: PlaybackEventWriters,
 ) {
-    for (asset_handle, mut playhead, mut player, options) in query.iter_mut() {
+    for (entity, asset_handle, mut playhead, mut player, options) in query.iter_mut() {
         // Get asset
-        let Some(VelloAsset {
-            file: VectorFile::Lottie(composition),
-            ..
-        }) = assets.get_mut(asset_handle.id())
+        let Some(
+            asset @ VelloAsset {
+                file: VectorFile::Lottie(composition),
+                ..
+            },
+        ) = assets.get(asset_handle.id())
         else {
             continue;
         };
 
         // Keep playhead bounded
-        let start_frame = options.segments.start.max(composition.frames.start);
-        let end_frame = options.segments.end.min(composition.frames.end).prev();
+        let Some((start_frame, end_frame)) = options.segments.bounds(asset) else {
+            continue;
+        };
         playhead.frame = playhead.frame.clamp(start_frame, end_frame);
 
         // Check if we are stopped
@@ -41,7 +48,19 @@ pub fn advance_dot_lottie_playheads(
         }
 
         // Set first render
-        playhead.first_render.get_or_insert(Instant::now());
+        playhead.shown = true;
+        playhead.elapsed += animation_time.scale(time.delta());
+
+        // Scrub to the driven progress
+        if let Some(frame) = options.progress_frame(start_frame, end_frame) {
+            playhead.frame = frame;
+            continue;
+        }
+
+        // Pause while nobody can see it
+        if options.pause_when_offscreen && playhead.offscreen {
+            continue;
+        }
 
         // Auto play
         if !player.started && options.autoplay {
@@ -53,160 +72,140 @@ pub fn advance_dot_lottie_playheads(
             continue;
         }
 
-        // Handle intermissions
-        if let Some(ref mut intermission) = playhead.intermission {
-            intermission.tick(time.delta());
-            if intermission.finished() {
-                playhead.intermission.take();
-                match options.direction {
-                    PlaybackDirection::Normal => {
-                        playhead.frame = start_frame;
-                    }
-                    PlaybackDirection::Reverse => {
-                        playhead.frame = end_frame;
-                    }
-                }
-            }
-            continue;
-        }
-
-        // Advance playhead
-        let length = end_frame - start_frame;
-        playhead.frame += (time.delta_seconds_f64()
-            * options.speed
-            * composition.frame_rate
-            * (options.direction as i32 as f64)
-            * playhead.playmode_dir)
-            % length;
+        let options = player.overrides.apply(options);
+        advance_playhead(
+            entity,
+            &mut playhead,
+            &options,
+            start_frame,
+            end_frame,
+            composition,
+            animation_time.scale(time.delta()),
+            &mut events,
+        );
+    }
+}
 
-        // Keep the playhead bounded between segments
-        let looping = match options.looping {
-            PlaybackLoopBehavior::Loop => true,
-            PlaybackLoopBehavior::Amount(amt) => playhead.loops_completed < amt,
-            PlaybackLoopBehavior::DoNotLoop => false,
-        };
-        if playhead.frame > end_frame {
-            if looping {
-                playhead.loops_completed += 1;
-                if let PlaybackPlayMode::Bounce = options.play_mode {
-                    playhead.playmode_dir *= -1.0;
-                }
-                // Trigger intermission, if applicable
-                if options.intermission > Duration::ZERO {
-                    playhead
-                        .intermission
-                        .replace(Timer::new(options.intermission, TimerMode::Once));
-                    playhead.frame = end_frame;
-                } else {
-                    // Wrap around to the beginning of the segment
-                    playhead.frame = start_frame + (playhead.frame - end_frame);
-                }
-            } else {
-                playhead.frame = end_frame;
-            }
-            // Obey play mode
-            if let PlaybackPlayMode::Bounce = options.play_mode {
-                playhead.frame = end_frame;
-            }
-        } else if playhead.frame < start_frame {
-            if looping {
-                playhead.loops_completed += 1;
-                if let PlaybackPlayMode::Bounce = options.play_mode {
-                    playhead.playmode_dir *= -1.0;
-                }
-                // Trigger intermission, if applicable
-                if options.intermission > Duration::ZERO {
-                    playhead
-                        .intermission
-                        .replace(Timer::new(options.intermission, TimerMode::Once));
-                    playhead.frame = start_frame;
-                } else {
-                    // Wrap around to the beginning of the segment
-                    playhead.frame = end_frame - (start_frame - playhead.frame);
-                }
-            } else {
-                playhead.frame = start_frame;
-            }
-            // Obey play mode
-            if let PlaybackPlayMode::Bounce = options.play_mode {
-                playhead.frame = start_frame;
+/// Check the guards of the current state's transitions, so guarded transitions
+/// only run when their guards hold.
+pub fn check_transition_guards(
+    world: &mut World,
+    query_player: &mut QueryState<(Entity, &DotLottiePlayer)>,
+) {
+    let blocked: Vec<(Entity, HashSet<usize>)> = query_player
+        .iter(world)
+        .map(|(entity, player)| {
+            let entity_ref = world.entity(entity);
+            let blocked: HashSet<usize> = player
+                .active_transitions()
+                .enumerate()
+                .filter(|(_, transition)| !transition.check_guards(entity_ref))
+                .map(|(i, _)| i)
+                .collect();
+            (entity, blocked)
+        })
+        .collect();
+    for (entity, blocked) in blocked {
+        if let Some(mut player) = world.get_mut::<DotLottiePlayer>(entity) {
+            if player.blocked_transitions != blocked {
+                player.blocked_transitions = blocked;
             }
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn run_transitions(
-    mut query_player: Query<(
-        &mut DotLottiePlayer,
-        &Playhead,
-        &PlaybackOptions,
-        &GlobalTransform,
-        &mut Handle<VelloAsset>,
-    )>,
-    mut assets: ResMut<Assets<VelloAsset>>,
+    mut query_player: Query<
+        (
+            &mut DotLottiePlayer,
+            &Playhead,
+            &PlaybackOptions,
+            &GlobalTransform,
+            &mut Handle<VelloAsset>,
+        ),
+        Without<AnimationGroup>,
+    >,
+    assets: Res<Assets<VelloAsset>>,
     windows: Query<&Window>,
     query_view: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
     buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    touches: Res<Touches>,
     mut hovered: Local<bool>,
 ) {
-    let Ok(window) = windows.get_single() else {
-        return;
-    };
-    let Ok((camera, view)) = query_view.get_single() else {
-        return;
+    let to_world = |position: Vec2| {
+        let (camera, view) = query_view.get_single().ok()?;
+        camera
+            .viewport_to_world(view, position)
+            .map(|ray| ray.origin.truncate())
     };
-
-    let pointer_pos = window
-        .cursor_position()
-        .and_then(|cursor| camera.viewport_to_world(view, cursor))
-        .map(|ray| ray.origin.truncate());
+    let pointer_pos = windows
+        .get_single()
+        .ok()
+        .and_then(Window::cursor_position)
+        .and_then(to_world);
+    // Touches act as pointers while pressed, and clicks when first pressed
+    let touch_pos: Vec<(Vec2, bool)> = touches
+        .iter()
+        .filter_map(|touch| {
+            to_world(touch.position()).map(|pos| (pos, touches.just_pressed(touch.id())))
+        })
+        .collect();
 
     for (mut player, playhead, options, gtransform, current_asset_handle) in query_player.iter_mut()
     {
+        // Triggers only fire on the frame they are received
+        let triggers = std::mem::take(&mut player.triggers);
         if player.stopped {
             continue;
         }
 
-        let current_asset = assets
-            .get_mut(current_asset_handle.id())
-            .unwrap_or_else(|| {
-                panic!(
-                    "asset not found for state: '{}'",
-                    player.current_state.unwrap()
-                )
-            });
+        // Wait for the asset to load
+        let Some(current_asset) = assets.get(current_asset_handle.id()) else {
+            continue;
+        };
+        if player.try_state().is_none() {
+            continue;
+        }
 
-        let is_inside = {
-            match pointer_pos {
-                Some(pointer_pos) => {
-                    let local_transform = current_asset
-                        .local_transform_center
-                        .compute_matrix()
-                        .inverse();
-                    let transform = gtransform.compute_matrix() * local_transform;
-                    let mouse_local = transform
-                        .inverse()
-                        .transform_point3(pointer_pos.extend(0.0));
-                    mouse_local.x <= current_asset.width
-                        && mouse_local.x >= 0.0
-                        && mouse_local.y >= -current_asset.height
-                        && mouse_local.y <= 0.0
-                }
-                None => false,
-            }
+        let contains = |pointer_pos: Vec2| {
+            let local_transform = current_asset
+                .local_transform_center
+                .compute_matrix()
+                .inverse();
+            let transform = gtransform.compute_matrix() * local_transform;
+            let mouse_local = transform
+                .inverse()
+                .transform_point3(pointer_pos.extend(0.0));
+            mouse_local.x <= current_asset.width
+                && mouse_local.x >= 0.0
+                && mouse_local.y >= -current_asset.height
+                && mouse_local.y <= 0.0
         };
+        let mouse_inside = pointer_pos.is_some_and(contains);
+        let is_inside = mouse_inside || touch_pos.iter().any(|(pos, _)| contains(*pos));
+        let is_clicked = (mouse_inside && buttons.just_pressed(MouseButton::Left))
+            || touch_pos
+                .iter()
+                .any(|(pos, just_pressed)| *just_pressed && contains(*pos));
 
-        for transition in player.state().transitions.iter() {
-            match transition {
+        let mut next = None;
+        // Transitions bubble up from substates to their ancestors
+        for (i, transition) in player.active_transitions().enumerate() {
+            if player.blocked_transitions.contains(&i) {
+                continue;
+            }
+            match transition.inner() {
                 PlayerTransition::OnAfter { state, secs } => {
-                    let started = playhead.first_render;
-                    if started.is_some_and(|s| s.elapsed().as_secs_f32() >= *secs) {
-                        player.next_state = Some(state);
+                    if playhead.elapsed.as_secs_f32() >= *secs {
+                        next = Some((state.clone(), transition.crossfade()));
                         break;
                     }
                 }
                 PlayerTransition::OnComplete { state } => {
-                    if let VectorFile::Lottie(composition) = &current_asset.file {
+                    if let Some((start_frame, end_frame)) = options.segments.bounds(current_asset) {
                         let loops_needed = match options.looping {
                             PlaybackLoopBehavior::DoNotLoop => Some(0),
                             PlaybackLoopBehavior::Amount(amt) => Some(amt),
@@ -214,119 +213,189 @@ pub fn run_transitions(
                         };
                         match options.direction {
                             PlaybackDirection::Normal => {
-                                let end_frame =
-                                    options.segments.end.min(composition.frames.end).prev();
                                 if playhead.frame == end_frame
                                     && loops_needed
                                         .is_some_and(|needed| playhead.loops_completed >= needed)
                                 {
-                                    player.next_state = Some(state);
+                                    next = Some((state.clone(), transition.crossfade()));
                                     break;
                                 }
                             }
                             PlaybackDirection::Reverse => {
-                                let start_frame =
-                                    options.segments.start.max(composition.frames.start);
                                 if playhead.frame == start_frame
                                     && loops_needed
                                         .is_some_and(|needed| playhead.loops_completed >= needed)
                                 {
-                                    player.next_state = Some(state);
+                                    next = Some((state.clone(), transition.crossfade()));
                                     break;
                                 }
                             }
                         }
                     }
                 }
+                PlayerTransition::OnLoopCount { state, loops } => {
+                    if playhead.loops_completed >= *loops {
+                        next = Some((state.clone(), transition.crossfade()));
+                        break;
+                    }
+                }
                 PlayerTransition::OnMouseEnter { state } => {
                     if is_inside {
-                        player.next_state = Some(state);
+                        next = Some((state.clone(), transition.crossfade()));
                         *hovered = true;
                         break;
                     }
                 }
                 PlayerTransition::OnMouseClick { state } => {
-                    if is_inside && buttons.just_pressed(MouseButton::Left) {
-                        player.next_state = Some(state);
+                    if is_clicked {
+                        next = Some((state.clone(), transition.crossfade()));
                         break;
                     }
                 }
                 PlayerTransition::OnMouseLeave { state } => {
                     if *hovered && !is_inside {
-                        player.next_state = Some(state);
+                        next = Some((state.clone(), transition.crossfade()));
                         *hovered = false;
                         break;
                     } else if is_inside {
                         *hovered = true;
                     }
                 }
+                PlayerTransition::OnKeyPress { state, key } => {
+                    if keys.just_pressed(*key) {
+                        next = Some((state.clone(), transition.crossfade()));
+                        break;
+                    }
+                }
+                PlayerTransition::OnGamepadButtonPress { state, button } => {
+                    if gamepad_buttons
+                        .get_just_pressed()
+                        .any(|pressed| pressed.button_type == *button)
+                    {
+                        next = Some((state.clone(), transition.crossfade()));
+                        break;
+                    }
+                }
                 PlayerTransition::OnShow { state } => {
-                    if playhead.first_render.is_some() {
-                        player.next_state = Some(state);
+                    if playhead.shown {
+                        next = Some((state.clone(), transition.crossfade()));
                         break;
                     }
                 }
+                PlayerTransition::OnEvent { state, trigger } => {
+                    if triggers.contains(trigger) {
+                        next = Some((state.clone(), transition.crossfade()));
+                        break;
+                    }
+                }
+                PlayerTransition::Guarded { .. } | PlayerTransition::Crossfade { .. } => {
+                    unreachable!("transitions are unwrapped")
+                }
             }
         }
+        if let Some((state, crossfade)) = next {
+            player.next_state = Some(state);
+            player.next_crossfade = crossfade;
+        }
+    }
+}
+
+/// Transition the players of followers to the state of their driver.
+pub fn sync_group_states(
+    drivers: Query<&DotLottiePlayer, Without<AnimationGroup>>,
+    mut followers: Query<(&AnimationGroup, &mut DotLottiePlayer)>,
+) {
+    for (group, mut player) in followers.iter_mut() {
+        let Ok(driver) = drivers.get(group.driver) else {
+            continue;
+        };
+        let Some(state) = driver.next_state.as_ref().or(driver.current_state.as_ref()) else {
+            continue;
+        };
+        if player.current_state.as_ref() != Some(state) && player.next_state.as_ref() != Some(state)
+        {
+            player.next_state = Some(state.clone());
+            player.next_crossfade = driver.next_crossfade;
+        }
     }
 }
 
 pub fn transition_state(
     mut commands: Commands,
-    mut query_sm: Query<(Entity, &mut DotLottiePlayer, &mut Playhead)>,
+    mut query_sm: Query<(
+        Entity,
+        &mut DotLottiePlayer,
+        &mut Playhead,
+        &Handle<VelloAsset>,
+        Option<&Theme>,
+    )>,
     assets: Res<Assets<VelloAsset>>,
+    mut warnings: EventWriter<PlayerWarning>,
+    mut entered: EventWriter<AnimationStateEntered>,
 ) {
-    for (entity, mut player, mut playhead) in query_sm.iter_mut() {
+    for (entity, mut player, mut playhead, current_handle, current_theme) in query_sm.iter_mut() {
         // Is there a state to transition to?
-        let Some(next_state) = player.next_state else {
+        let Some(next_state) = player.next_state.clone() else {
             continue;
         };
+        // Enter the initial substate of the state
+        let next_state = player.resolve_substate(next_state);
+        let crossfade = player.next_crossfade.take();
         // Is it the same state?
-        if Some(next_state) == player.current_state {
+        if player.current_state.as_ref() == Some(&next_state) {
             player.next_state.take();
             continue;
         }
 
+        let Some(target_state) = player.states.get(&next_state) else {
+            warn!("animation controller state not found: '{next_state}'");
+            warnings.send(PlayerWarning::StateNotFound {
+                entity,
+                state: next_state,
+            });
+            player.next_state.take();
+            continue;
+        };
         info!("animation controller transitioning to={next_state}");
-        let target_state = player
-            .states
-            .get(&next_state)
-            .unwrap_or_else(|| panic!("state not found: '{}'", next_state));
         let target_options = target_state
             .options
             .as_ref()
-            .or(player.state().options.as_ref())
+            .or(player.try_state().and_then(|state| state.options.as_ref()))
             .cloned()
             .unwrap_or_default();
 
         // Swap asset
         if let Some(target_handle) = target_state.asset.as_ref() {
+            if let Some(secs) = crossfade.filter(|_| target_handle != current_handle) {
+                commands.entity(entity).insert(PlayerCrossfade {
+                    asset: current_handle.clone(),
+                    frame: playhead.frame(),
+                    theme: current_theme.cloned(),
+                    elapsed: 0.0,
+                    secs,
+                });
+            }
             commands.entity(entity).insert(target_handle.clone());
         }
         // Reset playheads if requested
-        let reset_playhead =
-            player.state().reset_playhead_on_exit || target_state.reset_playhead_on_start;
+        let reset_playhead = player
+            .try_state()
+            .is_some_and(|state| state.reset_playhead_on_exit)
+            || target_state.reset_playhead_on_start;
         if reset_playhead {
             let target_asset = target_state.asset.as_ref();
             if let Some(target_asset) = target_asset {
-                let Some(VelloAsset {
-                    file: VectorFile::Lottie(composition),
-                    ..
-                }) = assets.get(target_asset)
+                let Some((start_frame, end_frame)) = assets
+                    .get(target_asset)
+                    .and_then(|asset| target_options.segments.bounds(asset))
                 else {
-                    warn!("not ready for state transition, re-queueing {next_state}...");
+                    debug!("not ready for state transition, re-queueing {next_state}...");
                     player.next_state = Some(next_state);
                     continue;
                 };
                 let frame = match target_options.direction {
-                    PlaybackDirection::Normal => {
-                        target_options.segments.start.max(composition.frames.start)
-                    }
-                    PlaybackDirection::Reverse => target_options
-                        .segments
-                        .end
-                        .min(composition.frames.end)
-                        .prev(),
+                    PlaybackDirection::Normal => start_frame,
+                    PlaybackDirection::Reverse => end_frame,
                 };
                 playhead.seek(frame);
             }
@@ -345,12 +414,18 @@ pub fn transition_state(
         // Reset playhead state
         playhead.intermission.take();
         playhead.loops_completed = 0;
-        playhead.first_render.take();
+        playhead.shown = false;
+        playhead.elapsed = Default::default();
         playhead.playmode_dir = 1.0;
 
         // Reset player state
         player.started = false;
         player.playing = false;
-        player.current_state.replace(next_state);
+        let from = player.current_state.replace(next_state.clone());
+        entered.send(AnimationStateEntered {
+            entity,
+            from,
+            to: next_state,
+        });
     }
 }
@@ -12,4 +12,13 @@ pub enum VectorLoaderError {
     #[cfg(feature = "lottie")]
     #[error("Could not parse lottie: {0}")]
     Velato(#[from] velato::VelatoError),
+    #[cfg(feature = "lottie")]
+    #[error("Could not parse lottie JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "lottie")]
+    #[error("Could not parse RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[cfg(any(feature = "svg", feature = "lottie"))]
+    #[error("Could not resolve asset path: {0}")]
+    AssetPath(#[from] bevy::asset::ParseAssetPathError),
 }
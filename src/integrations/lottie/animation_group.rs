@@ -0,0 +1,43 @@
+use super::Playhead;
+use bevy::ecs::entity::{EntityMapper, MapEntities};
+use bevy::ecs::reflect::ReflectMapEntities;
+use bevy::prelude::*;
+
+/// Synchronizes the playback of this entity with a driver entity, e.g. the
+/// shadow or aura of a character.
+///
+/// The entity doesn't advance its own playhead and instead shares the driver's
+/// playhead. dotLottie players also follow the driver's state, so the driver
+/// and its followers should have states with the same ids. The driver can't
+/// itself follow another entity.
+#[derive(PartialEq, Eq, Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct AnimationGroup {
+    /// The entity whose playback is shared.
+    pub driver: Entity,
+}
+
+impl AnimationGroup {
+    /// Follow the playback of the driver.
+    pub fn new(driver: Entity) -> Self {
+        Self { driver }
+    }
+}
+
+impl MapEntities for AnimationGroup {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.driver = entity_mapper.map_entity(self.driver);
+    }
+}
+
+/// Copy the playheads of drivers to their followers.
+pub fn sync_animation_groups(
+    drivers: Query<&Playhead, Without<AnimationGroup>>,
+    mut followers: Query<(&AnimationGroup, &mut Playhead)>,
+) {
+    for (group, mut playhead) in followers.iter_mut() {
+        if let Ok(driver) = drivers.get(group.driver) {
+            playhead.set_if_neq(driver.clone());
+        }
+    }
+}
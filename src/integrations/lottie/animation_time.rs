@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// Global control of every lottie animation, on top of the
+/// [`PlaybackClock`](super::PlaybackClock).
+///
+/// Playback already follows [`Time<Virtual>`], so pausing the game or changing
+/// its relative speed affects animations too. Use this to pause or slow only
+/// the animations.
+#[derive(Resource, PartialEq, Clone, Copy, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct VelloAnimationTime {
+    /// Whether every animation is paused.
+    pub paused: bool,
+    /// A multiplier applied to the speed of every animation.
+    pub speed: f32,
+}
+
+impl Default for VelloAnimationTime {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            speed: 1.0,
+        }
+    }
+}
+
+impl VelloAnimationTime {
+    /// Pause every animation.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resume every animation.
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+
+    /// Scale a clock delta to the delta animations advance by.
+    pub fn scale(&self, delta: Duration) -> Duration {
+        if self.paused {
+            Duration::ZERO
+        } else {
+            delta.mul_f32(self.speed.max(0.0))
+        }
+    }
+}
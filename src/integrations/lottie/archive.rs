@@ -0,0 +1,88 @@
+//! A minimal reader for zip archives, such as dotLottie (`.lottie`) files.
+//!
+//! Only stored and deflated entries are supported, without zip64.
+
+use crate::integrations::VectorLoaderError;
+use bevy::utils::HashMap;
+use std::io::Read;
+
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x02014b50;
+const LOCAL_FILE_HEADER: u32 = 0x04034b50;
+
+fn invalid(message: &str) -> VectorLoaderError {
+    VectorLoaderError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("Invalid zip archive: {message}"),
+    ))
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let bytes = bytes.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Read the files of a zip archive by path.
+pub(crate) fn read_zip(bytes: &[u8]) -> Result<HashMap<String, Vec<u8>>, VectorLoaderError> {
+    // The end of central directory record is followed by a comment of up to
+    // 64KiB
+    let end = (0..bytes.len().saturating_sub(21))
+        .rev()
+        .take(u16::MAX as usize + 1)
+        .find(|&offset| read_u32(bytes, offset) == Some(END_OF_CENTRAL_DIRECTORY))
+        .ok_or_else(|| invalid("missing end of central directory"))?;
+    let entries = read_u16(bytes, end + 10).ok_or_else(|| invalid("truncated"))?;
+    let mut offset = read_u32(bytes, end + 16).ok_or_else(|| invalid("truncated"))? as usize;
+
+    let mut files = HashMap::default();
+    for _ in 0..entries {
+        if read_u32(bytes, offset) != Some(CENTRAL_DIRECTORY_HEADER) {
+            return Err(invalid("bad central directory header"));
+        }
+        let header = |at: usize| read_u16(bytes, offset + at).map(usize::from);
+        let method = header(10).ok_or_else(|| invalid("truncated"))?;
+        let compressed_size =
+            read_u32(bytes, offset + 20).ok_or_else(|| invalid("truncated"))? as usize;
+        let name_len = header(28).ok_or_else(|| invalid("truncated"))?;
+        let extra_len = header(30).ok_or_else(|| invalid("truncated"))?;
+        let comment_len = header(32).ok_or_else(|| invalid("truncated"))?;
+        let local = read_u32(bytes, offset + 42).ok_or_else(|| invalid("truncated"))? as usize;
+        let name = bytes
+            .get(offset + 46..offset + 46 + name_len)
+            .ok_or_else(|| invalid("truncated"))?;
+        let name = String::from_utf8_lossy(name).into_owned();
+        offset += 46 + name_len + extra_len + comment_len;
+
+        // Directories
+        if name.ends_with('/') {
+            continue;
+        }
+        if read_u32(bytes, local) != Some(LOCAL_FILE_HEADER) {
+            return Err(invalid("bad local file header"));
+        }
+        let local_name_len = read_u16(bytes, local + 26).ok_or_else(|| invalid("truncated"))?;
+        let local_extra_len = read_u16(bytes, local + 28).ok_or_else(|| invalid("truncated"))?;
+        let start = local + 30 + local_name_len as usize + local_extra_len as usize;
+        let data = bytes
+            .get(start..start + compressed_size)
+            .ok_or_else(|| invalid("truncated"))?;
+        let data = match method {
+            // Stored
+            0 => data.to_vec(),
+            // Deflated
+            8 => {
+                let mut inflated = Vec::new();
+                flate2::read::DeflateDecoder::new(data).read_to_end(&mut inflated)?;
+                inflated
+            }
+            _ => return Err(invalid("unsupported compression method")),
+        };
+        files.insert(name, data);
+    }
+    Ok(files)
+}
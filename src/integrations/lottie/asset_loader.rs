@@ -1,3 +1,4 @@
+use super::images::decode_data_uri;
 use crate::integrations::lottie::load_lottie_from_bytes;
 use crate::integrations::VectorLoaderError;
 use crate::VelloAsset;
@@ -5,6 +6,7 @@ use bevy::asset::io::Reader;
 use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
 use bevy::prelude::*;
 use bevy::utils::BoxedFuture;
+use bevy::utils::HashMap;
 
 #[derive(Default)]
 pub struct VelloLottieLoader;
@@ -36,8 +38,9 @@ impl AssetLoader for VelloLottieLoader {
 
             debug!("parsing {}...", load_context.path().display());
             match ext {
-                "json" => {
-                    let vello_vector = load_lottie_from_bytes(&bytes)?;
+                "json" | "tgs" => {
+                    let mut vello_vector = load_lottie_from_bytes(&bytes)?;
+                    load_images(&mut vello_vector, load_context, &HashMap::default(), "").await?;
                     info!(
                         path = format!("{}", load_context.path().display()),
                         size = format!("{:?}", (vello_vector.width, vello_vector.height)),
@@ -54,6 +57,60 @@ impl AssetLoader for VelloLottieLoader {
     }
 
     fn extensions(&self) -> &[&str] {
-        &["json"]
+        &["json", "tgs"]
     }
 }
+
+/// Load the images of the image layers of a lottie file. Embedded images,
+/// and images in the given files of an archive, are decoded as labeled
+/// sub-assets with the given label prefix.
+pub(crate) async fn load_images(
+    vello_vector: &mut VelloAsset,
+    load_context: &mut LoadContext<'_>,
+    files: &HashMap<String, Vec<u8>>,
+    label_prefix: &str,
+) -> Result<(), VectorLoaderError> {
+    let mut handles: HashMap<String, Handle<Image>> = HashMap::default();
+    for layer in vello_vector.image_layers.values_mut() {
+        if let Some(handle) = handles.get(&layer.id) {
+            layer.handle = Some(handle.clone());
+            continue;
+        }
+        let embedded = if layer.path.starts_with("data:") {
+            let Some((bytes, mime)) = decode_data_uri(&layer.path) else {
+                warn!("could not decode embedded lottie image '{}'", layer.id);
+                continue;
+            };
+            // Decode with the image loader for the extension of the mime type
+            let ext = mime.rsplit('/').next().unwrap_or(mime);
+            Some((bytes, format!("{}.{ext}", layer.id)))
+        } else {
+            files
+                .get(layer.path.trim_start_matches('/'))
+                .map(|bytes| (bytes.clone(), layer.path.clone()))
+        };
+        let handle = match embedded {
+            Some((bytes, path)) => {
+                let image = match load_context
+                    .load_direct_with_reader(&mut bytes.as_slice(), path)
+                    .await
+                    .map(|loaded| loaded.take::<Image>())
+                {
+                    Ok(Some(image)) => image,
+                    Ok(None) | Err(_) => {
+                        warn!("could not decode embedded lottie image '{}'", layer.id);
+                        continue;
+                    }
+                };
+                load_context.add_labeled_asset(format!("{label_prefix}images/{}", layer.id), image)
+            }
+            None => {
+                let path = load_context.asset_path().resolve_embed(&layer.path)?;
+                load_context.load(path)
+            }
+        };
+        handles.insert(layer.id.clone(), handle.clone());
+        layer.handle = Some(handle);
+    }
+    Ok(())
+}
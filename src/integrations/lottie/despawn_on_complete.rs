@@ -0,0 +1,38 @@
+use super::{AnimationCompleted, Playhead};
+use crate::VelloAssetBundle;
+use bevy::prelude::*;
+
+/// Cleans up an entity when its lottie animation completes, e.g. for one-shot
+/// effects. Looping animations never complete.
+#[derive(PartialEq, Eq, Component, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub enum VelloDespawnOnComplete {
+    /// Despawn the entity and its descendants.
+    #[default]
+    Despawn,
+    /// Remove the [`VelloAssetBundle`] and playhead, keeping the entity.
+    RemoveBundle,
+}
+
+/// Clean up the entities with completed animations.
+pub fn despawn_on_complete(
+    mut commands: Commands,
+    mut events: EventReader<AnimationCompleted>,
+    query: Query<&VelloDespawnOnComplete>,
+) {
+    for AnimationCompleted { entity } in events.read() {
+        let Ok(despawn) = query.get(*entity) else {
+            continue;
+        };
+        match despawn {
+            VelloDespawnOnComplete::Despawn => {
+                commands.entity(*entity).despawn_recursive();
+            }
+            VelloDespawnOnComplete::RemoveBundle => {
+                commands
+                    .entity(*entity)
+                    .remove::<(VelloAssetBundle, Playhead, VelloDespawnOnComplete)>();
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! Loading of dotLottie (`.lottie`) archives.
+//!
+//! A dotLottie file is a zip archive of a manifest, lottie animations, their
+//! images and themes.
+//!
+//! See: <https://dotlottie.io/spec/2.0/>
+
+use super::archive::read_zip;
+use super::asset_loader::load_images;
+use super::{load_lottie_from_bytes, LottieSlotValue, LottieSlots};
+use crate::integrations::VectorLoaderError;
+use crate::VelloAsset;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::reflect::TypePath;
+use bevy::utils::{BoxedFuture, HashMap};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// The manifest of a dotLottie archive.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DotLottieManifest {
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub generator: Option<String>,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// The animation to play first, when defined.
+    #[serde(default)]
+    pub active_animation_id: Option<String>,
+    #[serde(default)]
+    pub animations: Vec<DotLottieManifestAnimation>,
+    #[serde(default)]
+    pub themes: Vec<DotLottieManifestTheme>,
+}
+
+/// An animation in the manifest of a dotLottie archive.
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DotLottieManifestAnimation {
+    pub id: String,
+    /// The theme to apply to this animation, when defined.
+    #[serde(default)]
+    pub initial_theme: Option<String>,
+    #[serde(default)]
+    pub speed: Option<f64>,
+    #[serde(default)]
+    pub autoplay: Option<bool>,
+    #[serde(default, rename = "loop")]
+    pub looping: Option<bool>,
+}
+
+/// A theme in the manifest of a dotLottie archive.
+#[derive(Deserialize, Default, Clone, Debug)]
+pub struct DotLottieManifestTheme {
+    pub id: String,
+}
+
+/// A dotLottie archive, loaded from a `.lottie` file.
+///
+/// Every animation is a labeled sub-asset, `animations/<id>`, which can be
+/// loaded directly with `asset_server.load("file.lottie#animations/<id>")`.
+#[derive(Asset, TypePath, Clone)]
+pub struct DotLottieArchive {
+    pub manifest: DotLottieManifest,
+    /// The animations by id.
+    pub animations: HashMap<String, Handle<VelloAsset>>,
+    /// The themes by id, as slot overrides for [`LottieSlots`].
+    pub themes: HashMap<String, LottieSlots>,
+}
+
+impl DotLottieArchive {
+    /// The animation to play first: the active animation of the manifest,
+    /// or the first animation.
+    pub fn active_animation(&self) -> Option<&Handle<VelloAsset>> {
+        self.manifest
+            .active_animation_id
+            .as_ref()
+            .or(self
+                .manifest
+                .animations
+                .first()
+                .map(|animation| &animation.id))
+            .and_then(|id| self.animations.get(id))
+    }
+}
+
+/// Parse the rules of a dotLottie theme. Only static color, scalar and vector
+/// rules are supported.
+fn parse_theme(value: &Value) -> LottieSlots {
+    let mut slots = LottieSlots::new();
+    let rules = value.get("rules").and_then(Value::as_array);
+    for rule in rules.into_iter().flatten() {
+        let (Some(id), Some(kind), Some(value)) = (
+            rule.get("id").and_then(Value::as_str),
+            rule.get("type").and_then(Value::as_str),
+            rule.get("value"),
+        ) else {
+            continue;
+        };
+        let numbers: Vec<f32> = match value {
+            Value::Array(array) => array
+                .iter()
+                .filter_map(Value::as_f64)
+                .map(|n| n as f32)
+                .collect(),
+            value => value.as_f64().map(|n| n as f32).into_iter().collect(),
+        };
+        let slot = match (kind, numbers.as_slice()) {
+            ("Color", [r, g, b]) => LottieSlotValue::Color(Color::rgb(*r, *g, *b)),
+            ("Color", [r, g, b, a, ..]) => LottieSlotValue::Color(Color::rgba(*r, *g, *b, *a)),
+            ("Scalar", [scalar, ..]) => LottieSlotValue::Scalar(*scalar as f64),
+            ("Vector" | "Position", [x, y, ..]) => LottieSlotValue::Vector(Vec2::new(*x, *y)),
+            _ => {
+                warn!("unsupported dotLottie theme rule '{id}' of type '{kind}'");
+                continue;
+            }
+        };
+        slots.edit(id, slot);
+    }
+    slots
+}
+
+/// Find the first file of an archive among the given paths.
+fn find_file<'a>(
+    files: &'a HashMap<String, Vec<u8>>,
+    paths: &[String],
+) -> Result<&'a Vec<u8>, VectorLoaderError> {
+    paths
+        .iter()
+        .find_map(|path| files.get(path))
+        .ok_or_else(|| {
+            VectorLoaderError::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Missing file in dotLottie archive: '{}'", paths[0]),
+            ))
+        })
+}
+
+#[derive(Default)]
+pub struct VelloDotLottieLoader;
+
+impl AssetLoader for VelloDotLottieLoader {
+    type Asset = DotLottieArchive;
+
+    type Settings = ();
+
+    type Error = VectorLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            debug!("parsing {}...", load_context.path().display());
+            let files = read_zip(&bytes)?;
+            let manifest: DotLottieManifest =
+                serde_json::from_slice(find_file(&files, &["manifest.json".to_string()])?)?;
+
+            // Version 2 archives use short directory names
+            let mut animations = HashMap::default();
+            for animation in manifest.animations.iter() {
+                let id = &animation.id;
+                let bytes = find_file(
+                    &files,
+                    &[format!("a/{id}.json"), format!("animations/{id}.json")],
+                )?;
+                let mut vello_vector = load_lottie_from_bytes(bytes)?;
+                let label = format!("animations/{id}");
+                load_images(
+                    &mut vello_vector,
+                    load_context,
+                    &files,
+                    &format!("{label}/"),
+                )
+                .await?;
+                let handle = load_context.add_labeled_asset(label, vello_vector);
+                animations.insert(id.clone(), handle);
+            }
+
+            let mut themes = HashMap::default();
+            for theme in manifest.themes.iter() {
+                let id = &theme.id;
+                let bytes = find_file(
+                    &files,
+                    &[format!("t/{id}.json"), format!("themes/{id}.json")],
+                )?;
+                let value: Value = serde_json::from_slice(bytes)?;
+                themes.insert(id.clone(), parse_theme(&value));
+            }
+
+            info!(
+                path = format!("{}", load_context.path().display()),
+                animations = animations.len(),
+                "finished parsing dotLottie archive"
+            );
+            Ok(DotLottieArchive {
+                manifest,
+                animations,
+                themes,
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["lottie"]
+    }
+}
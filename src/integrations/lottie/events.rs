@@ -0,0 +1,27 @@
+//! Events sent during lottie playback.
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+/// Sent when a lottie animation completes a loop and continues playing.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnimationLoopCompleted {
+    /// The entity playing the animation.
+    pub entity: Entity,
+    /// The number of loops completed so far.
+    pub loops_completed: usize,
+}
+
+/// Sent when a lottie animation reaches the end of its final loop.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnimationCompleted {
+    /// The entity playing the animation.
+    pub entity: Entity,
+}
+
+/// The playback event writers used by the playhead systems.
+#[derive(SystemParam)]
+pub(crate) struct PlaybackEventWriters<'w> {
+    pub loop_completed: EventWriter<'w, AnimationLoopCompleted>,
+    pub completed: EventWriter<'w, AnimationCompleted>,
+}
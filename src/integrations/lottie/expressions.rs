@@ -0,0 +1,739 @@
+//! Bake the common expressions of lottie files into keyframes, since velato
+//! doesn't evaluate expressions and freezes the properties which have them.
+//!
+//! The supported subset is the expressions After Effects exports for looped
+//! and jittered properties, with literal arguments:
+//!
+//! - `loopOut(type, numKeyframes)` and `loopIn(type, numKeyframes)`, where
+//!   the type is `"cycle"` (the default), `"pingpong"`, `"offset"` or
+//!   `"continue"`, and `numKeyframes` is the number of keyframes to loop,
+//!   all of them when 0 (the default).
+//! - `wiggle(frequency, amplitude)` of properties which aren't animated,
+//!   with deterministic noise. Further octaves are ignored.
+//!
+//! Expressions are baked over the frames the layers of the file are visible
+//! in. Other expressions are left as they are, so their properties keep the
+//! values of their keyframes.
+
+use serde_json::{Map, Value};
+
+/// The most keyframes a property is baked into, for very short loops.
+const MAX_KEYFRAMES: usize = 10_000;
+
+/// Bake the supported expressions of a lottie file into keyframes.
+pub(crate) fn bake_expressions(value: &mut Value) {
+    let frames = baked_frames(value);
+    let frame_rate = value.get("fr").and_then(Value::as_f64).unwrap_or(60.0);
+    let mut seed = 0;
+    bake_properties(value, &frames, frame_rate, &mut seed);
+}
+
+/// The frames expressions are baked over: from the first to the last frame
+/// any layer is visible in, in its own frames and in the local frames of
+/// the precomposition it instances.
+fn baked_frames(value: &Value) -> (f64, f64) {
+    let frame = |value: &Value, key| value.get(key).and_then(Value::as_f64);
+    let mut start = frame(value, "ip").unwrap_or(0.0).min(0.0);
+    let mut end = frame(value, "op").unwrap_or(0.0);
+    let assets = value.get("assets").and_then(Value::as_array).into_iter();
+    let layers = value
+        .get("layers")
+        .and_then(Value::as_array)
+        .into_iter()
+        .chain(
+            assets
+                .flatten()
+                .filter_map(|asset| asset.get("layers").and_then(Value::as_array)),
+        )
+        .flatten();
+    for layer in layers {
+        let offset = frame(layer, "st").unwrap_or(0.0);
+        let stretch = frame(layer, "sr").unwrap_or(1.0);
+        for bound in [frame(layer, "ip"), frame(layer, "op")]
+            .into_iter()
+            .flatten()
+        {
+            let local = (bound - offset) / stretch;
+            start = start.min(bound).min(local);
+            end = end.max(bound).max(local);
+        }
+    }
+    (start, end)
+}
+
+/// Walk the JSON for animated properties with expressions.
+fn bake_properties(value: &mut Value, frames: &(f64, f64), frame_rate: f64, seed: &mut u64) {
+    match value {
+        Value::Object(object) => {
+            if object.contains_key("k") {
+                if let Some(expression) = object.get("x").and_then(Value::as_str) {
+                    if let Some(expression) = Expression::parse(expression) {
+                        if bake_property(object, &expression, frames, frame_rate, seed) {
+                            object.remove("x");
+                        }
+                    }
+                }
+            }
+            for child in object.values_mut() {
+                bake_properties(child, frames, frame_rate, seed);
+            }
+        }
+        Value::Array(array) => {
+            for child in array {
+                bake_properties(child, frames, frame_rate, seed);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// How `loopOut` and `loopIn` repeat keyframes.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+enum LoopType {
+    Cycle,
+    PingPong,
+    Offset,
+    Continue,
+}
+
+/// A supported expression.
+#[derive(PartialEq, Clone, Copy, Debug)]
+enum Expression {
+    /// `loopOut`, or `loopIn` when reversed.
+    Loop {
+        kind: LoopType,
+        keyframes: usize,
+        reversed: bool,
+    },
+    Wiggle {
+        frequency: f64,
+        amplitude: f64,
+    },
+}
+
+impl Expression {
+    /// Parse an expression, as it's exported by bodymovin, or `None` if it
+    /// isn't supported.
+    fn parse(source: &str) -> Option<Self> {
+        let source: String = source.chars().filter(|c| !c.is_whitespace()).collect();
+        let source = source.replace("var$bm_rt;", "");
+        let source = source.trim_start_matches("$bm_rt=").trim_end_matches(';');
+        let (name, arguments) = source.strip_suffix(')')?.split_once('(')?;
+        let arguments: Vec<&str> = match arguments {
+            "" => Vec::new(),
+            arguments => arguments.split(',').collect(),
+        };
+        let number = |argument: &&str| argument.parse::<f64>().ok();
+        match name {
+            "loopOut" | "loopIn" if arguments.len() <= 2 => {
+                let kind = match arguments
+                    .first()
+                    .map(|argument| argument.trim_matches(['"', '\'']))
+                {
+                    None | Some("cycle") => LoopType::Cycle,
+                    Some("pingpong") => LoopType::PingPong,
+                    Some("offset") => LoopType::Offset,
+                    Some("continue") => LoopType::Continue,
+                    Some(_) => return None,
+                };
+                let keyframes = match arguments.get(1) {
+                    Some(argument) => number(argument)?,
+                    None => 0.0,
+                };
+                Some(Self::Loop {
+                    kind,
+                    keyframes: keyframes.max(0.0) as usize,
+                    reversed: name == "loopIn",
+                })
+            }
+            // Further octaves and their amplitude are ignored
+            "wiggle" if (2..=4).contains(&arguments.len()) => {
+                let arguments: Vec<f64> = arguments.iter().map(number).collect::<Option<_>>()?;
+                Some(Self::Wiggle {
+                    frequency: arguments[0],
+                    amplitude: arguments[1],
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Bake an expression into the keyframes of a property, returning whether
+/// it was baked.
+fn bake_property(
+    property: &mut Map<String, Value>,
+    expression: &Expression,
+    frames: &(f64, f64),
+    frame_rate: f64,
+    seed: &mut u64,
+) -> bool {
+    match *expression {
+        Expression::Loop {
+            kind,
+            keyframes,
+            reversed,
+        } => {
+            let Some(segments) = property.get("k").and_then(Segment::from_keyframes) else {
+                return false;
+            };
+            let segments = match reversed {
+                false => loop_out(segments, kind, keyframes, frames.1),
+                true => reverse(&loop_out(reverse(&segments), kind, keyframes, -frames.0)),
+            };
+            property.insert("k".to_string(), Segment::to_keyframes(&segments));
+            true
+        }
+        Expression::Wiggle {
+            frequency,
+            amplitude,
+        } => {
+            let Some(value) = property.get("k").and_then(numbers) else {
+                return false;
+            };
+            if frequency <= 0.0 {
+                return false;
+            }
+            *seed += 1;
+            let keyframes = wiggle(&value, frequency, amplitude, frames, frame_rate, *seed);
+            property.insert("a".to_string(), 1.into());
+            property.insert("k".to_string(), keyframes);
+            true
+        }
+    }
+}
+
+/// The numbers of a value, which is a number or an array of numbers.
+fn numbers(value: &Value) -> Option<Vec<f64>> {
+    match value {
+        Value::Number(number) => Some(vec![number.as_f64()?]),
+        Value::Array(array) => array.iter().map(Value::as_f64).collect(),
+        _ => None,
+    }
+}
+
+/// The interpolation of a property between two keyframes.
+#[derive(Clone, Debug)]
+struct Segment {
+    start: f64,
+    end: f64,
+    from: Value,
+    to: Value,
+    /// The easing and spatial tangents of the segment, and whether it
+    /// holds, as they are stored on the keyframe it starts at.
+    interpolation: Map<String, Value>,
+}
+
+impl Segment {
+    fn holds(&self) -> bool {
+        self.interpolation
+            .get("h")
+            .and_then(Value::as_u64)
+            .is_some_and(|hold| hold != 0)
+    }
+
+    /// The segments between the keyframes of a property. Keyframes which
+    /// only have the end value (`e`) of their previous keyframe are
+    /// supported.
+    fn from_keyframes(keyframes: &Value) -> Option<Vec<Segment>> {
+        // The frame, value, end value and interpolation of each keyframe
+        let mut points = Vec::new();
+        let mut end_value: Option<Value> = None;
+        for keyframe in keyframes.as_array()? {
+            let mut interpolation = keyframe.as_object()?.clone();
+            let frame = interpolation.remove("t")?.as_f64()?;
+            let value = interpolation.remove("s").or(end_value.take())?;
+            end_value = interpolation.remove("e");
+            points.push((frame, value, end_value.clone(), interpolation));
+        }
+        let segments: Vec<Segment> = points
+            .windows(2)
+            .map(|pair| Segment {
+                start: pair[0].0,
+                end: pair[1].0,
+                from: pair[0].1.clone(),
+                to: pair[0].2.clone().unwrap_or_else(|| pair[1].1.clone()),
+                interpolation: pair[0].3.clone(),
+            })
+            .collect();
+        (!segments.is_empty()).then_some(segments)
+    }
+
+    /// The keyframes of consecutive segments. Segments which don't start
+    /// at the value the previous one ends at jump to it.
+    fn to_keyframes(segments: &[Segment]) -> Value {
+        let mut keyframes: Vec<Map<String, Value>> = Vec::new();
+        for segment in segments {
+            let mut keyframe = segment.interpolation.clone();
+            keyframe.insert("t".to_string(), segment.start.into());
+            keyframe.insert("s".to_string(), segment.from.clone());
+            match keyframes.last_mut() {
+                Some(last)
+                    if last.get("t") == keyframe.get("t") && last.get("s") == keyframe.get("s") =>
+                {
+                    *last = keyframe;
+                }
+                last => {
+                    // Hold the previous value until the jump
+                    if let Some(last) = last {
+                        last.insert("h".to_string(), 1.into());
+                    }
+                    keyframes.push(keyframe);
+                }
+            }
+            let mut end = Map::new();
+            end.insert("t".to_string(), segment.end.into());
+            end.insert("s".to_string(), segment.to.clone());
+            keyframes.push(end);
+        }
+        Value::Array(keyframes.into_iter().map(Value::Object).collect())
+    }
+
+    fn shifted(&self, frames: f64, offset: Option<&[f64]>) -> Segment {
+        let shift = |value: &Value| match offset.zip(numbers(value)) {
+            Some((offset, numbers)) => Value::Array(
+                numbers
+                    .iter()
+                    .zip(offset.iter().chain(std::iter::repeat(&0.0)))
+                    .map(|(number, offset)| (number + offset).into())
+                    .collect(),
+            ),
+            None => value.clone(),
+        };
+        Segment {
+            start: self.start + frames,
+            end: self.end + frames,
+            from: shift(&self.from),
+            to: shift(&self.to),
+            interpolation: self.interpolation.clone(),
+        }
+    }
+}
+
+/// The segments played backwards, in negated frames.
+fn reverse(segments: &[Segment]) -> Vec<Segment> {
+    segments
+        .iter()
+        .rev()
+        .map(|segment| {
+            let mut interpolation = segment.interpolation.clone();
+            let easing = |key| interpolation.get(key).map(mirror_handle);
+            let (out_easing, in_easing) = (easing("i"), easing("o"));
+            for (key, easing) in [("o", out_easing), ("i", in_easing)] {
+                match easing {
+                    Some(easing) => interpolation.insert(key.to_string(), easing),
+                    None => interpolation.remove(key),
+                };
+            }
+            let (out_tangent, in_tangent) =
+                (interpolation.remove("ti"), interpolation.remove("to"));
+            for (key, tangent) in [("to", out_tangent), ("ti", in_tangent)] {
+                if let Some(tangent) = tangent {
+                    interpolation.insert(key.to_string(), tangent);
+                }
+            }
+            Segment {
+                start: -segment.end,
+                end: -segment.start,
+                // A held value is held backwards too
+                from: match segment.holds() {
+                    true => segment.from.clone(),
+                    false => segment.to.clone(),
+                },
+                to: segment.from.clone(),
+                interpolation,
+            }
+        })
+        .collect()
+}
+
+/// An easing handle of the reversed segment, which is the opposite handle
+/// mirrored.
+fn mirror_handle(handle: &Value) -> Value {
+    let Some(handle) = handle.as_object() else {
+        return handle.clone();
+    };
+    let mirror = |component: &Value| match component {
+        Value::Number(number) => number.as_f64().map_or(Value::Null, |x| (1.0 - x).into()),
+        Value::Array(array) => array
+            .iter()
+            .map(|x| x.as_f64().map_or(Value::Null, |x| (1.0 - x).into()))
+            .collect(),
+        other => other.clone(),
+    };
+    Value::Object(
+        handle
+            .iter()
+            .map(|(key, component)| (key.clone(), mirror(component)))
+            .collect(),
+    )
+}
+
+/// Repeat the last keyframes after the last keyframe, until a frame.
+fn loop_out(
+    mut segments: Vec<Segment>,
+    kind: LoopType,
+    keyframes: usize,
+    until: f64,
+) -> Vec<Segment> {
+    let first = match keyframes {
+        0 => 0,
+        keyframes => segments.len().saturating_sub(keyframes),
+    };
+    let looped: Vec<Segment> = segments[first..].to_vec();
+    let (Some(head), Some(tail)) = (looped.first(), looped.last()) else {
+        return segments;
+    };
+    let (start, end) = (head.start, tail.end);
+    let period = end - start;
+    if kind == LoopType::Continue {
+        if let Some(segment) = continuation(tail, until) {
+            segments.push(segment);
+        }
+        return segments;
+    }
+    if period <= 0.0 || end >= until {
+        return segments;
+    }
+    // The change of the value over a loop, which offset loops accumulate
+    let change = match kind {
+        LoopType::Offset => numbers(&head.from)
+            .zip(numbers(&tail.to))
+            .map(|(from, to)| {
+                to.iter()
+                    .zip(from)
+                    .map(|(to, from)| to - from)
+                    .collect::<Vec<_>>()
+            }),
+        _ => None,
+    };
+    let reversed = reverse(&looped);
+    let repeats = ((until - end) / period).ceil() as usize;
+    let repeats = repeats.min(MAX_KEYFRAMES / looped.len().max(1));
+    for repeat in 1..=repeats {
+        let offset = change.as_ref().map(|change| {
+            change
+                .iter()
+                .map(|change| change * repeat as f64)
+                .collect::<Vec<_>>()
+        });
+        let frames = repeat as f64 * period;
+        match kind {
+            LoopType::PingPong if repeat % 2 == 1 => segments.extend(
+                reversed
+                    .iter()
+                    .map(|segment| segment.shifted(start + end + frames, None)),
+            ),
+            LoopType::PingPong if repeat % 2 == 0 => {
+                segments.extend(looped.iter().map(|segment| segment.shifted(frames, None)));
+            }
+            _ => segments.extend(
+                looped
+                    .iter()
+                    .map(|segment| segment.shifted(frames, offset.as_deref())),
+            ),
+        }
+    }
+    segments
+}
+
+/// The segment continuing the property after its last keyframe at the rate
+/// it ends at, until a frame.
+fn continuation(last: &Segment, until: f64) -> Option<Segment> {
+    let duration = last.end - last.start;
+    if last.holds() || duration <= 0.0 || last.end >= until {
+        return None;
+    }
+    let (from, to) = (numbers(&last.from)?, numbers(&last.to)?);
+    // The slope of the easing at its end, for each dimension
+    let component = |handle: &str, key: &str, index: usize| {
+        let component = last.interpolation.get(handle)?.get(key)?;
+        match component {
+            Value::Array(array) => array.get(index).or(array.last())?.as_f64(),
+            component => component.as_f64(),
+        }
+    };
+    let handle = |handle: &str, index: usize| {
+        Some((
+            component(handle, "x", index)?,
+            component(handle, "y", index)?,
+        ))
+    };
+    let to: Vec<Value> = from
+        .iter()
+        .zip(&to)
+        .enumerate()
+        .map(|(index, (from, to))| {
+            // The easing ends towards its in handle, or its out handle when
+            // the in handle is at the end
+            let slope = [handle("i", index), handle("o", index)]
+                .into_iter()
+                .flatten()
+                .find(|&handle| handle != (1.0, 1.0))
+                .map_or(1.0, |(x, y)| match x < 1.0 {
+                    true => (1.0 - y) / (1.0 - x),
+                    false => 0.0,
+                });
+            let rate = (to - from) / duration * slope;
+            (to + rate * (until - last.end)).into()
+        })
+        .collect();
+    let mut interpolation = Map::new();
+    interpolation.insert("o".to_string(), easing_handle(0.0, 0.0));
+    interpolation.insert("i".to_string(), easing_handle(1.0, 1.0));
+    Some(Segment {
+        start: last.end,
+        end: until,
+        from: last.to.clone(),
+        to: Value::Array(to),
+        interpolation,
+    })
+}
+
+fn easing_handle(x: f64, y: f64) -> Value {
+    serde_json::json!({ "x": x, "y": y })
+}
+
+/// The keyframes of a value wiggling `frequency` times a second by up to
+/// `amplitude`.
+fn wiggle(
+    value: &[f64],
+    frequency: f64,
+    amplitude: f64,
+    frames: &(f64, f64),
+    frame_rate: f64,
+    seed: u64,
+) -> Value {
+    let step = frame_rate / frequency;
+    let count = (((frames.1 - frames.0) / step).ceil() as usize + 1).min(MAX_KEYFRAMES);
+    let keyframes = (0..count)
+        .map(|index| {
+            let wiggled: Vec<Value> = value
+                .iter()
+                .enumerate()
+                .map(|(dimension, value)| {
+                    (value + amplitude * noise(seed, index as u64, dimension as u64)).into()
+                })
+                .collect();
+            serde_json::json!({
+                "t": frames.0 + index as f64 * step,
+                "s": wiggled,
+                "o": easing_handle(0.5, 0.0),
+                "i": easing_handle(0.5, 1.0),
+            })
+        })
+        .collect();
+    Value::Array(keyframes)
+}
+
+/// Deterministic noise in `-1..1`.
+fn noise(seed: u64, index: u64, dimension: u64) -> f64 {
+    // SplitMix64
+    let mut x = seed
+        .wrapping_mul(0x9e37_79b9_7f4a_7c15)
+        .wrapping_add(index.wrapping_mul(0xbf58_476d_1ce4_e5b9))
+        .wrapping_add(dimension.wrapping_mul(0x94d0_49bb_1331_11eb));
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^= x >> 31;
+    (x >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// The value of baked keyframes at a frame, ignoring their easing.
+    fn value_at(keyframes: &Value, frame: f64) -> Vec<f64> {
+        let segments = Segment::from_keyframes(keyframes).unwrap();
+        let segment = segments
+            .iter()
+            .rev()
+            .find(|segment| segment.start <= frame)
+            .unwrap_or(&segments[0]);
+        let (from, to) = (
+            numbers(&segment.from).unwrap(),
+            numbers(&segment.to).unwrap(),
+        );
+        if segment.holds() {
+            return from;
+        }
+        if frame >= segment.end {
+            return to;
+        }
+        let t = (frame - segment.start) / (segment.end - segment.start);
+        from.iter()
+            .zip(&to)
+            .map(|(from, to)| from + (to - from) * t)
+            .collect()
+    }
+
+    /// A lottie file with one layer, whose position has an expression.
+    fn position_with(expression: &str, keyframes: Value, end: f64) -> Value {
+        json!({
+            "fr": 30,
+            "ip": 0,
+            "op": end,
+            "layers": [{
+                "ip": 0,
+                "op": end,
+                "ks": { "p": { "a": 1, "k": keyframes, "x": expression } },
+            }],
+        })
+    }
+
+    fn position(file: &Value) -> &Value {
+        &file["layers"][0]["ks"]["p"]
+    }
+
+    fn ramp() -> Value {
+        json!([{ "t": 0, "s": [0.0] }, { "t": 10, "s": [10.0] }])
+    }
+
+    #[test]
+    fn parse_supported_expressions() {
+        assert_eq!(
+            Expression::parse("loopOut()"),
+            Some(Expression::Loop {
+                kind: LoopType::Cycle,
+                keyframes: 0,
+                reversed: false,
+            })
+        );
+        assert_eq!(
+            Expression::parse("var $bm_rt;\n$bm_rt = loopIn('pingpong', 2);"),
+            Some(Expression::Loop {
+                kind: LoopType::PingPong,
+                keyframes: 2,
+                reversed: true,
+            })
+        );
+        assert_eq!(
+            Expression::parse("wiggle(2, 30, 3, 0.5)"),
+            Some(Expression::Wiggle {
+                frequency: 2.0,
+                amplitude: 30.0,
+            })
+        );
+    }
+
+    #[test]
+    fn reject_unsupported_syntax() {
+        for source in [
+            "time * 2",
+            "loopOut('bounce')",
+            "loopOut('cycle', 1, 2)",
+            "wiggle(freq, 30)",
+            "wiggle(2)",
+            "value + wiggle(2, 30)",
+            "loopOut('cycle'",
+        ] {
+            assert_eq!(Expression::parse(source), None, "{source}");
+        }
+        // Unsupported expressions are left as they are
+        let mut file = position_with("time * 2", ramp(), 30.0);
+        let unbaked = file.clone();
+        bake_expressions(&mut file);
+        assert_eq!(file, unbaked);
+    }
+
+    #[test]
+    fn loop_out_cycle() {
+        let mut file = position_with("loopOut('cycle')", ramp(), 30.0);
+        bake_expressions(&mut file);
+        let property = position(&file);
+        assert!(property.get("x").is_none());
+        for frame in [5.0, 15.0, 25.0] {
+            assert_eq!(value_at(&property["k"], frame), [5.0]);
+        }
+        assert_eq!(value_at(&property["k"], 12.0), [2.0]);
+        assert_eq!(value_at(&property["k"], 28.0), [8.0]);
+    }
+
+    #[test]
+    fn loop_out_pingpong() {
+        let mut file = position_with("loopOut('pingpong')", ramp(), 30.0);
+        bake_expressions(&mut file);
+        let keyframes = &position(&file)["k"];
+        assert_eq!(value_at(keyframes, 2.0), [2.0]);
+        // Played backwards, then forwards again
+        assert_eq!(value_at(keyframes, 12.0), [8.0]);
+        assert_eq!(value_at(keyframes, 22.0), [2.0]);
+        assert_eq!(value_at(keyframes, 30.0), [10.0]);
+    }
+
+    #[test]
+    fn loop_out_offset_accumulates() {
+        let mut file = position_with("loopOut('offset')", ramp(), 30.0);
+        bake_expressions(&mut file);
+        assert_eq!(value_at(&position(&file)["k"], 25.0), [25.0]);
+    }
+
+    #[test]
+    fn wiggle_is_deterministic() {
+        let wiggled = wiggle(&[1.0, 2.0], 2.0, 5.0, &(0.0, 60.0), 30.0, 1);
+        assert_eq!(
+            wiggled,
+            wiggle(&[1.0, 2.0], 2.0, 5.0, &(0.0, 60.0), 30.0, 1)
+        );
+        assert_ne!(
+            wiggled,
+            wiggle(&[1.0, 2.0], 2.0, 5.0, &(0.0, 60.0), 30.0, 2)
+        );
+        // A keyframe every 15 frames, within the amplitude
+        let keyframes = wiggled.as_array().unwrap();
+        assert_eq!(keyframes.len(), 5);
+        for keyframe in keyframes {
+            let values = numbers(&keyframe["s"]).unwrap();
+            assert!((values[0] - 1.0).abs() <= 5.0 && (values[1] - 2.0).abs() <= 5.0);
+        }
+
+        // Baking the same file twice wiggles it the same
+        let file = position_with("wiggle(2, 5)", json!([1.0, 2.0]), 60.0);
+        let (mut first, mut second) = (file.clone(), file);
+        bake_expressions(&mut first);
+        bake_expressions(&mut second);
+        assert_eq!(first, second);
+        assert_eq!(position(&first)["a"], 1);
+    }
+
+    #[test]
+    fn time_remapping() {
+        // A precomposition instanced 30 frames early, with its time remapped
+        // by a looped expression
+        let mut file = json!({
+            "fr": 30,
+            "ip": 0,
+            "op": 30,
+            "layers": [{
+                "ty": 0,
+                "refId": "precomp",
+                "ip": 0,
+                "op": 30,
+                "st": -30,
+                "tm": {
+                    "a": 1,
+                    "k": [{ "t": 0, "s": [0.0] }, { "t": 10, "s": [1.0] }],
+                    "x": "loopOut('cycle')",
+                },
+            }],
+            "assets": [{
+                "id": "precomp",
+                "layers": [{
+                    "ip": 0,
+                    "op": 60,
+                    "ks": { "p": { "a": 1, "k": ramp(), "x": "loopOut()" } },
+                }],
+            }],
+        });
+        assert_eq!(baked_frames(&file), (0.0, 60.0));
+        bake_expressions(&mut file);
+        let time_remap = &file["layers"][0]["tm"];
+        assert!(time_remap.get("x").is_none());
+        assert_eq!(value_at(&time_remap["k"], 25.0), [0.5]);
+        // The precomposition is baked over its local frames
+        let nested = &file["assets"][0]["layers"][0]["ks"]["p"]["k"];
+        assert_eq!(value_at(nested, 55.0), [5.0]);
+    }
+}
@@ -0,0 +1,174 @@
+//! Image layers of lottie files.
+//!
+//! Velato doesn't support image layers, so they are loaded as null layers to
+//! keep their transform and timing. Their images are loaded through the
+//! `AssetServer`, embedded images as labeled sub-assets, and drawn on top of
+//! the composition.
+
+use super::layers::layer_transform;
+use crate::brush::to_peniko_image;
+use crate::VelloAsset;
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+use serde_json::Value;
+use velato::Composition;
+use vello::kurbo::{Affine, Rect};
+use vello::peniko::{self, Mix};
+use vello::Scene;
+
+/// An image layer of a lottie file, which velato doesn't render.
+#[derive(Clone)]
+pub struct LottieImage {
+    /// The id of the image in the assets of the file.
+    pub id: String,
+    /// The path of the image relative to the file, or a data URI for
+    /// embedded images.
+    pub path: String,
+    /// The width of the layer.
+    pub width: f64,
+    /// The height of the layer.
+    pub height: f64,
+    /// The image, when loaded by the asset server.
+    pub handle: Option<Handle<Image>>,
+    /// The decoded image, once loaded.
+    pub image: Option<peniko::Image>,
+}
+
+/// Parse the top-level image layers of a lottie file, by layer name.
+pub(crate) fn parse_image_layers(value: &Value) -> HashMap<String, LottieImage> {
+    let images: HashMap<&str, &Value> = value
+        .get("assets")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|asset| asset.get("p").is_some())
+        .filter_map(|asset| Some((asset.get("id")?.as_str()?, asset)))
+        .collect();
+    let Some(layers) = value.get("layers").and_then(Value::as_array) else {
+        return HashMap::default();
+    };
+    layers
+        .iter()
+        .filter(|layer| layer.get("ty").and_then(Value::as_u64) == Some(2))
+        .filter_map(|layer| {
+            let name = layer.get("nm")?.as_str()?;
+            let id = layer.get("refId")?.as_str()?;
+            let image = images.get(id)?;
+            let file = image.get("p")?.as_str()?;
+            let embedded =
+                image.get("e").and_then(Value::as_u64) == Some(1) || file.starts_with("data:");
+            let path = match image.get("u").and_then(Value::as_str) {
+                Some(dir) if !embedded => format!("{dir}{file}"),
+                _ => file.to_string(),
+            };
+            Some((
+                name.to_string(),
+                LottieImage {
+                    id: id.to_string(),
+                    path,
+                    width: image.get("w").and_then(Value::as_f64).unwrap_or(0.0),
+                    height: image.get("h").and_then(Value::as_f64).unwrap_or(0.0),
+                    handle: None,
+                    image: None,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Decode the bytes and mime type of a base64 data URI.
+pub(crate) fn decode_data_uri(uri: &str) -> Option<(Vec<u8>, &str)> {
+    use base64::Engine;
+    let (header, data) = uri.strip_prefix("data:")?.split_once(',')?;
+    let mime = header.strip_suffix(";base64")?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .ok()?;
+    Some((bytes, mime))
+}
+
+/// Decode the images of lottie image layers, once loaded by the asset server.
+pub fn resolve_lottie_images(
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut vector_events: EventReader<AssetEvent<VelloAsset>>,
+    images: Res<Assets<Image>>,
+    mut assets: ResMut<Assets<VelloAsset>>,
+) {
+    let changed: HashSet<AssetId<Image>> = image_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    let loaded: HashSet<AssetId<VelloAsset>> = vector_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    // Only mutate the assets to update, which re-extracts them
+    let outdated: Vec<AssetId<VelloAsset>> = assets
+        .iter()
+        .filter(|(id, asset)| {
+            asset.image_layers.values().any(|layer| {
+                layer.handle.as_ref().is_some_and(|handle| {
+                    changed.contains(&handle.id()) || loaded.contains(id) && layer.image.is_none()
+                })
+            })
+        })
+        .map(|(id, _)| id)
+        .collect();
+    for id in outdated {
+        let Some(asset) = assets.get_mut(id) else {
+            continue;
+        };
+        for layer in asset.image_layers.values_mut() {
+            let Some(image) = layer.handle.as_ref().and_then(|handle| images.get(handle)) else {
+                continue;
+            };
+            layer.image = to_peniko_image(image);
+            if layer.image.is_none() {
+                warn!("unsupported format of lottie image '{}'", layer.id);
+            }
+        }
+    }
+}
+
+/// Draw the decoded images of the image layers of a composition.
+pub(crate) fn render_image_layers(
+    scene: &mut Scene,
+    composition: &Composition,
+    image_layers: &HashMap<String, LottieImage>,
+    frame: f64,
+    transform: Affine,
+    alpha: f32,
+) {
+    for (name, image_layer) in image_layers.iter() {
+        let Some(image) = &image_layer.image else {
+            continue;
+        };
+        let Some(layer) = composition
+            .layers
+            .iter()
+            .find(|layer| &layer.name == name && layer.frames.contains(&frame))
+        else {
+            continue;
+        };
+        let opacity = layer.opacity.evaluate(frame) / 100.0;
+        let transform = transform * layer_transform(&composition.layers, layer, frame);
+        let (width, height) = match (image_layer.width, image_layer.height) {
+            (width, height) if width > 0.0 && height > 0.0 => (width, height),
+            _ => (image.width as f64, image.height as f64),
+        };
+        let bounds = Rect::new(0.0, 0.0, width, height);
+        // Stretch the image to the size of the layer
+        let scale =
+            Affine::scale_non_uniform(width / image.width as f64, height / image.height as f64);
+        scene.push_layer(Mix::Normal, alpha * opacity as f32, transform, &bounds);
+        scene.draw_image(image, transform * scale);
+        scene.pop_layer();
+    }
+}
@@ -0,0 +1,91 @@
+//! Runtime visibility of the named layers of lottie animations, e.g. to
+//! toggle the accessories of a character per entity.
+
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use velato::model::Layer;
+use velato::Composition;
+
+#[derive(PartialEq, Eq, Component, Default, Clone, Debug, Reflect)]
+#[reflect(Component)]
+/// Add this component to a `VelloAssetBundle` entity to show or hide the
+/// layers of its lottie animation by layer name, so one composition can draw
+/// every combination of optional parts, such as a hat or a sword. Layers are
+/// visible unless hidden.
+///
+/// A hidden layer isn't drawn, nor are the precompositions it instances,
+/// but the layers parented to it still move with it. Layers within
+/// precompositions are hidden too, and so are image and text layers.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_vello::prelude::*;
+/// # fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// commands
+///     .spawn(VelloAssetBundle {
+///         vector: asset_server.load("knight.json"),
+///         ..default()
+///     })
+///     .insert(LottieLayerVisibility::default().with("hat", false));
+/// # }
+/// ```
+pub struct LottieLayerVisibility {
+    pub(crate) layers: HashMap<String, bool>,
+}
+
+impl LottieLayerVisibility {
+    /// Show or hide the layers with the given name.
+    pub fn with(mut self, layer_name: &str, visible: bool) -> Self {
+        self.layers.insert(layer_name.to_string(), visible);
+        self
+    }
+
+    /// Show or hide the layers with the given name. This will overwrite the
+    /// previous value.
+    pub fn set(&mut self, layer_name: &str, visible: bool) -> &mut Self {
+        self.layers.insert(layer_name.to_string(), visible);
+        self
+    }
+
+    /// Show the layers with the given name.
+    pub fn show(&mut self, layer_name: &str) -> &mut Self {
+        self.set(layer_name, true)
+    }
+
+    /// Hide the layers with the given name.
+    pub fn hide(&mut self, layer_name: &str) -> &mut Self {
+        self.set(layer_name, false)
+    }
+
+    /// Flip the visibility of the layers with the given name.
+    pub fn toggle(&mut self, layer_name: &str) -> &mut Self {
+        let visible = self.is_visible(layer_name);
+        self.set(layer_name, !visible)
+    }
+
+    /// Whether the layers with the given name are visible.
+    pub fn is_visible(&self, layer_name: &str) -> bool {
+        self.layers.get(layer_name).copied().unwrap_or(true)
+    }
+
+    /// A copy of a composition without the hidden layers, or `None` if no
+    /// layer is hidden.
+    pub(crate) fn hide_layers(&self, composition: &Composition) -> Option<Composition> {
+        if self.layers.values().all(|visible| *visible) {
+            return None;
+        }
+        let mut composition = composition.clone();
+        let hide = |layers: &mut Vec<Layer>| {
+            for layer in layers.iter_mut() {
+                // Layers aren't drawn outside of their frames, but their
+                // transforms are still followed by their children
+                if !self.is_visible(&layer.name) {
+                    layer.frames = 0.0..0.0;
+                }
+            }
+        };
+        hide(&mut composition.layers);
+        composition.assets.values_mut().for_each(hide);
+        Some(composition)
+    }
+}
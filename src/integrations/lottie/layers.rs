@@ -0,0 +1,30 @@
+use velato::model::{Content, Layer};
+use velato::Composition;
+use vello::kurbo::Affine;
+
+/// The transform of a layer at a frame, including its parents.
+pub(crate) fn layer_transform(layers: &[Layer], layer: &Layer, frame: f64) -> Affine {
+    let mut transform = layer.transform.evaluate(frame).into_owned();
+    let mut parent = layer.parent;
+    // Bounded by the number of layers, in case of cyclic parents
+    for _ in 0..layers.len() {
+        let Some(layer) = parent.and_then(|index| layers.get(index)) else {
+            break;
+        };
+        transform = layer.transform.evaluate(frame).into_owned() * transform;
+        parent = layer.parent;
+    }
+    transform
+}
+
+/// The local frame of the precomposition a layer instances, at a frame of
+/// the layer, following its time remapping (`tm`), which is in seconds.
+pub(crate) fn precomp_frame(composition: &Composition, layer: &Layer, frame: f64) -> f64 {
+    match &layer.content {
+        Content::Instance {
+            time_remap: Some(time_remap),
+            ..
+        } => time_remap.evaluate(frame) * composition.frame_rate,
+        _ => (frame - layer.start_frame) / layer.stretch,
+    }
+}
@@ -1,8 +1,34 @@
+mod animation_group;
+#[cfg(feature = "experimental-dotLottie")]
+pub(crate) use animation_group::sync_animation_groups;
+pub use animation_group::AnimationGroup;
+
+mod animation_time;
+pub use animation_time::VelloAnimationTime;
+
+mod archive;
+
 mod asset_loader;
 
+mod dot_lottie_archive;
+pub use dot_lottie_archive::{
+    DotLottieArchive, DotLottieManifest, DotLottieManifestAnimation, DotLottieManifestTheme,
+};
+
+mod expressions;
+
+mod images;
+pub(crate) use images::render_image_layers;
+pub use images::LottieImage;
+
+mod layer_visibility;
+pub use layer_visibility::LottieLayerVisibility;
+
+mod layers;
+
 mod systems;
 #[cfg(feature = "experimental-dotLottie")]
-pub(crate) use systems::spawn_playheads;
+pub(crate) use systems::{advance_playhead, mark_offscreen_playheads, spawn_playheads};
 
 mod parse;
 pub use parse::{load_lottie_from_bytes, load_lottie_from_str};
@@ -13,13 +39,45 @@ pub use lottie_ext::LottieExt;
 mod plugin;
 pub(crate) use plugin::LottieIntegrationPlugin;
 
+mod despawn_on_complete;
+pub use despawn_on_complete::VelloDespawnOnComplete;
+
+mod events;
+#[cfg(feature = "experimental-dotLottie")]
+pub(crate) use events::PlaybackEventWriters;
+pub use events::{AnimationCompleted, AnimationLoopCompleted};
+
+mod playback_clock;
+pub use playback_clock::PlaybackClock;
+
 mod playback_options;
 pub use playback_options::{
-    PlaybackDirection, PlaybackLoopBehavior, PlaybackOptions, PlaybackPlayMode,
+    PlaybackDirection, PlaybackDriver, PlaybackLoopBehavior, PlaybackOptions, PlaybackPlayMode,
+    PlaybackSegments, PlaybackStartFrame,
 };
 
+mod playback_state;
+pub use playback_state::PlaybackState;
+
 mod playhead;
 pub use playhead::Playhead;
 
+mod render;
+pub(crate) use render::{is_static, PrecompFrame, PrecompRenderer};
+
+mod slots;
+pub(crate) use slots::SlottedComposition;
+pub use slots::{LottieSlotValue, LottieSlots};
+
+mod text_overrides;
+pub(crate) use text_overrides::render_text_overrides;
+pub use text_overrides::{LottieTextLayer, LottieTextOverrides};
+
+mod sockets;
+pub use sockets::LottieSocket;
+
+mod strokes;
+pub(crate) use strokes::scale_strokes;
+
 mod theme;
-pub use theme::Theme;
+pub use theme::{Theme, ThemeLoader, ThemePaint, ThemeTarget};
@@ -1,12 +1,104 @@
+use super::{expressions, images, slots, text_overrides};
 use crate::integrations::VectorLoaderError;
 use crate::{VectorFile, VelloAsset};
 use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::Deserialize;
+use std::io::Read;
+use std::ops::Range;
 use std::sync::Arc;
 
-/// Deserialize a Lottie file from bytes.
+/// The markers of a lottie file, which velato doesn't expose.
+#[derive(Deserialize)]
+struct LottieMarkers {
+    #[serde(default)]
+    markers: Vec<LottieMarker>,
+}
+
+#[derive(Deserialize)]
+struct LottieMarker {
+    /// Name
+    #[serde(rename = "cm")]
+    comment: Option<String>,
+    /// Start frame
+    #[serde(rename = "tm")]
+    time: Option<f64>,
+    /// Duration in frames
+    #[serde(rename = "dr")]
+    duration: Option<f64>,
+}
+
+/// Parse the named segments from the markers of a lottie file.
+fn parse_markers(
+    value: &serde_json::Value,
+) -> Result<HashMap<String, Range<f64>>, VectorLoaderError> {
+    let LottieMarkers { markers } = LottieMarkers::deserialize(value)?;
+    Ok(markers
+        .into_iter()
+        .filter_map(|marker| {
+            let start = marker.time?;
+            let end = start + marker.duration.unwrap_or(0.0);
+            Some((marker.comment?, start..end))
+        })
+        .collect())
+}
+
+/// Replace the layers which velato can't parse, such as text and image
+/// layers, with null layers, so the rest of the file still loads and their
+/// transforms are kept for parented layers.
+fn nullify_unsupported_layers(value: &mut serde_json::Value) {
+    // Precomposition, solid color, null and shape layers
+    const SUPPORTED: [u64; 4] = [0, 1, 3, 4];
+    let Some(object) = value.as_object_mut() else {
+        return;
+    };
+    let mut layers = Vec::new();
+    for (key, child) in object.iter_mut() {
+        let child = child.as_array_mut().into_iter().flatten();
+        match key.as_str() {
+            "layers" => layers.extend(child),
+            "assets" => layers.extend(
+                child
+                    .filter_map(|asset| asset.get_mut("layers"))
+                    .filter_map(serde_json::Value::as_array_mut)
+                    .flatten(),
+            ),
+            _ => {}
+        }
+    }
+    for layer in layers {
+        let kind = layer.get("ty").and_then(serde_json::Value::as_u64);
+        if kind.is_some_and(|kind| !SUPPORTED.contains(&kind)) {
+            layer["ty"] = 3.into();
+        }
+    }
+}
+
+/// Deserialize a Lottie file from bytes, which may be gzipped, such as
+/// Telegram stickers (`.tgs`). The common expressions of After Effects, such
+/// as `loopOut` and `wiggle`, are baked into keyframes.
 pub fn load_lottie_from_bytes(bytes: &[u8]) -> Result<VelloAsset, VectorLoaderError> {
-    // Load Lottie JSON bytes with the Velato (bodymovin) parser
-    let composition = velato::Composition::from_slice(bytes).map_err(VectorLoaderError::Velato)?;
+    // Gzip magic number
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut inflated = Vec::new();
+        flate2::read::GzDecoder::new(bytes).read_to_end(&mut inflated)?;
+        return load_lottie_from_bytes(&inflated);
+    }
+    let mut value: serde_json::Value = serde_json::from_slice(bytes)?;
+    let markers = parse_markers(&value)?;
+    let text_layers = text_overrides::parse_text_layers(&value);
+    let image_layers = images::parse_image_layers(&value);
+    nullify_unsupported_layers(&mut value);
+    expressions::bake_expressions(&mut value);
+    // Keep the JSON of files with slots, to override them per entity
+    let (composition, source) = if slots::has_slots(&value) {
+        let composition = slots::resolve_slots(&value, &HashMap::default());
+        (composition, Some(Arc::new(value)))
+    } else {
+        // Load Lottie JSON with the Velato (bodymovin) parser
+        (velato::Composition::from_json(value), None)
+    };
+    let composition = composition.map_err(VectorLoaderError::Velato)?;
 
     let width = composition.width as f32;
     let height = composition.height as f32;
@@ -22,6 +114,14 @@ pub fn load_lottie_from_bytes(bytes: &[u8]) -> Result<VelloAsset, VectorLoaderEr
         width,
         height,
         alpha: 1.0,
+        #[cfg(feature = "svg")]
+        tree: None,
+        #[cfg(feature = "svg")]
+        svg_source: None,
+        markers,
+        source,
+        text_layers,
+        image_layers,
     };
 
     Ok(vello_vector)
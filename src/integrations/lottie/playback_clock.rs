@@ -0,0 +1,16 @@
+use bevy::prelude::*;
+
+/// The clock used to advance every lottie playhead.
+///
+/// Playback only advances with the deltas of this clock, so it can be paused,
+/// slowed or stepped with Bevy's [`Time`].
+#[derive(Resource, PartialEq, Eq, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Resource)]
+pub enum PlaybackClock {
+    /// Advance once per frame, in `PostUpdate`, with [`Time<Virtual>`].
+    #[default]
+    Virtual,
+    /// Advance in `FixedPostUpdate` with [`Time<Fixed>`], so playback is
+    /// deterministic, e.g. for rollback or frame-stepped replays.
+    Fixed,
+}
@@ -1,15 +1,21 @@
 //! Playback options for lottie files.
 
+use crate::{VectorFile, VelloAsset};
 use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
 use std::ops::Range;
 use std::time::Duration;
+use vello_svg::usvg::strict_num::Ulps;
 
 /// Playback options which adjust the playback of an asset.
 ///
 /// You can add this component directly to a `VelloAssetBundle` entity to adjust
 /// playback options.
-#[derive(PartialEq, Component, Clone, Debug, Reflect)]
+#[derive(PartialEq, Component, Clone, Debug, Reflect, Deserialize)]
 #[reflect(Component)]
+#[serde(default)]
 pub struct PlaybackOptions {
     /// Whether to automatically start the animation.
     pub autoplay: bool,
@@ -25,9 +31,17 @@ pub struct PlaybackOptions {
     pub play_mode: PlaybackPlayMode,
     /// Whether to loop, and how many.
     pub looping: PlaybackLoopBehavior,
-    /// The segments (frames) of the animation to play. Values out of range
-    /// will be ignored.
-    pub segments: Range<f64>,
+    /// The segments (frames) of the animation to play.
+    pub segments: PlaybackSegments,
+    /// What moves the playhead.
+    pub driver: PlaybackDriver,
+    /// Whether to pause playback while the entity can't be seen by any camera.
+    pub pause_when_offscreen: bool,
+    /// The maximum rate to render new frames at, e.g. `Some(12.0)` for distant
+    /// or background animations. The playhead still advances every frame, but
+    /// the rendered frame is quantized, so it's only encoded again at this
+    /// rate.
+    pub max_fps: Option<f64>,
 }
 
 impl Default for PlaybackOptions {
@@ -39,13 +53,143 @@ impl Default for PlaybackOptions {
             intermission: Duration::ZERO,
             play_mode: Default::default(),
             looping: Default::default(),
-            segments: f64::MIN..f64::MAX,
+            segments: Default::default(),
+            driver: Default::default(),
+            pause_when_offscreen: false,
+            max_fps: None,
         }
     }
 }
 
-/// The direction to play the segments of a lottie animation.
+impl PlaybackOptions {
+    /// Returns the frame for the playhead when driven by progress, between the
+    /// first and last frame to play, in the direction of playback.
+    pub(crate) fn progress_frame(&self, start_frame: f64, end_frame: f64) -> Option<f64> {
+        let PlaybackDriver::Progress(progress) = self.driver else {
+            return None;
+        };
+        let progress = (progress as f64).clamp(0.0, 1.0);
+        let progress = match self.direction {
+            PlaybackDirection::Normal => progress,
+            PlaybackDirection::Reverse => 1.0 - progress,
+        };
+        Some(start_frame + (end_frame - start_frame) * progress)
+    }
+
+    /// Returns the frame to render for the playhead, quantized to the maximum
+    /// rate to render frames at.
+    pub(crate) fn rendered_frame(&self, frame: f64, frame_rate: f64) -> f64 {
+        match self.max_fps {
+            Some(max_fps) if max_fps > 0.0 && max_fps < frame_rate => {
+                let step = frame_rate / max_fps;
+                (frame / step).floor() * step
+            }
+            _ => frame,
+        }
+    }
+}
+
+/// What moves the playhead of a lottie animation.
+#[derive(PartialEq, Component, Default, Clone, Copy, Debug, Reflect, Deserialize)]
+pub enum PlaybackDriver {
+    /// Advance the playhead with time.
+    #[default]
+    Time,
+    /// Place the playhead at a progress through the segments, from `0.0` to
+    /// `1.0`, e.g. bound to a scroll position or UI slider. Time, speed,
+    /// looping and intermission are ignored.
+    Progress(f32),
+}
+
+/// The segments (frames) of a lottie animation to play.
+#[derive(PartialEq, Clone, Debug, Reflect, Deserialize)]
+pub enum PlaybackSegments {
+    /// A range of frames. Values out of range will be ignored.
+    Frames(Range<f64>),
+    /// The frames of a named marker in the lottie file. If the marker doesn't
+    /// exist, the whole animation is played.
+    Marker(String),
+}
+
+impl Default for PlaybackSegments {
+    fn default() -> Self {
+        Self::Frames(f64::MIN..f64::MAX)
+    }
+}
+
+impl From<Range<f64>> for PlaybackSegments {
+    fn from(frames: Range<f64>) -> Self {
+        Self::Frames(frames)
+    }
+}
+
+impl PlaybackSegments {
+    /// Returns the range of frames to play for the given asset, bounded by the
+    /// frames of the animation. Returns `None` if the asset isn't a lottie.
+    pub fn frames(&self, asset: &VelloAsset) -> Option<Range<f64>> {
+        #[allow(irrefutable_let_patterns)]
+        let VectorFile::Lottie(composition) = &asset.file
+        else {
+            return None;
+        };
+        let frames = match self {
+            PlaybackSegments::Frames(frames) => frames,
+            PlaybackSegments::Marker(marker) => {
+                asset.markers.get(marker).unwrap_or(&composition.frames)
+            }
+        };
+        let start = frames.start.max(composition.frames.start);
+        let end = frames.end.min(composition.frames.end).max(start);
+        Some(start..end)
+    }
+
+    /// Returns the first and last frame to play for the given asset.
+    pub(crate) fn bounds(&self, asset: &VelloAsset) -> Option<(f64, f64)> {
+        let frames = self.frames(asset)?;
+        Some((frames.start, frames.end.prev().max(frames.start)))
+    }
+}
+
+/// The frame a playhead starts at when it's created, so identical animations
+/// don't play in lockstep. Add this component to a `VelloAssetBundle` entity.
 #[derive(PartialEq, Component, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub enum PlaybackStartFrame {
+    /// Start at the first frame to play, in the direction of playback.
+    #[default]
+    Beginning,
+    /// Start at a given frame, bounded by the segments.
+    Fixed(f64),
+    /// Start at a random frame within the segments.
+    Random,
+}
+
+impl PlaybackStartFrame {
+    /// Returns the starting frame for the entity between the first and last
+    /// frame to play.
+    pub(crate) fn frame(
+        &self,
+        entity: Entity,
+        direction: PlaybackDirection,
+        start_frame: f64,
+        end_frame: f64,
+    ) -> f64 {
+        match self {
+            PlaybackStartFrame::Beginning => match direction {
+                PlaybackDirection::Normal => start_frame,
+                PlaybackDirection::Reverse => end_frame,
+            },
+            PlaybackStartFrame::Fixed(frame) => frame.clamp(start_frame, end_frame),
+            PlaybackStartFrame::Random => {
+                let random = RandomState::new().hash_one(entity) as f64 / u64::MAX as f64;
+                start_frame + (end_frame - start_frame) * random
+            }
+        }
+    }
+}
+
+/// The direction to play the segments of a lottie animation.
+#[derive(PartialEq, Component, Default, Clone, Copy, Debug, Reflect, Deserialize)]
 pub enum PlaybackDirection {
     /// Play in the default direction, first frame to last frame.
     #[default]
@@ -55,7 +199,7 @@ pub enum PlaybackDirection {
 }
 
 /// How often to loop.
-#[derive(PartialEq, Component, Default, Clone, Copy, Debug, Reflect)]
+#[derive(PartialEq, Component, Default, Clone, Copy, Debug, Reflect, Deserialize)]
 pub enum PlaybackLoopBehavior {
     /// Do not loop. This is equivalent to `PlaybackLoopBehavior::Amount(0)`.
     DoNotLoop,
@@ -68,11 +212,12 @@ pub enum PlaybackLoopBehavior {
 
 /// Whether to reset (normal) the playhead every loop or to reverse directions
 /// (bounce).
-#[derive(PartialEq, Component, Default, Clone, Copy, Debug, Reflect)]
+#[derive(PartialEq, Component, Default, Clone, Copy, Debug, Reflect, Deserialize)]
 pub enum PlaybackPlayMode {
     /// Reset the playhead every loop.
     #[default]
     Normal,
-    /// Reverse the direction every loop.
+    /// Reverse the direction every loop, playing back and forth between the
+    /// start and end of the segments (ping-pong).
     Bounce,
 }
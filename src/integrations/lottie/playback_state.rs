@@ -0,0 +1,70 @@
+use super::{PlaybackOptions, Playhead};
+use crate::VelloAsset;
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// The playback of a lottie animation, for HUDs and gameplay to react to.
+///
+/// Added to every entity with a [`Playhead`], and updated every frame.
+#[derive(PartialEq, Component, Default, Clone, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PlaybackState {
+    /// The frame being rendered.
+    pub frame: f64,
+    /// The progress through the segments being played, from `0.0` at the
+    /// first frame to `1.0` at the last frame.
+    pub progress: f64,
+    /// The number of loops completed.
+    pub loops_completed: usize,
+    /// The time spent in the current intermission, if any.
+    pub intermission_elapsed: Option<Duration>,
+}
+
+/// Update the playback state of every playhead.
+pub fn update_playback_states(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &Playhead,
+        &Handle<VelloAsset>,
+        Option<&PlaybackOptions>,
+        Option<&mut PlaybackState>,
+    )>,
+    assets: Res<Assets<VelloAsset>>,
+) {
+    for (entity, playhead, handle, options, state) in query.iter_mut() {
+        let Some(asset) = assets.get(handle.id()) else {
+            continue;
+        };
+        let default_options;
+        let options = match options {
+            Some(options) => options,
+            None => {
+                default_options = PlaybackOptions::default();
+                &default_options
+            }
+        };
+        let Some((start_frame, end_frame)) = options.segments.bounds(asset) else {
+            continue;
+        };
+        let progress = if end_frame > start_frame {
+            ((playhead.frame - start_frame) / (end_frame - start_frame)).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let new_state = PlaybackState {
+            frame: playhead.frame,
+            progress,
+            loops_completed: playhead.loops_completed,
+            intermission_elapsed: playhead.intermission.as_ref().map(Timer::elapsed),
+        };
+        match state {
+            Some(mut state) => {
+                state.set_if_neq(new_state);
+            }
+            None => {
+                commands.entity(entity).insert(new_state);
+            }
+        }
+    }
+}
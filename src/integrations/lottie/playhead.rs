@@ -1,11 +1,19 @@
 use bevy::prelude::*;
-use bevy::utils::Instant;
+use bevy::utils::Duration;
 
-/// The playhead for a vello asset. This cannot be constructed by the user, it is created automatically and available on the first frame.
-#[derive(PartialEq, Component, Clone, Debug)]
+/// The playhead for a vello asset, owned by the entity playing it. Entities
+/// sharing the same `Handle<VelloAsset>` play independently.
+///
+/// A playhead is created automatically and available on the first frame. Insert
+/// one yourself with [`Playhead::new`] to start playback at a given frame.
+#[derive(PartialEq, Component, Clone, Debug, Reflect)]
+#[reflect(Component)]
 pub struct Playhead {
-    /// Used to track transitions relating to time.
-    pub(crate) first_render: Option<Instant>,
+    /// Whether the playhead has been rendered, for `OnShow` transitions.
+    pub(crate) shown: bool,
+    /// The time since the first render, by the playback clock. Used to track
+    /// transitions relating to time.
+    pub(crate) elapsed: Duration,
     /// The actual frame being rendered
     pub(crate) frame: f64,
     /// Used to track intermission.
@@ -15,6 +23,8 @@ pub struct Playhead {
     /// Used by play mode to track current direction. Only set to -1.0
     /// (reverse) or 1.0 (normal).
     pub(crate) playmode_dir: f64,
+    /// Whether the entity can't be seen by any camera.
+    pub(crate) offscreen: bool,
 }
 
 impl Playhead {
@@ -28,13 +38,16 @@ impl Playhead {
         self.frame = frame;
     }
 
-    pub(crate) fn new(frame: f64) -> Self {
+    /// Create a playhead starting at the given frame
+    pub fn new(frame: f64) -> Self {
         Self {
             frame,
-            first_render: None,
+            shown: false,
+            elapsed: Duration::ZERO,
             intermission: None,
             loops_completed: 0,
             playmode_dir: 1.0,
+            offscreen: false,
         }
     }
 }
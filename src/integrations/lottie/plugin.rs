@@ -1,18 +1,101 @@
-use super::{asset_loader::VelloLottieLoader, systems};
+use super::dot_lottie_archive::VelloDotLottieLoader;
+use super::{animation_group, asset_loader::VelloLottieLoader, despawn_on_complete};
+use super::{images, playback_state, slots, sockets, systems, theme};
+use super::{
+    AnimationCompleted, AnimationGroup, AnimationLoopCompleted, DotLottieArchive,
+    LottieLayerVisibility, LottieSlotValue, LottieSlots, LottieSocket, LottieTextOverrides,
+    PlaybackClock, PlaybackDirection, PlaybackDriver, PlaybackLoopBehavior, PlaybackOptions,
+    PlaybackPlayMode, PlaybackSegments, PlaybackStartFrame, PlaybackState, Playhead, Theme,
+    ThemeLoader, ThemePaint, ThemeTarget, VelloAnimationTime, VelloDespawnOnComplete,
+};
+use bevy::ecs::schedule::common_conditions::resource_equals;
 use bevy::prelude::*;
+use bevy::render::view::VisibilitySystems;
+use bevy::transform::TransformSystem;
 
 pub struct LottieIntegrationPlugin;
 
 impl Plugin for LottieIntegrationPlugin {
     fn build(&self, app: &mut App) {
         app.init_asset_loader::<VelloLottieLoader>()
+            .init_asset::<DotLottieArchive>()
+            .init_asset_loader::<VelloDotLottieLoader>()
+            .init_asset::<Theme>()
+            .init_asset_loader::<ThemeLoader>()
+            .add_event::<AnimationLoopCompleted>()
+            .add_event::<AnimationCompleted>()
+            .init_resource::<PlaybackClock>()
+            .init_resource::<VelloAnimationTime>()
+            .register_asset_reflect::<Theme>()
+            .register_type::<ThemeTarget>()
+            .register_type::<ThemePaint>()
+            .register_type::<PlaybackOptions>()
+            .register_type::<PlaybackDriver>()
+            .register_type::<PlaybackSegments>()
+            .register_type::<PlaybackStartFrame>()
+            .register_type::<PlaybackDirection>()
+            .register_type::<PlaybackLoopBehavior>()
+            .register_type::<PlaybackPlayMode>()
+            .register_type::<PlaybackState>()
+            .register_type::<PlaybackClock>()
+            .register_type::<Playhead>()
+            .register_type::<AnimationGroup>()
+            .register_type::<VelloAnimationTime>()
+            .register_type::<VelloDespawnOnComplete>()
+            .register_type::<LottieSlots>()
+            .register_type::<LottieSlotValue>()
+            .register_type::<LottieTextOverrides>()
+            .register_type::<LottieSocket>()
+            .register_type::<LottieLayerVisibility>()
+            .add_systems(
+                PostUpdate,
+                (
+                    images::resolve_lottie_images,
+                    slots::compose_slotted_lotties,
+                    theme::apply_theme_assets,
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                // The visibility of the last frame, so the playheads are
+                // advanced before the transforms of sockets are propagated
+                systems::mark_offscreen_playheads
+                    .before(VisibilitySystems::VisibilityPropagate)
+                    .before(TransformSystem::TransformPropagate),
+            )
             .add_systems(
                 PostUpdate,
                 (
                     systems::advance_playheads_without_options,
                     systems::advance_playheads_with_options,
-                ),
+                )
+                    .after(systems::mark_offscreen_playheads)
+                    .run_if(resource_equals(PlaybackClock::Virtual)),
+            )
+            .add_systems(
+                PostUpdate,
+                sockets::follow_lottie_sockets
+                    .after(systems::advance_playheads_without_options)
+                    .after(systems::advance_playheads_with_options)
+                    .before(TransformSystem::TransformPropagate),
+            )
+            .add_systems(
+                FixedPostUpdate,
+                (
+                    systems::advance_playheads_without_options,
+                    systems::advance_playheads_with_options,
+                )
+                    .run_if(resource_equals(PlaybackClock::Fixed)),
             )
-            .add_systems(Last, systems::spawn_playheads);
+            .add_systems(
+                Last,
+                (
+                    systems::spawn_playheads,
+                    animation_group::sync_animation_groups.after(systems::spawn_playheads),
+                    playback_state::update_playback_states
+                        .after(animation_group::sync_animation_groups),
+                    despawn_on_complete::despawn_on_complete,
+                ),
+            );
     }
 }
@@ -0,0 +1,444 @@
+//! Render a lottie composition into a vello scene, reusing the encoded
+//! fragments of precompositions whose content didn't change.
+//!
+//! This follows `velato::Renderer`, which walks the layers of every
+//! precomposition each time it is rendered, and also follows the time
+//! remapping of precompositions.
+
+use super::layers::precomp_frame;
+use crate::render::LottiePrecompCache;
+use std::ops::Range;
+use velato::model::{
+    fixed, Content, Draw, Geometry, GroupTransform, Layer, Shape, Stroke, Transform,
+};
+use velato::Composition;
+use vello::kurbo::{Affine, PathEl, Rect};
+use vello::peniko::{self, Fill, Mix};
+use vello::Scene;
+
+/// The content of a precomposition at a frame, keying its fragments.
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+pub(crate) enum PrecompFrame {
+    /// The layers visible at the frame, for precompositions which aren't
+    /// animated besides the frames their layers are visible in.
+    Static(Vec<bool>),
+    /// The frame, for animated precompositions.
+    Animated(u64),
+}
+
+/// Whether the layers of a precomposition draw the same at every frame they
+/// are visible in.
+pub(crate) fn is_static(layers: &[Layer]) -> bool {
+    layers.iter().all(|layer| {
+        layer.transform.is_fixed()
+            && layer.opacity.is_fixed()
+            && layer
+                .masks
+                .iter()
+                .all(|mask| mask.opacity.is_fixed() && is_geometry_static(&mask.geometry))
+            && match &layer.content {
+                Content::None => true,
+                // The frames of nested precompositions aren't followed
+                Content::Instance { .. } => false,
+                Content::Shape(shapes) => shapes.iter().all(is_shape_static),
+            }
+    })
+}
+
+fn is_shape_static(shape: &Shape) -> bool {
+    match shape {
+        Shape::Group(shapes, transform) => {
+            transform
+                .as_ref()
+                .map_or(true, |GroupTransform { transform, opacity }| {
+                    transform.is_fixed() && opacity.is_fixed()
+                })
+                && shapes.iter().all(is_shape_static)
+        }
+        Shape::Geometry(geometry) => is_geometry_static(geometry),
+        Shape::Draw(Draw {
+            stroke,
+            brush,
+            opacity,
+        }) => {
+            stroke.as_ref().map_or(true, Stroke::is_fixed) && brush.is_fixed() && opacity.is_fixed()
+        }
+        Shape::Repeater(repeater) => repeater.is_fixed(),
+    }
+}
+
+fn is_geometry_static(geometry: &Geometry) -> bool {
+    match geometry {
+        Geometry::Fixed(_) => true,
+        Geometry::Rect(rect) => rect.is_fixed(),
+        Geometry::Ellipse(ellipse) => ellipse.is_fixed(),
+        Geometry::Spline(_) => false,
+    }
+}
+
+/// Renders compositions like `velato::Renderer`, with the fragments of their
+/// precompositions cached.
+pub(crate) struct PrecompRenderer<'a> {
+    precomps: &'a LottiePrecompCache,
+    batch: Batch,
+    mask_elements: Vec<PathEl>,
+}
+
+impl<'a> PrecompRenderer<'a> {
+    pub fn new(precomps: &'a LottiePrecompCache) -> Self {
+        Self {
+            precomps,
+            batch: Batch::default(),
+            mask_elements: Vec::new(),
+        }
+    }
+
+    /// Render the composition at a frame into a scene.
+    pub fn render(
+        &mut self,
+        animation: &Composition,
+        frame: f64,
+        transform: Affine,
+        alpha: f64,
+        scene: &mut Scene,
+    ) {
+        self.batch.clear();
+        scene.push_layer(
+            Mix::Clip,
+            1.0,
+            transform,
+            &Rect::new(0.0, 0.0, animation.width as _, animation.height as _),
+        );
+        for layer in animation.layers.iter().rev() {
+            if layer.is_mask {
+                continue;
+            }
+            self.render_layer(
+                animation,
+                &animation.layers,
+                layer,
+                transform,
+                alpha,
+                frame,
+                scene,
+            );
+        }
+        scene.pop_layer();
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_layer(
+        &mut self,
+        animation: &Composition,
+        layer_set: &[Layer],
+        layer: &Layer,
+        transform: Affine,
+        alpha: f64,
+        frame: f64,
+        scene: &mut Scene,
+    ) {
+        if !layer.frames.contains(&frame) {
+            return;
+        }
+        let parent_transform = transform;
+        let transform = compute_transform(layer_set, layer, parent_transform, frame);
+        let full_rect = Rect::new(0.0, 0.0, animation.width as f64, animation.height as f64);
+        if let Some((mode, mask_index)) = layer.mask_layer {
+            // An extra layer isolates the blending of the mask
+            scene.push_layer(Mix::Normal, 1.0, parent_transform, &full_rect);
+            if let Some(mask) = layer_set.get(mask_index) {
+                self.render_layer(
+                    animation,
+                    layer_set,
+                    mask,
+                    parent_transform,
+                    alpha,
+                    frame,
+                    scene,
+                );
+            }
+            scene.push_layer(mode, 1.0, parent_transform, &full_rect);
+        }
+        let alpha = alpha * layer.opacity.evaluate(frame) / 100.0;
+        for mask in &layer.masks {
+            let alpha = mask.opacity.evaluate(frame) / 100.0;
+            mask.geometry.evaluate(frame, &mut self.mask_elements);
+            scene.push_layer(
+                Mix::Clip,
+                alpha as f32,
+                transform,
+                &self.mask_elements.as_slice(),
+            );
+            self.mask_elements.clear();
+        }
+        match &layer.content {
+            Content::None => {}
+            Content::Instance { name, .. } => {
+                if let Some(asset_layers) = animation.assets.get(name) {
+                    let frame = precomp_frame(animation, layer, frame);
+                    self.render_precomp(
+                        animation,
+                        name,
+                        asset_layers,
+                        transform,
+                        alpha,
+                        frame,
+                        scene,
+                    );
+                }
+            }
+            Content::Shape(shapes) => {
+                self.render_shapes(shapes, transform, alpha, frame);
+                self.batch.render(scene);
+                self.batch.clear();
+            }
+        }
+        for _ in 0..layer.masks.len() + (layer.mask_layer.is_some() as usize * 2) {
+            scene.pop_layer();
+        }
+    }
+
+    /// Append the fragment of a precomposition at its local frame, encoded
+    /// in its local space unless it was cached.
+    #[allow(clippy::too_many_arguments)]
+    fn render_precomp(
+        &mut self,
+        animation: &Composition,
+        name: &str,
+        layers: &[Layer],
+        transform: Affine,
+        alpha: f64,
+        frame: f64,
+        scene: &mut Scene,
+    ) {
+        let precomp_frame = match self.precomps.is_static(name, layers) {
+            true => PrecompFrame::Static(
+                layers
+                    .iter()
+                    .map(|layer| layer.frames.contains(&frame))
+                    .collect(),
+            ),
+            false => PrecompFrame::Animated(frame.to_bits()),
+        };
+        let fragment = self
+            .precomps
+            .get_or_encode(name, precomp_frame, alpha, |fragment| {
+                // Shapes are batched per layer, so the batch is empty here
+                for layer in layers.iter().rev() {
+                    if layer.is_mask {
+                        continue;
+                    }
+                    self.render_layer(
+                        animation,
+                        layers,
+                        layer,
+                        Affine::IDENTITY,
+                        alpha,
+                        frame,
+                        fragment,
+                    );
+                }
+            });
+        scene.append(&fragment, Some(transform));
+    }
+
+    fn render_shapes(&mut self, shapes: &[Shape], transform: Affine, alpha: f64, frame: f64) {
+        // Later draws are bounded by the geometries from here
+        let geometry_start = self.batch.geometries.len();
+        // And repeaters by the draws from here
+        let draw_start = self.batch.draws.len();
+        for shape in shapes {
+            match shape {
+                Shape::Group(shapes, group_transform) => {
+                    let (group_transform, group_alpha) =
+                        if let Some(GroupTransform { transform, opacity }) = group_transform {
+                            (
+                                transform.evaluate(frame).into_owned(),
+                                opacity.evaluate(frame) / 100.0,
+                            )
+                        } else {
+                            (Affine::IDENTITY, 1.0)
+                        };
+                    self.render_shapes(
+                        shapes,
+                        transform * group_transform,
+                        alpha * group_alpha,
+                        frame,
+                    );
+                }
+                Shape::Geometry(geometry) => {
+                    self.batch.push_geometry(geometry, transform, frame);
+                }
+                Shape::Draw(draw) => {
+                    self.batch.push_draw(draw, alpha, geometry_start, frame);
+                }
+                Shape::Repeater(repeater) => {
+                    let repeater = repeater.evaluate(frame);
+                    self.batch
+                        .repeat(repeater.as_ref(), geometry_start, draw_start);
+                }
+            }
+        }
+    }
+}
+
+/// The transform of a layer, followed through its parents.
+fn compute_transform(
+    layer_set: &[Layer],
+    layer: &Layer,
+    global_transform: Affine,
+    frame: f64,
+) -> Affine {
+    let evaluate = |transform: &Transform| transform.evaluate(frame).into_owned();
+    let mut transform = evaluate(&layer.transform);
+    let mut parent_index = layer.parent;
+    // Cycles aren't checked when importing, so stop after every layer
+    for _ in 0..layer_set.len() {
+        let Some(parent) = parent_index.and_then(|index| layer_set.get(index)) else {
+            break;
+        };
+        parent_index = parent.parent;
+        transform = evaluate(&parent.transform) * transform;
+    }
+    global_transform * transform
+}
+
+/// A brush with its alpha multiplied.
+fn brush_with_alpha(brush: &fixed::Brush, alpha: f64) -> fixed::Brush {
+    match brush {
+        _ if alpha == 1.0 => brush.clone(),
+        peniko::Brush::Solid(color) => color.with_alpha_factor(alpha as f32).into(),
+        peniko::Brush::Gradient(gradient) => peniko::Brush::Gradient(peniko::Gradient {
+            kind: gradient.kind,
+            extend: gradient.extend,
+            stops: gradient
+                .stops
+                .iter()
+                .map(|stop| stop.with_alpha_factor(alpha as f32))
+                .collect(),
+        }),
+        peniko::Brush::Image(_) => brush.clone(),
+    }
+}
+
+#[derive(Clone, Debug)]
+struct DrawData {
+    stroke: Option<fixed::Stroke>,
+    brush: fixed::Brush,
+    alpha: f64,
+    /// The range of the geometries drawn.
+    geometry: Range<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct GeometryData {
+    /// The range of the path elements of the geometry.
+    elements: Range<usize>,
+    transform: Affine,
+}
+
+/// The geometries and draws of a shape layer.
+#[derive(Default)]
+struct Batch {
+    elements: Vec<PathEl>,
+    geometries: Vec<GeometryData>,
+    draws: Vec<DrawData>,
+    repeat_geometries: Vec<GeometryData>,
+    repeat_draws: Vec<DrawData>,
+    /// The geometries at the last draw, which aren't merged into.
+    drawn_geometry: usize,
+}
+
+impl Batch {
+    fn push_geometry(&mut self, geometry: &Geometry, transform: Affine, frame: f64) {
+        // Merge with the last geometry if it isn't drawn yet and has the
+        // same transform
+        if self.drawn_geometry < self.geometries.len()
+            && self.geometries.last().map(|last| last.transform) == Some(transform)
+        {
+            geometry.evaluate(frame, &mut self.elements);
+            self.geometries.last_mut().unwrap().elements.end = self.elements.len();
+        } else {
+            let start = self.elements.len();
+            geometry.evaluate(frame, &mut self.elements);
+            let end = self.elements.len();
+            self.geometries.push(GeometryData {
+                elements: start..end,
+                transform,
+            });
+        }
+    }
+
+    fn push_draw(&mut self, draw: &Draw, alpha: f64, geometry_start: usize, frame: f64) {
+        self.draws.push(DrawData {
+            stroke: draw
+                .stroke
+                .as_ref()
+                .map(|stroke| stroke.evaluate(frame).into_owned()),
+            brush: draw.brush.evaluate(1.0, frame).into_owned(),
+            alpha: alpha * draw.opacity.evaluate(frame) / 100.0,
+            geometry: geometry_start..self.geometries.len(),
+        });
+        self.drawn_geometry = self.geometries.len();
+    }
+
+    fn repeat(&mut self, repeater: &fixed::Repeater, geometry_start: usize, draw_start: usize) {
+        self.repeat_geometries
+            .extend(self.geometries.drain(geometry_start..));
+        self.repeat_draws.extend(self.draws.drain(draw_start..));
+        for geometry in self.repeat_geometries.iter() {
+            for i in 0..repeater.copies {
+                let mut geometry = geometry.clone();
+                geometry.transform *= repeater.transform(i);
+                self.geometries.push(geometry);
+            }
+        }
+        let start_alpha = repeater.start_opacity / 100.0;
+        let end_alpha = repeater.end_opacity / 100.0;
+        // After Effects doesn't cover the full range of opacity, like Skottie
+        let delta_alpha = match repeater.copies > 1 {
+            true => (end_alpha - start_alpha) / repeater.copies as f64,
+            false => 0.0,
+        };
+        for i in 0..repeater.copies {
+            let alpha = start_alpha + delta_alpha * i as f64;
+            if alpha <= 0.0 {
+                continue;
+            }
+            for mut draw in self.repeat_draws.iter().cloned() {
+                draw.alpha *= alpha;
+                let count = draw.geometry.end - draw.geometry.start;
+                draw.geometry.start =
+                    geometry_start + (draw.geometry.start - geometry_start) * repeater.copies;
+                draw.geometry.end = draw.geometry.start + count * repeater.copies;
+                self.draws.push(draw);
+            }
+        }
+        self.repeat_geometries.clear();
+        self.repeat_draws.clear();
+        // Don't merge until new geometries are pushed
+        self.drawn_geometry = self.geometries.len();
+    }
+
+    fn render(&self, scene: &mut Scene) {
+        for draw in self.draws.iter().rev() {
+            let brush = brush_with_alpha(&draw.brush, draw.alpha);
+            for geometry in self.geometries[draw.geometry.clone()].iter() {
+                let path = &self.elements[geometry.elements.clone()];
+                match &draw.stroke {
+                    Some(stroke) => scene.stroke(stroke, geometry.transform, &brush, None, &path),
+                    None => scene.fill(Fill::NonZero, geometry.transform, &brush, None, &path),
+                }
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.elements.clear();
+        self.geometries.clear();
+        self.draws.clear();
+        self.repeat_geometries.clear();
+        self.repeat_draws.clear();
+        self.drawn_geometry = 0;
+    }
+}
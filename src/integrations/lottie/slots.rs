@@ -0,0 +1,213 @@
+//! Runtime overrides for the slots (dynamic properties) of a lottie file.
+//!
+//! Properties of a lottie file with a slot id (`sid`) take their value from
+//! the `slots` of the file. Velato doesn't resolve slots, so they are resolved
+//! on the JSON of the file before it is parsed.
+
+use crate::VelloAsset;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use velato::Composition;
+
+/// The value of a lottie slot.
+#[derive(PartialEq, Clone, Copy, Debug, Reflect)]
+pub enum LottieSlotValue {
+    /// A color property, such as the color of a fill or stroke.
+    Color(Color),
+    /// A scalar property, such as an opacity, rotation or stroke width.
+    Scalar(f64),
+    /// A vector property, such as a position, scale or size.
+    Vector(Vec2),
+}
+
+impl LottieSlotValue {
+    /// The static lottie property of this value.
+    fn to_property(self) -> Value {
+        let value = match self {
+            LottieSlotValue::Color(color) => json!(color.as_rgba_f32()),
+            LottieSlotValue::Scalar(scalar) => json!(scalar),
+            LottieSlotValue::Vector(vector) => json!([vector.x, vector.y]),
+        };
+        json!({ "a": 0, "k": value })
+    }
+}
+
+#[derive(PartialEq, Component, Default, Clone, Debug, Reflect)]
+#[reflect(Component)]
+/// Add this component to a `VelloAssetBundle` entity to override the slots of
+/// a lottie file, by slot id. The asset is shared, so only this entity is
+/// affected.
+///
+/// Only color, scalar and vector slots are supported. Text and image slots
+/// are ignored, as velato doesn't support those layers.
+pub struct LottieSlots {
+    pub(crate) values: HashMap<String, LottieSlotValue>,
+}
+
+impl LottieSlots {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Override the slot with the given id.
+    pub fn add(mut self, sid: &str, value: LottieSlotValue) -> Self {
+        self.values.insert(sid.to_string(), value);
+        self
+    }
+
+    /// Override the slot with the given id. This will overwrite the previous
+    /// value.
+    pub fn edit(&mut self, sid: &str, value: LottieSlotValue) -> &mut Self {
+        self.values.insert(sid.to_string(), value);
+        self
+    }
+
+    /// Restore the value of the slot with the given id from the file.
+    pub fn remove(&mut self, sid: &str) -> Option<LottieSlotValue> {
+        self.values.remove(sid)
+    }
+
+    pub fn get(&self, sid: &str) -> Option<&LottieSlotValue> {
+        self.values.get(sid)
+    }
+
+    pub fn get_mut(&mut self, sid: &str) -> Option<&mut LottieSlotValue> {
+        self.values.get_mut(sid)
+    }
+}
+
+/// The composition of an entity with [`LottieSlots`], patched from its asset.
+#[derive(Component, Clone)]
+pub(crate) struct SlottedComposition {
+    pub asset: AssetId<VelloAsset>,
+    pub composition: Arc<Composition>,
+}
+
+/// Whether the JSON of a lottie file has slots.
+pub(crate) fn has_slots(value: &Value) -> bool {
+    match value {
+        Value::Object(object) => {
+            object.contains_key("sid")
+                || object.contains_key("slots")
+                || object.values().any(has_slots)
+        }
+        Value::Array(array) => array.iter().any(has_slots),
+        _ => false,
+    }
+}
+
+/// Resolve the slots of the JSON of a lottie file, with overrides.
+pub(crate) fn resolve_slots(
+    source: &Value,
+    overrides: &HashMap<String, LottieSlotValue>,
+) -> Result<Composition, velato::VelatoError> {
+    let mut value = source.clone();
+    let mut slots: HashMap<String, Value> = value
+        .get("slots")
+        .and_then(Value::as_object)
+        .map(|slots| {
+            slots
+                .iter()
+                .filter_map(|(sid, slot)| Some((sid.clone(), slot.get("p")?.clone())))
+                .collect()
+        })
+        .unwrap_or_default();
+    for (sid, slot) in overrides {
+        slots.insert(sid.clone(), slot.to_property());
+    }
+    if let Some(object) = value.as_object_mut() {
+        object.remove("slots");
+    }
+    replace_slotted_properties(&mut value, &slots);
+    Composition::from_json(value)
+}
+
+/// Replace every property with a slot id by the value of the slot.
+fn replace_slotted_properties(value: &mut Value, slots: &HashMap<String, Value>) {
+    match value {
+        Value::Object(object) => {
+            if let Some(slot) = slotted_property(object, slots) {
+                *value = slot;
+                return;
+            }
+            for child in object.values_mut() {
+                replace_slotted_properties(child, slots);
+            }
+        }
+        Value::Array(array) => {
+            for child in array.iter_mut() {
+                replace_slotted_properties(child, slots);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn slotted_property(object: &Map<String, Value>, slots: &HashMap<String, Value>) -> Option<Value> {
+    let sid = object.get("sid")?.as_str()?;
+    slots.get(sid).cloned()
+}
+
+/// Patch the composition of every entity with slots, when the slots or the
+/// asset change.
+pub fn compose_slotted_lotties(
+    mut commands: Commands,
+    query: Query<(
+        Entity,
+        &Handle<VelloAsset>,
+        Ref<LottieSlots>,
+        Option<&SlottedComposition>,
+    )>,
+    mut removed: RemovedComponents<LottieSlots>,
+    mut asset_events: EventReader<AssetEvent<VelloAsset>>,
+    assets: Res<Assets<VelloAsset>>,
+) {
+    for entity in removed.read() {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.remove::<SlottedComposition>();
+        }
+    }
+    let modified: Vec<AssetId<VelloAsset>> = asset_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    for (entity, handle, slots, composed) in query.iter() {
+        let outdated = match composed {
+            Some(composed) => {
+                slots.is_changed()
+                    || composed.asset != handle.id()
+                    || modified.contains(&composed.asset)
+            }
+            None => true,
+        };
+        if !outdated {
+            continue;
+        }
+        let Some(VelloAsset {
+            file: crate::VectorFile::Lottie(original),
+            source: Some(source),
+            ..
+        }) = assets.get(handle.id())
+        else {
+            continue;
+        };
+        let composition = match resolve_slots(source, &slots.values) {
+            Ok(composition) => Arc::new(composition),
+            Err(err) => {
+                // Fall back to the asset, rather than retrying every frame
+                error!("could not resolve lottie slots: {err}");
+                original.clone()
+            }
+        };
+        commands.entity(entity).insert(SlottedComposition {
+            asset: handle.id(),
+            composition,
+        });
+    }
+}
@@ -0,0 +1,143 @@
+//! Entities which follow the layers of lottie animations, e.g. a sword in
+//! the hand of a character, or a collider on its weapon.
+
+use super::layers::{layer_transform, precomp_frame};
+use super::Playhead;
+use crate::{VectorFile, VelloAsset, VelloAssetAlignment};
+use bevy::prelude::*;
+use velato::model::{Content, Layer};
+use velato::Composition;
+use vello::kurbo::Affine;
+
+/// The depth of nested precompositions searched for a layer, in case of
+/// cyclic precompositions.
+const MAX_PRECOMP_DEPTH: usize = 8;
+
+/// Add this component to a child of a lottie entity to move it with a layer
+/// of the animation, like a socket on a bone. The transform of the child is
+/// replaced by the transform of the layer at the playhead of its parent,
+/// keeping its Z.
+///
+/// The layer is usually a null layer, placed where the child should be.
+/// Layers within precompositions are found too. Sizing, such as
+/// [`VelloSize`](crate::VelloSize), isn't followed, so sized entities should
+/// scale their children with their transform instead.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_vello::prelude::*;
+/// # fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// commands
+///     .spawn(VelloAssetBundle {
+///         vector: asset_server.load("knight.json"),
+///         ..default()
+///     })
+///     .with_children(|knight| {
+///         knight
+///             .spawn(VelloAssetBundle {
+///                 vector: asset_server.load("sword.svg"),
+///                 ..default()
+///             })
+///             .insert(LottieSocket::new("right hand"));
+///     });
+/// # }
+/// ```
+#[derive(PartialEq, Eq, Component, Default, Clone, Debug, Reflect)]
+#[reflect(Component)]
+pub struct LottieSocket {
+    /// The name of the layer to follow.
+    pub layer: String,
+}
+
+impl LottieSocket {
+    pub fn new(layer: impl Into<String>) -> Self {
+        Self {
+            layer: layer.into(),
+        }
+    }
+}
+
+impl VelloAsset {
+    /// The transform of a named layer of a lottie animation at a frame,
+    /// including its parents and the precompositions it is in, in the
+    /// coordinates of the asset with Y down. `None` if the asset isn't a
+    /// lottie animation or has no such layer.
+    pub fn lottie_layer_transform(&self, layer: &str, frame: f64) -> Option<Affine> {
+        match &self.file {
+            VectorFile::Lottie(composition) => find_layer_transform(
+                composition,
+                &composition.layers,
+                layer,
+                frame,
+                MAX_PRECOMP_DEPTH,
+            ),
+            #[cfg(feature = "svg")]
+            VectorFile::Svg(_) => None,
+        }
+    }
+}
+
+/// The transform of a named layer, searched in the layers and then in the
+/// precompositions they instance.
+fn find_layer_transform(
+    composition: &Composition,
+    layers: &[Layer],
+    name: &str,
+    frame: f64,
+    depth: usize,
+) -> Option<Affine> {
+    if let Some(layer) = layers.iter().find(|layer| layer.name == name) {
+        return Some(layer_transform(layers, layer, frame));
+    }
+    if depth == 0 {
+        return None;
+    }
+    layers.iter().find_map(|layer| {
+        let Content::Instance { name: asset, .. } = &layer.content else {
+            return None;
+        };
+        let precomp = composition.assets.get(asset)?;
+        let local_frame = precomp_frame(composition, layer, frame);
+        let transform = find_layer_transform(composition, precomp, name, local_frame, depth - 1)?;
+        Some(layer_transform(layers, layer, frame) * transform)
+    })
+}
+
+/// Move [`LottieSocket`]s to their layers, at the playheads of their parents.
+pub(crate) fn follow_lottie_sockets(
+    mut sockets: Query<(&LottieSocket, &Parent, &mut Transform)>,
+    parents: Query<(&Handle<VelloAsset>, &Playhead, Option<&VelloAssetAlignment>)>,
+    assets: Res<Assets<VelloAsset>>,
+) {
+    for (socket, parent, mut transform) in &mut sockets {
+        let Ok((handle, playhead, alignment)) = parents.get(parent.get()) else {
+            continue;
+        };
+        let Some(asset) = assets.get(handle) else {
+            continue;
+        };
+        let Some(layer) = asset.lottie_layer_transform(&socket.layer, playhead.frame()) else {
+            continue;
+        };
+        // From the asset with Y down to the entity with Y up, by alignment
+        let size = Vec2::new(asset.width, asset.height);
+        let offset = alignment
+            .copied()
+            .unwrap_or_default()
+            .offset(size)
+            .truncate();
+        let flip = Affine::FLIP_Y;
+        let to_local = Affine::translate((
+            (offset.x - size.x / 2.0) as f64,
+            (offset.y + size.y / 2.0) as f64,
+        )) * flip;
+        let [a, b, c, d, e, f] = (to_local * layer * flip).as_coeffs().map(|x| x as f32);
+        let matrix = Mat4::from_cols(
+            Vec4::new(a, b, 0.0, 0.0),
+            Vec4::new(c, d, 0.0, 0.0),
+            Vec4::Z,
+            Vec4::new(e, f, transform.translation.z, 1.0),
+        );
+        transform.set_if_neq(Transform::from_matrix(matrix));
+    }
+}
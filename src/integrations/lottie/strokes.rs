@@ -0,0 +1,50 @@
+use velato::model::{Content, Shape, Stroke, Value};
+use velato::Composition;
+
+/// A copy of a composition with the widths of its strokes scaled, for
+/// [`VelloNonScalingStroke`](crate::VelloNonScalingStroke).
+pub(crate) fn scale_strokes(composition: &Composition, scale: f32) -> Composition {
+    let mut composition = composition.clone();
+    let scale = scale as f64;
+    let layers = composition
+        .layers
+        .iter_mut()
+        .chain(composition.assets.values_mut().flatten());
+    for layer in layers {
+        if let Content::Shape(shapes) = &mut layer.content {
+            for shape in shapes.iter_mut() {
+                scale_shape_strokes(shape, scale);
+            }
+        }
+    }
+    composition
+}
+
+fn scale_shape_strokes(shape: &mut Shape, scale: f64) {
+    match shape {
+        Shape::Group(shapes, _) => {
+            for shape in shapes.iter_mut() {
+                scale_shape_strokes(shape, scale);
+            }
+        }
+        Shape::Draw(draw) => match &mut draw.stroke {
+            Some(Stroke::Fixed(stroke)) => {
+                stroke.width *= scale;
+                stroke.dash_offset *= scale;
+                for length in stroke.dash_pattern.iter_mut() {
+                    *length *= scale;
+                }
+            }
+            Some(Stroke::Animated(stroke)) => match &mut stroke.width {
+                Value::Fixed(width) => *width *= scale,
+                Value::Animated(keyframes) => {
+                    for width in keyframes.values.iter_mut() {
+                        *width *= scale;
+                    }
+                }
+            },
+            None => {}
+        },
+        Shape::Repeater(_) | Shape::Geometry(_) => {}
+    }
+}
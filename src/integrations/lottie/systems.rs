@@ -1,61 +1,132 @@
-use crate::integrations::lottie::PlaybackPlayMode;
+use super::events::PlaybackEventWriters;
+use crate::integrations::lottie::{
+    AnimationCompleted, AnimationGroup, AnimationLoopCompleted, PlaybackPlayMode,
+    PlaybackStartFrame,
+};
 use crate::{
-    PlaybackDirection, PlaybackLoopBehavior, PlaybackOptions, Playhead, VectorFile, VelloAsset,
+    CoordinateSpace, PlaybackDirection, PlaybackLoopBehavior, PlaybackOptions, Playhead,
+    VectorFile, VelloAnimationTime, VelloAsset, VelloAssetAlignment,
 };
 use bevy::prelude::*;
-use bevy::utils::Instant;
 use std::time::Duration;
 use vello_svg::usvg::strict_num::Ulps;
 
 /// Spawn playheads for Lotties. Every Lottie gets exactly 1 playhead.
 pub fn spawn_playheads(
     mut commands: Commands,
-    query: Query<(Entity, &Handle<VelloAsset>, Option<&PlaybackOptions>), Without<Playhead>>,
+    query: Query<
+        (
+            Entity,
+            &Handle<VelloAsset>,
+            Option<&PlaybackOptions>,
+            Option<&PlaybackStartFrame>,
+        ),
+        Without<Playhead>,
+    >,
     assets: Res<Assets<VelloAsset>>,
 ) {
-    for (entity, handle, options) in query.iter() {
+    for (entity, handle, options, start) in query.iter() {
         if let Some(
-            _asset @ VelloAsset {
+            asset @ VelloAsset {
                 file: _file @ VectorFile::Lottie(composition),
                 ..
             },
         ) = assets.get(handle)
         {
-            let frame = match options {
-                Some(options) => match options.direction {
-                    PlaybackDirection::Normal => {
-                        options.segments.start.max(composition.frames.start)
-                    }
-                    PlaybackDirection::Reverse => {
-                        options.segments.end.min(composition.frames.end).prev()
-                    }
-                },
-                None => composition.frames.start,
-            };
+            let (direction, (start_frame, end_frame)) = options
+                .and_then(|options| {
+                    options
+                        .segments
+                        .bounds(asset)
+                        .map(|bounds| (options.direction, bounds))
+                })
+                .unwrap_or((
+                    PlaybackDirection::Normal,
+                    (composition.frames.start, composition.frames.end.prev()),
+                ));
+            let frame =
+                start
+                    .copied()
+                    .unwrap_or_default()
+                    .frame(entity, direction, start_frame, end_frame);
             commands.entity(entity).insert(Playhead::new(frame));
         }
     }
 }
 
+/// Mark the playheads of entities which couldn't be seen by any camera in
+/// the last frame.
+pub fn mark_offscreen_playheads(
+    mut query: Query<(
+        &mut Playhead,
+        &Handle<VelloAsset>,
+        &VelloAssetAlignment,
+        &CoordinateSpace,
+        &GlobalTransform,
+        &ViewVisibility,
+    )>,
+    query_view: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    assets: Res<Assets<VelloAsset>>,
+) {
+    // The world space visible to each camera
+    let views: Vec<Rect> = query_view
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .filter_map(|(camera, view)| {
+            let viewport = camera.logical_viewport_rect()?;
+            let min = camera.viewport_to_world_2d(view, viewport.min)?;
+            let max = camera.viewport_to_world_2d(view, viewport.max)?;
+            Some(Rect::from_corners(min, max))
+        })
+        .collect();
+
+    for (mut playhead, handle, alignment, coord_space, gtransform, view_visibility) in
+        query.iter_mut()
+    {
+        let Some(asset) = assets.get(handle.id()) else {
+            continue;
+        };
+        let offscreen = !view_visibility.get()
+            || match coord_space {
+                CoordinateSpace::WorldSpace => {
+                    let bounds = asset.bb_in_world_space(&alignment.compute(asset, gtransform));
+                    let bounds = Rect::from_corners(bounds.min, bounds.max);
+                    views.iter().all(|view| view.intersect(bounds).is_empty())
+                }
+                CoordinateSpace::ScreenSpace => false,
+            };
+        if playhead.offscreen != offscreen {
+            playhead.offscreen = offscreen;
+        }
+    }
+}
+
 /// Advance all lottie playheads without playback options in the scene
 pub fn advance_playheads_without_options(
     #[cfg(feature = "experimental-dotLottie")] mut query: Query<
-        (&Handle<VelloAsset>, &mut Playhead),
-        (Without<PlaybackOptions>, Without<crate::DotLottiePlayer>),
+        (Entity, &Handle<VelloAsset>, &mut Playhead),
+        (
+            Without<PlaybackOptions>,
+            Without<crate::DotLottiePlayer>,
+            Without<AnimationGroup>,
+        ),
     >,
     #[cfg(not(feature = "experimental-dotLottie"))] mut query: Query<
-        (&Handle<VelloAsset>, &mut Playhead),
-        Without<PlaybackOptions>,
+        (Entity, &Handle<VelloAsset>, &mut Playhead),
+        (Without<PlaybackOptions>, Without<AnimationGroup>),
     >,
-    mut assets: ResMut<Assets<VelloAsset>>,
+    assets: Res<Assets<VelloAsset>>,
     time: Res<Time>,
+    animation_time: Res<VelloAnimationTime>,
+    mut events: PlaybackEventWriters,
 ) {
-    for (asset_handle, mut playhead) in query.iter_mut() {
+    let options = PlaybackOptions::default();
+    for (entity, asset_handle, mut playhead) in query.iter_mut() {
         // Get asset
         let Some(VelloAsset {
             file: VectorFile::Lottie(composition),
             ..
-        }) = assets.get_mut(asset_handle.id())
+        }) = assets.get(asset_handle.id())
         else {
             continue;
         };
@@ -66,61 +137,106 @@ pub fn advance_playheads_without_options(
         playhead.frame = playhead.frame.clamp(start_frame, end_frame);
 
         // Set first render
-        playhead.first_render.get_or_insert(Instant::now());
+        playhead.shown = true;
 
-        // Advance playhead
-        let length = end_frame - start_frame;
-        playhead.frame += (time.delta_seconds_f64() * composition.frame_rate) % length;
-
-        if playhead.frame > end_frame {
-            // Wrap around to the beginning of the segment
-            playhead.frame = start_frame + (playhead.frame - end_frame);
-        }
+        advance_playhead(
+            entity,
+            &mut playhead,
+            &options,
+            start_frame,
+            end_frame,
+            composition,
+            animation_time.scale(time.delta()),
+            &mut events,
+        );
     }
 }
 
 /// Advance all lottie playheads with playback options in the scene
 pub fn advance_playheads_with_options(
     #[cfg(feature = "experimental-dotLottie")] mut query: Query<
-        (&Handle<VelloAsset>, &mut Playhead, &PlaybackOptions),
-        Without<crate::DotLottiePlayer>,
+        (Entity, &Handle<VelloAsset>, &mut Playhead, &PlaybackOptions),
+        (Without<crate::DotLottiePlayer>, Without<AnimationGroup>),
     >,
-    #[cfg(not(feature = "experimental-dotLottie"))] mut query: Query<(
-        &Handle<VelloAsset>,
-        &mut Playhead,
-        &PlaybackOptions,
-    )>,
-    mut assets: ResMut<Assets<VelloAsset>>,
+    #[cfg(not(feature = "experimental-dotLottie"))] mut query: Query<
+        (Entity, &Handle<VelloAsset>, &mut Playhead, &PlaybackOptions),
+        Without<AnimationGroup>,
+    >,
+    assets: Res<Assets<VelloAsset>>,
     time: Res<Time>,
+    animation_time: Res<VelloAnimationTime>,
+    mut events: PlaybackEventWriters,
 ) {
-    for (asset_handle, mut playhead, options) in query.iter_mut() {
+    for (entity, asset_handle, mut playhead, options) in query.iter_mut() {
         // Get asset
-        let Some(VelloAsset {
-            file: VectorFile::Lottie(composition),
-            ..
-        }) = assets.get_mut(asset_handle.id())
+        let Some(
+            asset @ VelloAsset {
+                file: VectorFile::Lottie(composition),
+                ..
+            },
+        ) = assets.get(asset_handle.id())
         else {
             continue;
         };
 
         // Keep playhead bounded
-        let start_frame = options.segments.start.max(composition.frames.start);
-        let end_frame = options.segments.end.min(composition.frames.end).prev();
+        let Some((start_frame, end_frame)) = options.segments.bounds(asset) else {
+            continue;
+        };
         playhead.frame = playhead.frame.clamp(start_frame, end_frame);
 
         // Set first render
-        playhead.first_render.get_or_insert(Instant::now());
+        playhead.shown = true;
+
+        // Scrub to the driven progress
+        if let Some(frame) = options.progress_frame(start_frame, end_frame) {
+            playhead.frame = frame;
+            continue;
+        }
+
+        // Pause while nobody can see it
+        if options.pause_when_offscreen && playhead.offscreen {
+            continue;
+        }
 
         // Auto play
         if !options.autoplay {
             continue;
         }
 
-        // Handle intermissions
-        if let Some(ref mut intermission) = playhead.intermission {
-            intermission.tick(time.delta());
-            if intermission.finished() {
-                playhead.intermission.take();
+        advance_playhead(
+            entity,
+            &mut playhead,
+            options,
+            start_frame,
+            end_frame,
+            composition,
+            animation_time.scale(time.delta()),
+            &mut events,
+        );
+    }
+}
+
+/// Advance a playhead through its segment by the elapsed time, obeying the
+/// playback options, and send events for completed loops and animations.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn advance_playhead(
+    entity: Entity,
+    playhead: &mut Playhead,
+    options: &PlaybackOptions,
+    start_frame: f64,
+    end_frame: f64,
+    composition: &velato::Composition,
+    delta: Duration,
+    events: &mut PlaybackEventWriters,
+) {
+    // Handle intermissions
+    if let Some(ref mut intermission) = playhead.intermission {
+        intermission.tick(delta);
+        if intermission.finished() {
+            playhead.intermission.take();
+            // Bouncing continues from the boundary it stopped at
+            if let PlaybackPlayMode::Normal = options.play_mode {
                 match options.direction {
                     PlaybackDirection::Normal => {
                         playhead.frame = start_frame;
@@ -130,70 +246,70 @@ pub fn advance_playheads_with_options(
                     }
                 }
             }
-            continue;
         }
+        return;
+    }
 
-        // Advance playhead
-        let length = end_frame - start_frame;
-        playhead.frame += (time.delta_seconds_f64()
-            * options.speed
-            * composition.frame_rate
-            * (options.direction as i32 as f64)
-            * playhead.playmode_dir)
-            % length;
-
-        // Keep the playhead bounded between segments
-        let looping = match options.looping {
-            PlaybackLoopBehavior::Loop => true,
-            PlaybackLoopBehavior::Amount(amt) => playhead.loops_completed < amt,
-            PlaybackLoopBehavior::DoNotLoop => false,
-        };
-        if playhead.frame > end_frame {
-            if looping {
-                playhead.loops_completed += 1;
-                if let PlaybackPlayMode::Bounce = options.play_mode {
-                    playhead.playmode_dir *= -1.0;
-                }
-                // Trigger intermission, if applicable
-                if options.intermission > Duration::ZERO {
-                    playhead
-                        .intermission
-                        .replace(Timer::new(options.intermission, TimerMode::Once));
-                    playhead.frame = end_frame;
-                } else {
-                    // Wrap around to the beginning of the segment
-                    playhead.frame = start_frame + (playhead.frame - end_frame);
-                }
-            } else {
-                playhead.frame = end_frame;
-            }
-            // Obey play mode
-            if let PlaybackPlayMode::Bounce = options.play_mode {
-                playhead.frame = end_frame;
-            }
-        } else if playhead.frame < start_frame {
-            if looping {
-                playhead.loops_completed += 1;
-                if let PlaybackPlayMode::Bounce = options.play_mode {
-                    playhead.playmode_dir *= -1.0;
-                }
-                // Trigger intermission, if applicable
-                if options.intermission > Duration::ZERO {
-                    playhead
-                        .intermission
-                        .replace(Timer::new(options.intermission, TimerMode::Once));
-                    playhead.frame = start_frame;
-                } else {
-                    // Wrap around to the beginning of the segment
-                    playhead.frame = end_frame - (start_frame - playhead.frame);
-                }
+    // Advance playhead
+    let previous_frame = playhead.frame;
+    let length = end_frame - start_frame;
+    playhead.frame += (delta.as_secs_f64()
+        * options.speed
+        * composition.frame_rate
+        * (options.direction as i32 as f64)
+        * playhead.playmode_dir)
+        % length;
+
+    // Keep the playhead bounded between segments
+    let boundary = if playhead.frame > end_frame {
+        end_frame
+    } else if playhead.frame < start_frame {
+        start_frame
+    } else {
+        return;
+    };
+    let looping = match options.looping {
+        PlaybackLoopBehavior::Loop => true,
+        PlaybackLoopBehavior::Amount(amt) => playhead.loops_completed < amt,
+        PlaybackLoopBehavior::DoNotLoop => false,
+    };
+    if !looping {
+        playhead.frame = boundary;
+        // Only notify when first reaching the end
+        if previous_frame != boundary {
+            events.completed.send(AnimationCompleted { entity });
+        }
+        return;
+    }
+    playhead.loops_completed += 1;
+    events.loop_completed.send(AnimationLoopCompleted {
+        entity,
+        loops_completed: playhead.loops_completed,
+    });
+
+    // The distance travelled past the boundary
+    let overshoot = (playhead.frame - boundary).abs();
+    let at_end = boundary == end_frame;
+    playhead.frame = match options.play_mode {
+        // Wrap around to the other end of the segment
+        PlaybackPlayMode::Normal if at_end => start_frame + overshoot,
+        PlaybackPlayMode::Normal => end_frame - overshoot,
+        // Reflect back from the boundary and reverse direction
+        PlaybackPlayMode::Bounce => {
+            playhead.playmode_dir *= -1.0;
+            if at_end {
+                end_frame - overshoot
             } else {
-                playhead.frame = start_frame;
-            }
-            // Obey play mode
-            if let PlaybackPlayMode::Bounce = options.play_mode {
-                playhead.frame = start_frame;
+                start_frame + overshoot
             }
         }
+    };
+
+    // Trigger intermission, if applicable
+    if options.intermission > Duration::ZERO {
+        playhead
+            .intermission
+            .replace(Timer::new(options.intermission, TimerMode::Once));
+        playhead.frame = boundary;
     }
 }
@@ -0,0 +1,166 @@
+//! Runtime replacement of the strings of lottie text layers.
+//!
+//! Velato doesn't support text layers, so they are loaded as null layers to
+//! keep their transform and timing. The text layers of a file are parsed
+//! alongside it, and the replaced strings are drawn on top of the composition
+//! with a [`VelloFont`].
+
+use super::layers::layer_transform;
+use crate::text::VelloTextAlignment;
+use crate::{VelloFont, VelloFontFallback, VelloText};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde_json::Value;
+use velato::Composition;
+use vello::kurbo::Affine;
+use vello::peniko::Brush;
+use vello::Scene;
+
+/// A text layer of a lottie file, which velato doesn't render.
+#[derive(Clone)]
+pub struct LottieTextLayer {
+    /// The original string of the layer.
+    pub text: String,
+    /// The font size.
+    pub size: f32,
+    /// The fill color.
+    pub color: Color,
+    /// The justification of the text, from the layer's origin.
+    pub alignment: VelloTextAlignment,
+}
+
+#[derive(PartialEq, Component, Default, Clone, Debug, Reflect)]
+#[reflect(Component)]
+/// Add this component to a `VelloAssetBundle` entity to replace the strings
+/// of lottie text layers by layer name, such as score counters or localized
+/// labels. The text keeps the size, color, justification and animated
+/// transform of its layer.
+///
+/// Velato doesn't render text layers, nor the fonts of a composition, so
+/// the strings are drawn on top of the composition with the given font.
+pub struct LottieTextOverrides {
+    /// The font to draw the strings with.
+    pub font: Handle<VelloFont>,
+    pub(crate) texts: HashMap<String, String>,
+}
+
+impl LottieTextOverrides {
+    pub fn new(font: Handle<VelloFont>) -> Self {
+        Self {
+            font,
+            texts: HashMap::default(),
+        }
+    }
+
+    /// Replace the string of the text layer with the given name.
+    pub fn add(mut self, layer_name: &str, text: impl Into<String>) -> Self {
+        self.texts.insert(layer_name.to_string(), text.into());
+        self
+    }
+
+    /// Replace the string of the text layer with the given name. This will
+    /// overwrite the previous value.
+    pub fn edit(&mut self, layer_name: &str, text: impl Into<String>) -> &mut Self {
+        self.texts.insert(layer_name.to_string(), text.into());
+        self
+    }
+
+    /// Stop replacing the string of the text layer with the given name.
+    pub fn remove(&mut self, layer_name: &str) -> Option<String> {
+        self.texts.remove(layer_name)
+    }
+
+    pub fn get(&self, layer_name: &str) -> Option<&String> {
+        self.texts.get(layer_name)
+    }
+}
+
+/// Parse the top-level text layers of a lottie file, by layer name.
+pub(crate) fn parse_text_layers(value: &Value) -> HashMap<String, LottieTextLayer> {
+    let Some(layers) = value.get("layers").and_then(Value::as_array) else {
+        return HashMap::default();
+    };
+    layers
+        .iter()
+        .filter(|layer| layer.get("ty").and_then(Value::as_u64) == Some(5))
+        .filter_map(|layer| {
+            let name = layer.get("nm")?.as_str()?;
+            // The first keyframe of the text document
+            let document = layer.pointer("/t/d/k/0/s")?;
+            let text = document.get("t")?.as_str()?.replace('\r', "\n");
+            let size = document.get("s").and_then(Value::as_f64).unwrap_or(12.0) as f32;
+            let color = match document.get("fc").and_then(Value::as_array) {
+                Some(rgb) => {
+                    let channel =
+                        |i: usize| rgb.get(i).and_then(Value::as_f64).unwrap_or(0.0) as f32;
+                    Color::rgb(channel(0), channel(1), channel(2))
+                }
+                None => Color::BLACK,
+            };
+            let alignment = match document.get("j").and_then(Value::as_u64) {
+                Some(1) => VelloTextAlignment::BottomRight,
+                Some(2) => VelloTextAlignment::Bottom,
+                _ => VelloTextAlignment::BottomLeft,
+            };
+            Some((
+                name.to_string(),
+                LottieTextLayer {
+                    text,
+                    size,
+                    color,
+                    alignment,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Draw the replaced strings of the text layers of a composition.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render_text_overrides(
+    scene: &mut Scene,
+    composition: &Composition,
+    text_layers: &HashMap<String, LottieTextLayer>,
+    overrides: &LottieTextOverrides,
+    font: &VelloFont,
+    frame: f64,
+    transform: Affine,
+    alpha: f32,
+) {
+    for (name, text) in overrides.texts.iter() {
+        let Some(text_layer) = text_layers.get(name) else {
+            continue;
+        };
+        let Some(layer) = composition
+            .layers
+            .iter()
+            .find(|layer| &layer.name == name && layer.frames.contains(&frame))
+        else {
+            continue;
+        };
+        let opacity = layer.opacity.evaluate(frame) / 100.0;
+        let color = text_layer.color.as_rgba_f32();
+        let color = vello::peniko::Color::rgba(
+            color[0] as f64,
+            color[1] as f64,
+            color[2] as f64,
+            color[3] as f64 * opacity * alpha as f64,
+        );
+        let text = VelloText {
+            content: text.clone(),
+            size: text_layer.size,
+            brush: Some(Brush::Solid(color)),
+            ..default()
+        };
+        let layout = font.layout_text(&text, &VelloFontFallback::default(), |_| None);
+        font.render(
+            scene,
+            transform * layer_transform(&composition.layers, layer, frame),
+            &text,
+            text_layer.alignment,
+            None,
+            None,
+            &layout,
+        );
+    }
+}
@@ -2,28 +2,74 @@
 //!
 //! A long-term vision here is a selector-styled language, but now is just color swapping by layer name.
 
+use crate::brush::to_peniko_color;
+use crate::integrations::VectorLoaderError;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
 use bevy::prelude::*;
-use bevy::utils::HashMap;
+use bevy::utils::{BoxedFuture, HashMap};
+use serde::Deserialize;
 use velato::model::{Brush, Shape};
 use velato::Composition;
 
-#[derive(PartialEq, Component, Default, Clone, Debug, Reflect)]
+/// The draws of a layer to recolor with a [`Theme`].
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Reflect)]
+pub enum ThemeTarget {
+    /// Fills, solid or gradient.
+    Fill,
+    /// Strokes, solid or gradient.
+    Stroke,
+}
+
+/// A color swap of a [`Theme`].
+#[derive(PartialEq, Clone, Debug, Reflect, Deserialize)]
+pub enum ThemePaint {
+    /// Swap solid colors, and every stop of gradients, with a color.
+    Solid(Color),
+    /// Swap the stops of gradients by index, keeping their offsets. Solid
+    /// colors are swapped with the first color, and stops past the given
+    /// colors are kept.
+    Gradient(Vec<Color>),
+}
+
+impl From<Color> for ThemePaint {
+    fn from(color: Color) -> Self {
+        Self::Solid(color)
+    }
+}
+
+#[derive(PartialEq, Component, Asset, Default, Clone, Debug, Reflect, Deserialize)]
 #[reflect(Component)]
+#[serde(default)]
 /// Add this component to a `VelloAssetBundle` entity to enable runtime color
 /// editing. This interface allows swapping colors in a lottie composition by
 /// selecting the desired layer and shape and overriding the original color with
 /// a new color.
 ///
+/// Colors swapped with [`Theme::add`] apply to fills and strokes, unless
+/// the layer has a swap for that [`ThemeTarget`].
+///
+/// Themes can also be loaded from `.theme.ron` or `.theme.json` files, and
+/// referenced by adding their `Handle<Theme>` to the entity instead. The
+/// theme is updated when the file is modified:
+///
+/// ```ron
+/// (
+///     colors: {"calendar": Rgba(red: 0.1, green: 0.1, blue: 0.2, alpha: 1.0)},
+///     strokes: {"outline": Gradient([Rgba(red: 1.0, green: 1.0, blue: 1.0, alpha: 1.0)])},
+/// )
+/// ```
+///
 /// Only works for layer shapes with fill or stroke elements.
 pub struct Theme {
     pub(crate) colors: HashMap<String, Color>,
+    pub(crate) fills: HashMap<String, ThemePaint>,
+    pub(crate) strokes: HashMap<String, ThemePaint>,
 }
 
 impl Theme {
     pub fn new() -> Self {
-        Self {
-            colors: HashMap::default(),
-        }
+        Self::default()
     }
 
     /// Swap a color with the given layer name.
@@ -46,61 +92,120 @@ impl Theme {
     pub fn get_mut(&mut self, layer_name: &str) -> Option<&mut Color> {
         self.colors.get_mut(layer_name)
     }
+
+    /// Swap the fills or strokes of the given layer name.
+    pub fn add_target(
+        mut self,
+        layer_name: &str,
+        target: ThemeTarget,
+        paint: impl Into<ThemePaint>,
+    ) -> Self {
+        self.edit_target(layer_name, target, paint);
+        self
+    }
+
+    /// Swap the fills or strokes for the selected layer name. This will
+    /// overwrite the previous value.
+    pub fn edit_target(
+        &mut self,
+        layer_name: &str,
+        target: ThemeTarget,
+        paint: impl Into<ThemePaint>,
+    ) -> &mut Self {
+        self.targets_mut(target)
+            .insert(layer_name.to_string(), paint.into());
+        self
+    }
+
+    pub fn get_target(&self, layer_name: &str, target: ThemeTarget) -> Option<&ThemePaint> {
+        match target {
+            ThemeTarget::Fill => self.fills.get(layer_name),
+            ThemeTarget::Stroke => self.strokes.get(layer_name),
+        }
+    }
+
+    pub fn get_target_mut(
+        &mut self,
+        layer_name: &str,
+        target: ThemeTarget,
+    ) -> Option<&mut ThemePaint> {
+        self.targets_mut(target).get_mut(layer_name)
+    }
+
+    fn targets_mut(&mut self, target: ThemeTarget) -> &mut HashMap<String, ThemePaint> {
+        match target {
+            ThemeTarget::Fill => &mut self.fills,
+            ThemeTarget::Stroke => &mut self.strokes,
+        }
+    }
 }
 
 impl Theme {
     pub fn recolor(&self, composition: &Composition) -> Composition {
         let mut composition = composition.clone();
-        'layers: for layer in composition.layers.iter_mut() {
+        for layer in composition.layers.iter_mut() {
+            let color = self
+                .colors
+                .get(&layer.name)
+                .map(|color| ThemePaint::Solid(*color));
+            let fill = self.fills.get(&layer.name).or(color.as_ref());
+            let stroke = self.strokes.get(&layer.name).or(color.as_ref());
             // Continue if this layer doesn't have a color swap
-            let Some(target_color) = self.colors.get(&layer.name) else {
-                continue 'layers;
-            };
+            if fill.is_none() && stroke.is_none() {
+                continue;
+            }
             let shapes = match &mut layer.content {
                 velato::model::Content::Shape(shapes) => shapes,
                 velato::model::Content::None | velato::model::Content::Instance { .. } => {
-                    continue 'layers;
+                    continue;
                 }
             };
-            let target_color = vello::peniko::Color::rgba(
-                target_color.r().into(),
-                target_color.g().into(),
-                target_color.b().into(),
-                target_color.a().into(),
-            );
             for shape in shapes.iter_mut() {
-                recolor_shape(shape, target_color);
+                recolor_shape(shape, fill, stroke);
             }
         }
         composition
     }
 }
 
-/// A helper method to recolor a shape with a target color.
-fn recolor_shape(shape: &mut Shape, target_color: vello::peniko::Color) {
+/// A helper method to recolor a shape with the target paints.
+fn recolor_shape(shape: &mut Shape, fill: Option<&ThemePaint>, stroke: Option<&ThemePaint>) {
     match shape {
         velato::model::Shape::Group(shapes, _) => {
             for shape in shapes.iter_mut() {
-                recolor_shape(shape, target_color);
+                recolor_shape(shape, fill, stroke);
             }
         }
         velato::model::Shape::Draw(draw) => {
-            recolor_brush(&mut draw.brush, target_color);
+            let paint = if draw.stroke.is_some() { stroke } else { fill };
+            if let Some(paint) = paint {
+                recolor_brush(&mut draw.brush, paint);
+            }
         }
         velato::model::Shape::Repeater(_) | velato::model::Shape::Geometry(_) => {}
     }
 }
 
-/// A helper method to recolor a brush with a target color.
-fn recolor_brush(brush: &mut Brush, target_color: vello::peniko::Color) {
+/// A helper method to recolor a brush with a target paint.
+fn recolor_brush(brush: &mut Brush, paint: &ThemePaint) {
+    // The color of the stop at an index, if swapped
+    let stop_color = |index: usize| match paint {
+        ThemePaint::Solid(color) => Some(to_peniko_color(*color)),
+        ThemePaint::Gradient(colors) => colors.get(index).copied().map(to_peniko_color),
+    };
+    let Some(solid_color) = stop_color(0) else {
+        return;
+    };
     match brush {
         velato::model::Brush::Fixed(brush) => match brush {
             vello::peniko::Brush::Solid(solid) => {
-                *solid = target_color;
+                *solid = solid_color;
             }
             vello::peniko::Brush::Gradient(gradient) => {
-                for stop in gradient.stops.iter_mut() {
-                    stop.color = target_color;
+                for (index, stop) in gradient.stops.iter_mut().enumerate() {
+                    if let Some(color) = stop_color(index) {
+                        stop.color = color;
+                    }
                 }
             }
             vello::peniko::Brush::Image(_) => {}
@@ -108,32 +213,33 @@ fn recolor_brush(brush: &mut Brush, target_color: vello::peniko::Color) {
         velato::model::Brush::Animated(brush) => match brush {
             velato::model::animated::Brush::Solid(brush) => match brush {
                 velato::model::Value::Fixed(solid) => {
-                    *solid = target_color;
+                    *solid = solid_color;
                 }
                 velato::model::Value::Animated(keyframes) => {
                     for solid in keyframes.values.iter_mut() {
-                        *solid = target_color;
+                        *solid = solid_color;
                     }
                 }
             },
             velato::model::animated::Brush::Gradient(gr) => match &mut gr.stops {
                 velato::model::ColorStops::Fixed(stops) => {
-                    for stop in stops.iter_mut() {
-                        stop.color = target_color;
+                    for (index, stop) in stops.iter_mut().enumerate() {
+                        if let Some(color) = stop_color(index) {
+                            stop.color = color;
+                        }
                     }
                 }
                 velato::model::ColorStops::Animated(stops) => {
-                    for _ in 0..stops.count {
-                        for stop in stops.values.iter_mut() {
-                            let _offset = stop[0];
-                            let r = &mut stop[1];
-                            *r = target_color.r as f64;
-                            let g = &mut stop[2];
-                            *g = target_color.g as f64;
-                            let b = &mut stop[3];
-                            *b = target_color.b as f64;
-                            let a = &mut stop[4];
-                            *a = target_color.a as f64;
+                    // Every keyframe is a sequence of (offset, r, g, b, a)
+                    for keyframe in stops.values.iter_mut() {
+                        for (index, stop) in keyframe.chunks_exact_mut(5).enumerate() {
+                            let Some(color) = stop_color(index) else {
+                                continue;
+                            };
+                            stop[1] = color.r as f64 / 255.0;
+                            stop[2] = color.g as f64 / 255.0;
+                            stop[3] = color.b as f64 / 255.0;
+                            stop[4] = color.a as f64 / 255.0;
                         }
                     }
                 }
@@ -141,3 +247,77 @@ fn recolor_brush(brush: &mut Brush, target_color: vello::peniko::Color) {
         },
     }
 }
+
+#[derive(Default)]
+pub struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    type Asset = Theme;
+
+    type Settings = ();
+
+    type Error = VectorLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+
+            debug!("parsing {}...", load_context.path().display());
+            let is_json = load_context
+                .path()
+                .extension()
+                .is_some_and(|ext| ext == "json");
+            let theme = if is_json {
+                serde_json::from_slice(&bytes)?
+            } else {
+                ron::de::from_bytes(&bytes)?
+            };
+            Ok(theme)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron", "theme.json"]
+    }
+}
+
+/// Copy themes loaded from files to the entities referencing them, when the
+/// handle or the asset changes.
+pub fn apply_theme_assets(
+    mut commands: Commands,
+    query: Query<(Entity, Ref<Handle<Theme>>, Option<&Theme>)>,
+    mut removed: RemovedComponents<Handle<Theme>>,
+    mut asset_events: EventReader<AssetEvent<Theme>>,
+    themes: Res<Assets<Theme>>,
+) {
+    for entity in removed.read() {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.remove::<Theme>();
+        }
+    }
+    let changed: Vec<AssetId<Theme>> = asset_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    for (entity, handle, current) in query.iter() {
+        if !(handle.is_changed() || current.is_none() || changed.contains(&handle.id())) {
+            continue;
+        }
+        let Some(theme) = themes.get(handle.id()) else {
+            continue;
+        };
+        if current != Some(theme) {
+            commands.entity(entity).insert(theme.clone());
+        }
+    }
+}
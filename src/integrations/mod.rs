@@ -2,7 +2,7 @@
 //!
 //! # Features
 //! - `svg` - Enables SVG loading and rendering
-//! - `lottie` - Enable Lottie (JSON) loading and rendering
+//! - `lottie` - Enable Lottie (JSON, `.lottie` and `.tgs`) loading and rendering
 //! - `experimental-dotLottie` - Enables experimental support for dotLottie interactivity. WIP.
 
 #[cfg(feature = "svg")]
@@ -18,6 +18,7 @@ mod error;
 pub use error::VectorLoaderError;
 
 mod asset;
+mod outlines;
 pub use asset::{VelloAsset, VelloAssetAlignment};
 
 #[derive(Clone)]
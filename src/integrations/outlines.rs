@@ -0,0 +1,270 @@
+//! The outlines of the paths drawn by vector assets, decoded from their
+//! encoded scenes, e.g. to hit test their shapes rather than their bounds.
+#![cfg_attr(not(any(feature = "svg", feature = "lottie")), allow(unused))]
+
+use crate::VelloAsset;
+use bevy::prelude::*;
+use vello::kurbo::{self, Affine, BezPath, Cap, Join, PathEl, Point, Shape, Stroke, StrokeOpts};
+use vello::peniko::Fill;
+use vello::Scene;
+use vello_encoding::{DrawTag, PathTag, Style};
+
+/// The tolerance of expanded strokes, in the units of the asset.
+const STROKE_TOLERANCE: f64 = 0.1;
+
+/// The outline of a path, and the rule of its fill.
+pub(crate) struct Outline {
+    pub path: BezPath,
+    pub fill: Fill,
+}
+
+impl Outline {
+    fn contains(&self, point: Point) -> bool {
+        let winding = self.path.winding(point);
+        match self.fill {
+            Fill::NonZero => winding != 0,
+            Fill::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+impl VelloAsset {
+    /// The outlines of the paths drawn by the asset, at the given frame of a
+    /// lottie animation, in the coordinates of the asset with Y down. Strokes
+    /// are expanded into the outlines of the areas they cover, and clips and
+    /// masks are ignored.
+    pub fn outlines(&self, frame: f64) -> Vec<BezPath> {
+        self.fill_outlines(frame)
+            .into_iter()
+            .map(|outline| outline.path)
+            .collect()
+    }
+
+    /// The outlines of the paths drawn by the asset, at the given frame of a
+    /// lottie animation, flattened into polygons, e.g. for the colliders of a
+    /// physics engine. Curves are flattened to lines within the tolerance, in
+    /// the units of the asset.
+    ///
+    /// The points are relative to the center of the asset with Y up, the
+    /// local space of an entity with the default [`VelloAssetAlignment`](crate::VelloAssetAlignment).
+    pub fn polygons(&self, frame: f64, tolerance: f64) -> Vec<Vec<Vec2>> {
+        let center = Vec2::new(self.width, self.height) / 2.0;
+        let to_local =
+            |point: Point| Vec2::new(point.x as f32 - center.x, center.y - point.y as f32);
+        let mut polygons = Vec::new();
+        for outline in self.outlines(frame) {
+            let mut polygon: Vec<Vec2> = Vec::new();
+            kurbo::flatten(outline, tolerance, |element| match element {
+                PathEl::MoveTo(point) => {
+                    polygons.push(std::mem::take(&mut polygon));
+                    polygon.push(to_local(point));
+                }
+                PathEl::LineTo(point) => {
+                    let point = to_local(point);
+                    if polygon.last() != Some(&point) {
+                        polygon.push(point);
+                    }
+                }
+                PathEl::ClosePath => {
+                    polygons.push(std::mem::take(&mut polygon));
+                }
+                // Flattened paths only contain lines
+                PathEl::QuadTo(..) | PathEl::CurveTo(..) => {}
+            });
+            polygons.push(polygon);
+        }
+        for polygon in &mut polygons {
+            // Closed polygons don't repeat their first point
+            if polygon.len() > 1 && polygon.first() == polygon.last() {
+                polygon.pop();
+            }
+        }
+        polygons.retain(|polygon| polygon.len() >= 3);
+        polygons
+    }
+
+    /// The area covered by the paths drawn by the asset, at the given frame
+    /// of a lottie animation, in the coordinates of the asset with Y down, as
+    /// one path for the operations of [`path_ops`](crate::path_ops). Curves
+    /// are flattened to lines within the tolerance, in the units of the asset.
+    pub fn silhouette(&self, frame: f64, tolerance: f64) -> BezPath {
+        let outlines = self.fill_outlines(frame);
+        let paths: Vec<_> = outlines
+            .iter()
+            .map(|outline| (&outline.path, outline.fill))
+            .collect();
+        crate::path_ops::boolean_with_fills(&paths, &[], crate::path_ops::PathOp::Union, tolerance)
+    }
+
+    /// Whether a point, in the coordinates of the asset with Y down, is
+    /// within any path drawn by the asset at the given frame of a lottie
+    /// animation.
+    pub fn contains(&self, point: Vec2, frame: f64) -> bool {
+        let point = Point::new(point.x as f64, point.y as f64);
+        self.fill_outlines(frame)
+            .iter()
+            .any(|outline| outline.contains(point))
+    }
+
+    #[cfg_attr(not(feature = "lottie"), allow(unused_variables))]
+    pub(crate) fn fill_outlines(&self, frame: f64) -> Vec<Outline> {
+        match &self.file {
+            #[cfg(feature = "svg")]
+            crate::VectorFile::Svg(scene) => scene_outlines(scene),
+            #[cfg(feature = "lottie")]
+            crate::VectorFile::Lottie(composition) => {
+                let mut scene = Scene::new();
+                velato::Renderer::new().render(
+                    composition,
+                    frame,
+                    Affine::IDENTITY,
+                    1.0,
+                    &mut scene,
+                );
+                scene_outlines(&scene)
+            }
+            #[cfg(not(any(feature = "svg", feature = "lottie")))]
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// The stroke of an encoded style, or `None` for a fill.
+fn style_stroke(style: &Style) -> Option<Stroke> {
+    let flags = style.flags_and_miter_limit;
+    if flags & Style::FLAGS_STYLE_BIT == 0 {
+        return None;
+    }
+    let join = match flags & Style::FLAGS_JOIN_MASK {
+        Style::FLAGS_JOIN_BITS_MITER => Join::Miter,
+        Style::FLAGS_JOIN_BITS_ROUND => Join::Round,
+        _ => Join::Bevel,
+    };
+    let cap = |bits: u32| match bits {
+        Style::FLAGS_CAP_BITS_SQUARE => Cap::Square,
+        Style::FLAGS_CAP_BITS_ROUND => Cap::Round,
+        _ => Cap::Butt,
+    };
+    Some(
+        Stroke::new(style.line_width as f64)
+            .with_join(join)
+            .with_start_cap(cap((flags & Style::FLAGS_START_CAP_MASK) >> 2))
+            .with_end_cap(cap(flags & Style::FLAGS_END_CAP_MASK)),
+    )
+}
+
+/// Decode the outlines of the paths filled and stroked by a scene.
+pub(crate) fn scene_outlines(scene: &Scene) -> Vec<Outline> {
+    let encoding = scene.encoding();
+    let mut data = encoding.path_data.as_slice();
+    let mut transforms = encoding.transforms.iter();
+    let mut styles = encoding.styles.iter();
+    let mut draw_tags = encoding.draw_tags.iter();
+    let mut transform = Affine::IDENTITY;
+    let mut style = Style::default();
+
+    let mut outlines = Vec::new();
+    let mut path = BezPath::new();
+    let mut path_transform = transform;
+    let mut subpath_start = true;
+    for tag in &encoding.path_tags {
+        match *tag {
+            PathTag::TRANSFORM => {
+                if let Some(next) = transforms.next() {
+                    let [a, b, c, d] = next.matrix.map(f64::from);
+                    let [e, f] = next.translation.map(f64::from);
+                    transform = Affine::new([a, b, c, d, e, f]);
+                }
+            }
+            PathTag::STYLE => {
+                if let Some(next) = styles.next() {
+                    style = *next;
+                }
+            }
+            PathTag::PATH => {
+                let draw_tag = draw_tags.next().copied();
+                let path = std::mem::take(&mut path);
+                // Clips don't draw their paths
+                if path.is_empty()
+                    || matches!(draw_tag, Some(DrawTag::BEGIN_CLIP | DrawTag::END_CLIP))
+                {
+                    continue;
+                }
+                let outline = match style_stroke(&style) {
+                    Some(stroke) => Outline {
+                        path: kurbo::stroke(
+                            path.path_elements(STROKE_TOLERANCE),
+                            &stroke,
+                            &StrokeOpts::default(),
+                            STROKE_TOLERANCE,
+                        ),
+                        fill: Fill::NonZero,
+                    },
+                    None => Outline {
+                        path,
+                        fill: match style.flags_and_miter_limit & Style::FLAGS_FILL_BIT {
+                            0 => Fill::NonZero,
+                            _ => Fill::EvenOdd,
+                        },
+                    },
+                };
+                outlines.push(Outline {
+                    path: path_transform * outline.path,
+                    fill: outline.fill,
+                });
+            }
+            tag if tag.is_path_segment() => {
+                let mut read_point = || {
+                    let size = if tag.is_f32() { 8 } else { 4 };
+                    let (point, rest) = data.split_at(size);
+                    data = rest;
+                    decode_point(point)
+                };
+                if subpath_start {
+                    path_transform = transform;
+                    path.move_to(read_point());
+                    subpath_start = false;
+                }
+                let n_points = tag.path_segment_type().0 as usize;
+                let points: Vec<Point> = (0..n_points).map(|_| read_point()).collect();
+                let is_stroke = style.flags_and_miter_limit & Style::FLAGS_STYLE_BIT != 0;
+                // The last segment of a stroked subpath marks its caps, and is
+                // a line if the subpath is closed
+                if is_stroke && tag.is_subpath_end() {
+                    if n_points == 1 {
+                        path.close_path();
+                    }
+                } else {
+                    match points[..] {
+                        [p0] => path.line_to(p0),
+                        [p0, p1] => path.quad_to(p0, p1),
+                        [p0, p1, p2] => path.curve_to(p0, p1, p2),
+                        _ => {}
+                    }
+                    if tag.is_subpath_end() {
+                        path.close_path();
+                    }
+                }
+                if tag.is_subpath_end() {
+                    subpath_start = true;
+                }
+            }
+            _ => {}
+        }
+    }
+    outlines
+}
+
+/// Decode a point of `f32` or `i16` coordinates.
+fn decode_point(bytes: &[u8]) -> Point {
+    match bytes.len() {
+        8 => Point::new(
+            f32::from_ne_bytes(bytes[0..4].try_into().unwrap()) as f64,
+            f32::from_ne_bytes(bytes[4..8].try_into().unwrap()) as f64,
+        ),
+        _ => Point::new(
+            i16::from_ne_bytes([bytes[0], bytes[1]]) as f64,
+            i16::from_ne_bytes([bytes[2], bytes[3]]) as f64,
+        ),
+    }
+}
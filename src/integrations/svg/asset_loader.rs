@@ -1,10 +1,12 @@
-use crate::integrations::svg::load_svg_from_bytes;
+use super::parse::image_hrefs;
+use super::symbols::symbols;
+use crate::integrations::svg::load_svg_from_bytes_with_images;
 use crate::integrations::VectorLoaderError;
 use crate::VelloAsset;
 use bevy::asset::io::Reader;
 use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
 use bevy::prelude::*;
-use bevy::utils::BoxedFuture;
+use bevy::utils::{BoxedFuture, HashMap};
 
 #[derive(Default)]
 pub struct VelloSvgLoader;
@@ -37,7 +39,41 @@ impl AssetLoader for VelloSvgLoader {
             debug!("parsing {}...", load_context.path().display());
             match ext {
                 "svg" => {
-                    let vello_vector = load_svg_from_bytes(&bytes)?;
+                    // Read external images as dependencies, to hot-reload them
+                    let mut images = HashMap::default();
+                    for href in image_hrefs(&bytes)? {
+                        if href.contains("://") {
+                            continue;
+                        }
+                        let path = load_context.asset_path().resolve_embed(&href)?;
+                        match load_context.read_asset_bytes(path).await {
+                            Ok(data) => {
+                                images.insert(href, data);
+                            }
+                            Err(err) => warn!("could not read svg image '{href}': {err}"),
+                        }
+                    }
+                    // Every symbol of a sprite sheet is a labeled sub-asset
+                    let symbols = std::str::from_utf8(&bytes)
+                        .map_err(|err| err.to_string())
+                        .and_then(|text| symbols(text).map_err(|err| err.to_string()));
+                    match symbols {
+                        Ok(symbols) => {
+                            for (id, symbol) in symbols {
+                                match load_svg_from_bytes_with_images(
+                                    symbol.as_bytes(),
+                                    images.clone(),
+                                ) {
+                                    Ok(symbol) => {
+                                        load_context.add_labeled_asset(id, symbol);
+                                    }
+                                    Err(err) => warn!("could not load svg symbol '{id}': {err}"),
+                                }
+                            }
+                        }
+                        Err(err) => warn!("could not read svg symbols: {err}"),
+                    }
+                    let vello_vector = load_svg_from_bytes_with_images(&bytes, images)?;
                     info!(
                         path = format!("{}", load_context.path().display()),
                         size = format!("{:?}", (vello_vector.width, vello_vector.height)),
@@ -0,0 +1,293 @@
+//! Runtime overrides of SVG elements by their `id` attribute.
+//!
+//! The usvg tree is immutable, so the overrides are applied to the source of
+//! the file, which is parsed again for every entity with overrides.
+
+use super::parse::{render_tree, svg_options};
+use crate::stroke::{VelloStroke, VelloStrokeCap, VelloStrokeJoin};
+use crate::VelloAsset;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::ops::Range;
+use std::sync::Arc;
+use vello_svg::usvg;
+
+/// The overrides of an SVG element.
+#[derive(PartialEq, Default, Clone, Debug, Reflect)]
+pub struct SvgElementOverride {
+    /// Whether the element is displayed, overriding its `display` attribute.
+    pub visible: Option<bool>,
+    /// The fill of the element and its descendants with a `fill` attribute.
+    pub fill: Option<Color>,
+    /// The style of the strokes of the element and its descendants, e.g.
+    /// dashed. Their colors are kept.
+    pub stroke: Option<VelloStroke>,
+}
+
+#[derive(PartialEq, Component, Default, Clone, Debug, Reflect)]
+#[reflect(Component)]
+/// Add this component to a `VelloAssetBundle` entity to hide, show or recolor
+/// the elements of an SVG file by their `id` attribute, such as toggling the
+/// badge of an icon. The asset is shared, so only this entity is affected.
+///
+/// Fills and strokes set in `style` attributes or stylesheets are not
+/// overridden.
+pub struct SvgElementOverrides {
+    pub(crate) elements: HashMap<String, SvgElementOverride>,
+}
+
+impl SvgElementOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hide the element with the given id.
+    pub fn hide(mut self, id: &str) -> Self {
+        self.set_visible(id, false);
+        self
+    }
+
+    /// Show the element with the given id, even if hidden in the file.
+    pub fn show(mut self, id: &str) -> Self {
+        self.set_visible(id, true);
+        self
+    }
+
+    /// Recolor the element with the given id.
+    pub fn recolor(mut self, id: &str, color: Color) -> Self {
+        self.set_fill(id, color);
+        self
+    }
+
+    /// Restyle the strokes of the element with the given id, e.g. dashed.
+    pub fn restroke(mut self, id: &str, stroke: VelloStroke) -> Self {
+        self.set_stroke(id, stroke);
+        self
+    }
+
+    /// Show or hide the element with the given id. This will overwrite the
+    /// previous value.
+    pub fn set_visible(&mut self, id: &str, visible: bool) -> &mut Self {
+        self.elements.entry(id.to_string()).or_default().visible = Some(visible);
+        self
+    }
+
+    /// Recolor the element with the given id. This will overwrite the
+    /// previous value.
+    pub fn set_fill(&mut self, id: &str, color: Color) -> &mut Self {
+        self.elements.entry(id.to_string()).or_default().fill = Some(color);
+        self
+    }
+
+    /// Restyle the strokes of the element with the given id. This will
+    /// overwrite the previous value.
+    pub fn set_stroke(&mut self, id: &str, stroke: VelloStroke) -> &mut Self {
+        self.elements.entry(id.to_string()).or_default().stroke = Some(stroke);
+        self
+    }
+
+    /// Restore the element with the given id from the file.
+    pub fn clear(&mut self, id: &str) -> Option<SvgElementOverride> {
+        self.elements.remove(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&SvgElementOverride> {
+        self.elements.get(id)
+    }
+}
+
+/// The scene of an entity with [`SvgElementOverrides`], rendered from its
+/// asset.
+#[derive(Component, Clone)]
+pub(crate) struct OverriddenSvg {
+    pub asset: AssetId<VelloAsset>,
+    pub scene: Arc<vello::Scene>,
+    /// The tree the scene is rendered from, or `None` if the overrides
+    /// couldn't be applied.
+    pub tree: Option<Arc<usvg::Tree>>,
+}
+
+/// The source of an SVG file, to parse it again with overrides.
+#[derive(Clone)]
+pub struct SvgSource {
+    pub text: String,
+    /// The contents of the external images by reference (`xlink:href`).
+    pub images: Arc<HashMap<String, Arc<Vec<u8>>>>,
+}
+
+/// Set an attribute of an element, replacing it if present.
+fn set_attribute(
+    text: &str,
+    edits: &mut Vec<(Range<usize>, String, String)>,
+    node: roxmltree::Node,
+    name: &str,
+    value: &str,
+) {
+    match node.attribute_node(name) {
+        Some(attribute) => edits.push((
+            attribute.range(),
+            name.to_string(),
+            format!("{name}=\"{value}\""),
+        )),
+        None => {
+            // After the tag name
+            let start = node.range().start + 1;
+            let tag_len = text[start..]
+                .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+                .unwrap_or(0);
+            let at = start + tag_len;
+            edits.push((at..at, name.to_string(), format!(" {name}=\"{value}\"")));
+        }
+    }
+}
+
+/// The presentation attributes of a stroke style.
+fn stroke_attributes(stroke: &VelloStroke) -> [(&'static str, String); 6] {
+    let cap = match stroke.cap {
+        VelloStrokeCap::Butt => "butt",
+        VelloStrokeCap::Square => "square",
+        VelloStrokeCap::Round => "round",
+    };
+    let join = match stroke.join {
+        VelloStrokeJoin::Bevel => "bevel",
+        VelloStrokeJoin::Miter => "miter",
+        VelloStrokeJoin::Round => "round",
+    };
+    let dash_array = match stroke.dash_pattern.is_empty() {
+        true => "none".to_string(),
+        false => stroke
+            .dash_pattern
+            .iter()
+            .map(f32::to_string)
+            .collect::<Vec<_>>()
+            .join(" "),
+    };
+    [
+        ("stroke-width", stroke.width.to_string()),
+        ("stroke-linecap", cap.to_string()),
+        ("stroke-linejoin", join.to_string()),
+        ("stroke-miterlimit", stroke.miter_limit.to_string()),
+        ("stroke-dasharray", dash_array),
+        ("stroke-dashoffset", stroke.dash_offset.to_string()),
+    ]
+}
+
+/// Apply the overrides to the source of an SVG file.
+fn apply_overrides(
+    text: &str,
+    overrides: &HashMap<String, SvgElementOverride>,
+) -> Result<String, roxmltree::Error> {
+    let document = roxmltree::Document::parse(text)?;
+    let mut edits = Vec::new();
+    for node in document.descendants().filter(|node| node.is_element()) {
+        let Some(element) = node.attribute("id").and_then(|id| overrides.get(id)) else {
+            continue;
+        };
+        if let Some(visible) = element.visible {
+            let display = if visible { "inline" } else { "none" };
+            set_attribute(text, &mut edits, node, "display", display);
+        }
+        if let Some(fill) = element.fill {
+            let [r, g, b, a] = fill.as_rgba_u8();
+            let fill = format!("#{r:02x}{g:02x}{b:02x}");
+            let opacity = format!("{}", a as f32 / 255.0);
+            set_attribute(text, &mut edits, node, "fill", &fill);
+            set_attribute(text, &mut edits, node, "fill-opacity", &opacity);
+            for child in node.descendants().skip(1) {
+                if child.has_attribute("fill") {
+                    set_attribute(text, &mut edits, child, "fill", &fill);
+                }
+            }
+        }
+        if let Some(stroke) = &element.stroke {
+            for (name, value) in stroke_attributes(stroke) {
+                set_attribute(text, &mut edits, node, name, &value);
+                for child in node.descendants().skip(1) {
+                    if child.has_attribute(name) {
+                        set_attribute(text, &mut edits, child, name, &value);
+                    }
+                }
+            }
+        }
+    }
+    // Apply from the end, so the ranges stay valid, and keep the last edit of
+    // an attribute, so nested elements take precedence
+    edits.reverse();
+    edits.sort_by_key(|(range, _, _)| std::cmp::Reverse(range.start));
+    edits.dedup_by(|(a, a_name, _), (b, b_name, _)| a == b && a_name == b_name);
+    let mut text = text.to_string();
+    for (range, _, replacement) in edits {
+        text.replace_range(range, &replacement);
+    }
+    Ok(text)
+}
+
+/// Render the scene of every entity with element overrides, when the
+/// overrides or the asset change.
+pub fn compose_svg_overrides(
+    mut commands: Commands,
+    query: Query<(
+        Entity,
+        &Handle<VelloAsset>,
+        Ref<SvgElementOverrides>,
+        Option<&OverriddenSvg>,
+    )>,
+    mut removed: RemovedComponents<SvgElementOverrides>,
+    mut asset_events: EventReader<AssetEvent<VelloAsset>>,
+    assets: Res<Assets<VelloAsset>>,
+) {
+    for entity in removed.read() {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.remove::<OverriddenSvg>();
+        }
+    }
+    let modified: Vec<AssetId<VelloAsset>> = asset_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+
+    for (entity, handle, overrides, overridden) in query.iter() {
+        let outdated = match overridden {
+            Some(overridden) => {
+                overrides.is_changed()
+                    || overridden.asset != handle.id()
+                    || modified.contains(&overridden.asset)
+            }
+            None => true,
+        };
+        if !outdated {
+            continue;
+        }
+        let Some(VelloAsset {
+            file: crate::VectorFile::Svg(original),
+            svg_source: Some(source),
+            ..
+        }) = assets.get(handle.id())
+        else {
+            continue;
+        };
+        let tree = apply_overrides(&source.text, &overrides.elements)
+            .map_err(|err| err.to_string())
+            .and_then(|text| {
+                let options = svg_options(source.images.clone());
+                usvg::Tree::from_str(&text, &options, &super::parse::FONT_DB)
+                    .map_err(|err| err.to_string())
+            });
+        let (scene, tree) = match tree {
+            Ok(tree) => (Arc::new(render_tree(&tree)), Some(Arc::new(tree))),
+            Err(err) => {
+                // Fall back to the asset, rather than retrying every frame
+                error!("could not apply svg element overrides: {err}");
+                (original.clone(), None)
+            }
+        };
+        commands.entity(entity).insert(OverriddenSvg {
+            asset: handle.id(),
+            scene,
+            tree,
+        });
+    }
+}
@@ -1,7 +1,16 @@
 mod asset_loader;
 
+mod element_overrides;
+pub(crate) use element_overrides::OverriddenSvg;
+pub use element_overrides::{SvgElementOverride, SvgElementOverrides, SvgSource};
+
 mod parse;
-pub use parse::{load_svg_from_bytes, load_svg_from_str};
+pub use parse::{load_svg_from_bytes, load_svg_from_bytes_with_images, load_svg_from_str};
 
 mod plugin;
 pub(crate) use plugin::SvgIntegrationPlugin;
+
+mod render;
+pub(crate) use render::render_tree_with_stroke_scale;
+
+mod symbols;
@@ -1,26 +1,108 @@
+use super::SvgSource;
 use crate::{integrations::VectorLoaderError, VectorFile, VelloAsset};
 use bevy::transform::components::Transform;
+use bevy::utils::HashMap;
 use once_cell::sync::Lazy;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use vello_svg::usvg::{self, fontdb::Database};
 
 pub static FONT_DB: Lazy<Database> = Lazy::new(usvg::fontdb::Database::default);
 
+/// The external image references (`xlink:href`) of an SVG file, excluding
+/// data URLs.
+pub(crate) fn image_hrefs(bytes: &[u8]) -> Result<Vec<String>, VectorLoaderError> {
+    let svg_str = std::str::from_utf8(bytes)?;
+    let hrefs = Arc::new(Mutex::new(Vec::new()));
+    let options = usvg::Options {
+        image_href_resolver: usvg::ImageHrefResolver {
+            resolve_data: usvg::ImageHrefResolver::default_data_resolver(),
+            resolve_string: {
+                let hrefs = hrefs.clone();
+                Box::new(move |href, _, _| {
+                    hrefs.lock().unwrap().push(href.to_string());
+                    None
+                })
+            },
+        },
+        ..Default::default()
+    };
+    usvg::Tree::from_str(svg_str, &options, &FONT_DB)?;
+    let mut hrefs = std::mem::take(&mut *hrefs.lock().unwrap());
+    hrefs.sort();
+    hrefs.dedup();
+    Ok(hrefs)
+}
+
 /// Deserialize an SVG file from bytes.
 pub fn load_svg_from_bytes(bytes: &[u8]) -> Result<VelloAsset, VectorLoaderError> {
-    let svg_str = std::str::from_utf8(bytes)?;
+    load_svg_with_options(bytes, &usvg::Options::default(), Default::default())
+}
 
-    let usvg = usvg::Tree::from_str(svg_str, &usvg::Options::default(), &FONT_DB)?;
+/// Deserialize an SVG file from bytes, with the contents of its external
+/// images by reference (`xlink:href`).
+pub fn load_svg_from_bytes_with_images(
+    bytes: &[u8],
+    images: HashMap<String, Vec<u8>>,
+) -> Result<VelloAsset, VectorLoaderError> {
+    let images: Arc<HashMap<String, Arc<Vec<u8>>>> = Arc::new(
+        images
+            .into_iter()
+            .map(|(href, data)| (href, Arc::new(data)))
+            .collect(),
+    );
+    load_svg_with_options(bytes, &svg_options(images.clone()), images)
+}
 
-    // Process the loaded SVG into Vello-compatible data
+/// The options to parse an SVG file, resolving external images from their
+/// contents by reference.
+pub(crate) fn svg_options(images: Arc<HashMap<String, Arc<Vec<u8>>>>) -> usvg::Options {
+    usvg::Options {
+        image_href_resolver: usvg::ImageHrefResolver {
+            resolve_data: usvg::ImageHrefResolver::default_data_resolver(),
+            resolve_string: Box::new(move |href, options, fontdb| {
+                let data = images.get(href)?.clone();
+                // Sniff the format, as hrefs don't always have an extension
+                match data.as_slice() {
+                    [0x89, b'P', b'N', b'G', ..] => Some(usvg::ImageKind::PNG(data)),
+                    [0xff, 0xd8, ..] => Some(usvg::ImageKind::JPEG(data)),
+                    [b'G', b'I', b'F', ..] => Some(usvg::ImageKind::GIF(data)),
+                    _ => usvg::Tree::from_data(&data, options, fontdb)
+                        .ok()
+                        .map(usvg::ImageKind::SVG),
+                }
+            }),
+        },
+        ..Default::default()
+    }
+}
+
+/// Process a usvg tree into Vello-compatible data.
+pub(crate) fn render_tree(tree: &usvg::Tree) -> vello::Scene {
     let mut scene = vello::Scene::new();
-    vello_svg::render_tree(&mut scene, &usvg);
+    vello_svg::render_tree(&mut scene, tree);
+    scene
+}
+
+fn load_svg_with_options(
+    bytes: &[u8],
+    options: &usvg::Options,
+    images: Arc<HashMap<String, Arc<Vec<u8>>>>,
+) -> Result<VelloAsset, VectorLoaderError> {
+    let svg_str = std::str::from_utf8(bytes)?;
+
+    let usvg = usvg::Tree::from_str(svg_str, options, &FONT_DB)?;
+    let scene = render_tree(&usvg);
 
     let width = usvg.size().width();
     let height = usvg.size().height();
 
     let vello_vector = VelloAsset {
         file: VectorFile::Svg(Arc::new(scene)),
+        tree: Some(Arc::new(usvg)),
+        svg_source: Some(Arc::new(SvgSource {
+            text: svg_str.to_string(),
+            images,
+        })),
         local_transform_center: {
             let mut transform = Transform::default();
             transform.translation.x = width / 2.0;
@@ -30,6 +112,14 @@ pub fn load_svg_from_bytes(bytes: &[u8]) -> Result<VelloAsset, VectorLoaderError
         width,
         height,
         alpha: 1.0,
+        #[cfg(feature = "lottie")]
+        markers: Default::default(),
+        #[cfg(feature = "lottie")]
+        source: None,
+        #[cfg(feature = "lottie")]
+        text_layers: Default::default(),
+        #[cfg(feature = "lottie")]
+        image_layers: Default::default(),
     };
 
     Ok(vello_vector)
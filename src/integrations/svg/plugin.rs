@@ -1,10 +1,14 @@
 use super::asset_loader::VelloSvgLoader;
+use super::element_overrides::{self, SvgElementOverride, SvgElementOverrides};
 use bevy::prelude::*;
 
 pub struct SvgIntegrationPlugin;
 
 impl Plugin for SvgIntegrationPlugin {
     fn build(&self, app: &mut App) {
-        app.init_asset_loader::<VelloSvgLoader>();
+        app.init_asset_loader::<VelloSvgLoader>()
+            .register_type::<SvgElementOverrides>()
+            .register_type::<SvgElementOverride>()
+            .add_systems(PostUpdate, element_overrides::compose_svg_overrides);
     }
 }
@@ -0,0 +1,205 @@
+//! Render a usvg tree into a vello scene with its strokes scaled, for
+//! [`VelloNonScalingStroke`](crate::VelloNonScalingStroke).
+//!
+//! This follows `vello_svg::render_tree`, whose strokes can't be changed once
+//! encoded.
+
+use vello::kurbo::{Affine, Rect, Stroke};
+use vello::peniko::{BlendMode, Compose, Fill, Mix};
+use vello::Scene;
+use vello_svg::{usvg, util};
+
+/// A layer which clips to a shape.
+const CLIP: BlendMode = BlendMode {
+    mix: Mix::Clip,
+    compose: Compose::SrcOver,
+};
+
+/// Render a tree with the widths and dashes of its strokes scaled.
+pub(crate) fn render_tree_with_stroke_scale(tree: &usvg::Tree, stroke_scale: f32) -> Scene {
+    let mut scene = Scene::new();
+    render_tree(
+        &mut scene,
+        tree,
+        &tree.view_box(),
+        &usvg::Transform::identity(),
+        stroke_scale as f64,
+    );
+    scene
+}
+
+/// Render a tree, within its view box.
+fn render_tree(
+    scene: &mut Scene,
+    tree: &usvg::Tree,
+    view_box: &usvg::ViewBox,
+    ts: &usvg::Transform,
+    stroke_scale: f64,
+) {
+    let ts = &ts.pre_concat(view_box.to_transform(tree.size()));
+    scene.push_layer(CLIP, 1.0, util::to_affine(ts), &view_box_rect(view_box));
+    render_group(
+        scene,
+        tree.root(),
+        &ts.pre_concat(tree.root().transform()),
+        stroke_scale,
+    );
+    scene.pop_layer();
+}
+
+fn view_box_rect(view_box: &usvg::ViewBox) -> Rect {
+    Rect::new(
+        view_box.rect.left().into(),
+        view_box.rect.top().into(),
+        view_box.rect.right().into(),
+        view_box.rect.bottom().into(),
+    )
+}
+
+fn scale_stroke(stroke: &usvg::Stroke, stroke_scale: f64) -> Stroke {
+    let mut stroke = util::to_stroke(stroke);
+    stroke.width *= stroke_scale;
+    stroke.dash_offset *= stroke_scale;
+    for length in stroke.dash_pattern.iter_mut() {
+        *length *= stroke_scale;
+    }
+    stroke
+}
+
+fn render_group(scene: &mut Scene, group: &usvg::Group, ts: &usvg::Transform, stroke_scale: f64) {
+    for node in group.children() {
+        let transform = util::to_affine(ts);
+        match node {
+            usvg::Node::Group(g) => {
+                // Only clip paths of a single path are supported
+                let clip_path =
+                    g.clip_path()
+                        .and_then(|clip_path| match clip_path.root().children().first() {
+                            Some(usvg::Node::Path(path)) => Some(util::to_bez_path(path)),
+                            _ => None,
+                        });
+                if let Some(clip_path) = &clip_path {
+                    scene.push_layer(CLIP, 1.0, transform, clip_path);
+                }
+                render_group(scene, g, &ts.pre_concat(g.transform()), stroke_scale);
+                if clip_path.is_some() {
+                    scene.pop_layer();
+                }
+            }
+            usvg::Node::Path(path) => {
+                if path.visibility() != usvg::Visibility::Visible {
+                    continue;
+                }
+                render_path(scene, node, path, transform, stroke_scale);
+            }
+            usvg::Node::Image(image) => {
+                if image.visibility() != usvg::Visibility::Visible {
+                    continue;
+                }
+                render_image(scene, node, image, ts, stroke_scale);
+            }
+            usvg::Node::Text(_) => {
+                let _ = util::default_error_handler(scene, node);
+            }
+        }
+    }
+}
+
+fn render_path(
+    scene: &mut Scene,
+    node: &usvg::Node,
+    path: &usvg::Path,
+    transform: Affine,
+    stroke_scale: f64,
+) {
+    let local_path = util::to_bez_path(path);
+    let fill = |scene: &mut Scene| {
+        let Some(fill) = path.fill() else {
+            return;
+        };
+        match util::to_brush(fill.paint(), fill.opacity()) {
+            Some((brush, brush_transform)) => {
+                let rule = match fill.rule() {
+                    usvg::FillRule::NonZero => Fill::NonZero,
+                    usvg::FillRule::EvenOdd => Fill::EvenOdd,
+                };
+                scene.fill(rule, transform, &brush, Some(brush_transform), &local_path);
+            }
+            None => {
+                let _ = util::default_error_handler(scene, node);
+            }
+        }
+    };
+    let stroke = |scene: &mut Scene| {
+        let Some(stroke) = path.stroke() else {
+            return;
+        };
+        match util::to_brush(stroke.paint(), stroke.opacity()) {
+            Some((brush, brush_transform)) => scene.stroke(
+                &scale_stroke(stroke, stroke_scale),
+                transform,
+                &brush,
+                Some(brush_transform),
+                &local_path,
+            ),
+            None => {
+                let _ = util::default_error_handler(scene, node);
+            }
+        }
+    };
+    match path.paint_order() {
+        usvg::PaintOrder::FillAndStroke => {
+            fill(scene);
+            stroke(scene);
+        }
+        usvg::PaintOrder::StrokeAndFill => {
+            stroke(scene);
+            fill(scene);
+        }
+    }
+}
+
+fn render_image(
+    scene: &mut Scene,
+    node: &usvg::Node,
+    image: &usvg::Image,
+    ts: &usvg::Transform,
+    stroke_scale: f64,
+) {
+    let kind = image.kind();
+    if let usvg::ImageKind::SVG(svg) = kind {
+        render_tree(scene, svg, &image.view_box(), ts, stroke_scale);
+        return;
+    }
+    let Some(decoded) = util::decode_raw_raster_image(kind)
+        .ok()
+        .map(util::into_image)
+    else {
+        let _ = util::default_error_handler(scene, node);
+        return;
+    };
+    let Some(size) = usvg::Size::from_wh(decoded.width as f32, decoded.height as f32) else {
+        let _ = util::default_error_handler(scene, node);
+        return;
+    };
+    let view_box = image.view_box();
+    let new_size = view_box.rect.size();
+    let (tx, ty) = usvg::utils::aligned_pos(
+        view_box.aspect.align,
+        view_box.rect.x(),
+        view_box.rect.y(),
+        view_box.rect.width() - new_size.width(),
+        view_box.rect.height() - new_size.height(),
+    );
+    let (sx, sy) = (
+        new_size.width() / size.width(),
+        new_size.height() / size.height(),
+    );
+    let view_box_transform = usvg::Transform::from_row(sx, 0.0, 0.0, sy, tx, ty);
+    scene.push_layer(CLIP, 1.0, util::to_affine(ts), &view_box_rect(&view_box));
+    scene.draw_image(
+        &decoded,
+        util::to_affine(&ts.pre_concat(view_box_transform)),
+    );
+    scene.pop_layer();
+}
@@ -0,0 +1,62 @@
+//! Symbols of SVG sprite sheets, loaded as labeled sub-assets.
+
+/// The symbols (`<symbol id="...">`) of an SVG file, by id, each as a
+/// standalone SVG file with the definitions of the sheet.
+pub(crate) fn symbols(text: &str) -> Result<Vec<(String, String)>, roxmltree::Error> {
+    let document = roxmltree::Document::parse(text)?;
+    let root = document.root_element();
+    let namespaces: String = root
+        .namespaces()
+        .map(|namespace| match namespace.name() {
+            Some(prefix) => format!(" xmlns:{prefix}=\"{}\"", namespace.uri()),
+            None => format!(" xmlns=\"{}\"", namespace.uri()),
+        })
+        .collect();
+    // Definitions, such as gradients, may be shared by every symbol
+    let defs: String = document
+        .descendants()
+        .filter(|node| node.has_tag_name("defs"))
+        .filter(|node| !node.ancestors().any(|node| node.has_tag_name("symbol")))
+        .map(|node| &text[node.range()])
+        .collect();
+    // Symbols may use other symbols, which are only rendered when used
+    let sheet: String = document
+        .descendants()
+        .filter(|node| node.has_tag_name("symbol"))
+        .map(|node| &text[node.range()])
+        .collect();
+
+    let mut symbols = Vec::new();
+    for symbol in document
+        .descendants()
+        .filter(|node| node.has_tag_name("symbol"))
+    {
+        let Some(id) = symbol.attribute("id") else {
+            continue;
+        };
+        let content = match (symbol.first_child(), symbol.last_child()) {
+            (Some(first), Some(last)) => &text[first.range().start..last.range().end],
+            _ => "",
+        };
+        let view_box = symbol.attribute("viewBox");
+        let size = view_box
+            .map(|view_box| view_box.split([' ', ',']).filter(|s| !s.is_empty()))
+            .and_then(|mut view_box| Some((view_box.nth(2)?, view_box.next()?)));
+        let width = symbol.attribute("width").or(size.map(|(width, _)| width));
+        let height = symbol
+            .attribute("height")
+            .or(size.map(|(_, height)| height));
+
+        let mut attributes = namespaces.clone();
+        for (name, value) in [("viewBox", view_box), ("width", width), ("height", height)] {
+            if let Some(value) = value {
+                attributes.push_str(&format!(" {name}=\"{value}\""));
+            }
+        }
+        symbols.push((
+            id.to_string(),
+            format!("<svg{attributes}>{defs}{sheet}{content}</svg>"),
+        ));
+    }
+    Ok(symbols)
+}
@@ -8,32 +8,82 @@ use bevy::prelude::*;
 mod plugin;
 pub use plugin::VelloPlugin;
 
+pub mod brush;
 pub mod debug;
+#[cfg(feature = "debug-overlay")]
+pub mod debug_overlay;
+pub mod draw;
+pub mod gizmos;
 pub mod integrations;
+pub mod morph;
+pub mod particles;
+pub mod path_ops;
+#[cfg(feature = "bevy_mod_picking")]
+pub mod picking_backend;
+pub mod pointer;
 pub mod render;
+mod scene;
+pub mod stroke;
 pub mod text;
+pub mod tween;
 
 // Re-exports
-pub use {velato, vello, vello_svg};
+pub use {::velato, ::vello, ::vello_svg};
 
 pub mod prelude {
     pub use {vello, vello::kurbo, vello::peniko, vello::skrifa};
 
+    pub use bevy::sprite::Anchor;
+
+    pub use crate::brush::VelloGradient;
     pub use crate::debug::DebugVisualizations;
+    #[cfg(feature = "debug-overlay")]
+    pub use crate::debug_overlay::{VelloDebugOverlay, VelloDebugPlugin};
+    pub use crate::draw::VelloDraw;
+    pub use crate::gizmos::{VelloGizmoConfig, VelloGizmos, VelloGizmosPlugin};
     pub use crate::integrations::{VectorFile, VelloAsset, VelloAssetAlignment};
-    pub use crate::render::{VelloCanvasMaterial, ZFunction};
-    pub use crate::text::{VelloFont, VelloText, VelloTextAlignment};
+    pub use crate::morph::VelloPathMorph;
+    pub use crate::particles::{VelloParticleCurve, VelloParticleEmitter, VelloParticles};
+    pub use crate::pointer::{VelloPickable, VelloPointerEvent, VelloPointerEventKind};
+    pub use crate::render::{
+        AlphaOverride, VelloBakeRequests, VelloBaked, VelloBlendMode, VelloClip,
+        VelloConstantScale, VelloFlip, VelloInstance, VelloInstances, VelloNineSlice,
+        VelloNonScalingStroke, VelloRenderSettings, VelloScaleMode, VelloSize, VelloTint,
+        VelloZIndex, ZFunction,
+    };
+    pub use crate::stroke::{VelloStroke, VelloStrokeCap, VelloStrokeJoin};
+    #[cfg(feature = "system-fonts")]
+    pub use crate::text::VelloSystemFonts;
+    pub use crate::text::{
+        VelloFont, VelloFontFallback, VelloText, VelloTextAlign, VelloTextAlignment,
+        VelloTextDirection, VelloTextLayout, VelloTextNodeBounds, VelloTextOutline,
+        VelloTextReveal, VelloTextSection, VelloTextSelection,
+    };
+    pub use crate::tween::{
+        AlphaLens, PathMorphLens, TweenEasing, TweenRepeat, VelloLens, VelloTween,
+    };
+    #[cfg(feature = "lottie")]
+    pub use crate::tween::{LerpColorSpace, PlaybackSpeedLens, ThemeColorLens, ThemeLerp};
     pub use crate::{
         CoordinateSpace, VelloAssetBundle, VelloScene, VelloSceneBundle, VelloTextBundle,
     };
 
     #[cfg(feature = "experimental-dotLottie")]
-    pub use crate::integrations::dot_lottie::{DotLottiePlayer, PlayerState, PlayerTransition};
+    pub use crate::integrations::dot_lottie::{
+        AnimationStateEntered, DotLottieAppExt, DotLottiePlayer, PlaybackOverrides,
+        PlayerCrossfade, PlayerState, PlayerStateMachine, PlayerTransition, PlayerWarning,
+        TransitionGuard,
+    };
     #[cfg(feature = "lottie")]
     pub use crate::integrations::lottie::{
-        LottieExt, PlaybackDirection, PlaybackLoopBehavior, PlaybackOptions, PlaybackPlayMode,
-        Playhead, Theme,
+        AnimationCompleted, AnimationGroup, AnimationLoopCompleted, DotLottieArchive, LottieExt,
+        LottieLayerVisibility, LottieSlotValue, LottieSlots, LottieSocket, LottieTextOverrides,
+        PlaybackClock, PlaybackDirection, PlaybackDriver, PlaybackLoopBehavior, PlaybackOptions,
+        PlaybackPlayMode, PlaybackSegments, Playhead, Theme, ThemePaint, ThemeTarget,
+        VelloAnimationTime,
     };
+    #[cfg(feature = "svg")]
+    pub use crate::integrations::svg::SvgElementOverrides;
 }
 
 /// Which coordinate space the transform is relative to.
@@ -59,6 +109,8 @@ pub struct VelloAssetBundle {
     pub global_transform: GlobalTransform,
     /// Use a depth-sorting function for this asset, used when rendering. By default, all assets use the transform's Z-coordinate for depth sorting in the renderer's painter's algorithm (see [`ZFunction::Inherited`]).
     pub z_function: ZFunction,
+    /// The order of this asset among all vello instances with the same Z-index.
+    pub z_index: VelloZIndex,
     /// Whether to render debug visualizations
     pub debug_visualizations: DebugVisualizations,
     /// User indication of whether an entity is visible. Propagates down the entity hierarchy.
@@ -77,6 +129,8 @@ pub struct VelloSceneBundle {
     pub coordinate_space: CoordinateSpace,
     /// A transform to apply to this scene
     pub transform: Transform,
+    /// The order of this scene among all vello instances with the same Z-index.
+    pub z_index: VelloZIndex,
     /// The global transform managed by Bevy
     pub global_transform: GlobalTransform,
     /// User indication of whether an entity is visible. Propagates down the entity hierarchy.
@@ -89,7 +143,8 @@ pub struct VelloSceneBundle {
 
 #[derive(Bundle, Default)]
 pub struct VelloTextBundle {
-    /// Font to render
+    /// Font to render, by default the embedded Fira Mono with the
+    /// `default-font` feature
     pub font: Handle<VelloFont>,
     /// Text to render
     pub text: VelloText,
@@ -99,6 +154,8 @@ pub struct VelloTextBundle {
     pub coordinate_space: CoordinateSpace,
     /// A transform to apply to this text
     pub transform: Transform,
+    /// The order of this text among all vello instances with the same Z-index.
+    pub z_index: VelloZIndex,
     /// The global transform managed by Bevy
     pub global_transform: GlobalTransform,
     /// Whether to render debug visualizations
@@ -112,8 +169,12 @@ pub struct VelloTextBundle {
 }
 
 /// A simple newtype component wrapper for [`vello::Scene`] for rendering.
-#[derive(Component, Default, Clone)]
-pub struct VelloScene(vello::Scene);
+///
+/// The scene isn't reflected, so scenes spawned from a `DynamicScene` are
+/// empty until they are drawn.
+#[derive(Component, Default, Clone, Reflect)]
+#[reflect(Component)]
+pub struct VelloScene(#[reflect(ignore)] vello::Scene);
 
 impl std::ops::Deref for VelloScene {
     type Target = vello::Scene;
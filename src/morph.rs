@@ -0,0 +1,317 @@
+//! Morphs between two paths, e.g. for icons changing shape (play to pause)
+//! or blobby UI.
+//!
+//! Paths with different numbers of subpaths or segments are resampled, by
+//! splitting their longest segments and collapsing missing subpaths to
+//! points, so any two paths can be morphed.
+
+use crate::brush::to_peniko_color;
+use crate::stroke::VelloStroke;
+use crate::VelloScene;
+use bevy::prelude::*;
+use vello::kurbo::{Affine, BezPath, CubicBez, ParamCurve, ParamCurveArclen, PathEl, Point, Shape};
+use vello::peniko::Fill;
+
+/// The accuracy of the lengths of segments when resampling, in the units of
+/// the paths.
+const ARCLEN_ACCURACY: f64 = 0.1;
+
+/// Draws the shapes of [`VelloPathMorph`]s into their scenes.
+pub struct VelloMorphPlugin;
+
+impl Plugin for VelloMorphPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<VelloPathMorph>()
+            .add_systems(PostUpdate, draw_path_morphs);
+    }
+}
+
+/// Draw the shape between two paths into the [`VelloScene`] of this entity,
+/// e.g. of a [`VelloSceneBundle`](crate::VelloSceneBundle), replacing the
+/// scene whenever the morph changes. Tween `t` with a
+/// [`PathMorphLens`](crate::tween::PathMorphLens).
+///
+/// The paths are in the coordinates of the scene with Y down, like the
+/// shapes of [`kurbo`](vello::kurbo).
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_vello::prelude::*;
+/// # use bevy_vello::vello::kurbo::BezPath;
+/// # use std::time::Duration;
+/// # fn setup(mut commands: Commands) {
+/// # let (play_icon, pause_icon) = (BezPath::new(), BezPath::new());
+/// commands
+///     .spawn(VelloSceneBundle::default())
+///     .insert(VelloPathMorph::new(play_icon, pause_icon).with_color(Color::WHITE))
+///     .insert(VelloTween::new(
+///         PathMorphLens { start: 0.0, end: 1.0 },
+///         Duration::from_millis(300),
+///     ));
+/// # }
+/// ```
+#[derive(Component, Default, Clone, Debug, Reflect)]
+#[reflect(Component)]
+pub struct VelloPathMorph {
+    /// The path at `t` of `0.0`.
+    #[reflect(ignore)]
+    pub from: BezPath,
+    /// The path at `t` of `1.0`.
+    #[reflect(ignore)]
+    pub to: BezPath,
+    /// The ratio of the morph from `from` to `to`.
+    pub t: f32,
+    /// The color of the shape.
+    pub color: Color,
+    /// The stroke of the shape, or `None` to fill it.
+    pub stroke: Option<VelloStroke>,
+}
+
+impl VelloPathMorph {
+    pub fn new(from: BezPath, to: BezPath) -> Self {
+        Self {
+            from,
+            to,
+            ..default()
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_stroke(mut self, stroke: VelloStroke) -> Self {
+        self.stroke = Some(stroke);
+        self
+    }
+
+    /// The shape at `t`.
+    pub fn path(&self) -> BezPath {
+        lerp_paths(&self.from, &self.to, self.t as f64)
+    }
+}
+
+/// The path between two paths at a ratio, from `from` at `0.0` to `to` at
+/// `1.0`. Resample the paths once with [`MorphPaths`] to morph them every
+/// frame.
+pub fn lerp_paths(from: &BezPath, to: &BezPath, t: f64) -> BezPath {
+    MorphPaths::new(from, to).path(t)
+}
+
+/// Two paths resampled to the same subpaths and segments, so their shapes
+/// can be interpolated.
+#[derive(Clone, Debug, Default)]
+pub struct MorphPaths {
+    contours: Vec<(Contour, Contour)>,
+}
+
+impl MorphPaths {
+    pub fn new(from: &BezPath, to: &BezPath) -> Self {
+        let mut from = contours(from);
+        let mut to = contours(to);
+        // Missing subpaths grow from the centers of the ones they morph into
+        while from.len() < to.len() {
+            from.push(to[from.len()].collapsed());
+        }
+        while to.len() < from.len() {
+            to.push(from[to.len()].collapsed());
+        }
+        let contours = from
+            .into_iter()
+            .zip(to)
+            .map(|(mut from, mut to)| {
+                while from.segments.len() < to.segments.len() {
+                    from.split_longest();
+                }
+                while to.segments.len() < from.segments.len() {
+                    to.split_longest();
+                }
+                if from.closed && to.closed {
+                    to.align_start(&from);
+                }
+                (from, to)
+            })
+            .collect();
+        Self { contours }
+    }
+
+    /// The path at a ratio, from the first path at `0.0` to the second at
+    /// `1.0`.
+    pub fn path(&self, t: f64) -> BezPath {
+        let lerp = |a: Point, b: Point| a.lerp(b, t);
+        let mut path = BezPath::new();
+        for (from, to) in &self.contours {
+            for (i, (a, b)) in from.segments.iter().zip(&to.segments).enumerate() {
+                if i == 0 {
+                    path.move_to(lerp(a.p0, b.p0));
+                }
+                path.curve_to(lerp(a.p1, b.p1), lerp(a.p2, b.p2), lerp(a.p3, b.p3));
+            }
+            let closed = match t < 0.5 {
+                true => from.closed,
+                false => to.closed,
+            };
+            if closed {
+                path.close_path();
+            }
+        }
+        path
+    }
+}
+
+/// A subpath as cubic segments.
+#[derive(Clone, Debug, Default)]
+struct Contour {
+    segments: Vec<CubicBez>,
+    closed: bool,
+}
+
+impl Contour {
+    /// A point at the center of the contour, with as many segments.
+    fn collapsed(&self) -> Self {
+        let mut path = BezPath::new();
+        for segment in &self.segments {
+            path.move_to(segment.p0);
+            path.curve_to(segment.p1, segment.p2, segment.p3);
+        }
+        let center = path.bounding_box().center();
+        Self {
+            segments: vec![CubicBez::new(center, center, center, center); self.segments.len()],
+            closed: self.closed,
+        }
+    }
+
+    /// Split the longest segment in half.
+    fn split_longest(&mut self) {
+        let Some((index, _)) = self
+            .segments
+            .iter()
+            .map(|segment| segment.arclen(ARCLEN_ACCURACY))
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+        else {
+            return;
+        };
+        let (first, second) = self.segments[index].subdivide();
+        self.segments[index] = first;
+        self.segments.insert(index + 1, second);
+    }
+
+    /// Rotate the segments of a closed contour to start nearest the start of
+    /// another, so the shape doesn't twist as it morphs.
+    fn align_start(&mut self, other: &Contour) {
+        let distance = |offset: usize| -> f64 {
+            let n = self.segments.len();
+            (0..n)
+                .map(|i| {
+                    let point = self.segments[(i + offset) % n].p0;
+                    (point - other.segments[i].p0).hypot2()
+                })
+                .sum()
+        };
+        let best = (0..self.segments.len()).min_by(|a, b| distance(*a).total_cmp(&distance(*b)));
+        if let Some(best) = best {
+            self.segments.rotate_left(best);
+        }
+    }
+}
+
+/// The subpaths of a path with at least one segment, with lines and
+/// quadratic segments raised to cubics.
+fn contours(path: &BezPath) -> Vec<Contour> {
+    let mut contours = Vec::new();
+    let mut contour = Contour::default();
+    let mut start = Point::ORIGIN;
+    let mut last = Point::ORIGIN;
+    let line = |from: Point, to: Point| {
+        CubicBez::new(from, from.lerp(to, 1.0 / 3.0), from.lerp(to, 2.0 / 3.0), to)
+    };
+    for element in path.elements() {
+        match *element {
+            PathEl::MoveTo(point) => {
+                contours.push(std::mem::take(&mut contour));
+                start = point;
+                last = point;
+            }
+            PathEl::LineTo(point) => {
+                contour.segments.push(line(last, point));
+                last = point;
+            }
+            PathEl::QuadTo(p1, p2) => {
+                let quad = vello::kurbo::QuadBez::new(last, p1, p2);
+                contour.segments.push(quad.raise());
+                last = p2;
+            }
+            PathEl::CurveTo(p1, p2, p3) => {
+                contour.segments.push(CubicBez::new(last, p1, p2, p3));
+                last = p3;
+            }
+            PathEl::ClosePath => {
+                if last != start {
+                    contour.segments.push(line(last, start));
+                }
+                contour.closed = true;
+                contours.push(std::mem::take(&mut contour));
+                last = start;
+            }
+        }
+    }
+    contours.push(contour);
+    contours.retain(|contour| !contour.segments.is_empty());
+    contours
+}
+
+/// The resampled paths of a [`VelloPathMorph`], kept until its paths change.
+#[derive(Component)]
+struct MorphCache {
+    from: BezPath,
+    to: BezPath,
+    paths: MorphPaths,
+}
+
+impl MorphCache {
+    fn new(morph: &VelloPathMorph) -> Self {
+        Self {
+            from: morph.from.clone(),
+            to: morph.to.clone(),
+            paths: MorphPaths::new(&morph.from, &morph.to),
+        }
+    }
+}
+
+fn draw_path_morphs(
+    mut commands: Commands,
+    mut morphs: Query<
+        (
+            Entity,
+            &VelloPathMorph,
+            &mut VelloScene,
+            Option<&mut MorphCache>,
+        ),
+        Changed<VelloPathMorph>,
+    >,
+) {
+    for (entity, morph, mut scene, cache) in &mut morphs {
+        let t = morph.t as f64;
+        let path = match cache {
+            Some(cache) if cache.from == morph.from && cache.to == morph.to => cache.paths.path(t),
+            Some(mut cache) => {
+                *cache = MorphCache::new(morph);
+                cache.paths.path(t)
+            }
+            None => {
+                let cache = MorphCache::new(morph);
+                let path = cache.paths.path(t);
+                commands.entity(entity).insert(cache);
+                path
+            }
+        };
+        *scene = VelloScene::new();
+        let color = to_peniko_color(morph.color);
+        match &morph.stroke {
+            Some(stroke) => scene.stroke(&stroke.into(), Affine::IDENTITY, color, None, &path),
+            None => scene.fill(Fill::NonZero, Affine::IDENTITY, color, None, &path),
+        }
+    }
+}
@@ -0,0 +1,295 @@
+//! Emitters of vector particles, e.g. for sparks, magic and UI effects which
+//! stay crisp at any zoom.
+//!
+//! A [`VelloParticleEmitter`] draws its particles with the asset or scene of
+//! its entity, e.g. of a [`VelloAssetBundle`](crate::VelloAssetBundle), as the
+//! [`VelloInstances`] of the entity, so thousands of particles only cost one
+//! encode of the asset.
+
+use crate::render::{VelloInstance, VelloInstances};
+use crate::tween::TweenEasing;
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+
+/// Updates the particles of [`VelloParticleEmitter`]s.
+pub struct VelloParticlesPlugin;
+
+impl Plugin for VelloParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<VelloParticleEmitter>().add_systems(
+            PostUpdate,
+            update_particle_emitters.after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// A value over the lifetime of a particle, eased from its start to its end.
+#[derive(PartialEq, Clone, Copy, Debug, Reflect)]
+pub struct VelloParticleCurve {
+    pub start: f32,
+    pub end: f32,
+    pub easing: TweenEasing,
+}
+
+impl VelloParticleCurve {
+    /// The same value over the lifetime of a particle.
+    pub fn constant(value: f32) -> Self {
+        Self::linear(value, value)
+    }
+
+    /// A value changing at a constant rate over the lifetime of a particle.
+    pub fn linear(start: f32, end: f32) -> Self {
+        Self {
+            start,
+            end,
+            easing: TweenEasing::Linear,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: TweenEasing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// The value at a ratio of the lifetime of a particle, from `0.0` to
+    /// `1.0`.
+    pub fn sample(&self, t: f32) -> f32 {
+        self.start + (self.end - self.start) * self.easing.ease(t)
+    }
+}
+
+/// Emit particles drawn with the asset or scene of this entity. The
+/// [`VelloInstances`] of the entity are replaced by its [`VelloParticles`]
+/// every frame.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_vello::prelude::*;
+/// # fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// commands
+///     .spawn(VelloAssetBundle {
+///         vector: asset_server.load("spark.svg"),
+///         ..default()
+///     })
+///     .insert(VelloParticleEmitter {
+///         rate: 200.0,
+///         spread: std::f32::consts::FRAC_PI_4,
+///         acceleration: Vec2::new(0.0, -200.0),
+///         ..default()
+///     });
+/// # }
+/// ```
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component)]
+pub struct VelloParticleEmitter {
+    /// Whether new particles are emitted. Live particles finish their
+    /// lifetime either way.
+    pub emitting: bool,
+    /// The particles emitted per second.
+    pub rate: f32,
+    /// The seconds a particle lives.
+    pub lifetime: f32,
+    /// The most particles alive at once.
+    pub max_particles: usize,
+    /// The velocity of new particles, in units per second.
+    pub velocity: Vec2,
+    /// The angle in radians around the velocity within which the directions
+    /// of new particles are randomized.
+    pub spread: f32,
+    /// The acceleration of particles in units per second squared, e.g.
+    /// gravity.
+    pub acceleration: Vec2,
+    /// The speed of particles over their lifetime, as a multiple of their
+    /// velocity.
+    pub speed: VelloParticleCurve,
+    /// The scale of particles over their lifetime.
+    pub size: VelloParticleCurve,
+    /// The opacity of particles over their lifetime.
+    pub alpha: VelloParticleCurve,
+    /// The color multiplied with the colors of particles.
+    pub tint: Option<Color>,
+    /// Whether particles move in the world, so they stay where they were
+    /// emitted as the emitter moves, rather than moving with it.
+    pub world_space: bool,
+}
+
+impl Default for VelloParticleEmitter {
+    fn default() -> Self {
+        Self {
+            emitting: true,
+            rate: 10.0,
+            lifetime: 1.0,
+            max_particles: 1000,
+            velocity: Vec2::new(0.0, 100.0),
+            spread: 0.0,
+            acceleration: Vec2::ZERO,
+            speed: VelloParticleCurve::constant(1.0),
+            size: VelloParticleCurve::constant(1.0),
+            alpha: VelloParticleCurve::linear(1.0, 0.0),
+            tint: None,
+            world_space: false,
+        }
+    }
+}
+
+/// The live particles of a [`VelloParticleEmitter`], inserted with the
+/// emitter's first update unless the entity already has them.
+#[derive(Component, Default, Clone, Debug)]
+pub struct VelloParticles {
+    particles: Vec<Particle>,
+    /// The particles owed by the rate of the emitter, and bursts.
+    pending: f32,
+    rng: u64,
+}
+
+/// A live particle, in the space it is simulated in.
+#[derive(Clone, Copy, Debug)]
+struct Particle {
+    position: Vec2,
+    velocity: Vec2,
+    age: f32,
+}
+
+impl VelloParticles {
+    /// Emit a number of particles at the next update, e.g. for an explosion.
+    pub fn burst(&mut self, count: usize) {
+        self.pending += count as f32;
+    }
+
+    /// The number of live particles.
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Remove every live particle.
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.pending = 0.0;
+    }
+
+    /// A random number from `0.0` to `1.0`, by splitmix64.
+    fn random(&mut self) -> f32 {
+        self.rng = self.rng.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Age and move the live particles, and emit new ones.
+    fn simulate(
+        &mut self,
+        emitter: &VelloParticleEmitter,
+        delta: f32,
+        transform: &GlobalTransform,
+    ) {
+        let lifetime = emitter.lifetime;
+        self.particles.retain_mut(|particle| {
+            particle.age += delta;
+            particle.age < lifetime
+        });
+        for particle in self.particles.iter_mut() {
+            let speed = emitter.speed.sample(particle.age / lifetime);
+            particle.velocity += emitter.acceleration * delta;
+            particle.position += particle.velocity * speed * delta;
+        }
+
+        if emitter.emitting {
+            self.pending += emitter.rate * delta;
+        }
+        let count = self.pending.floor();
+        self.pending -= count;
+        let (origin, velocity) = match emitter.world_space {
+            true => (
+                transform.translation().truncate(),
+                transform
+                    .affine()
+                    .transform_vector3(emitter.velocity.extend(0.0))
+                    .truncate(),
+            ),
+            false => (Vec2::ZERO, emitter.velocity),
+        };
+        for _ in 0..count as usize {
+            if self.particles.len() >= emitter.max_particles {
+                break;
+            }
+            let angle = (self.random() - 0.5) * emitter.spread;
+            self.particles.push(Particle {
+                position: origin,
+                velocity: Vec2::from_angle(angle).rotate(velocity),
+                age: 0.0,
+            });
+        }
+    }
+
+    /// The live particles, relative to the emitter.
+    fn instances<'a>(
+        &'a self,
+        emitter: &'a VelloParticleEmitter,
+        transform: &GlobalTransform,
+    ) -> impl Iterator<Item = VelloInstance> + 'a {
+        let to_local = match emitter.world_space {
+            true => transform.compute_matrix().inverse(),
+            false => Mat4::IDENTITY,
+        };
+        let tint = emitter.tint.unwrap_or(Color::WHITE);
+        self.particles.iter().map(move |particle| {
+            let t = particle.age / emitter.lifetime;
+            let local = Transform::from_translation(particle.position.extend(0.0))
+                .with_scale(Vec3::splat(emitter.size.sample(t)));
+            let tint = tint.with_a(tint.a() * emitter.alpha.sample(t).clamp(0.0, 1.0));
+            VelloInstance {
+                transform: Transform::from_matrix(to_local * local.compute_matrix()),
+                tint: (tint.as_rgba_f32() != [1.0; 4]).then_some(tint),
+            }
+        })
+    }
+}
+
+/// Simulate the particles of emitters, and draw them as the instances of
+/// their entities.
+fn update_particle_emitters(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut emitters: Query<(
+        Entity,
+        &VelloParticleEmitter,
+        &GlobalTransform,
+        Option<&mut VelloParticles>,
+        Option<&mut VelloInstances>,
+    )>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, emitter, transform, particles, instances) in emitters.iter_mut() {
+        let mut inserted = None;
+        let particles = match particles {
+            Some(particles) => particles.into_inner(),
+            None => inserted.insert(VelloParticles::default()),
+        };
+        // Emitters spawned together don't emit the same particles
+        if particles.rng == 0 {
+            particles.rng = entity.to_bits();
+        }
+        particles.simulate(emitter, delta, transform);
+        let drawn = particles.instances(emitter, transform);
+        match instances {
+            Some(mut instances) => {
+                instances.instances.clear();
+                instances.instances.extend(drawn);
+            }
+            None => {
+                commands.entity(entity).insert(VelloInstances {
+                    instances: drawn.collect(),
+                });
+            }
+        }
+        if let Some(particles) = inserted {
+            commands.entity(entity).insert(particles);
+        }
+    }
+}
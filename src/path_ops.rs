@@ -0,0 +1,465 @@
+//! Boolean operations on filled paths, e.g. to cut a fog of war around
+//! revealed areas, or compose shapes before encoding them into a scene.
+//!
+//! Curves are flattened into lines within a tolerance, so the results are
+//! polygons. Every pair of lines which overlap horizontally is tested for
+//! intersections, which suits shapes of up to a few thousand lines rather
+//! than whole maps.
+//!
+//! ```no_run
+//! # use bevy_vello::path_ops;
+//! # use bevy_vello::vello::kurbo::{Affine, Circle, Rect, Shape};
+//! # use bevy_vello::vello::peniko::{Color, Fill};
+//! # let mut scene = bevy_vello::vello::Scene::new();
+//! let hole = Circle::new((50.0, 50.0), 20.0).to_path(0.1);
+//! let fog = path_ops::difference(&Rect::new(0.0, 0.0, 100.0, 100.0).to_path(0.1), &hole, 0.1);
+//! scene.fill(Fill::NonZero, Affine::IDENTITY, Color::BLACK, None, &fog);
+//! ```
+
+use bevy::utils::{HashMap, HashSet};
+use vello::kurbo::{self, BezPath, PathEl, Point, Vec2};
+use vello::peniko::Fill;
+
+/// A boolean operation on the areas filled by two paths.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum PathOp {
+    /// The areas filled by either path.
+    Union,
+    /// The areas filled by both paths.
+    Intersection,
+    /// The areas filled by the first path but not the second.
+    Difference,
+    /// The areas filled by exactly one of the paths.
+    Xor,
+}
+
+impl PathOp {
+    /// Whether a point is in the result, from whether it is in either path.
+    pub fn apply(&self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            PathOp::Union => in_a || in_b,
+            PathOp::Intersection => in_a && in_b,
+            PathOp::Difference => in_a && !in_b,
+            PathOp::Xor => in_a != in_b,
+        }
+    }
+}
+
+/// The area filled by either path.
+pub fn union(a: &BezPath, b: &BezPath, tolerance: f64) -> BezPath {
+    boolean(a, b, PathOp::Union, tolerance)
+}
+
+/// The area filled by both paths.
+pub fn intersection(a: &BezPath, b: &BezPath, tolerance: f64) -> BezPath {
+    boolean(a, b, PathOp::Intersection, tolerance)
+}
+
+/// The area filled by the first path but not the second.
+pub fn difference(a: &BezPath, b: &BezPath, tolerance: f64) -> BezPath {
+    boolean(a, b, PathOp::Difference, tolerance)
+}
+
+/// The area filled by exactly one of the paths.
+pub fn xor(a: &BezPath, b: &BezPath, tolerance: f64) -> BezPath {
+    boolean(a, b, PathOp::Xor, tolerance)
+}
+
+/// A boolean operation on the areas filled by two paths with the nonzero
+/// rule. Curves are flattened within the tolerance.
+///
+/// The result is closed polygons with the filled area on their left, as
+/// seen with Y up, so it can be filled with either rule, or used as the
+/// operand of another operation.
+pub fn boolean(a: &BezPath, b: &BezPath, op: PathOp, tolerance: f64) -> BezPath {
+    boolean_with_fills(&[(a, Fill::NonZero)], &[(b, Fill::NonZero)], op, tolerance)
+}
+
+/// A boolean operation on the areas filled by two sets of paths, each with
+/// a fill rule. A point is in a set if it is filled by any of its paths.
+pub fn boolean_with_fills(
+    a: &[(&BezPath, Fill)],
+    b: &[(&BezPath, Fill)],
+    op: PathOp,
+    tolerance: f64,
+) -> BezPath {
+    let a = Operand::new(a, tolerance);
+    let b = Operand::new(b, tolerance);
+    // Vertices closer than this are merged
+    let snap = (tolerance * 1e-3).max(1e-9);
+    // Lines of disjoint operands can't cross, so they are only split by the
+    // lines of their own operand
+    let disjoint = match (a.bounds(), b.bounds()) {
+        (Some(bounds_a), Some(bounds_b)) => {
+            let bounds_a = bounds_a.inflate(snap * 10.0, snap * 10.0);
+            bounds_a.x1 < bounds_b.x0
+                || bounds_b.x1 < bounds_a.x0
+                || bounds_a.y1 < bounds_b.y0
+                || bounds_b.y1 < bounds_a.y0
+        }
+        _ => true,
+    };
+    if disjoint && op == PathOp::Intersection {
+        return BezPath::new();
+    }
+    let key = |point: Point| {
+        (
+            (point.x / snap).round() as i64,
+            (point.y / snap).round() as i64,
+        )
+    };
+
+    // Split every line where it crosses or touches another, then keep the
+    // pieces with the result on one side only, with the result on the left
+    let pieces = match disjoint {
+        true => {
+            let mut pieces = split_lines(&a.lines().collect::<Vec<_>>());
+            pieces.extend(split_lines(&b.lines().collect::<Vec<_>>()));
+            pieces
+        }
+        false => split_lines(&a.lines().chain(b.lines()).collect::<Vec<_>>()),
+    };
+    let offset = snap * 10.0;
+    let mut kept: Vec<Line> = Vec::new();
+    let mut seen = HashSet::new();
+    for (start, end) in pieces {
+        let (start, end) = (snap_point(start, snap), snap_point(end, snap));
+        let direction = end - start;
+        let length = direction.hypot();
+        if length < snap {
+            continue;
+        }
+        let middle = start.midpoint(end);
+        let left_normal = Vec2::new(-direction.y, direction.x) / length * offset;
+        let in_result = |point: Point| op.apply(a.contains(point), b.contains(point));
+        let line = match (
+            in_result(middle + left_normal),
+            in_result(middle - left_normal),
+        ) {
+            (true, false) => (start, end),
+            (false, true) => (end, start),
+            _ => continue,
+        };
+        // Lines shared by the paths are kept once
+        if seen.insert((key(line.0), key(line.1))) {
+            kept.push(line);
+        }
+    }
+
+    // Join the kept lines into closed polygons
+    let mut outgoing: HashMap<(i64, i64), Vec<usize>> = HashMap::default();
+    for (index, (start, _)) in kept.iter().enumerate() {
+        outgoing.entry(key(*start)).or_default().push(index);
+    }
+    let mut used = vec![false; kept.len()];
+    let mut path = BezPath::new();
+    for first in 0..kept.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let (start, mut end) = kept[first];
+        path.move_to(start);
+        while key(end) != key(start) {
+            let next = outgoing
+                .get(&key(end))
+                .and_then(|candidates| candidates.iter().copied().find(|index| !used[*index]));
+            let Some(next) = next else {
+                break;
+            };
+            used[next] = true;
+            path.line_to(end);
+            end = kept[next].1;
+        }
+        path.close_path();
+    }
+    path
+}
+
+type Line = (Point, Point);
+
+/// The flattened lines of a set of paths, each with a fill rule.
+struct Operand {
+    paths: Vec<(Vec<Line>, Fill)>,
+}
+
+impl Operand {
+    fn new(paths: &[(&BezPath, Fill)], tolerance: f64) -> Self {
+        Self {
+            paths: paths
+                .iter()
+                .map(|(path, fill)| (flatten(path, tolerance), *fill))
+                .collect(),
+        }
+    }
+
+    fn lines(&self) -> impl Iterator<Item = Line> + '_ {
+        self.paths
+            .iter()
+            .flat_map(|(lines, _)| lines.iter().copied())
+    }
+
+    /// The bounds of every line, if there are any.
+    fn bounds(&self) -> Option<kurbo::Rect> {
+        self.lines()
+            .map(|(start, end)| kurbo::Rect::from_points(start, end))
+            .reduce(|bounds, line| bounds.union(line))
+    }
+
+    fn contains(&self, point: Point) -> bool {
+        self.paths.iter().any(|(lines, fill)| {
+            let winding = winding(lines, point);
+            match fill {
+                Fill::NonZero => winding != 0,
+                Fill::EvenOdd => winding % 2 != 0,
+            }
+        })
+    }
+}
+
+/// The lines of a path flattened within a tolerance, with every subpath
+/// closed as it is when filled.
+fn flatten(path: &BezPath, tolerance: f64) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut start = Point::ORIGIN;
+    let mut last = Point::ORIGIN;
+    let push = |lines: &mut Vec<Line>, from: Point, to: Point| {
+        if from != to {
+            lines.push((from, to));
+        }
+    };
+    kurbo::flatten(path, tolerance, |element| match element {
+        PathEl::MoveTo(point) => {
+            push(&mut lines, last, start);
+            start = point;
+            last = point;
+        }
+        PathEl::LineTo(point) => {
+            push(&mut lines, last, point);
+            last = point;
+        }
+        PathEl::ClosePath => {
+            push(&mut lines, last, start);
+            last = start;
+        }
+        // Flattened paths only contain lines
+        PathEl::QuadTo(..) | PathEl::CurveTo(..) => {}
+    });
+    push(&mut lines, last, start);
+    lines
+}
+
+/// The winding number of lines around a point.
+fn winding(lines: &[Line], point: Point) -> i32 {
+    let mut winding = 0;
+    for (start, end) in lines {
+        let side = (*end - *start).cross(point - *start);
+        if start.y <= point.y {
+            if end.y > point.y && side > 0.0 {
+                winding += 1;
+            }
+        } else if end.y <= point.y && side < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+fn snap_point(point: Point, snap: f64) -> Point {
+    Point::new(
+        (point.x / snap).round() * snap,
+        (point.y / snap).round() * snap,
+    )
+}
+
+/// The lines split at every point where they cross or touch another line.
+fn split_lines(lines: &[Line]) -> Vec<Line> {
+    const EPSILON: f64 = 1e-12;
+    let mut splits: Vec<Vec<f64>> = vec![Vec::new(); lines.len()];
+    let bounds: Vec<kurbo::Rect> = lines
+        .iter()
+        .map(|(start, end)| kurbo::Rect::from_points(*start, *end))
+        .collect();
+    // Sweep the lines from left to right, so each is only tested against the
+    // lines which overlap it horizontally
+    let mut order: Vec<usize> = (0..lines.len()).collect();
+    order.sort_by(|i, j| bounds[*i].x0.total_cmp(&bounds[*j].x0));
+    for (position, &i) in order.iter().enumerate() {
+        let (a0, a1) = lines[i];
+        let r = a1 - a0;
+        let bounds_i = bounds[i];
+        for &j in &order[position + 1..] {
+            let bounds_j = bounds[j];
+            if bounds_i.x1 < bounds_j.x0 {
+                break;
+            }
+            if bounds_i.y1 < bounds_j.y0 || bounds_j.y1 < bounds_i.y0 {
+                continue;
+            }
+            let (b0, b1) = lines[j];
+            let s = b1 - b0;
+            let denominator = r.cross(s);
+            let offset = b0 - a0;
+            if denominator.abs() > EPSILON * r.hypot() * s.hypot() {
+                let t = offset.cross(s) / denominator;
+                let u = offset.cross(r) / denominator;
+                if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+                    splits[i].push(t);
+                    splits[j].push(u);
+                }
+            } else if offset.cross(r).abs() <= EPSILON * r.hypot() * offset.hypot().max(1.0) {
+                // Collinear lines split each other where they overlap
+                for point in [b0, b1] {
+                    splits[i].push((point - a0).dot(r) / r.hypot2());
+                }
+                for point in [a0, a1] {
+                    splits[j].push((point - b0).dot(s) / s.hypot2());
+                }
+            }
+        }
+    }
+    let mut pieces = Vec::new();
+    for ((start, end), mut splits) in lines.iter().copied().zip(splits) {
+        splits.retain(|t| *t > 0.0 && *t < 1.0);
+        splits.sort_by(f64::total_cmp);
+        let mut from = start;
+        for t in splits {
+            let to = start.lerp(end, t);
+            pieces.push((from, to));
+            from = to;
+        }
+        pieces.push((from, end));
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vello::kurbo::{Circle, Rect, Shape};
+
+    const TOLERANCE: f64 = 1e-3;
+
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> BezPath {
+        Rect::new(x0, y0, x1, y1).to_path(TOLERANCE)
+    }
+
+    fn circle(x: f64, y: f64, radius: f64) -> BezPath {
+        Circle::new((x, y), radius).to_path(TOLERANCE)
+    }
+
+    /// The filled area of a result, whose holes wind the other way.
+    fn area(path: &BezPath) -> f64 {
+        path.area().abs()
+    }
+
+    fn subpaths(path: &BezPath) -> usize {
+        path.elements()
+            .iter()
+            .filter(|element| matches!(element, PathEl::MoveTo(_)))
+            .count()
+    }
+
+    fn assert_area(path: &BezPath, expected: f64, epsilon: f64) {
+        let area = area(path);
+        assert!(
+            (area - expected).abs() < epsilon,
+            "area {area}, expected {expected}"
+        );
+    }
+
+    #[test]
+    fn overlapping_rects() {
+        let (a, b) = (rect(0.0, 0.0, 2.0, 2.0), rect(1.0, 1.0, 3.0, 3.0));
+        assert_area(&union(&a, &b, TOLERANCE), 7.0, 1e-9);
+        assert_area(&intersection(&a, &b, TOLERANCE), 1.0, 1e-9);
+        assert_area(&difference(&a, &b, TOLERANCE), 3.0, 1e-9);
+        assert_area(&xor(&a, &b, TOLERANCE), 6.0, 1e-9);
+        assert_eq!(subpaths(&union(&a, &b, TOLERANCE)), 1);
+    }
+
+    #[test]
+    fn overlapping_circles() {
+        let (a, b) = (circle(0.0, 0.0, 1.0), circle(1.0, 0.0, 1.0));
+        let circle_area = std::f64::consts::PI;
+        let lens = 2.0 * 0.5f64.acos() - 0.5 * 3.0f64.sqrt();
+        assert_area(&intersection(&a, &b, TOLERANCE), lens, 1e-2);
+        assert_area(&union(&a, &b, TOLERANCE), 2.0 * circle_area - lens, 1e-2);
+        assert_area(&difference(&a, &b, TOLERANCE), circle_area - lens, 1e-2);
+        assert_area(&xor(&a, &b, TOLERANCE), 2.0 * (circle_area - lens), 1e-2);
+    }
+
+    #[test]
+    fn disjoint_shapes() {
+        let (a, b) = (rect(0.0, 0.0, 1.0, 1.0), circle(5.0, 5.0, 1.0));
+        let circle_area = std::f64::consts::PI;
+        assert_area(&union(&a, &b, TOLERANCE), 1.0 + circle_area, 1e-2);
+        assert!(intersection(&a, &b, TOLERANCE).is_empty());
+        assert_area(&difference(&a, &b, TOLERANCE), 1.0, 1e-9);
+        assert_area(&difference(&b, &a, TOLERANCE), circle_area, 1e-2);
+        assert_area(&xor(&a, &b, TOLERANCE), 1.0 + circle_area, 1e-2);
+    }
+
+    #[test]
+    fn nested_shapes() {
+        let (outer, inner) = (rect(0.0, 0.0, 4.0, 4.0), rect(1.0, 1.0, 2.0, 2.0));
+        assert_area(&union(&outer, &inner, TOLERANCE), 16.0, 1e-9);
+        assert_area(&intersection(&outer, &inner, TOLERANCE), 1.0, 1e-9);
+        assert!(difference(&inner, &outer, TOLERANCE).is_empty());
+        // The inner rect is cut as a hole
+        let cut = difference(&outer, &inner, TOLERANCE);
+        assert_area(&cut, 15.0, 1e-9);
+        assert_eq!(subpaths(&cut), 2);
+        assert!(cut.contains(Point::new(0.5, 0.5)));
+        assert!(!cut.contains(Point::new(1.5, 1.5)));
+    }
+
+    #[test]
+    fn shared_edges() {
+        let (a, b) = (rect(0.0, 0.0, 1.0, 1.0), rect(1.0, 0.0, 2.0, 1.0));
+        let merged = union(&a, &b, TOLERANCE);
+        assert_area(&merged, 2.0, 1e-9);
+        assert_eq!(subpaths(&merged), 1);
+        assert!(intersection(&a, &b, TOLERANCE).is_empty());
+        assert_area(&difference(&a, &b, TOLERANCE), 1.0, 1e-9);
+        // A shorter edge shared with part of a longer one
+        let tall = rect(1.0, 0.0, 2.0, 2.0);
+        let merged = union(&a, &tall, TOLERANCE);
+        assert_area(&merged, 3.0, 1e-9);
+        assert_eq!(subpaths(&merged), 1);
+    }
+
+    #[test]
+    fn collinear_segments() {
+        let (a, b) = (rect(0.0, 0.0, 2.0, 1.0), rect(1.0, 0.0, 3.0, 1.0));
+        assert_area(&union(&a, &b, TOLERANCE), 3.0, 1e-9);
+        assert_area(&intersection(&a, &b, TOLERANCE), 1.0, 1e-9);
+        assert_area(&difference(&a, &b, TOLERANCE), 1.0, 1e-9);
+        assert_area(&xor(&a, &b, TOLERANCE), 2.0, 1e-9);
+        assert_eq!(subpaths(&union(&a, &b, TOLERANCE)), 1);
+    }
+
+    #[test]
+    fn empty_paths() {
+        let (a, empty) = (rect(0.0, 0.0, 1.0, 1.0), BezPath::new());
+        assert_area(&union(&a, &empty, TOLERANCE), 1.0, 1e-9);
+        assert_area(&union(&empty, &a, TOLERANCE), 1.0, 1e-9);
+        assert!(intersection(&a, &empty, TOLERANCE).is_empty());
+        assert_area(&difference(&a, &empty, TOLERANCE), 1.0, 1e-9);
+        assert!(difference(&empty, &a, TOLERANCE).is_empty());
+        assert!(union(&empty, &empty, TOLERANCE).is_empty());
+    }
+
+    #[test]
+    fn zero_area_paths() {
+        let a = rect(0.0, 0.0, 1.0, 1.0);
+        let mut line = BezPath::new();
+        line.move_to((-1.0, 0.5));
+        line.line_to((2.0, 0.5));
+        line.close_path();
+        assert_area(&union(&a, &line, TOLERANCE), 1.0, 1e-9);
+        assert_area(&difference(&a, &line, TOLERANCE), 1.0, 1e-9);
+        assert!(intersection(&a, &line, TOLERANCE).is_empty());
+        assert!(union(&line, &line, TOLERANCE).is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+//! A [`bevy_mod_picking`](https://github.com/aevyrie/bevy_mod_picking)
+//! backend, which reports the [`VelloPickable`](crate::pointer::VelloPickable)
+//! entities under each pointer to the picking pipeline.
+//!
+//! Entities are hit tested by [`VelloPicking`], so they are ordered by their
+//! depth from the camera, and then by their [`VelloZIndex`](crate::VelloZIndex).
+
+use crate::pointer::VelloPicking;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_picking_core::backend::{HitData, PointerHits};
+use bevy_picking_core::pointer::{PointerId, PointerLocation};
+use bevy_picking_core::PickSet;
+
+/// Sends the [`PointerHits`] of vello entities. Add it with the plugins of
+/// `bevy_mod_picking`.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_vello::picking_backend::VelloPickingBackendPlugin;
+/// # use bevy_vello::VelloPlugin;
+/// App::new()
+///     .add_plugins((DefaultPlugins, VelloPlugin, VelloPickingBackendPlugin))
+///     .run();
+/// ```
+pub struct VelloPickingBackendPlugin;
+
+impl Plugin for VelloPickingBackendPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PointerHits>()
+            .add_systems(PreUpdate, send_pointer_hits.in_set(PickSet::Backend));
+    }
+}
+
+/// Hit test the vello entities under every pointer, from each 2D camera
+/// whose viewport contains the pointer.
+fn send_pointer_hits(
+    pointers: Query<(&PointerId, &PointerLocation)>,
+    cameras: Query<(Entity, &Camera), With<Camera2d>>,
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+    picking: VelloPicking,
+    mut output: EventWriter<PointerHits>,
+) {
+    for (pointer, location) in pointers
+        .iter()
+        .filter_map(|(pointer, location)| Some((pointer, location.location()?)))
+    {
+        for (entity, camera) in cameras.iter() {
+            if !camera.is_active || !location.is_in_viewport(camera, &primary_window) {
+                continue;
+            }
+            let Some(viewport) = camera.logical_viewport_rect() else {
+                continue;
+            };
+            let picks: Vec<_> = picking
+                .hits(entity, location.position - viewport.min)
+                .into_iter()
+                .map(|(hit, depth)| (hit, HitData::new(entity, depth, None, None)))
+                .collect();
+            if !picks.is_empty() {
+                output.send(PointerHits::new(*pointer, picks, camera.order as f32));
+            }
+        }
+    }
+}
@@ -1,8 +1,22 @@
-use crate::debug::DebugVisualizationsPlugin;
-use crate::render::VelloRenderPlugin;
-use crate::text::VelloFontLoader;
-use crate::{VelloAsset, VelloFont};
+use crate::debug::{DebugVisualizations, DebugVisualizationsPlugin};
+use crate::draw::VelloDrawPlugin;
+use crate::morph::VelloMorphPlugin;
+use crate::particles::VelloParticlesPlugin;
+use crate::pointer::VelloPointerPlugin;
+use crate::render::{
+    AlphaOverride, VelloBaked, VelloBlendMode, VelloConstantScale, VelloFlip, VelloInstances,
+    VelloNineSlice, VelloNonScalingStroke, VelloRenderPlugin, VelloScaleMode, VelloSize, VelloTint,
+    VelloZIndex,
+};
+use crate::text::{
+    VelloFontFallback, VelloFontLoader, VelloTextAlign, VelloTextAlignment, VelloTextDirection,
+    VelloTextNodeBounds, VelloTextOutline, VelloTextReveal, VelloTextSection, VelloTextSelection,
+};
+use crate::tween::TweenPlugin;
+use crate::{CoordinateSpace, VelloAsset, VelloAssetAlignment, VelloFont, VelloScene, VelloText};
 use bevy::prelude::*;
+use bevy::render::view::VisibilitySystems;
+use bevy::ui::UiSystem;
 
 pub struct VelloPlugin;
 
@@ -10,14 +24,68 @@ impl Plugin for VelloPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(VelloRenderPlugin)
             .add_plugins(DebugVisualizationsPlugin)
+            .add_plugins(TweenPlugin)
+            .add_plugins(VelloPointerPlugin)
+            .add_plugins(VelloDrawPlugin)
+            .add_plugins(VelloParticlesPlugin)
+            .add_plugins(VelloMorphPlugin)
             .init_asset::<VelloAsset>()
             .init_asset::<VelloFont>()
-            .init_asset_loader::<VelloFontLoader>();
+            .init_asset_loader::<VelloFontLoader>()
+            .init_resource::<VelloFontFallback>()
+            .add_systems(Update, crate::text::blink_text_carets)
+            .add_systems(
+                PostUpdate,
+                (
+                    crate::text::fit_texts_to_nodes.after(UiSystem::Layout),
+                    crate::text::cache_text_layouts.before(VisibilitySystems::CalculateBounds),
+                )
+                    .chain(),
+            )
+            // Reflected for inspectors and scenes
+            .register_type::<Handle<VelloAsset>>()
+            .register_type::<Handle<VelloFont>>()
+            .register_type::<CoordinateSpace>()
+            .register_type::<VelloAssetAlignment>()
+            .register_type::<VelloScene>()
+            .register_type::<VelloText>()
+            .register_type::<VelloTextAlignment>()
+            .register_type::<VelloTextSection>()
+            .register_type::<VelloTextDirection>()
+            .register_type::<VelloTextAlign>()
+            .register_type::<VelloTextOutline>()
+            .register_type::<VelloTextNodeBounds>()
+            .register_type::<VelloTextReveal>()
+            .register_type::<VelloTextSelection>()
+            .register_type::<VelloFontFallback>()
+            .register_type::<DebugVisualizations>()
+            .register_type::<AlphaOverride>()
+            .register_type::<VelloZIndex>()
+            .register_type::<VelloTint>()
+            .register_type::<VelloSize>()
+            .register_type::<VelloScaleMode>()
+            .register_type::<VelloFlip>()
+            .register_type::<VelloConstantScale>()
+            .register_type::<VelloInstances>()
+            .register_type::<VelloNineSlice>()
+            .register_type::<VelloNonScalingStroke>()
+            .register_type::<VelloBaked>()
+            .register_type::<VelloBlendMode>();
         #[cfg(feature = "svg")]
         app.add_plugins(crate::integrations::svg::SvgIntegrationPlugin);
         #[cfg(feature = "lottie")]
         app.add_plugins(crate::integrations::lottie::LottieIntegrationPlugin);
         #[cfg(feature = "experimental-dotLottie")]
         app.add_plugins(crate::integrations::dot_lottie::DotLottieIntegrationPlugin);
+        #[cfg(feature = "system-fonts")]
+        app.add_plugins(crate::text::VelloSystemFontsPlugin);
+        // The default handle of texts, e.g. of `VelloTextBundle::default()`
+        #[cfg(feature = "default-font")]
+        bevy::asset::load_internal_binary_asset!(
+            app,
+            Handle::default(),
+            "text/FiraMono-subset.ttf",
+            |bytes: &[u8], _path: String| VelloFont::new(bytes.to_vec())
+        );
     }
 }
@@ -0,0 +1,332 @@
+//! Hit testing of vello entities, and the pointer events of entities which
+//! are [`VelloPickable`].
+//!
+//! Entities are hit tested against their [`Aabb`], which is computed for
+//! world-space assets and texts. Scenes have no bounds, so insert an [`Aabb`]
+//! to make a scene pickable. Screen-space entities and UI nodes aren't hit
+//! tested, use Bevy UI's [`Interaction`] instead.
+//!
+//! Assets which are [`VelloPickable::Paths`] are hit tested against the paths
+//! they draw instead, so e.g. a circular button isn't entered from the corners
+//! of its bounds.
+//!
+//! [`VelloPicking`] reports the same hits with their depth from a camera, so
+//! a picking backend, e.g. for `bevy_mod_picking`, can send them to its
+//! pipeline as the hits of a pointer.
+
+use crate::render::{VelloFlip, VelloNineSlice};
+use crate::{CoordinateSpace, VelloAsset, VelloZIndex};
+use bevy::ecs::system::SystemParam;
+use bevy::input::touch::Touch;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use bevy::utils::HashMap;
+
+/// Sends [`VelloPointerEvent`]s for [`VelloPickable`] entities.
+pub struct VelloPointerPlugin;
+
+impl Plugin for VelloPointerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<VelloPointerEvent>()
+            .register_type::<VelloPickable>()
+            .add_systems(PreUpdate, send_pointer_events.after(InputSystem));
+    }
+}
+
+/// Add this component to a vello entity to receive [`VelloPointerEvent`]s
+/// when the mouse or a touch interacts with it.
+#[derive(PartialEq, Eq, Component, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub enum VelloPickable {
+    /// Hit test the bounds of the entity.
+    #[default]
+    Bounds,
+    /// Hit test the paths drawn by the asset of the entity, which is costlier
+    /// as they are decoded every test. Nine-sliced assets, texts and scenes
+    /// are hit tested against their bounds.
+    Paths,
+}
+
+/// The interaction of a pointer with a [`VelloPickable`] entity.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Reflect)]
+pub enum VelloPointerEventKind {
+    /// A pointer entered the entity.
+    Over,
+    /// A pointer left the entity.
+    Out,
+    /// The left mouse button or a touch was pressed over the entity.
+    Pressed,
+    /// The left mouse button or a touch was released over the entity.
+    Released,
+    /// A pointer was pressed and released over the entity.
+    Clicked,
+}
+
+/// Sent when a pointer interacts with a [`VelloPickable`] entity. Only the
+/// topmost entity under a pointer receives events.
+#[derive(Event, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct VelloPointerEvent {
+    /// The entity under the pointer.
+    pub entity: Entity,
+    /// The kind of interaction.
+    pub kind: VelloPointerEventKind,
+}
+
+/// Whether a point in world space is within the bounds of an entity.
+pub fn hit_test(transform: &GlobalTransform, aabb: &Aabb, point: Vec2) -> bool {
+    let local = transform
+        .affine()
+        .inverse()
+        .transform_point3(point.extend(0.0));
+    let (min, max) = (Vec3::from(aabb.min()), Vec3::from(aabb.max()));
+    local.x >= min.x && local.x <= max.x && local.y >= min.y && local.y <= max.y
+}
+
+/// Whether a point in world space is within the paths drawn by the asset of
+/// an entity, at the given frame of a lottie animation.
+pub fn hit_test_paths(
+    transform: &GlobalTransform,
+    aabb: &Aabb,
+    asset: &VelloAsset,
+    flip: Option<&VelloFlip>,
+    frame: f64,
+    point: Vec2,
+) -> bool {
+    if !hit_test(transform, aabb, point) {
+        return false;
+    }
+    let local = transform
+        .affine()
+        .inverse()
+        .transform_point3(point.extend(0.0))
+        .truncate();
+    // The bounds of the entity are the bounds of the asset, scaled
+    let size = Vec2::new(asset.width, asset.height);
+    let scale =
+        aabb.half_extents.truncate() * 2.0 / size * flip.map_or(Vec2::ONE, VelloFlip::scale);
+    let from_center = (local - aabb.center.truncate()) / scale;
+    let point = Vec2::new(size.x / 2.0 + from_center.x, size.y / 2.0 - from_center.y);
+    asset.contains(point, frame)
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+enum PointerId {
+    Mouse,
+    Touch(u64),
+}
+
+/// The entities hovered and pressed by each pointer.
+#[derive(Default)]
+struct PointerState {
+    hovered: HashMap<PointerId, Entity>,
+    pressed: HashMap<PointerId, Entity>,
+}
+
+/// A pointer this frame, in the window.
+struct PointerInput {
+    id: PointerId,
+    position: Option<Vec2>,
+    just_pressed: bool,
+    just_released: bool,
+}
+
+/// Hit tests pickable entities from the viewports of 2D cameras, e.g. for a
+/// picking backend reporting vello entities to another picking pipeline.
+#[derive(SystemParam)]
+pub struct VelloPicking<'w, 's> {
+    cameras: Query<'w, 's, (Entity, &'static Camera, &'static GlobalTransform), With<Camera2d>>,
+    pickables: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static GlobalTransform,
+            &'static Aabb,
+            &'static CoordinateSpace,
+            &'static InheritedVisibility,
+            Option<&'static VelloZIndex>,
+            &'static VelloPickable,
+            Option<&'static Handle<VelloAsset>>,
+            Option<&'static VelloFlip>,
+            Has<VelloNineSlice>,
+        ),
+        Without<Node>,
+    >,
+    assets: Res<'w, Assets<VelloAsset>>,
+    #[cfg(feature = "lottie")]
+    playheads: Query<'w, 's, &'static crate::integrations::lottie::Playhead>,
+}
+
+impl VelloPicking<'_, '_> {
+    /// The pickable entities under a position in the viewport of a camera,
+    /// with their depth from the camera, nearest first.
+    pub fn hits(&self, camera: Entity, position: Vec2) -> Vec<(Entity, f32)> {
+        let Ok((_, camera, view)) = self.cameras.get(camera) else {
+            return Vec::new();
+        };
+        let Some(world_position) = camera.viewport_to_world_2d(view, position) else {
+            return Vec::new();
+        };
+        let mut hits: Vec<_> = self
+            .pickables
+            .iter()
+            .filter(
+                |(
+                    entity,
+                    transform,
+                    aabb,
+                    space,
+                    visibility,
+                    _,
+                    pickable,
+                    asset,
+                    flip,
+                    nine_slice,
+                )| {
+                    if **space != CoordinateSpace::WorldSpace || !visibility.get() {
+                        return false;
+                    }
+                    let asset = asset.and_then(|asset| self.assets.get(asset));
+                    match (pickable, asset) {
+                        (VelloPickable::Paths, Some(asset)) if !nine_slice => hit_test_paths(
+                            transform,
+                            aabb,
+                            asset,
+                            *flip,
+                            self.frame(*entity),
+                            world_position,
+                        ),
+                        _ => hit_test(transform, aabb, world_position),
+                    }
+                },
+            )
+            .map(|(entity, transform, _, _, _, z_index, ..)| {
+                let depth = view.translation().z - transform.translation().z;
+                (entity, depth, z_index.map_or(0, |z_index| z_index.0))
+            })
+            .collect();
+        // Vectors at the same depth are drawn by their z-index
+        hits.sort_by(|a, b| a.1.total_cmp(&b.1).then(b.2.cmp(&a.2)));
+        hits.into_iter()
+            .map(|(entity, depth, _)| (entity, depth))
+            .collect()
+    }
+
+    /// The frame of the lottie animation played by an entity.
+    fn frame(&self, entity: Entity) -> f64 {
+        #[cfg(feature = "lottie")]
+        if let Ok(playhead) = self.playheads.get(entity) {
+            return playhead.frame();
+        }
+        let _ = entity;
+        0.0
+    }
+
+    /// The nearest pickable entity under a position in the window, from the
+    /// topmost active camera whose viewport contains the position.
+    pub fn topmost(&self, position: Vec2) -> Option<Entity> {
+        let mut cameras: Vec<_> = self
+            .cameras
+            .iter()
+            .filter(|(_, camera, _)| {
+                camera.is_active
+                    && camera
+                        .logical_viewport_rect()
+                        .is_some_and(|rect| rect.contains(position))
+            })
+            .collect();
+        cameras.sort_by_key(|(_, camera, _)| std::cmp::Reverse(camera.order));
+        let (entity, camera, _) = cameras.first()?;
+        let viewport_position = position - camera.logical_viewport_rect()?.min;
+        self.hits(*entity, viewport_position)
+            .first()
+            .map(|(entity, _)| *entity)
+    }
+}
+
+/// Hit test the pointers against pickable entities, and send the events of
+/// the entities they enter, leave, press and release.
+fn send_pointer_events(
+    mut state: Local<PointerState>,
+    mut events: EventWriter<VelloPointerEvent>,
+    picking: VelloPicking,
+    windows: Query<&Window>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+) {
+    let touch_input = |touch: &Touch, just_released: bool| PointerInput {
+        id: PointerId::Touch(touch.id()),
+        position: Some(touch.position()),
+        just_pressed: touches.just_pressed(touch.id()),
+        just_released,
+    };
+    let mut pointers = vec![PointerInput {
+        id: PointerId::Mouse,
+        position: windows.get_single().ok().and_then(Window::cursor_position),
+        just_pressed: buttons.just_pressed(MouseButton::Left),
+        just_released: buttons.just_released(MouseButton::Left),
+    }];
+    pointers.extend(touches.iter().map(|touch| touch_input(touch, false)));
+    pointers.extend(
+        touches
+            .iter_just_released()
+            .map(|touch| touch_input(touch, true)),
+    );
+
+    let mut send = |entity: Entity, kind: VelloPointerEventKind| {
+        events.send(VelloPointerEvent { entity, kind });
+    };
+    for pointer in pointers {
+        let hovered = pointer
+            .position
+            .and_then(|position| picking.topmost(position));
+        let previous = state.hovered.get(&pointer.id).copied();
+        if previous != hovered {
+            // Despawned entities don't receive events
+            if let Some(previous) =
+                previous.filter(|previous| picking.pickables.contains(*previous))
+            {
+                send(previous, VelloPointerEventKind::Out);
+            }
+            if let Some(hovered) = hovered {
+                send(hovered, VelloPointerEventKind::Over);
+            }
+        }
+        if let Some(hovered) = hovered {
+            if pointer.just_pressed {
+                send(hovered, VelloPointerEventKind::Pressed);
+                state.pressed.insert(pointer.id, hovered);
+            }
+            if pointer.just_released {
+                send(hovered, VelloPointerEventKind::Released);
+                if state.pressed.get(&pointer.id) == Some(&hovered) {
+                    send(hovered, VelloPointerEventKind::Clicked);
+                }
+            }
+        }
+        if pointer.just_released {
+            state.pressed.remove(&pointer.id);
+        }
+        // Released touches leave the entity
+        match (pointer.id, hovered) {
+            (PointerId::Touch(_), Some(hovered)) if pointer.just_released => {
+                send(hovered, VelloPointerEventKind::Out);
+                state.hovered.remove(&pointer.id);
+            }
+            (_, Some(hovered)) => {
+                state.hovered.insert(pointer.id, hovered);
+            }
+            (_, None) => {
+                state.hovered.remove(&pointer.id);
+            }
+        }
+    }
+    for touch in touches.iter_just_canceled() {
+        let id = PointerId::Touch(touch.id());
+        state.pressed.remove(&id);
+        if let Some(hovered) = state.hovered.remove(&id) {
+            send(hovered, VelloPointerEventKind::Out);
+        }
+    }
+}
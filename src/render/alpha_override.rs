@@ -0,0 +1,14 @@
+use bevy::prelude::*;
+
+/// The opacity of a vello asset instance, from `0.0` (transparent) to `1.0`
+/// (opaque), multiplied with the alpha of the asset itself. Instances without
+/// this component are opaque.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct AlphaOverride(pub f32);
+
+impl Default for AlphaOverride {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
@@ -0,0 +1,286 @@
+//! Rasterization of vector assets into images, so many identical static
+//! vectors can be drawn as sprites instead of being encoded every frame.
+
+use super::{VelloRenderSettings, VelloRenderer};
+use crate::{VelloAsset, VelloAssetAlignment};
+use bevy::prelude::*;
+use bevy::render::extract_resource::ExtractResource;
+use bevy::render::render_asset::{RenderAssetUsages, RenderAssets};
+use bevy::render::render_resource::{
+    Extent3d, TextureDimension, TextureFormat, TextureUsages, TextureViewDescriptor,
+};
+use bevy::render::renderer::{RenderDevice, RenderQueue};
+use bevy::sprite::Anchor;
+use bevy::utils::HashMap;
+use vello::kurbo::Affine;
+use vello::{RenderParams, Scene};
+
+/// A request to rasterize an asset into the texture of an image.
+#[derive(Clone)]
+#[cfg_attr(not(any(feature = "svg", feature = "lottie")), allow(unused))]
+pub struct BakeRequest {
+    image: AssetId<Image>,
+    asset: VelloAsset,
+    resolution: UVec2,
+    #[cfg_attr(not(feature = "lottie"), allow(dead_code))]
+    frame: f64,
+}
+
+/// The rasterizations requested by [`VelloAsset::rasterize`] this frame. They
+/// are extracted to the render world, and rendered once their image is
+/// prepared.
+#[derive(Resource, ExtractResource, Default, Clone)]
+pub struct VelloBakeRequests(Vec<BakeRequest>);
+
+/// Vello renders into linear textures, which are viewed as sRGB by sprites.
+const BAKED_VIEW_FORMATS: &[TextureFormat] = &[TextureFormat::Rgba8UnormSrgb];
+
+impl VelloAsset {
+    /// Rasterize the asset at the given resolution, and at the given frame of
+    /// a lottie animation, into a new image. The image is rendered on the GPU
+    /// by the next frames, and can be drawn as a sprite instead of the asset.
+    #[cfg_attr(not(any(feature = "svg", feature = "lottie")), allow(unused))]
+    pub fn rasterize(
+        &self,
+        images: &mut Assets<Image>,
+        requests: &mut VelloBakeRequests,
+        resolution: UVec2,
+        frame: f64,
+    ) -> Handle<Image> {
+        let resolution = resolution.max(UVec2::ONE);
+        let mut image = Image::new_fill(
+            Extent3d {
+                width: resolution.x,
+                height: resolution.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0; 4],
+            TextureFormat::Rgba8Unorm,
+            RenderAssetUsages::default(),
+        );
+        image.texture_descriptor.usage |= TextureUsages::STORAGE_BINDING;
+        image.texture_descriptor.view_formats = BAKED_VIEW_FORMATS;
+        image.texture_view_descriptor = Some(TextureViewDescriptor {
+            format: Some(TextureFormat::Rgba8UnormSrgb),
+            ..default()
+        });
+        let handle = images.add(image);
+        requests.0.push(BakeRequest {
+            image: handle.id(),
+            asset: self.clone(),
+            resolution,
+            frame,
+        });
+        handle
+    }
+}
+
+#[derive(PartialEq, Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+/// Add this component to a `VelloAssetBundle` entity to draw its asset as a
+/// sprite of a rasterized image, rather than encoding the vector every frame.
+/// Entities baking the same asset at the same resolution and frame share the
+/// image.
+///
+/// The sprite is drawn at the size of the asset, aligned by its [`Anchor`]
+/// or alignment.
+pub struct VelloBaked {
+    /// The resolution of the image, in pixels.
+    pub resolution: UVec2,
+    /// The frame of a lottie animation to rasterize.
+    pub frame: f64,
+}
+
+impl VelloBaked {
+    pub fn new(resolution: UVec2) -> Self {
+        Self {
+            resolution,
+            frame: 0.0,
+        }
+    }
+
+    pub fn with_frame(mut self, frame: f64) -> Self {
+        self.frame = frame;
+        self
+    }
+}
+
+/// The images of baked assets, by asset, resolution and frame.
+#[derive(Resource, Default)]
+pub struct BakedImages(HashMap<(AssetId<VelloAsset>, UVec2, u64), Handle<Image>>);
+
+/// The sprite anchor equivalent to the alignment of an asset.
+fn alignment_anchor(alignment: VelloAssetAlignment) -> Anchor {
+    match alignment {
+        VelloAssetAlignment::BottomLeft => Anchor::BottomLeft,
+        VelloAssetAlignment::Bottom => Anchor::BottomCenter,
+        VelloAssetAlignment::BottomRight => Anchor::BottomRight,
+        VelloAssetAlignment::Left => Anchor::CenterLeft,
+        VelloAssetAlignment::Center => Anchor::Center,
+        VelloAssetAlignment::Right => Anchor::CenterRight,
+        VelloAssetAlignment::TopLeft => Anchor::TopLeft,
+        VelloAssetAlignment::Top => Anchor::TopCenter,
+        VelloAssetAlignment::TopRight => Anchor::TopRight,
+    }
+}
+
+/// Swap the assets of baked entities for sprites of their images, when
+/// baked or when the asset changes.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn bake_vello_assets(
+    mut commands: Commands,
+    query: Query<(
+        Entity,
+        Ref<Handle<VelloAsset>>,
+        Ref<VelloBaked>,
+        &VelloAssetAlignment,
+        Option<&Anchor>,
+        Has<Handle<Image>>,
+    )>,
+    mut removed: RemovedComponents<VelloBaked>,
+    mut asset_events: EventReader<AssetEvent<VelloAsset>>,
+    assets: Res<Assets<VelloAsset>>,
+    mut images: ResMut<Assets<Image>>,
+    mut requests: ResMut<VelloBakeRequests>,
+    mut baked_images: ResMut<BakedImages>,
+) {
+    for entity in removed.read() {
+        if let Some(mut entity) = commands.get_entity(entity) {
+            entity.remove::<(Sprite, Handle<Image>)>();
+        }
+    }
+    let modified: Vec<AssetId<VelloAsset>> = asset_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } | AssetEvent::Removed { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    baked_images
+        .0
+        .retain(|(asset, _, _), _| !modified.contains(asset));
+
+    for (entity, handle, baked, alignment, anchor, has_image) in query.iter() {
+        let outdated = !has_image
+            || handle.is_changed()
+            || baked.is_changed()
+            || modified.contains(&handle.id());
+        if !outdated {
+            continue;
+        }
+        let Some(asset) = assets.get(handle.id()) else {
+            continue;
+        };
+        let image = baked_images
+            .0
+            .entry((handle.id(), baked.resolution, baked.frame.to_bits()))
+            .or_insert_with(|| {
+                asset.rasterize(&mut images, &mut requests, baked.resolution, baked.frame)
+            })
+            .clone();
+        commands.entity(entity).insert((
+            image,
+            Sprite {
+                custom_size: Some(Vec2::new(asset.width, asset.height)),
+                anchor: anchor.cloned().unwrap_or(alignment_anchor(*alignment)),
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Forget the rasterizations extracted last frame.
+pub fn clear_bake_requests(mut requests: ResMut<VelloBakeRequests>) {
+    if !requests.0.is_empty() {
+        requests.0.clear();
+    }
+}
+
+/// Renders the requested rasterizations into the textures of their images.
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(not(any(feature = "svg", feature = "lottie")), allow(unused))]
+pub fn render_baked_images(
+    mut pending: Local<Vec<BakeRequest>>,
+    mut requests: ResMut<VelloBakeRequests>,
+    mut vello_renderer: Local<Option<Option<VelloRenderer>>>,
+    gpu_images: Res<RenderAssets<Image>>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+    settings: Option<Res<VelloRenderSettings>>,
+    #[cfg(feature = "lottie")] mut velato_renderer: ResMut<super::VelatoRenderer>,
+) {
+    pending.append(&mut requests.0);
+    if pending.is_empty() {
+        return;
+    }
+    let settings = settings.map(|settings| *settings).unwrap_or_default();
+    let Some(renderer) = vello_renderer
+        .get_or_insert_with(|| VelloRenderer::new(&device, &settings))
+        .as_mut()
+    else {
+        pending.clear();
+        return;
+    };
+
+    // Wait until the image is prepared
+    pending.retain(|bake| {
+        let Some(gpu_image) = gpu_images.get(bake.image) else {
+            return true;
+        };
+        let asset = &bake.asset;
+        let transform = Affine::scale_non_uniform(
+            bake.resolution.x as f64 / asset.width as f64,
+            bake.resolution.y as f64 / asset.height as f64,
+        );
+        let mut scene = Scene::new();
+        match &asset.file {
+            #[cfg(feature = "svg")]
+            crate::VectorFile::Svg(svg) => {
+                scene.push_layer(
+                    vello::peniko::Mix::Normal,
+                    asset.alpha,
+                    transform,
+                    &vello::kurbo::Rect::new(0.0, 0.0, asset.width as f64, asset.height as f64),
+                );
+                scene.append(svg, Some(transform));
+                scene.pop_layer();
+            }
+            #[cfg(feature = "lottie")]
+            crate::VectorFile::Lottie(composition) => {
+                velato_renderer.render(
+                    composition,
+                    bake.frame,
+                    transform,
+                    asset.alpha as f64,
+                    &mut scene,
+                );
+            }
+            #[cfg(not(any(feature = "svg", feature = "lottie")))]
+            _ => {}
+        }
+        // Prevent a panic in the vello renderer when the encoding is empty
+        if scene.encoding().is_empty() {
+            return false;
+        }
+        let view = gpu_image.texture.create_view(&TextureViewDescriptor {
+            format: Some(TextureFormat::Rgba8Unorm),
+            ..default()
+        });
+        if let Err(e) = renderer.render_to_texture(
+            device.wgpu_device(),
+            &queue,
+            &scene,
+            &view,
+            &RenderParams {
+                base_color: vello::peniko::Color::TRANSPARENT,
+                width: bake.resolution.x,
+                height: bake.resolution.y,
+                antialiasing_method: settings.antialiasing,
+            },
+        ) {
+            error!("could not rasterize vello asset: {e}");
+        }
+        false
+    });
+}
@@ -0,0 +1,38 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use vello::peniko::{BlendMode, Compose, Mix};
+
+/// How a vello entity is composited over what rendered beneath it in the
+/// same canvas, e.g. to multiply a shadow or add a glow. Entities without
+/// this component are drawn normally.
+///
+/// This component is reflected as an opaque value, since the blend modes of
+/// peniko aren't reflected.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Default, Reflect, Serialize, Deserialize)]
+#[reflect_value(Component, Debug, PartialEq, Default, Serialize, Deserialize)]
+pub struct VelloBlendMode(pub BlendMode);
+
+impl VelloBlendMode {
+    /// Multiply the colors beneath, which darkens.
+    pub const MULTIPLY: Self = Self::new(Mix::Multiply, Compose::SrcOver);
+    /// Multiply the inverse of the colors beneath, which lightens.
+    pub const SCREEN: Self = Self::new(Mix::Screen, Compose::SrcOver);
+    /// Add to the colors beneath.
+    pub const ADDITIVE: Self = Self::new(Mix::Normal, Compose::Plus);
+
+    pub const fn new(mix: Mix, compose: Compose) -> Self {
+        Self(BlendMode { mix, compose })
+    }
+}
+
+impl From<Mix> for VelloBlendMode {
+    fn from(mix: Mix) -> Self {
+        Self::new(mix, Compose::SrcOver)
+    }
+}
+
+impl From<Compose> for VelloBlendMode {
+    fn from(compose: Compose) -> Self {
+        Self::new(Mix::Normal, compose)
+    }
+}
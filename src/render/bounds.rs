@@ -0,0 +1,149 @@
+//! Bounds of vello entities, for Bevy's frustum culling.
+
+use super::bake::VelloBaked;
+use super::constant_scale::VelloConstantScale;
+use super::flip::VelloFlip;
+use super::instances::VelloInstances;
+use super::nine_slice::VelloNineSlice;
+use super::size::VelloSize;
+use crate::pointer::VelloPickable;
+use crate::text::{CachedTextLayout, VelloTextAlignment};
+use crate::{
+    CoordinateSpace, VelloAsset, VelloAssetAlignment, VelloFont, VelloFontFallback, VelloText,
+};
+use bevy::prelude::*;
+use bevy::render::primitives::Aabb;
+use bevy::render::view::NoFrustumCulling;
+use bevy::sprite::Anchor;
+
+/// Insert the bounds, or remove them from entities that aren't culled.
+fn update_aabb(
+    commands: &mut Commands,
+    entity: Entity,
+    current: Option<&Aabb>,
+    aabb: Option<Aabb>,
+) {
+    match aabb {
+        Some(aabb) if current != Some(&aabb) => {
+            commands.entity(entity).insert(aabb);
+        }
+        None if current.is_some() => {
+            commands.entity(entity).remove::<Aabb>();
+        }
+        _ => {}
+    }
+}
+
+/// Compute the [`Aabb`] of world-space assets, so Bevy marks off-screen
+/// assets as not visible and they aren't extracted.
+#[allow(clippy::type_complexity)]
+pub fn calculate_asset_bounds(
+    mut commands: Commands,
+    query: Query<
+        (
+            Entity,
+            &Handle<VelloAsset>,
+            &VelloAssetAlignment,
+            &CoordinateSpace,
+            Has<Node>,
+            (
+                Option<&VelloNineSlice>,
+                Option<&VelloSize>,
+                Option<&Anchor>,
+                Option<&VelloFlip>,
+                Has<VelloConstantScale>,
+                Has<VelloInstances>,
+            ),
+            Option<&Aabb>,
+        ),
+        // Baked assets are bounded by their sprite
+        (
+            Or<(Without<NoFrustumCulling>, With<VelloPickable>)>,
+            Without<VelloBaked>,
+        ),
+    >,
+    assets: Res<Assets<VelloAsset>>,
+) {
+    for (
+        entity,
+        handle,
+        alignment,
+        coordinate_space,
+        ui_node,
+        (nine_slice, size, anchor, flip, constant_scale, instanced),
+        aabb,
+    ) in query.iter()
+    {
+        let Some(asset) = assets.get(handle) else {
+            continue;
+        };
+        // UI nodes are laid out by Bevy UI, rather than culled, the size of
+        // constant scale assets in the world depends on the camera, and
+        // instanced assets are culled by instance
+        let bounds = match coordinate_space {
+            CoordinateSpace::WorldSpace if !ui_node && !constant_scale && !instanced => {
+                let intrinsic_size = match nine_slice {
+                    Some(nine_slice) => nine_slice.size,
+                    None => Vec2::new(asset.width, asset.height),
+                };
+                let scale = size.map_or(Vec2::ONE, |size| size.scale(intrinsic_size))
+                    * flip.map_or(Vec2::ONE, VelloFlip::scale);
+                // The center of the asset from its pivot
+                let pivot_offset = -anchor.map_or(Vec2::ZERO, Anchor::as_vec) * intrinsic_size;
+                let size = intrinsic_size * scale.abs();
+                let center = alignment.offset(size) + (pivot_offset * scale).extend(0.0);
+                Some(Aabb::from_min_max(
+                    center - (size / 2.0).extend(0.0),
+                    center + (size / 2.0).extend(0.0),
+                ))
+            }
+            _ => None,
+        };
+        update_aabb(&mut commands, entity, aabb, bounds);
+    }
+}
+
+/// Compute the [`Aabb`] of world-space texts, so Bevy marks off-screen texts
+/// as not visible and they aren't extracted.
+#[allow(clippy::type_complexity)]
+pub fn calculate_text_bounds(
+    mut commands: Commands,
+    query: Query<
+        (
+            Entity,
+            &Handle<VelloFont>,
+            &VelloText,
+            &VelloTextAlignment,
+            &CoordinateSpace,
+            Option<&CachedTextLayout>,
+            Has<VelloConstantScale>,
+            Option<&Aabb>,
+        ),
+        Or<(Without<NoFrustumCulling>, With<VelloPickable>)>,
+    >,
+    fonts: Res<Assets<VelloFont>>,
+    fallback: Res<VelloFontFallback>,
+) {
+    for (entity, handle, text, alignment, coordinate_space, layout, constant_scale, aabb) in
+        query.iter()
+    {
+        let Some(font) = fonts.get(handle) else {
+            continue;
+        };
+        let bounds = match coordinate_space {
+            CoordinateSpace::WorldSpace if !constant_scale => {
+                let size = match layout {
+                    Some(layout) => layout.0.size(),
+                    None => font.sizeof_with(text, &fallback, |font| fonts.get(font)),
+                };
+                let min = alignment.offset(size);
+                Some(Aabb::from_min_max(
+                    min.extend(0.0),
+                    (min + size).extend(0.0),
+                ))
+            }
+            _ => None,
+        };
+        update_aabb(&mut commands, entity, aabb, bounds);
+    }
+}
@@ -0,0 +1,244 @@
+//! Encoded fragments of lottie instances, reused while their parameters
+//! don't change.
+//!
+//! SVG files are encoded once when loaded, but a lottie composition is walked
+//! by velato every time it is rendered. Static and paused animations are
+//! encoded once at their frame instead, and appended with their transform.
+//! Within an animation, precompositions which don't change between frames
+//! are encoded once too, and appended wherever they are instanced.
+
+use crate::integrations::lottie::{
+    is_static, LottieLayerVisibility, LottieTextOverrides, PrecompFrame,
+};
+use crate::{Theme, VelloAsset};
+use bevy::prelude::*;
+use bevy::render::Extract;
+use bevy::utils::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use velato::Composition;
+use vello::Scene;
+
+/// The parameters of a lottie instance, besides its asset and playhead.
+#[derive(Clone)]
+pub(crate) struct LottieFragmentKey {
+    pub theme: Option<Theme>,
+    pub composition: Option<Arc<Composition>>,
+    pub text_overrides: Option<LottieTextOverrides>,
+    pub layer_visibility: Option<LottieLayerVisibility>,
+    pub alpha: f32,
+    /// Whether the font of the text overrides is loaded.
+    pub font_loaded: bool,
+    /// The scale of the stroke widths, for non-scaling strokes.
+    pub stroke_scale: f32,
+}
+
+impl PartialEq for LottieFragmentKey {
+    fn eq(&self, other: &Self) -> bool {
+        let composition = match (&self.composition, &other.composition) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        composition
+            && self.theme == other.theme
+            && self.text_overrides == other.text_overrides
+            && self.layer_visibility == other.layer_visibility
+            && self.alpha == other.alpha
+            && self.font_loaded == other.font_loaded
+            && self.stroke_scale == other.stroke_scale
+    }
+}
+
+struct CachedFragment {
+    key: LottieFragmentKey,
+    scene: Arc<Scene>,
+    /// Whether the fragment was rendered since the last eviction.
+    used: bool,
+}
+
+/// The precompositions of the lottie instances of an asset with the same
+/// parameters.
+struct AssetPrecomps {
+    key: LottieFragmentKey,
+    precomps: Arc<LottiePrecompCache>,
+    /// Whether an instance was rendered since the last eviction.
+    used: bool,
+}
+
+/// The encoded fragments of the lottie instances rendered last frame, by
+/// asset and playhead. Identical instances share a fragment.
+#[derive(Resource, Default)]
+pub struct LottieFragmentCache {
+    fragments: Mutex<HashMap<(AssetId<VelloAsset>, u64), Vec<CachedFragment>>>,
+    precomps: Mutex<HashMap<AssetId<VelloAsset>, Vec<AssetPrecomps>>>,
+}
+
+impl LottieFragmentCache {
+    /// The fragment of a lottie instance, in the local space of the asset,
+    /// encoded if no instance was rendered with the same parameters last
+    /// frame.
+    pub(crate) fn get_or_encode(
+        &self,
+        asset: AssetId<VelloAsset>,
+        playhead: f64,
+        key: LottieFragmentKey,
+        encode: impl FnOnce(&mut Scene, &LottiePrecompCache),
+    ) -> Arc<Scene> {
+        let precomps = self.precomps(asset, &key);
+        let slot = (asset, playhead.to_bits());
+        if let Some(fragment) = self
+            .fragments
+            .lock()
+            .unwrap()
+            .get_mut(&slot)
+            .and_then(|fragments| fragments.iter_mut().find(|fragment| fragment.key == key))
+        {
+            fragment.used = true;
+            return fragment.scene.clone();
+        }
+        // Encode without the lock, so other instances are encoded meanwhile
+        let mut scene = Scene::new();
+        encode(&mut scene, &precomps);
+        precomps.encoded.store(true, Ordering::Relaxed);
+        let scene = Arc::new(scene);
+        self.fragments
+            .lock()
+            .unwrap()
+            .entry(slot)
+            .or_default()
+            .push(CachedFragment {
+                key,
+                scene: scene.clone(),
+                used: true,
+            });
+        scene
+    }
+
+    /// The precompositions of the instances of an asset with the same
+    /// parameters.
+    fn precomps(
+        &self,
+        asset: AssetId<VelloAsset>,
+        key: &LottieFragmentKey,
+    ) -> Arc<LottiePrecompCache> {
+        let mut precomps = self.precomps.lock().unwrap();
+        let precomps = precomps.entry(asset).or_default();
+        let cached = match precomps.iter().position(|cached| cached.key == *key) {
+            Some(index) => &mut precomps[index],
+            None => {
+                precomps.push(AssetPrecomps {
+                    key: key.clone(),
+                    precomps: default(),
+                    used: false,
+                });
+                precomps.last_mut().unwrap()
+            }
+        };
+        cached.used = true;
+        cached.precomps.clone()
+    }
+
+    /// Drop the fragments which weren't rendered since the last eviction.
+    pub(crate) fn evict_unused(&mut self) {
+        self.fragments.get_mut().unwrap().retain(|_, fragments| {
+            fragments.retain_mut(|fragment| std::mem::take(&mut fragment.used));
+            !fragments.is_empty()
+        });
+        self.precomps.get_mut().unwrap().retain(|_, precomps| {
+            precomps.retain_mut(|cached| {
+                cached.precomps.evict_unused();
+                std::mem::take(&mut cached.used)
+            });
+            !precomps.is_empty()
+        });
+    }
+}
+
+/// The encoded fragments of the precompositions of lottie instances which
+/// were rendered last frame, by precomposition, frame and opacity. Instances
+/// of a precomposition at the same frame share a fragment.
+#[derive(Default)]
+pub(crate) struct LottiePrecompCache {
+    /// Whether each precomposition draws the same at every frame.
+    statics: Mutex<HashMap<String, bool>>,
+    fragments: Mutex<HashMap<(String, PrecompFrame, u64), CachedPrecomp>>,
+    /// Whether an instance was encoded since the last eviction, so held
+    /// instances keep the fragments of their precompositions.
+    encoded: AtomicBool,
+}
+
+struct CachedPrecomp {
+    scene: Arc<Scene>,
+    /// Whether the fragment was rendered since the last eviction.
+    used: bool,
+}
+
+impl LottiePrecompCache {
+    /// Whether a precomposition draws the same at every frame, besides the
+    /// frames its layers are visible in.
+    pub(crate) fn is_static(&self, name: &str, layers: &[velato::model::Layer]) -> bool {
+        *self
+            .statics
+            .lock()
+            .unwrap()
+            .entry(name.to_owned())
+            .or_insert_with(|| is_static(layers))
+    }
+
+    /// The fragment of a precomposition, in its local space, encoded unless
+    /// it was rendered at the same frame and opacity last frame.
+    pub(crate) fn get_or_encode(
+        &self,
+        name: &str,
+        frame: PrecompFrame,
+        alpha: f64,
+        encode: impl FnOnce(&mut Scene),
+    ) -> Arc<Scene> {
+        let slot = (name.to_owned(), frame, alpha.to_bits());
+        if let Some(fragment) = self.fragments.lock().unwrap().get_mut(&slot) {
+            fragment.used = true;
+            return fragment.scene.clone();
+        }
+        // Encode without the lock, so nested precompositions are cached too
+        let mut scene = Scene::new();
+        encode(&mut scene);
+        let scene = Arc::new(scene);
+        self.fragments.lock().unwrap().insert(
+            slot,
+            CachedPrecomp {
+                scene: scene.clone(),
+                used: true,
+            },
+        );
+        scene
+    }
+
+    /// Drop the fragments which weren't rendered since the last eviction,
+    /// unless no instance was encoded since.
+    fn evict_unused(&self) {
+        if self.encoded.swap(false, Ordering::Relaxed) {
+            self.fragments
+                .lock()
+                .unwrap()
+                .retain(|_, fragment| std::mem::take(&mut fragment.used));
+        }
+    }
+}
+
+/// Drop the fragments of assets which were modified or removed, such as when
+/// their images are loaded or the file is reloaded.
+pub fn invalidate_lottie_fragments(
+    mut asset_events: Extract<EventReader<AssetEvent<VelloAsset>>>,
+    mut cache: ResMut<LottieFragmentCache>,
+) {
+    for event in asset_events.read() {
+        if let AssetEvent::Modified { id } | AssetEvent::Removed { id } = event {
+            cache
+                .fragments
+                .get_mut()
+                .unwrap()
+                .retain(|(asset, _), _| asset != id);
+            cache.precomps.get_mut().unwrap().remove(id);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::VelloAsset;
+use bevy::prelude::*;
+use vello::kurbo::{BezPath, Rect, RoundedRect};
+
+/// Clips what a vello entity draws, e.g. for circular avatars or wipe
+/// reveals. The shapes are in the coordinates of the entity's content, with
+/// the origin at the top left of an asset.
+///
+/// This component isn't reflected, as the kurbo shapes it holds aren't.
+/// Entities without it, such as those spawned from a `DynamicScene`, aren't
+/// clipped.
+#[derive(Component, Debug, Clone)]
+pub enum VelloClip {
+    Rect(Rect),
+    RoundedRect(RoundedRect),
+    Path(BezPath),
+    /// Mask by the silhouette of an SVG asset, drawn in the coordinates of
+    /// the entity. Nothing is drawn until the asset is loaded.
+    Svg(Handle<VelloAsset>),
+}
+
+impl From<Rect> for VelloClip {
+    fn from(rect: Rect) -> Self {
+        Self::Rect(rect)
+    }
+}
+
+impl From<RoundedRect> for VelloClip {
+    fn from(rect: RoundedRect) -> Self {
+        Self::RoundedRect(rect)
+    }
+}
+
+impl From<BezPath> for VelloClip {
+    fn from(path: BezPath) -> Self {
+        Self::Path(path)
+    }
+}
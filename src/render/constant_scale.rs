@@ -0,0 +1,13 @@
+use bevy::prelude::*;
+
+/// Draw a world-space vello entity at a constant size on screen, one logical
+/// pixel per unit of its transform, whatever the zoom of the camera, e.g. for
+/// nameplates, damage numbers and map pins. The entity is still positioned by
+/// its transform in the world.
+///
+/// As their size in the world depends on the camera, these entities have no
+/// [`Aabb`](bevy::render::primitives::Aabb), so they aren't frustum culled or
+/// hit tested by their bounds.
+#[derive(Component, PartialEq, Eq, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct VelloConstantScale;
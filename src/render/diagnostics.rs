@@ -0,0 +1,82 @@
+//! Diagnostics of the vectors rendered every frame.
+
+use bevy::diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy::prelude::*;
+use bevy::render::RenderApp;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Adds diagnostics of the vectors rendered every frame, such as the number
+/// of extracted entities and the time spent encoding and rendering them.
+/// Add [`LogDiagnosticsPlugin`](bevy::diagnostic::LogDiagnosticsPlugin) to
+/// output them to the console.
+#[derive(Default)]
+pub struct VelloDiagnosticsPlugin;
+
+impl VelloDiagnosticsPlugin {
+    /// The number of extracted assets.
+    pub const ASSETS: DiagnosticPath = DiagnosticPath::const_new("vello/assets");
+    /// The number of extracted scenes.
+    pub const SCENES: DiagnosticPath = DiagnosticPath::const_new("vello/scenes");
+    /// The number of extracted texts.
+    pub const TEXTS: DiagnosticPath = DiagnosticPath::const_new("vello/texts");
+    /// The number of encoded paths.
+    pub const PATHS: DiagnosticPath = DiagnosticPath::const_new("vello/paths");
+    /// The number of encoded path segments.
+    pub const PATH_SEGMENTS: DiagnosticPath = DiagnosticPath::const_new("vello/path_segments");
+    /// The time spent encoding the scenes of every canvas.
+    pub const ENCODE_TIME: DiagnosticPath = DiagnosticPath::const_new("vello/encode_time");
+    /// The time spent submitting the scenes of every canvas to the GPU.
+    pub const RENDER_TIME: DiagnosticPath = DiagnosticPath::const_new("vello/render_time");
+
+    fn diagnostic_system(mut diagnostics: Diagnostics, stats: Res<VelloRenderStats>) {
+        let stats = *stats.0.lock().unwrap();
+        diagnostics.add_measurement(&Self::ASSETS, || stats.assets as f64);
+        diagnostics.add_measurement(&Self::SCENES, || stats.scenes as f64);
+        diagnostics.add_measurement(&Self::TEXTS, || stats.texts as f64);
+        diagnostics.add_measurement(&Self::PATHS, || stats.paths as f64);
+        diagnostics.add_measurement(&Self::PATH_SEGMENTS, || stats.path_segments as f64);
+        diagnostics.add_measurement(&Self::ENCODE_TIME, || {
+            stats.encode_time.as_secs_f64() * 1000.0
+        });
+        diagnostics.add_measurement(&Self::RENDER_TIME, || {
+            stats.render_time.as_secs_f64() * 1000.0
+        });
+    }
+}
+
+impl Plugin for VelloDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        let stats = VelloRenderStats::default();
+        app.insert_resource(stats.clone())
+            .register_diagnostic(Diagnostic::new(Self::ASSETS))
+            .register_diagnostic(Diagnostic::new(Self::SCENES))
+            .register_diagnostic(Diagnostic::new(Self::TEXTS))
+            .register_diagnostic(Diagnostic::new(Self::PATHS))
+            .register_diagnostic(Diagnostic::new(Self::PATH_SEGMENTS))
+            .register_diagnostic(Diagnostic::new(Self::ENCODE_TIME).with_suffix("ms"))
+            .register_diagnostic(Diagnostic::new(Self::RENDER_TIME).with_suffix("ms"))
+            .add_systems(Update, Self::diagnostic_system);
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.insert_resource(stats);
+    }
+}
+
+/// The measurements of the last rendered frame.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct RenderStats {
+    pub assets: usize,
+    pub scenes: usize,
+    pub texts: usize,
+    pub paths: u32,
+    pub path_segments: u32,
+    pub encode_time: Duration,
+    pub render_time: Duration,
+}
+
+/// The measurements of the render world, shared with the main world.
+#[derive(Resource, Clone, Default)]
+pub(crate) struct VelloRenderStats(pub Arc<Mutex<RenderStats>>);
@@ -1,38 +1,139 @@
+#[cfg(any(feature = "svg", feature = "lottie"))]
+use super::alpha_override::AlphaOverride;
+#[cfg(any(feature = "svg", feature = "lottie"))]
+use super::bake::VelloBaked;
+use super::blend_mode::VelloBlendMode;
+use super::clip::VelloClip;
+use super::constant_scale::VelloConstantScale;
+use super::flip::VelloFlip;
+use super::instances::VelloInstances;
+use super::nine_slice::VelloNineSlice;
+#[cfg(any(feature = "svg", feature = "lottie"))]
+use super::non_scaling_stroke::VelloNonScalingStroke;
+use super::size::VelloSize;
+#[cfg(any(feature = "svg", feature = "lottie"))]
+use super::tint::VelloTint;
 use super::z_function::ZFunction;
-use crate::text::VelloTextAlignment;
+use super::z_index::VelloZIndex;
+use crate::text::{
+    CachedTextLayout, VelloTextAlignment, VelloTextNodeBounds, VelloTextReveal, VelloTextSelection,
+};
 use crate::{CoordinateSpace, VelloAsset, VelloAssetAlignment, VelloFont, VelloScene, VelloText};
 use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
 use bevy::render::{extract_component::ExtractComponent, Extract};
-use bevy::window::PrimaryWindow;
+use bevy::sprite::Anchor;
+use bevy::ui::CalculatedClip;
 
 #[derive(Component, Clone)]
 pub struct ExtractedRenderAsset {
-    pub asset: VelloAsset,
+    pub asset: Handle<VelloAsset>,
     pub alignment: VelloAssetAlignment,
     pub transform: GlobalTransform,
     pub z_function: ZFunction,
+    pub z_index: VelloZIndex,
     #[cfg(feature = "lottie")]
     pub theme: Option<crate::Theme>,
     pub render_mode: CoordinateSpace,
+    #[cfg(feature = "lottie")]
     pub playhead: f64,
+    /// The scene rendered with the element overrides of this instance,
+    /// rendered instead of the asset's.
+    #[cfg(feature = "svg")]
+    pub scene: Option<std::sync::Arc<vello::Scene>>,
+    /// The tree the overridden scene is rendered from.
+    #[cfg(feature = "svg")]
+    pub svg_tree: Option<std::sync::Arc<vello_svg::usvg::Tree>>,
+    /// The composition patched by the slots of this instance, rendered
+    /// instead of the asset's.
+    #[cfg(feature = "lottie")]
+    pub composition: Option<std::sync::Arc<velato::Composition>>,
+    /// The replaced strings of the lottie text layers of this instance.
+    #[cfg(feature = "lottie")]
+    pub text_overrides: Option<crate::integrations::lottie::LottieTextOverrides>,
+    /// The shown and hidden lottie layers of this instance.
+    #[cfg(feature = "lottie")]
+    pub layer_visibility: Option<crate::integrations::lottie::LottieLayerVisibility>,
+    /// The opacity of this instance, multiplied with the asset's alpha.
+    #[cfg(any(feature = "svg", feature = "lottie"))]
     pub alpha: f32,
+    /// The opaque color multiplied with the colors of this instance, if not
+    /// white.
+    #[cfg(any(feature = "svg", feature = "lottie"))]
+    pub tint: Option<Color>,
+    /// How this instance is composited over the canvas beneath.
+    pub blend_mode: Option<VelloBlendMode>,
+    pub clip: Option<VelloClip>,
+    /// The clip rect of the UI node, in logical pixels, when in an overflow
+    /// container.
+    pub ui_clip: Option<bevy::math::Rect>,
+    /// The nine-slice scaling of an SVG instance, sized to its UI node or
+    /// [`VelloSize`].
+    pub nine_slice: Option<VelloNineSlice>,
+    /// The size to scale this instance to, unless nine-sliced.
+    pub size: Option<VelloSize>,
+    /// The pivot of this instance, instead of the center of the asset.
+    pub anchor: Option<Anchor>,
+    pub flip: Option<VelloFlip>,
+    /// Whether this instance is drawn at a constant size on screen.
+    pub constant_scale: bool,
+    /// The transforms this instance is drawn at, instead of its own.
+    pub instances: Option<VelloInstances>,
+    /// Whether the strokes of this instance are drawn at a constant width on
+    /// screen.
+    #[cfg(any(feature = "svg", feature = "lottie"))]
+    pub non_scaling_stroke: bool,
     pub ui_node: Option<Node>,
+    pub render_layers: Option<RenderLayers>,
+}
+
+/// The opacity of an instance, from its alpha override and tint.
+#[cfg(any(feature = "svg", feature = "lottie"))]
+fn instance_alpha(alpha: Option<&AlphaOverride>, tint: Option<&VelloTint>) -> f32 {
+    alpha.map_or(1.0, |alpha| alpha.0) * tint.map_or(1.0, |tint| tint.0.a())
+}
+
+/// The opaque color of a tint, unless it is white and has no effect.
+#[cfg(any(feature = "svg", feature = "lottie"))]
+fn instance_tint(tint: Option<&VelloTint>) -> Option<Color> {
+    tint.map(|tint| tint.0.with_a(1.0))
+        .filter(|tint| tint.as_rgba_f32() != [1.0; 4])
 }
 
 #[cfg(feature = "svg")]
 pub fn extract_svg_instances(
     mut commands: Commands,
     query_vectors: Extract<
-        Query<(
-            &Handle<VelloAsset>,
-            &VelloAssetAlignment,
-            &CoordinateSpace,
-            &ZFunction,
-            &GlobalTransform,
-            Option<&Node>,
-            &ViewVisibility,
-            &InheritedVisibility,
-        )>,
+        Query<
+            (
+                &Handle<VelloAsset>,
+                &VelloAssetAlignment,
+                &CoordinateSpace,
+                Option<&ZFunction>,
+                Option<&VelloZIndex>,
+                &GlobalTransform,
+                Option<&Node>,
+                Option<&RenderLayers>,
+                (
+                    Option<&AlphaOverride>,
+                    Option<&VelloTint>,
+                    Option<&VelloBlendMode>,
+                    Option<&VelloClip>,
+                    Option<&CalculatedClip>,
+                    Option<&VelloNineSlice>,
+                    Option<&VelloSize>,
+                    Option<&Anchor>,
+                    Option<&VelloFlip>,
+                    Has<VelloConstantScale>,
+                    Option<&VelloInstances>,
+                    Has<VelloNonScalingStroke>,
+                ),
+                Option<&crate::integrations::svg::OverriddenSvg>,
+                &ViewVisibility,
+                &InheritedVisibility,
+            ),
+            Without<VelloBaked>,
+        >,
     >,
     assets: Extract<Res<Assets<VelloAsset>>>,
 ) {
@@ -41,54 +142,130 @@ pub fn extract_svg_instances(
         alignment,
         coord_space,
         z_function,
+        z_index,
         transform,
         ui_node,
+        render_layers,
+        (
+            alpha,
+            tint,
+            blend_mode,
+            clip,
+            ui_clip,
+            nine_slice,
+            size,
+            anchor,
+            flip,
+            constant_scale,
+            instances,
+            non_scaling_stroke,
+        ),
+        overridden,
         view_visibility,
         inherited_visibility,
     ) in query_vectors.iter()
     {
-        if let Some(
-            asset @ VelloAsset {
-                file: _file @ crate::VectorFile::Svg(_),
-                alpha,
-                ..
-            },
-        ) = assets.get(vello_vector_handle)
+        if let Some(VelloAsset {
+            file: _file @ crate::VectorFile::Svg(_),
+            ..
+        }) = assets.get(vello_vector_handle)
         {
             if view_visibility.get() && inherited_visibility.get() {
                 commands.spawn(ExtractedRenderAsset {
-                    asset: asset.to_owned(),
+                    asset: vello_vector_handle.clone(),
                     transform: *transform,
                     alignment: *alignment,
-                    z_function: *z_function,
+                    z_function: z_function.copied().unwrap_or_default(),
+                    z_index: z_index.copied().unwrap_or_default(),
                     #[cfg(feature = "lottie")]
                     theme: None,
                     render_mode: *coord_space,
+                    #[cfg(feature = "lottie")]
                     playhead: 0.0,
-                    alpha: *alpha,
+                    scene: overridden
+                        .filter(|overridden| overridden.asset == vello_vector_handle.id())
+                        .map(|overridden| overridden.scene.clone()),
+                    svg_tree: overridden
+                        .filter(|overridden| overridden.asset == vello_vector_handle.id())
+                        .and_then(|overridden| overridden.tree.clone()),
+                    #[cfg(feature = "lottie")]
+                    composition: None,
+                    #[cfg(feature = "lottie")]
+                    text_overrides: None,
+                    #[cfg(feature = "lottie")]
+                    layer_visibility: None,
+                    alpha: instance_alpha(alpha, tint),
+                    tint: instance_tint(tint),
+                    blend_mode: blend_mode.copied(),
+                    clip: clip.cloned(),
+                    ui_clip: ui_clip.map(|ui_clip| ui_clip.clip),
+                    nine_slice: nine_slice.map(|nine_slice| VelloNineSlice {
+                        size: ui_node
+                            .map(Node::size)
+                            .or(size.map(|size| size.size))
+                            .unwrap_or(nine_slice.size),
+                        ..*nine_slice
+                    }),
+                    size: size.filter(|_| nine_slice.is_none()).copied(),
+                    anchor: anchor.copied(),
+                    flip: flip.copied(),
+                    constant_scale,
+                    instances: instances.cloned(),
+                    non_scaling_stroke,
                     ui_node: ui_node.cloned(),
+                    render_layers: render_layers.copied(),
                 });
             }
         }
     }
 }
 
+/// The crossfade of a dotLottie player, when that integration is enabled.
+#[cfg(feature = "experimental-dotLottie")]
+type ExtractedCrossfade = Option<&'static crate::PlayerCrossfade>;
+#[cfg(all(feature = "lottie", not(feature = "experimental-dotLottie")))]
+type ExtractedCrossfade = ();
+
 #[cfg(feature = "lottie")]
+#[cfg_attr(not(feature = "experimental-dotLottie"), allow(unused_variables))]
 pub fn extract_lottie_instances(
     mut commands: Commands,
     query_vectors: Extract<
-        Query<(
-            &Handle<VelloAsset>,
-            &VelloAssetAlignment,
-            &CoordinateSpace,
-            &ZFunction,
-            &GlobalTransform,
-            &crate::Playhead,
-            Option<&crate::Theme>,
-            Option<&Node>,
-            &ViewVisibility,
-            &InheritedVisibility,
-        )>,
+        Query<
+            (
+                &Handle<VelloAsset>,
+                &VelloAssetAlignment,
+                &CoordinateSpace,
+                Option<&ZFunction>,
+                Option<&VelloZIndex>,
+                &GlobalTransform,
+                &crate::Playhead,
+                Option<&crate::Theme>,
+                Option<&Node>,
+                Option<&RenderLayers>,
+                (
+                    Option<&AlphaOverride>,
+                    Option<&VelloTint>,
+                    Option<&VelloBlendMode>,
+                    Option<&VelloClip>,
+                    Option<&CalculatedClip>,
+                    Option<&VelloSize>,
+                    Option<&Anchor>,
+                    Option<&VelloFlip>,
+                    Option<&crate::integrations::lottie::SlottedComposition>,
+                    Option<&crate::integrations::lottie::LottieTextOverrides>,
+                    Option<&crate::integrations::lottie::LottieLayerVisibility>,
+                    Option<&crate::PlaybackOptions>,
+                    Has<VelloConstantScale>,
+                    Option<&VelloInstances>,
+                    Has<VelloNonScalingStroke>,
+                ),
+                &ViewVisibility,
+                &InheritedVisibility,
+                ExtractedCrossfade,
+            ),
+            Without<VelloBaked>,
+        >,
     >,
     assets: Extract<Res<Assets<VelloAsset>>>,
 ) {
@@ -97,35 +274,98 @@ pub fn extract_lottie_instances(
         alignment,
         coord_space,
         z_function,
+        z_index,
         transform,
         playhead,
         theme,
         ui_node,
+        render_layers,
+        (
+            alpha,
+            tint,
+            blend_mode,
+            clip,
+            ui_clip,
+            size,
+            anchor,
+            flip,
+            slotted,
+            text_overrides,
+            layer_visibility,
+            options,
+            constant_scale,
+            instances,
+            non_scaling_stroke,
+        ),
         view_visibility,
         inherited_visibility,
+        crossfade,
     ) in query_vectors.iter()
     {
-        if let Some(
-            asset @ VelloAsset {
-                file: _file @ crate::VectorFile::Lottie(_),
-                alpha,
-                ..
-            },
-        ) = assets.get(vello_vector_handle)
+        if let Some(VelloAsset {
+            file: crate::VectorFile::Lottie(composition),
+            ..
+        }) = assets.get(vello_vector_handle)
         {
             if view_visibility.get() && inherited_visibility.get() {
-                let playhead = playhead.frame();
-                commands.spawn(ExtractedRenderAsset {
-                    asset: asset.to_owned(),
+                let playhead = options.map_or(playhead.frame(), |options| {
+                    options.rendered_frame(playhead.frame(), composition.frame_rate)
+                });
+                let instance = ExtractedRenderAsset {
+                    asset: vello_vector_handle.clone(),
                     transform: *transform,
                     alignment: *alignment,
-                    z_function: *z_function,
+                    z_function: z_function.copied().unwrap_or_default(),
+                    z_index: z_index.copied().unwrap_or_default(),
                     theme: theme.cloned(),
                     render_mode: *coord_space,
                     playhead,
-                    alpha: *alpha,
+                    #[cfg(feature = "svg")]
+                    scene: None,
+                    #[cfg(feature = "svg")]
+                    svg_tree: None,
+                    composition: slotted
+                        .filter(|slotted| slotted.asset == vello_vector_handle.id())
+                        .map(|slotted| slotted.composition.clone()),
+                    text_overrides: text_overrides.cloned(),
+                    layer_visibility: layer_visibility.cloned(),
+                    alpha: instance_alpha(alpha, tint),
+                    tint: instance_tint(tint),
+                    blend_mode: blend_mode.copied(),
+                    clip: clip.cloned(),
+                    ui_clip: ui_clip.map(|ui_clip| ui_clip.clip),
+                    nine_slice: None,
+                    size: size.copied(),
+                    anchor: anchor.copied(),
+                    flip: flip.copied(),
+                    constant_scale,
+                    instances: instances.cloned(),
+                    non_scaling_stroke,
                     ui_node: ui_node.cloned(),
-                });
+                    render_layers: render_layers.copied(),
+                };
+                // Draw the animation being faded out beneath the current one
+                #[cfg(feature = "experimental-dotLottie")]
+                if let Some(crossfade) = crossfade {
+                    let progress = crossfade.progress();
+                    if assets.contains(crossfade.asset.id()) {
+                        commands.spawn(ExtractedRenderAsset {
+                            asset: crossfade.asset.clone(),
+                            theme: crossfade.theme.clone(),
+                            playhead: crossfade.frame,
+                            composition: None,
+                            text_overrides: None,
+                            alpha: instance.alpha * (1.0 - progress),
+                            ..instance.clone()
+                        });
+                    }
+                    commands.spawn(ExtractedRenderAsset {
+                        alpha: instance.alpha * progress,
+                        ..instance
+                    });
+                    continue;
+                }
+                commands.spawn(instance);
             }
         }
     }
@@ -135,8 +375,16 @@ pub fn extract_lottie_instances(
 pub struct ExtractedRenderScene {
     pub scene: VelloScene,
     pub transform: GlobalTransform,
+    pub z_index: VelloZIndex,
     pub render_mode: CoordinateSpace,
+    pub blend_mode: Option<VelloBlendMode>,
+    pub clip: Option<VelloClip>,
+    pub ui_clip: Option<bevy::math::Rect>,
     pub ui_node: Option<Node>,
+    pub constant_scale: bool,
+    /// The transforms this scene is drawn at, instead of its own.
+    pub instances: Option<VelloInstances>,
+    pub render_layers: Option<RenderLayers>,
 }
 
 pub fn scene_instances(
@@ -146,21 +394,48 @@ pub fn scene_instances(
             &VelloScene,
             &CoordinateSpace,
             &GlobalTransform,
+            Option<&VelloZIndex>,
             &ViewVisibility,
             &InheritedVisibility,
             Option<&Node>,
+            Option<&RenderLayers>,
+            Option<&VelloBlendMode>,
+            Option<&VelloClip>,
+            Option<&CalculatedClip>,
+            Has<VelloConstantScale>,
+            Option<&VelloInstances>,
         )>,
     >,
 ) {
-    for (scene, coord_space, transform, view_visibility, inherited_visibility, ui_node) in
-        query_scenes.iter()
+    for (
+        scene,
+        coord_space,
+        transform,
+        z_index,
+        view_visibility,
+        inherited_visibility,
+        ui_node,
+        render_layers,
+        blend_mode,
+        clip,
+        ui_clip,
+        constant_scale,
+        instances,
+    ) in query_scenes.iter()
     {
         if view_visibility.get() && inherited_visibility.get() {
             commands.spawn(ExtractedRenderScene {
                 transform: *transform,
+                z_index: z_index.copied().unwrap_or_default(),
                 render_mode: *coord_space,
+                blend_mode: blend_mode.copied(),
+                clip: clip.cloned(),
+                ui_clip: ui_clip.map(|ui_clip| ui_clip.clip),
                 scene: scene.clone(),
                 ui_node: ui_node.cloned(),
+                constant_scale,
+                instances: instances.cloned(),
+                render_layers: render_layers.copied(),
             });
         }
     }
@@ -171,8 +446,19 @@ pub struct ExtractedRenderText {
     pub font: Handle<VelloFont>,
     pub text: VelloText,
     pub alignment: VelloTextAlignment,
+    pub reveal: Option<VelloTextReveal>,
+    pub selection: Option<VelloTextSelection>,
+    pub layout: Option<CachedTextLayout>,
     pub transform: GlobalTransform,
+    pub z_index: VelloZIndex,
     pub render_mode: CoordinateSpace,
+    pub blend_mode: Option<VelloBlendMode>,
+    pub clip: Option<VelloClip>,
+    pub ui_clip: Option<bevy::math::Rect>,
+    /// The UI node the text is laid out in, with [`VelloTextNodeBounds`].
+    pub ui_node: Option<Node>,
+    pub constant_scale: bool,
+    pub render_layers: Option<RenderLayers>,
 }
 
 impl ExtractComponent for ExtractedRenderText {
@@ -180,8 +466,18 @@ impl ExtractComponent for ExtractedRenderText {
         &'static Handle<VelloFont>,
         &'static VelloText,
         &'static VelloTextAlignment,
+        Option<&'static VelloTextReveal>,
+        Option<&'static VelloTextSelection>,
+        Option<&'static CachedTextLayout>,
         &'static GlobalTransform,
+        Option<&'static VelloZIndex>,
         &'static CoordinateSpace,
+        Option<&'static RenderLayers>,
+        Option<&'static VelloBlendMode>,
+        Option<&'static VelloClip>,
+        Option<&'static CalculatedClip>,
+        (Option<&'static Node>, Has<VelloTextNodeBounds>),
+        Has<VelloConstantScale>,
     );
 
     type QueryFilter = ();
@@ -189,49 +485,60 @@ impl ExtractComponent for ExtractedRenderText {
     type Out = Self;
 
     fn extract_component(
-        (vello_font_handle, text, alignment, transform, render_mode): bevy::ecs::query::QueryItem<
-            '_,
-            Self::QueryData,
-        >,
+        (
+            vello_font_handle,
+            text,
+            alignment,
+            reveal,
+            selection,
+            layout,
+            transform,
+            z_index,
+            render_mode,
+            render_layers,
+            blend_mode,
+            clip,
+            ui_clip,
+            (ui_node, node_bounds),
+            constant_scale,
+        ): bevy::ecs::query::QueryItem<'_, Self::QueryData>,
     ) -> Option<Self> {
         Some(Self {
             font: vello_font_handle.clone(),
             text: text.clone(),
             alignment: *alignment,
+            reveal: reveal.copied(),
+            selection: selection.cloned(),
+            layout: layout.cloned(),
             transform: *transform,
+            z_index: z_index.copied().unwrap_or_default(),
             render_mode: *render_mode,
+            blend_mode: blend_mode.copied(),
+            clip: clip.cloned(),
+            ui_clip: ui_clip.map(|ui_clip| ui_clip.clip),
+            ui_node: ui_node.filter(|_| node_bounds).cloned(),
+            constant_scale,
+            render_layers: render_layers.copied(),
         })
     }
 }
 
-#[derive(Component, Default)]
-pub struct SSRenderTarget(pub Handle<Image>);
-
-impl ExtractComponent for SSRenderTarget {
-    type QueryData = &'static SSRenderTarget;
-
-    type QueryFilter = ();
-
-    type Out = Self;
-
-    fn extract_component(
-        ss_render_target: bevy::ecs::query::QueryItem<'_, Self::QueryData>,
-    ) -> Option<Self> {
-        Some(Self(ss_render_target.0.clone()))
-    }
-}
-
-#[derive(Resource)]
+/// The scale factor of a camera's render target, e.g. the scale factor of the
+/// window it renders to, or `1.0` for an image.
+#[derive(Component, Clone, Copy)]
 pub struct ExtractedPixelScale(pub f32);
 
 pub fn extract_pixel_scale(
-    mut pixel_scale: ResMut<ExtractedPixelScale>,
-    windows: Extract<Query<&Window, With<PrimaryWindow>>>,
+    mut commands: Commands,
+    cameras: Extract<Query<(Entity, &Camera), With<Camera2d>>>,
 ) {
-    let scale_factor = windows
-        .get_single()
-        .map(|window| window.resolution.scale_factor())
-        .unwrap_or(1.0);
-
-    pixel_scale.0 = scale_factor;
+    for (entity, camera) in cameras.iter() {
+        if !camera.is_active {
+            continue;
+        }
+        let scale_factor = camera.target_scaling_factor().unwrap_or(1.0);
+        commands
+            .get_or_spawn(entity)
+            .insert(ExtractedPixelScale(scale_factor));
+    }
 }
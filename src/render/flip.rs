@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+
+/// Mirror a vello asset around its pivot, e.g. for characters facing left or
+/// right, without a negative scale on the `Transform` which also flips its
+/// children.
+#[derive(Component, PartialEq, Eq, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct VelloFlip {
+    /// Mirror horizontally.
+    pub x: bool,
+    /// Mirror vertically.
+    pub y: bool,
+}
+
+impl VelloFlip {
+    /// The scale which mirrors the flipped axes.
+    pub fn scale(&self) -> Vec2 {
+        Vec2::new(
+            if self.x { -1.0 } else { 1.0 },
+            if self.y { -1.0 } else { 1.0 },
+        )
+    }
+}
@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+
+/// Draw the asset or scene of an entity at many transforms, e.g. a swarm of
+/// identical shapes. The asset or scene is encoded once, and appended at
+/// every instance, which is much cheaper than an entity per shape.
+///
+/// The entity is only drawn at its instances, which are relative to its
+/// transform. Instanced entities have no
+/// [`Aabb`](bevy::render::primitives::Aabb), so they aren't frustum culled or
+/// hit tested, but instances of an asset off screen aren't drawn.
+#[derive(Component, PartialEq, Default, Clone, Debug, Reflect)]
+#[reflect(Component)]
+pub struct VelloInstances {
+    pub instances: Vec<VelloInstance>,
+}
+
+impl VelloInstances {
+    /// Instances at the given transforms, without tints.
+    pub fn from_transforms(transforms: impl IntoIterator<Item = Transform>) -> Self {
+        Self {
+            instances: transforms
+                .into_iter()
+                .map(|transform| VelloInstance {
+                    transform,
+                    tint: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An instance of [`VelloInstances`].
+#[derive(PartialEq, Default, Clone, Copy, Debug, Reflect)]
+pub struct VelloInstance {
+    /// The transform of the instance, relative to the entity.
+    pub transform: Transform,
+    /// The color multiplied with the colors of the instance, whose alpha is
+    /// the opacity of the instance.
+    pub tint: Option<Color>,
+}
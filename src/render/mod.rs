@@ -1,65 +1,82 @@
 //! Components and logic for rendering.
 
 use bevy::prelude::*;
-use bevy::render::mesh::MeshVertexBufferLayout;
-use bevy::render::render_resource::{
-    AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
-    VertexBufferLayout, VertexFormat, VertexStepMode,
-};
-use bevy::sprite::{Material2d, Material2dKey};
+use bevy::render::renderer::RenderDevice;
+use vello::{Renderer, RendererOptions};
 
+mod alpha_override;
+mod bake;
+mod blend_mode;
+mod bounds;
+#[cfg(feature = "lottie")]
+mod cache;
+mod clip;
+mod constant_scale;
+mod diagnostics;
 mod extract;
+mod flip;
+mod instances;
+mod nine_slice;
+mod non_scaling_stroke;
+mod pipeline;
 mod plugin;
 mod prepare;
+mod settings;
+mod size;
+#[cfg(feature = "svg")]
+mod stroke_cache;
 mod systems;
+mod tint;
 mod z_function;
+mod z_index;
 
+pub use alpha_override::AlphaOverride;
+pub use bake::{VelloBakeRequests, VelloBaked};
+pub use blend_mode::VelloBlendMode;
+#[cfg(feature = "lottie")]
+pub(crate) use cache::LottiePrecompCache;
+pub use clip::VelloClip;
+pub use constant_scale::VelloConstantScale;
+pub use diagnostics::VelloDiagnosticsPlugin;
+pub use flip::VelloFlip;
+pub use instances::{VelloInstance, VelloInstances};
+pub use nine_slice::VelloNineSlice;
+pub use non_scaling_stroke::VelloNonScalingStroke;
 pub use plugin::VelloRenderPlugin;
+pub use settings::VelloRenderSettings;
+pub use size::{VelloScaleMode, VelloSize};
+pub use tint::VelloTint;
 pub use z_function::ZFunction;
+pub use z_index::VelloZIndex;
 
-/// A handle to the screen space render target shader.
-pub const SSRT_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(2314894693238056781);
-
-/// A canvas material, with a shader that samples a texture with view-independent UV coordinates.
-#[derive(AsBindGroup, TypePath, Asset, Clone)]
-pub struct VelloCanvasMaterial {
-    #[texture(0)]
-    #[sampler(1)]
-    pub texture: Handle<Image>,
-}
-
-impl Material2d for VelloCanvasMaterial {
-    fn vertex_shader() -> ShaderRef {
-        SSRT_SHADER_HANDLE.into()
-    }
-
-    fn fragment_shader() -> ShaderRef {
-        SSRT_SHADER_HANDLE.into()
-    }
+/// A handle to the shader which composites vello canvases into the 2D main pass.
+pub const CANVAS_SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(2314894693238056781);
 
-    fn specialize(
-        descriptor: &mut RenderPipelineDescriptor,
-        _layout: &MeshVertexBufferLayout,
-        _key: Material2dKey<Self>,
-    ) -> Result<(), SpecializedMeshPipelineError> {
-        let formats = vec![
-            // Position
-            VertexFormat::Float32x3,
-            VertexFormat::Float32x2,
-        ];
-
-        let vertex_layout =
-            VertexBufferLayout::from_vertex_formats(VertexStepMode::Vertex, formats);
-
-        descriptor.vertex.buffers = vec![vertex_layout];
+#[derive(Deref, DerefMut)]
+pub struct VelloRenderer(vello::Renderer);
 
-        Ok(())
+impl VelloRenderer {
+    /// Creates a renderer for the given settings.
+    pub fn new(device: &RenderDevice, settings: &VelloRenderSettings) -> Option<Self> {
+        let renderer = Renderer::new(
+            device.wgpu_device(),
+            RendererOptions {
+                surface_format: None,
+                use_cpu: settings.use_cpu,
+                antialiasing_support: settings.antialiasing_support(),
+                num_init_threads: None,
+            },
+        );
+        match renderer {
+            Ok(renderer) => Some(Self(renderer)),
+            Err(e) => {
+                error!("Failed to create the vello renderer: {e}");
+                None
+            }
+        }
     }
 }
 
-#[derive(Deref, DerefMut)]
-pub struct VelloRenderer(vello::Renderer);
-
 #[derive(Resource, Deref, DerefMut)]
 #[cfg(feature = "lottie")]
 pub struct VelatoRenderer(velato::Renderer);
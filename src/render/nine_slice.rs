@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use bevy::sprite::BorderRect;
+#[cfg(feature = "svg")]
+use vello::{
+    kurbo::{Affine, Rect},
+    peniko::Mix,
+    Scene,
+};
+
+/// Draw an SVG asset at another size by only scaling its middle regions,
+/// keeping the corners crisp, e.g. for vector UI panels and buttons.
+///
+/// Assets in UI nodes are drawn at the size of the node instead.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct VelloNineSlice {
+    /// The size of the unscaled borders, in asset pixels.
+    pub insets: BorderRect,
+    /// The size to draw the asset at, in asset pixels.
+    pub size: Vec2,
+}
+
+impl VelloNineSlice {
+    pub fn new(insets: impl Into<BorderRect>, size: Vec2) -> Self {
+        Self {
+            insets: insets.into(),
+            size,
+        }
+    }
+}
+
+/// The edges of the three slices of an axis, in the asset and at the target
+/// size. Borders larger than the target size are shrunk.
+#[cfg(feature = "svg")]
+fn slices(start: f32, end: f32, asset_size: f32, target_size: f32) -> ([f64; 4], [f64; 4]) {
+    let shrink = if start + end > target_size {
+        target_size / (start + end)
+    } else {
+        1.0
+    };
+    let (start, end) = (start as f64, end as f64);
+    let (asset_size, target_size, shrink) = (asset_size as f64, target_size as f64, shrink as f64);
+    (
+        [0.0, start, asset_size - end, asset_size],
+        [0.0, start * shrink, target_size - end * shrink, target_size],
+    )
+}
+
+/// Draw the nine slices of a scene, each clipped to its region at the target
+/// size.
+#[cfg(feature = "svg")]
+pub(crate) fn render_nine_slice(
+    scene_buffer: &mut Scene,
+    scene: &Scene,
+    asset_size: Vec2,
+    nine_slice: &VelloNineSlice,
+    affine: Affine,
+) {
+    let insets = nine_slice.insets;
+    let (source_x, target_x) = slices(insets.left, insets.right, asset_size.x, nine_slice.size.x);
+    let (source_y, target_y) = slices(insets.top, insets.bottom, asset_size.y, nine_slice.size.y);
+    for column in 0..3 {
+        for row in 0..3 {
+            let (source_width, target_width) = (
+                source_x[column + 1] - source_x[column],
+                target_x[column + 1] - target_x[column],
+            );
+            let (source_height, target_height) = (
+                source_y[row + 1] - source_y[row],
+                target_y[row + 1] - target_y[row],
+            );
+            if source_width <= 0.0
+                || source_height <= 0.0
+                || target_width <= 0.0
+                || target_height <= 0.0
+            {
+                continue;
+            }
+            let slice = Affine::translate((target_x[column], target_y[row]))
+                * Affine::scale_non_uniform(
+                    target_width / source_width,
+                    target_height / source_height,
+                )
+                * Affine::translate((-source_x[column], -source_y[row]));
+            let bounds = Rect::new(
+                target_x[column],
+                target_y[row],
+                target_x[column + 1],
+                target_y[row + 1],
+            );
+            scene_buffer.push_layer(Mix::Clip, 1.0, affine, &bounds);
+            scene_buffer.append(scene, Some(affine * slice));
+            scene_buffer.pop_layer();
+        }
+    }
+}
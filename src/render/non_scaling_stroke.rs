@@ -0,0 +1,31 @@
+#![cfg_attr(not(any(feature = "svg", feature = "lottie")), allow(unused))]
+
+use bevy::prelude::*;
+use vello::kurbo::Affine;
+
+/// Draw the strokes of an SVG or lottie asset at a constant width on screen,
+/// one logical pixel per unit of width, whatever the zoom of the camera and
+/// the scale of the entity, e.g. for the hairlines of maps and CAD drawings.
+///
+/// The asset is encoded again when its scale on screen changes, instead of
+/// once. Transforms within the file still scale its strokes, and scenes and
+/// texts are drawn as they are encoded.
+#[derive(Component, PartialEq, Eq, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct VelloNonScalingStroke;
+
+/// The steps per doubling of the scale of non-scaling strokes.
+const STROKE_SCALE_STEPS: f32 = 16.0;
+
+/// The scale of the stroke widths of a non-scaling instance drawn with a view
+/// affine, which undoes the units of the instance in logical pixels. It is
+/// rounded, so zooming only encodes the instance again a few times.
+pub(crate) fn stroke_scale(affine: Affine, pixel_scale: f32) -> f32 {
+    let pixels_per_unit = affine.determinant().abs().sqrt() as f32 / pixel_scale;
+    let steps = (-pixels_per_unit.log2() * STROKE_SCALE_STEPS).round();
+    let scale = (steps / STROKE_SCALE_STEPS).exp2();
+    match scale.is_finite() && scale > 0.0 {
+        true => scale,
+        false => 1.0,
+    }
+}
@@ -0,0 +1,142 @@
+//! The pipeline and draw commands used to composite vello canvases into the
+//! `Transparent2d` render phase.
+
+use super::CANVAS_SHADER_HANDLE;
+use bevy::core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state;
+use bevy::ecs::query::ROQueryItem;
+use bevy::ecs::system::lifetimeless::Read;
+use bevy::ecs::system::SystemParamItem;
+use bevy::prelude::*;
+use bevy::render::render_phase::{
+    PhaseItem, RenderCommand, RenderCommandResult, SetItemPipeline, TrackedRenderPass,
+};
+use bevy::render::render_resource::binding_types::{sampler, texture_2d};
+use bevy::render::render_resource::{
+    BindGroup, BindGroupLayout, BindGroupLayoutEntries, BlendState, ColorTargetState, ColorWrites,
+    FragmentState, MultisampleState, PrimitiveState, RenderPipelineDescriptor, Sampler,
+    SamplerBindingType, SamplerDescriptor, ShaderStages, SpecializedRenderPipeline, TextureFormat,
+    TextureSampleType,
+};
+use bevy::render::renderer::RenderDevice;
+use bevy::render::texture::BevyDefault;
+use bevy::render::view::ViewTarget;
+
+/// The pipeline which draws a vello canvas texture over the whole viewport.
+#[derive(Resource)]
+pub struct VelloCanvasPipeline {
+    pub texture_layout: BindGroupLayout,
+    pub sampler: Sampler,
+}
+
+impl FromWorld for VelloCanvasPipeline {
+    fn from_world(render_world: &mut World) -> Self {
+        let render_device = render_world.resource::<RenderDevice>();
+
+        let texture_layout = render_device.create_bind_group_layout(
+            "vello_canvas_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    sampler(SamplerBindingType::NonFiltering),
+                ),
+            ),
+        );
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+
+        Self {
+            texture_layout,
+            sampler,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
+pub struct VelloCanvasPipelineKey {
+    pub hdr: bool,
+    pub samples: u32,
+}
+
+impl SpecializedRenderPipeline for VelloCanvasPipeline {
+    type Key = VelloCanvasPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let format = match key.hdr {
+            true => ViewTarget::TEXTURE_FORMAT_HDR,
+            false => TextureFormat::bevy_default(),
+        };
+
+        RenderPipelineDescriptor {
+            label: Some("vello canvas pipeline".into()),
+            layout: vec![self.texture_layout.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: CANVAS_SHADER_HANDLE,
+                shader_defs: vec![],
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: Some(BlendState::ALPHA_BLENDING),
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState {
+                count: key.samples,
+                ..Default::default()
+            },
+            push_constant_ranges: Vec::new(),
+        }
+    }
+}
+
+/// The bind group of a canvas texture, prepared for the draw call.
+#[derive(Component)]
+pub struct VelloCanvasBindGroup(pub BindGroup);
+
+pub type DrawVelloCanvas = (
+    SetItemPipeline,
+    SetVelloCanvasBindGroup<0>,
+    DrawFullscreenTriangle,
+);
+
+pub struct SetVelloCanvasBindGroup<const I: usize>;
+impl<P: PhaseItem, const I: usize> RenderCommand<P> for SetVelloCanvasBindGroup<I> {
+    type Param = ();
+    type ViewQuery = ();
+    type ItemQuery = Read<VelloCanvasBindGroup>;
+
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        bind_group: Option<ROQueryItem<'w, Self::ItemQuery>>,
+        _param: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(bind_group) = bind_group else {
+            return RenderCommandResult::Failure;
+        };
+        pass.set_bind_group(I, &bind_group.0, &[]);
+        RenderCommandResult::Success
+    }
+}
+
+pub struct DrawFullscreenTriangle;
+impl<P: PhaseItem> RenderCommand<P> for DrawFullscreenTriangle {
+    type Param = ();
+    type ViewQuery = ();
+    type ItemQuery = ();
+
+    fn render<'w>(
+        _item: &P,
+        _view: (),
+        _entity: Option<()>,
+        _param: SystemParamItem<'w, '_, Self::Param>,
+        pass: &mut TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        pass.draw(0..3, 0..1);
+        RenderCommandResult::Success
+    }
+}
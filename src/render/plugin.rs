@@ -1,15 +1,22 @@
-use super::extract::{self, ExtractedPixelScale, SSRenderTarget};
-use super::{prepare, systems};
+use super::bake;
+use super::bounds;
+use super::extract;
+use super::pipeline::{DrawVelloCanvas, VelloCanvasPipeline};
+use super::systems;
 use crate::render::extract::ExtractedRenderText;
-use crate::render::SSRT_SHADER_HANDLE;
-use crate::{VelloCanvasMaterial, VelloFont};
+use crate::render::CANVAS_SHADER_HANDLE;
+use crate::{VelloAsset, VelloFont, VelloRenderSettings};
 use bevy::asset::load_internal_asset;
+use bevy::core_pipeline::core_2d::Transparent2d;
 use bevy::prelude::*;
 use bevy::render::extract_component::ExtractComponentPlugin;
+use bevy::render::extract_resource::ExtractResourcePlugin;
 use bevy::render::render_asset::RenderAssetPlugin;
-use bevy::render::renderer::RenderDevice;
+use bevy::render::render_phase::{sort_phase_system, AddRenderCommand};
+use bevy::render::render_resource::SpecializedRenderPipelines;
+use bevy::render::renderer::{render_system, RenderDevice};
+use bevy::render::view::VisibilitySystems;
 use bevy::render::{Render, RenderApp, RenderSet};
-use bevy::sprite::Material2dPlugin;
 
 pub struct VelloRenderPlugin;
 
@@ -17,24 +24,56 @@ impl Plugin for VelloRenderPlugin {
     fn build(&self, app: &mut App) {
         load_internal_asset!(
             app,
-            SSRT_SHADER_HANDLE,
-            "../../shaders/vello_ss_rendertarget.wgsl",
+            CANVAS_SHADER_HANDLE,
+            "../../shaders/vello_canvas.wgsl",
             Shader::from_wgsl
         );
 
+        app.init_resource::<VelloRenderSettings>()
+            .add_plugins((
+                ExtractComponentPlugin::<ExtractedRenderText>::default(),
+                ExtractResourcePlugin::<VelloRenderSettings>::default(),
+                ExtractResourcePlugin::<crate::VelloFontFallback>::default(),
+                ExtractResourcePlugin::<bake::VelloBakeRequests>::default(),
+                RenderAssetPlugin::<VelloAsset>::default(),
+                RenderAssetPlugin::<VelloFont>::default(),
+            ))
+            .init_resource::<bake::BakedImages>()
+            .init_resource::<bake::VelloBakeRequests>()
+            .add_systems(First, bake::clear_bake_requests)
+            .add_systems(PostUpdate, bake::bake_vello_assets)
+            .add_systems(
+                PostUpdate,
+                (
+                    bounds::calculate_asset_bounds,
+                    bounds::calculate_text_bounds,
+                )
+                    .in_set(VisibilitySystems::CalculateBounds),
+            );
+
         let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
             return;
         };
 
         #[cfg(feature = "svg")]
-        render_app.add_systems(ExtractSchedule, extract::extract_svg_instances);
+        render_app
+            .init_resource::<super::stroke_cache::SvgStrokeCache>()
+            .add_systems(ExtractSchedule, extract::extract_svg_instances);
         #[cfg(feature = "lottie")]
         render_app
             .init_resource::<super::VelatoRenderer>()
-            .add_systems(ExtractSchedule, extract::extract_lottie_instances);
+            .init_resource::<super::cache::LottieFragmentCache>()
+            .add_systems(
+                ExtractSchedule,
+                (
+                    extract::extract_lottie_instances,
+                    super::cache::invalidate_lottie_fragments,
+                ),
+            );
 
         render_app
-            .insert_resource(ExtractedPixelScale(1.0))
+            .init_resource::<SpecializedRenderPipelines<VelloCanvasPipeline>>()
+            .add_render_command::<Transparent2d, DrawVelloCanvas>()
             .add_systems(
                 ExtractSchedule,
                 (
@@ -45,29 +84,27 @@ impl Plugin for VelloRenderPlugin {
             .add_systems(
                 Render,
                 (
-                    prepare::prepare_vector_affines,
-                    prepare::prepare_scene_affines,
-                    prepare::prepare_text_affines,
-                )
-                    .in_set(RenderSet::Prepare),
+                    systems::queue_vello_canvases
+                        .in_set(RenderSet::PhaseSort)
+                        .before(sort_phase_system::<Transparent2d>),
+                    systems::prepare_canvas_textures.in_set(RenderSet::PrepareResources),
+                    systems::prepare_canvas_bind_groups.in_set(RenderSet::PrepareBindGroups),
+                ),
             )
             .add_systems(
                 Render,
-                systems::render_scene
+                (bake::render_baked_images, systems::render_scene)
                     .in_set(RenderSet::Render)
+                    .before(render_system)
                     .run_if(resource_exists::<RenderDevice>),
             );
+    }
 
-        app.add_plugins((
-            Material2dPlugin::<VelloCanvasMaterial>::default(),
-            ExtractComponentPlugin::<ExtractedRenderText>::default(),
-            ExtractComponentPlugin::<SSRenderTarget>::default(),
-            RenderAssetPlugin::<VelloFont>::default(),
-        ))
-        .add_systems(Startup, systems::setup_ss_rendertarget)
-        .add_systems(
-            Update,
-            (systems::resize_rendertargets, systems::clear_when_empty),
-        );
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.init_resource::<VelloCanvasPipeline>();
     }
 }
@@ -1,9 +1,7 @@
-use super::extract::{
-    ExtractedPixelScale, ExtractedRenderAsset, ExtractedRenderScene, ExtractedRenderText,
-};
-use crate::CoordinateSpace;
+use super::extract::{ExtractedRenderAsset, ExtractedRenderScene, ExtractedRenderText};
+use crate::{CoordinateSpace, VelloAsset};
 use bevy::prelude::*;
-use bevy::render::camera::ExtractedCamera;
+use bevy::render::texture::CachedTexture;
 use bevy::render::view::ExtractedView;
 use vello::kurbo::Affine;
 
@@ -29,13 +27,57 @@ pub trait PrepareRenderInstance {
     ) -> PreparedAffine;
 }
 
-impl PrepareRenderInstance for ExtractedRenderAsset {
+/// The scale which undoes the zoom of a view, from its world to pixels matrix,
+/// so a [`VelloConstantScale`](super::VelloConstantScale) instance is drawn
+/// at one logical pixel per unit.
+fn constant_scale(view_matrix: Mat4, pixel_scale: f32) -> Mat4 {
+    let pixels_per_unit = view_matrix.x_axis.truncate().truncate().length();
+    Mat4::from_scale(Vec3::splat(pixel_scale / pixels_per_unit))
+}
+
+/// An extracted asset instance, paired with the render asset it references.
+pub struct ExtractedRenderAssetInstance<'a> {
+    pub instance: &'a ExtractedRenderAsset,
+    pub asset: &'a VelloAsset,
+}
+
+impl ExtractedRenderAssetInstance<'_> {
+    /// The size the asset is drawn at, before scaling.
+    pub fn size(&self) -> Vec2 {
+        match self.instance.nine_slice {
+            Some(nine_slice) => nine_slice.size,
+            None => Vec2::new(self.asset.width, self.asset.height),
+        }
+    }
+
+    /// A transform of the instance, adjusted by the alignment of the asset.
+    pub fn aligned_transform(&self, transform: &GlobalTransform) -> PreparedTransform {
+        PreparedTransform(
+            self.instance
+                .alignment
+                .compute_sized(self.size() * self.scale(), transform),
+        )
+    }
+
+    /// The scale of the asset by its [`VelloSize`](crate::VelloSize).
+    pub fn scale(&self) -> Vec2 {
+        self.instance
+            .size
+            .map_or(Vec2::ONE, |size| size.scale(self.size()))
+    }
+}
+
+impl PrepareRenderInstance for ExtractedRenderAssetInstance<'_> {
     fn z_index(&self, prepared_transform: GlobalTransform) -> PreparedZIndex {
-        PreparedZIndex(self.z_function.compute(&self.asset, &prepared_transform))
+        PreparedZIndex(
+            self.instance
+                .z_function
+                .compute(self.asset, &prepared_transform),
+        )
     }
 
     fn final_transform(&self) -> PreparedTransform {
-        PreparedTransform(self.alignment.compute(&self.asset, &self.transform))
+        self.aligned_transform(&self.instance.transform)
     }
 
     fn scene_affine(
@@ -45,17 +87,29 @@ impl PrepareRenderInstance for ExtractedRenderAsset {
         pixel_scale: f32,
         viewport_size: UVec2,
     ) -> PreparedAffine {
-        let local_center_matrix = self.asset.local_transform_center.compute_matrix().inverse();
-
-        let raw_transform = match self.render_mode {
+        let size = self.size();
+        let local_center_matrix = match (self.instance.anchor, self.instance.nine_slice) {
+            // The pivot, from the center with Y up
+            (Some(anchor), _) => {
+                let pivot = (anchor.as_vec() + Vec2::new(0.5, -0.5)) * size;
+                Mat4::from_translation(pivot.extend(0.0))
+            }
+            (None, Some(_)) => Mat4::from_translation(Vec3::new(size.x / 2.0, -size.y / 2.0, 0.0)),
+            (None, None) => self.asset.local_transform_center.compute_matrix(),
+        }
+        .inverse();
+        let flip = self.instance.flip.map_or(Vec2::ONE, |flip| flip.scale());
+        let scale_matrix = Mat4::from_scale((self.scale() * flip).extend(1.0));
+
+        let raw_transform = match self.instance.render_mode {
             CoordinateSpace::ScreenSpace => {
                 let mut model_matrix = world_transform.compute_matrix().mul_scalar(pixel_scale);
 
-                let vector_size = Vec2::new(self.asset.width, self.asset.height);
+                let vector_size = size;
 
                 // Make the screen space vector instance sized to fill the
                 // entire UI Node box if it's bundled with a Node
-                if let Some(node) = &self.ui_node {
+                if let (Some(node), None) = (&self.instance.ui_node, self.instance.size) {
                     let fill_scale = node.size() / vector_size;
                     model_matrix.x_axis.x *= fill_scale.x;
                     model_matrix.y_axis.y *= fill_scale.y;
@@ -63,10 +117,10 @@ impl PrepareRenderInstance for ExtractedRenderAsset {
 
                 let mut local_center_matrix = local_center_matrix;
                 local_center_matrix.w_axis.y *= -1.0;
-                model_matrix * local_center_matrix
+                model_matrix * scale_matrix * local_center_matrix
             }
             CoordinateSpace::WorldSpace => {
-                let local_matrix = local_center_matrix;
+                let mut local_matrix = scale_matrix * local_center_matrix;
 
                 let (pixels_x, pixels_y) = (viewport_size.x as f32, viewport_size.y as f32);
                 let ndc_to_pixels_matrix = Mat4::from_cols_array_2d(&[
@@ -77,9 +131,6 @@ impl PrepareRenderInstance for ExtractedRenderAsset {
                 ])
                 .transpose();
 
-                let mut model_matrix = world_transform.compute_matrix() * local_matrix;
-                model_matrix.w_axis.y *= -1.0;
-
                 let (projection_mat, view_mat) = {
                     let mut view_mat = view.transform.compute_matrix();
                     view_mat.w_axis.y *= -1.0;
@@ -88,8 +139,15 @@ impl PrepareRenderInstance for ExtractedRenderAsset {
                 };
 
                 let view_proj_matrix = projection_mat * view_mat.inverse();
+                let vello_matrix = ndc_to_pixels_matrix * view_proj_matrix;
+                if self.instance.constant_scale {
+                    local_matrix = constant_scale(vello_matrix, pixel_scale) * local_matrix;
+                }
+
+                let mut model_matrix = world_transform.compute_matrix() * local_matrix;
+                model_matrix.w_axis.y *= -1.0;
 
-                ndc_to_pixels_matrix * view_proj_matrix * model_matrix
+                vello_matrix * model_matrix
             }
         };
 
@@ -111,41 +169,23 @@ impl PrepareRenderInstance for ExtractedRenderAsset {
     }
 }
 
-pub fn prepare_vector_affines(
-    mut commands: Commands,
-    camera: Query<(&ExtractedCamera, &ExtractedView), With<Camera2d>>,
-    mut render_vectors: Query<(Entity, &ExtractedRenderAsset)>,
-    pixel_scale: Res<ExtractedPixelScale>,
-) {
-    let Ok((camera, view)) = camera.get_single() else {
-        return;
-    };
-    let viewport_size: UVec2 = camera.physical_viewport_size.unwrap();
-    for (entity, render_vector) in render_vectors.iter_mut() {
-        // Prepare render data needed for the subsequent render system
-        let final_transform = render_vector.final_transform();
-        let affine =
-            render_vector.scene_affine(view, *final_transform, pixel_scale.0, viewport_size);
-        let z_index = render_vector.z_index(*final_transform);
-
-        commands
-            .entity(entity)
-            .insert((affine, final_transform, z_index));
+impl PrepareRenderInstance for ExtractedRenderScene {
+    fn z_index(&self, transform: GlobalTransform) -> PreparedZIndex {
+        PreparedZIndex(transform.translation().z)
+    }
+
+    fn final_transform(&self) -> PreparedTransform {
+        PreparedTransform(self.transform)
     }
-}
 
-pub fn prepare_scene_affines(
-    mut commands: Commands,
-    camera: Query<(&ExtractedCamera, &ExtractedView), With<Camera2d>>,
-    mut render_scenes: Query<(Entity, &ExtractedRenderScene)>,
-    pixel_scale: Res<ExtractedPixelScale>,
-) {
-    let Ok((camera, view)) = camera.get_single() else {
-        return;
-    };
-    let size_pixels: UVec2 = camera.physical_viewport_size.unwrap();
-    let (pixels_x, pixels_y) = (size_pixels.x as f32, size_pixels.y as f32);
-    for (entity, render_scene) in render_scenes.iter_mut() {
+    fn scene_affine(
+        &self,
+        view: &ExtractedView,
+        world_transform: GlobalTransform,
+        pixel_scale: f32,
+        viewport_size: UVec2,
+    ) -> PreparedAffine {
+        let (pixels_x, pixels_y) = (viewport_size.x as f32, viewport_size.y as f32);
         let ndc_to_pixels_matrix = Mat4::from_cols_array_2d(&[
             [pixels_x / 2.0, 0.0, 0.0, pixels_x / 2.0],
             [0.0, pixels_y / 2.0, 0.0, pixels_y / 2.0],
@@ -154,19 +194,17 @@ pub fn prepare_scene_affines(
         ])
         .transpose();
 
-        let world_transform = render_scene.transform;
-
-        let raw_transform = match render_scene.render_mode {
+        let raw_transform = match self.render_mode {
             CoordinateSpace::ScreenSpace => {
-                let mut model_matrix = world_transform.compute_matrix().mul_scalar(pixel_scale.0);
+                let mut model_matrix = world_transform.compute_matrix().mul_scalar(pixel_scale);
 
-                if let Some(node) = &render_scene.ui_node {
+                if let Some(node) = &self.ui_node {
                     // The Bevy Transform for a UI node seems to always have the origin
                     // of the translation at the center of its bounding box. Here we
                     // move the origin back to the top left, so that, e.g., drawing a
                     // shape with center=(20,20) inside of a 40x40 UI node results in
                     // the shape being centered within the node.
-                    let Vec2 { x, y } = node.size() * pixel_scale.0;
+                    let Vec2 { x, y } = node.size() * pixel_scale;
                     model_matrix.w_axis.x -= x / 2.0;
                     model_matrix.w_axis.y -= y / 2.0;
 
@@ -190,8 +228,12 @@ pub fn prepare_scene_affines(
                 };
 
                 let view_proj_matrix = projection_mat * view_mat.inverse();
+                let vello_matrix = ndc_to_pixels_matrix * view_proj_matrix;
+                if self.constant_scale {
+                    model_matrix *= constant_scale(vello_matrix, pixel_scale);
+                }
 
-                ndc_to_pixels_matrix * view_proj_matrix * model_matrix
+                vello_matrix * model_matrix
             }
         };
 
@@ -209,24 +251,27 @@ pub fn prepare_scene_affines(
             transform[13] as f64, // f
         ];
 
-        commands
-            .entity(entity)
-            .insert(PreparedAffine(Affine::new(transform)));
+        PreparedAffine(Affine::new(transform))
     }
 }
 
-pub fn prepare_text_affines(
-    mut commands: Commands,
-    camera: Query<(&ExtractedCamera, &ExtractedView), With<Camera2d>>,
-    render_texts: Query<(Entity, &ExtractedRenderText)>,
-    pixel_scale: Res<ExtractedPixelScale>,
-) {
-    let Ok((camera, view)) = camera.get_single() else {
-        return;
-    };
-    let size_pixels: UVec2 = camera.physical_viewport_size.unwrap();
-    let (pixels_x, pixels_y) = (size_pixels.x as f32, size_pixels.y as f32);
-    for (entity, render_text) in render_texts.iter() {
+impl PrepareRenderInstance for ExtractedRenderText {
+    fn z_index(&self, transform: GlobalTransform) -> PreparedZIndex {
+        PreparedZIndex(transform.translation().z)
+    }
+
+    fn final_transform(&self) -> PreparedTransform {
+        PreparedTransform(self.transform)
+    }
+
+    fn scene_affine(
+        &self,
+        view: &ExtractedView,
+        world_transform: GlobalTransform,
+        pixel_scale: f32,
+        viewport_size: UVec2,
+    ) -> PreparedAffine {
+        let (pixels_x, pixels_y) = (viewport_size.x as f32, viewport_size.y as f32);
         let ndc_to_pixels_matrix = Mat4::from_cols_array_2d(&[
             [pixels_x / 2.0, 0.0, 0.0, pixels_x / 2.0],
             [0.0, pixels_y / 2.0, 0.0, pixels_y / 2.0],
@@ -235,8 +280,6 @@ pub fn prepare_text_affines(
         ])
         .transpose();
 
-        let world_transform = render_text.transform;
-
         let mut model_matrix = world_transform.compute_matrix();
         model_matrix.w_axis.y *= -1.0;
 
@@ -250,9 +293,21 @@ pub fn prepare_text_affines(
         let view_proj_matrix = projection_mat * view_mat.inverse();
         let vello_matrix = ndc_to_pixels_matrix * view_proj_matrix;
 
-        let raw_transform = match render_text.render_mode {
+        let raw_transform = match self.render_mode {
             CoordinateSpace::ScreenSpace => {
-                world_transform.compute_matrix().mul_scalar(pixel_scale.0)
+                let model_matrix = world_transform.compute_matrix().mul_scalar(pixel_scale);
+                match &self.ui_node {
+                    // The origin of a UI node is its center, and the text is
+                    // aligned from the point of the node box it's aligned to
+                    Some(node) => {
+                        let anchor = self.alignment.anchor(node.size());
+                        model_matrix * Mat4::from_translation(anchor.extend(0.0))
+                    }
+                    None => model_matrix,
+                }
+            }
+            CoordinateSpace::WorldSpace if self.constant_scale => {
+                vello_matrix * model_matrix * constant_scale(vello_matrix, pixel_scale)
             }
             CoordinateSpace::WorldSpace => vello_matrix * model_matrix,
         };
@@ -271,8 +326,58 @@ pub fn prepare_text_affines(
             transform[13] as f64, // f
         ];
 
-        commands
-            .entity(entity)
-            .insert(PreparedAffine(Affine::new(transform)));
+        PreparedAffine(Affine::new(transform))
+    }
+}
+
+/// A reference to an extracted render instance in the render world.
+#[derive(Clone, Copy)]
+pub enum PreparedItem {
+    Asset(Entity),
+    Scene(Entity),
+    Text(Entity),
+}
+
+/// The view affines of the [`VelloInstances`](super::VelloInstances) of an
+/// item, relative to the affine it is encoded at, with their tints.
+#[derive(Clone, Default)]
+pub struct PreparedInstances(pub Vec<(Affine, Option<Color>)>);
+
+impl PreparedInstances {
+    /// The affine to encode an instanced item at, the first of its instances,
+    /// and every instance relative to it, or `None` if no instance is drawn.
+    /// Instances scaled to nothing aren't drawn.
+    pub fn relative_to_first(
+        affines: impl IntoIterator<Item = (Affine, Option<Color>)>,
+    ) -> Option<(PreparedAffine, Self)> {
+        let mut affines = affines
+            .into_iter()
+            .filter(|(affine, _)| affine.determinant() != 0.0 && affine.is_finite());
+        let (first, first_tint) = affines.next()?;
+        let inverse = first.inverse();
+        let mut instances = vec![(Affine::IDENTITY, first_tint)];
+        instances.extend(affines.map(|(affine, tint)| (affine * inverse, tint)));
+        Some((PreparedAffine(first), Self(instances)))
     }
 }
+
+/// A run of render instances that can be encoded together, because no other
+/// `Transparent2d` phase item (sprites, meshes, etc.) sorts between them.
+///
+/// Every canvas is rendered by vello into its own texture, and composited into
+/// the view as a single fullscreen phase item at the Z-index of its first
+/// instance.
+#[derive(Component)]
+pub struct VelloCanvas {
+    /// The physical size of the viewport this canvas covers.
+    pub size: UVec2,
+    /// The scale factor of the view, from logical to physical pixels.
+    pub pixel_scale: f32,
+    /// The render instances, sorted back to front, with their view affines,
+    /// and the affines they are appended at when instanced.
+    pub items: Vec<(PreparedAffine, PreparedItem, Option<PreparedInstances>)>,
+}
+
+/// The texture a [`VelloCanvas`] is rendered into.
+#[derive(Component)]
+pub struct VelloCanvasTexture(pub CachedTexture);
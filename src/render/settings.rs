@@ -0,0 +1,43 @@
+use bevy::prelude::*;
+use bevy::render::extract_resource::ExtractResource;
+use vello::{AaConfig, AaSupport};
+
+/// Settings used by the vello renderer.
+///
+/// Insert this resource to configure the renderer, or mutate it at runtime.
+/// Changing these settings recreates the renderer on the next frame, which
+/// compiles the shaders for the new configuration.
+#[derive(Resource, Clone, Copy, PartialEq, ExtractResource)]
+pub struct VelloRenderSettings {
+    /// The anti-aliasing method used to render vello content.
+    ///
+    /// [`AaConfig::Area`] is the cheapest, but may shimmer on thin strokes.
+    /// [`AaConfig::Msaa8`] and [`AaConfig::Msaa16`] render with multisampling.
+    pub antialiasing: AaConfig,
+    /// Run every vello stage up to fine rasterization on the CPU.
+    ///
+    /// Fine rasterization still runs as a compute shader writing to a storage
+    /// texture, so this doesn't make vello render on targets without compute,
+    /// such as WebGL2. It can be useful for debugging the other stages.
+    pub use_cpu: bool,
+}
+
+impl Default for VelloRenderSettings {
+    fn default() -> Self {
+        Self {
+            antialiasing: AaConfig::Area,
+            use_cpu: false,
+        }
+    }
+}
+
+impl VelloRenderSettings {
+    /// The pipeline permutations the renderer must support for these settings.
+    pub(crate) fn antialiasing_support(&self) -> AaSupport {
+        AaSupport {
+            area: self.antialiasing == AaConfig::Area,
+            msaa8: self.antialiasing == AaConfig::Msaa8,
+            msaa16: self.antialiasing == AaConfig::Msaa16,
+        }
+    }
+}
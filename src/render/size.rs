@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+
+/// How a [`VelloSize`] scales an asset from its intrinsic size.
+#[derive(PartialEq, Eq, Default, Clone, Copy, Debug, Reflect)]
+pub enum VelloScaleMode {
+    /// Scale uniformly to fit inside the size.
+    #[default]
+    Fit,
+    /// Scale uniformly to cover the size, overflowing on one axis.
+    Fill,
+    /// Scale each axis to the size, which distorts the aspect ratio.
+    Stretch,
+    /// Keep the intrinsic size.
+    None,
+}
+
+/// Draw a vello asset at a size, in the units of its transform, instead of
+/// scaling its transform against the intrinsic width and height.
+///
+/// In UI nodes, this replaces stretching the asset to the size of the node.
+#[derive(Component, PartialEq, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct VelloSize {
+    pub size: Vec2,
+    pub mode: VelloScaleMode,
+}
+
+impl VelloSize {
+    pub fn new(size: Vec2, mode: VelloScaleMode) -> Self {
+        Self { size, mode }
+    }
+
+    /// The scale of each axis which sizes the intrinsic size.
+    pub fn scale(&self, intrinsic_size: Vec2) -> Vec2 {
+        if intrinsic_size.x <= 0.0 || intrinsic_size.y <= 0.0 {
+            return Vec2::ONE;
+        }
+        let scale = self.size / intrinsic_size;
+        match self.mode {
+            VelloScaleMode::Fit => Vec2::splat(scale.min_element()),
+            VelloScaleMode::Fill => Vec2::splat(scale.max_element()),
+            VelloScaleMode::Stretch => scale,
+            VelloScaleMode::None => Vec2::ONE,
+        }
+    }
+}
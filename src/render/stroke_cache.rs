@@ -0,0 +1,55 @@
+//! Encoded fragments of SVG instances with non-scaling strokes, rendered
+//! again from their trees at the stroke scale of every instance.
+
+use crate::integrations::svg::render_tree_with_stroke_scale;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::sync::{Arc, Mutex};
+use vello::Scene;
+use vello_svg::usvg;
+
+struct CachedFragment {
+    /// Held so the address of the tree keying the fragment isn't reused.
+    _tree: Arc<usvg::Tree>,
+    scene: Arc<Scene>,
+    /// Whether the fragment was rendered since the last eviction.
+    used: bool,
+}
+
+/// The fragments of the non-scaling SVG instances rendered last frame, by
+/// tree and stroke scale. Instances at the same scale share a fragment.
+#[derive(Resource, Default)]
+pub struct SvgStrokeCache {
+    fragments: Mutex<HashMap<(usize, u32), CachedFragment>>,
+}
+
+impl SvgStrokeCache {
+    /// The fragment of a tree with its strokes scaled, in the local space of
+    /// the asset, rendered if no instance was at the same scale last frame.
+    pub(crate) fn get_or_render(&self, tree: &Arc<usvg::Tree>, stroke_scale: f32) -> Arc<Scene> {
+        let slot = (Arc::as_ptr(tree) as usize, stroke_scale.to_bits());
+        if let Some(fragment) = self.fragments.lock().unwrap().get_mut(&slot) {
+            fragment.used = true;
+            return fragment.scene.clone();
+        }
+        // Render without the lock, so other instances are rendered meanwhile
+        let scene = Arc::new(render_tree_with_stroke_scale(tree, stroke_scale));
+        self.fragments.lock().unwrap().insert(
+            slot,
+            CachedFragment {
+                _tree: tree.clone(),
+                scene: scene.clone(),
+                used: true,
+            },
+        );
+        scene
+    }
+
+    /// Drop the fragments which weren't rendered since the last eviction.
+    pub(crate) fn evict_unused(&mut self) {
+        self.fragments
+            .get_mut()
+            .unwrap()
+            .retain(|_, fragment| std::mem::take(&mut fragment.used));
+    }
+}
@@ -1,312 +1,826 @@
-use super::extract::{ExtractedRenderAsset, ExtractedRenderText, SSRenderTarget};
-use super::prepare::PreparedAffine;
-use super::VelloRenderer;
-use crate::render::extract::ExtractedRenderScene;
-use crate::render::prepare::PreparedZIndex;
-use crate::{CoordinateSpace, VelloCanvasMaterial, VelloFont};
+use super::diagnostics::{RenderStats, VelloRenderStats};
+use super::extract::{
+    ExtractedPixelScale, ExtractedRenderAsset, ExtractedRenderScene, ExtractedRenderText,
+};
+use super::pipeline::{
+    DrawVelloCanvas, VelloCanvasBindGroup, VelloCanvasPipeline, VelloCanvasPipelineKey,
+};
+use super::prepare::{
+    ExtractedRenderAssetInstance, PrepareRenderInstance, PreparedAffine, PreparedInstances,
+    PreparedItem, VelloCanvas, VelloCanvasTexture,
+};
+use super::{VelloBlendMode, VelloClip, VelloRenderSettings, VelloRenderer};
+use crate::{CoordinateSpace, VelloAsset, VelloFont, VelloFontFallback, VelloZIndex};
+use bevy::core_pipeline::core_2d::Transparent2d;
 use bevy::prelude::*;
-use bevy::render::mesh::Indices;
-use bevy::render::render_asset::{RenderAssetUsages, RenderAssets};
+use bevy::render::camera::ExtractedCamera;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_phase::{DrawFunctions, RenderPhase};
 use bevy::render::render_resource::{
-    Extent3d, PrimitiveTopology, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    BindGroupEntries, Extent3d, PipelineCache, SpecializedRenderPipelines, TextureDescriptor,
+    TextureDimension, TextureFormat, TextureUsages,
 };
 use bevy::render::renderer::{RenderDevice, RenderQueue};
-use bevy::render::view::NoFrustumCulling;
-use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
-use bevy::window::{WindowResized, WindowResolution};
-use vello::kurbo::Affine;
-use vello::{AaSupport, RenderParams, Renderer, RendererOptions, Scene};
-
-pub fn setup_image(images: &mut Assets<Image>, window: &WindowResolution) -> Handle<Image> {
-    let size = Extent3d {
-        width: window.physical_width(),
-        height: window.physical_height(),
-        ..default()
-    };
-
-    // This is the texture that will be rendered to.
-    let mut image = Image {
-        texture_descriptor: TextureDescriptor {
-            label: None,
-            size,
-            dimension: TextureDimension::D2,
-            format: TextureFormat::Rgba8Unorm,
-            mip_level_count: 1,
-            sample_count: 1,
-            usage: TextureUsages::TEXTURE_BINDING
-                | TextureUsages::COPY_DST
-                | TextureUsages::STORAGE_BINDING,
-            view_formats: &[],
-        },
-        ..default()
-    };
+use bevy::render::texture::TextureCache;
+use bevy::render::view::{ExtractedView, RenderLayers};
+use bevy::tasks::ComputeTaskPool;
+use bevy::utils::{FloatOrd, Instant};
+use vello::kurbo::{Affine, Rect};
+use vello::peniko::{BlendMode, Compose, Mix};
+use vello::{RenderParams, Scene};
 
-    // fill image.data with zeroes
-    image.resize(size);
-    images.add(image)
+/// Whether the local bounds of an item overlap the viewport, once transformed
+/// to its pixels. Items outside of it aren't encoded.
+fn is_onscreen(affine: Affine, bounds: Rect, viewport_size: UVec2) -> bool {
+    let viewport = Rect::new(0.0, 0.0, viewport_size.x as f64, viewport_size.y as f64);
+    affine.transform_rect_bbox(bounds).overlaps(viewport)
 }
 
-/// Transforms all the vectors extracted from the game world and places them in
-/// a scene, and renders the scene to a texture with WGPU
-#[allow(clippy::complexity)]
-pub fn render_scene(
-    ss_render_target: Query<&SSRenderTarget>,
-    query_render_vectors: Query<(&PreparedAffine, &PreparedZIndex, &ExtractedRenderAsset)>,
-    query_render_scenes: Query<(&PreparedAffine, &ExtractedRenderScene)>,
-    query_render_texts: Query<(&PreparedAffine, &ExtractedRenderText)>,
-    mut font_render_assets: ResMut<RenderAssets<VelloFont>>,
-    gpu_images: Res<RenderAssets<Image>>,
-    device: Res<RenderDevice>,
-    queue: Res<RenderQueue>,
-    mut vello_renderer: Local<Option<VelloRenderer>>,
-    #[cfg(feature = "lottie")] mut velato_renderer: ResMut<super::VelatoRenderer>,
+/// Sorts the vectors extracted from the game world for every 2D view, and
+/// groups them into canvases which are queued as `Transparent2d` phase items,
+/// so they interleave by Z with sprites, meshes and other 2D renderers.
+///
+/// This runs before the phase is sorted, so every other phase item has
+/// already been queued.
+#[allow(clippy::too_many_arguments)]
+pub fn queue_vello_canvases(
+    mut commands: Commands,
+    mut views: Query<(
+        &ExtractedCamera,
+        &ExtractedView,
+        Option<&ExtractedPixelScale>,
+        Option<&RenderLayers>,
+        &mut RenderPhase<Transparent2d>,
+    )>,
+    render_vectors: Query<(Entity, &ExtractedRenderAsset)>,
+    render_scenes: Query<(Entity, &ExtractedRenderScene)>,
+    render_texts: Query<(Entity, &ExtractedRenderText)>,
+    render_assets: Res<RenderAssets<VelloAsset>>,
+    font_render_assets: Res<RenderAssets<VelloFont>>,
+    font_fallback: Res<VelloFontFallback>,
+    draw_functions: Res<DrawFunctions<Transparent2d>>,
+    canvas_pipeline: Res<VelloCanvasPipeline>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<VelloCanvasPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    msaa: Res<Msaa>,
 ) {
-    let renderer = vello_renderer.get_or_insert_with(|| {
-        VelloRenderer(
-            Renderer::new(
-                device.wgpu_device(),
-                RendererOptions {
-                    surface_format: None,
-                    use_cpu: false,
-                    antialiasing_support: AaSupport::area_only(),
-                    num_init_threads: None,
-                },
-            )
-            // TODO: Attempt CPU fallback. Support changing antialias settings.
-            .expect("No GPU Device"),
-        )
-    });
+    let draw_function = draw_functions.read().id::<DrawVelloCanvas>();
 
-    if let Ok(SSRenderTarget(render_target_image)) = ss_render_target.get_single() {
-        let gpu_image = gpu_images.get(render_target_image).unwrap();
+    for (camera, view, pixel_scale, view_layers, mut phase) in views.iter_mut() {
+        // Views render into their own target, which may be a window or an image
+        let Some(viewport_size) = camera.physical_viewport_size else {
+            continue;
+        };
+        let pixel_scale = pixel_scale.map_or(1.0, |scale| scale.0);
+        // Only render the instances which share a layer with this view
+        let view_layers = view_layers.copied().unwrap_or_default();
+        let is_visible =
+            |layers: Option<RenderLayers>| view_layers.intersects(&layers.unwrap_or_default());
+        if viewport_size.x == 0 || viewport_size.y == 0 {
+            continue;
+        }
 
-        enum RenderItem<'a> {
-            Asset(&'a ExtractedRenderAsset),
-            Scene(&'a ExtractedRenderScene),
-            Text(&'a ExtractedRenderText),
+        let mut render_queue: Vec<(
+            f32,
+            VelloZIndex,
+            CoordinateSpace,
+            (PreparedAffine, PreparedItem, Option<PreparedInstances>),
+        )> = vec![];
+        for (entity, render_vector) in render_vectors.iter() {
+            if !is_visible(render_vector.render_layers) {
+                continue;
+            }
+            let Some(asset) = render_assets.get(&render_vector.asset) else {
+                continue;
+            };
+            // Prevent a panic in the vello renderer when the encoding is empty
+            let is_empty = match &asset.file {
+                #[cfg(feature = "svg")]
+                crate::VectorFile::Svg(scene) => scene.encoding().is_empty(),
+                #[cfg(feature = "lottie")]
+                crate::VectorFile::Lottie(composition) => composition.layers.is_empty(),
+                #[cfg(not(any(feature = "svg", feature = "lottie")))]
+                _ => true,
+            };
+            if is_empty {
+                continue;
+            }
+            let instance = ExtractedRenderAssetInstance {
+                instance: render_vector,
+                asset,
+            };
+            let final_transform = instance.final_transform();
+            let size = instance.size();
+            let bounds = Rect::new(0.0, 0.0, size.x as f64, size.y as f64);
+            let (affine, instances) = match &render_vector.instances {
+                None => {
+                    let affine =
+                        instance.scene_affine(view, *final_transform, pixel_scale, viewport_size);
+                    if !is_onscreen(*affine, bounds, viewport_size) {
+                        continue;
+                    }
+                    (affine, None)
+                }
+                // Only the instances on screen are drawn
+                Some(instances) => {
+                    let affines = instances.instances.iter().filter_map(|vello_instance| {
+                        let transform = instance.aligned_transform(
+                            &render_vector
+                                .transform
+                                .mul_transform(vello_instance.transform),
+                        );
+                        let affine =
+                            instance.scene_affine(view, *transform, pixel_scale, viewport_size);
+                        is_onscreen(*affine, bounds, viewport_size)
+                            .then_some((*affine, vello_instance.tint))
+                    });
+                    let Some((affine, instances)) = PreparedInstances::relative_to_first(affines)
+                    else {
+                        continue;
+                    };
+                    (affine, Some(instances))
+                }
+            };
+            let z_index = instance.z_index(*final_transform);
+            render_queue.push((
+                *z_index,
+                render_vector.z_index,
+                render_vector.render_mode,
+                (affine, PreparedItem::Asset(entity), instances),
+            ));
+        }
+        for (entity, render_scene) in render_scenes.iter() {
+            if !is_visible(render_scene.render_layers) || render_scene.scene.encoding().is_empty() {
+                continue;
+            }
+            let final_transform = render_scene.final_transform();
+            let (affine, instances) = match &render_scene.instances {
+                None => (
+                    render_scene.scene_affine(view, *final_transform, pixel_scale, viewport_size),
+                    None,
+                ),
+                Some(instances) => {
+                    let affines = instances.instances.iter().map(|instance| {
+                        let transform = render_scene.transform.mul_transform(instance.transform);
+                        let affine =
+                            render_scene.scene_affine(view, transform, pixel_scale, viewport_size);
+                        (*affine, instance.tint)
+                    });
+                    let Some((affine, instances)) = PreparedInstances::relative_to_first(affines)
+                    else {
+                        continue;
+                    };
+                    (affine, Some(instances))
+                }
+            };
+            let z_index = render_scene.z_index(*final_transform);
+            render_queue.push((
+                *z_index,
+                render_scene.z_index,
+                render_scene.render_mode,
+                (affine, PreparedItem::Scene(entity), instances),
+            ));
+        }
+        for (entity, render_text) in render_texts.iter() {
+            if !is_visible(render_text.render_layers) || render_text.text.content.is_empty() {
+                continue;
+            }
+            let Some(font) = font_render_assets.get(&render_text.font) else {
+                continue;
+            };
+            let final_transform = render_text.final_transform();
+            let affine =
+                render_text.scene_affine(view, *final_transform, pixel_scale, viewport_size);
+            // The text is aligned when rendered, with Y down
+            let size = match &render_text.layout {
+                Some(layout) => layout.0.size(),
+                None => font.sizeof_with(&render_text.text, &font_fallback, |font| {
+                    font_render_assets.get(font)
+                }),
+            };
+            let min = render_text.alignment.offset(size);
+            let bounds = Rect::new(
+                min.x as f64,
+                -(min.y + size.y) as f64,
+                (min.x + size.x) as f64,
+                -min.y as f64,
+            );
+            if !is_onscreen(*affine, bounds, viewport_size) {
+                continue;
+            }
+            let z_index = render_text.z_index(*final_transform);
+            render_queue.push((
+                *z_index,
+                render_text.z_index,
+                render_text.render_mode,
+                (affine, PreparedItem::Text(entity), None),
+            ));
+        }
+        if render_queue.is_empty() {
+            continue;
         }
-        let mut render_queue: Vec<(f32, CoordinateSpace, (Affine, RenderItem))> =
-            query_render_vectors
-                .iter()
-                .map(|(&a, &b, c)| (*b, c.render_mode, (*a, RenderItem::Asset(c))))
-                .collect();
-        render_queue.extend(query_render_scenes.iter().map(|(&a, b)| {
-            (
-                b.transform.translation().z,
-                b.render_mode,
-                (*a, RenderItem::Scene(b)),
-            )
-        }));
-        render_queue.extend(query_render_texts.iter().map(|(&a, b)| {
-            (
-                b.transform.translation().z,
-                b.render_mode,
-                (*a, RenderItem::Text(b)),
-            )
-        }));
 
-        // Sort by render mode with screen space on top, then by z-index
+        // Sort by render mode with screen space on top, then by z-index, then
+        // by the explicit order of instances sharing a z-index
         render_queue.sort_by(
-            |(a_z_index, a_render_mode, _), (b_z_index, b_render_mode, _)| {
+            |(a_z_index, a_order, a_render_mode, _), (b_z_index, b_order, b_render_mode, _)| {
                 let z_index = a_z_index
                     .partial_cmp(b_z_index)
                     .unwrap_or(std::cmp::Ordering::Equal);
                 let render_mode = a_render_mode.cmp(b_render_mode);
-                render_mode.then(z_index)
+                render_mode.then(z_index).then(a_order.cmp(b_order))
             },
         );
 
-        // Apply transforms to the respective fragments and add them to the
-        // scene to be rendered
-        let mut scene_buffer = Scene::new();
-        for (_, _, (affine, render_item)) in render_queue.iter_mut() {
-            match render_item {
-                RenderItem::Asset(ExtractedRenderAsset {
-                    asset,
-                    #[cfg(feature = "lottie")]
-                    alpha,
-                    #[cfg(feature = "lottie")]
-                    theme,
-                    #[cfg(feature = "lottie")]
-                    playhead,
-                    ..
-                }) => match &asset.file {
-                    #[cfg(feature = "svg")]
-                    crate::VectorFile::Svg(scene) => {
-                        // TODO: Apply alpha
-                        scene_buffer.append(scene, Some(*affine));
-                    }
-                    #[cfg(feature = "lottie")]
-                    crate::VectorFile::Lottie(composition) => {
-                        velato_renderer.render(
-                            {
-                                theme
-                                    .as_ref()
-                                    .map(|cs| cs.recolor(composition))
-                                    .as_ref()
-                                    .unwrap_or(composition)
-                            },
-                            *playhead as f64,
-                            *affine,
-                            *alpha as f64,
-                            &mut scene_buffer,
-                        );
-                    }
-                    #[cfg(not(any(feature = "svg", feature = "lottie")))]
-                    _ => unimplemented!(),
-                },
-                RenderItem::Scene(ExtractedRenderScene { scene, .. }) => {
-                    scene_buffer.append(scene, Some(*affine));
+        // The sort keys of everything else in the phase. A new canvas must be
+        // started whenever one of these falls between two consecutive vectors.
+        let mut foreign_keys: Vec<f32> = phase.items.iter().map(|item| item.sort_key.0).collect();
+        foreign_keys.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+        let pipeline = pipelines.specialize(
+            &pipeline_cache,
+            &canvas_pipeline,
+            VelloCanvasPipelineKey {
+                hdr: view.hdr,
+                samples: msaa.samples(),
+            },
+        );
+
+        // Group into canvases. World space canvases are keyed by the Z-index of
+        // their first vector, while screen space is always rendered on top.
+        let mut canvases: Vec<(f32, VelloCanvas)> = vec![];
+        let mut current_layer = None;
+        for (z_index, _, render_mode, item) in render_queue {
+            let layer = match render_mode {
+                CoordinateSpace::WorldSpace => {
+                    Some(foreign_keys.partition_point(|k| *k <= z_index))
                 }
-                RenderItem::Text(ExtractedRenderText {
-                    font,
-                    text,
-                    alignment,
-                    ..
-                }) => {
-                    if let Some(font) = font_render_assets.get_mut(font) {
-                        font.render(&mut scene_buffer, *affine, text, *alignment);
-                    }
+                CoordinateSpace::ScreenSpace => None,
+            };
+            match canvases.last_mut() {
+                Some((_, canvas)) if current_layer == Some(layer) => canvas.items.push(item),
+                _ => {
+                    let sort_key = match render_mode {
+                        CoordinateSpace::WorldSpace => z_index,
+                        CoordinateSpace::ScreenSpace => f32::MAX,
+                    };
+                    canvases.push((
+                        sort_key,
+                        VelloCanvas {
+                            size: viewport_size,
+                            pixel_scale,
+                            items: vec![item],
+                        },
+                    ));
+                    current_layer = Some(layer);
                 }
             }
         }
 
-        // TODO: Vello should be ignoring 0-sized buffers in the future, so this could go away.
-        // Prevent a panic in the vello renderer if all the items contain empty encoding data
-        let empty_encodings = render_queue
-            .iter()
-            .filter(|(_, _, (_, item))| match item {
-                RenderItem::Asset(a) => match &a.asset.file {
-                    #[cfg(feature = "svg")]
-                    crate::VectorFile::Svg(scene) => scene.encoding().is_empty(),
-                    #[cfg(feature = "lottie")]
-                    crate::VectorFile::Lottie(composition) => composition.layers.is_empty(),
-                    #[cfg(not(any(feature = "svg", feature = "lottie")))]
-                    _ => unimplemented!(),
-                },
-                RenderItem::Scene(s) => s.scene.encoding().is_empty(),
-                RenderItem::Text(t) => t.text.content.is_empty(),
-            })
-            .count()
-            == render_queue.len();
-
-        if !render_queue.is_empty() && !empty_encodings {
-            renderer
-                .render_to_texture(
-                    device.wgpu_device(),
-                    &queue,
-                    &scene_buffer,
-                    &gpu_image.texture_view,
-                    &RenderParams {
-                        base_color: vello::peniko::Color::TRANSPARENT,
-                        width: gpu_image.size.x as u32,
-                        height: gpu_image.size.y as u32,
-                        antialiasing_method: vello::AaConfig::Area,
-                    },
-                )
-                .unwrap();
+        for (sort_key, canvas) in canvases {
+            let entity = commands.spawn(canvas).id();
+            phase.add(Transparent2d {
+                sort_key: FloatOrd(sort_key),
+                entity,
+                pipeline,
+                draw_function,
+                batch_range: 0..1,
+                dynamic_offset: None,
+            });
         }
     }
 }
 
-pub fn resize_rendertargets(
-    mut window_resize_events: EventReader<WindowResized>,
-    mut query: Query<(&mut SSRenderTarget, &Handle<VelloCanvasMaterial>)>,
-    mut images: ResMut<Assets<Image>>,
-    mut target_materials: ResMut<Assets<VelloCanvasMaterial>>,
-    windows: Query<&Window>,
+/// Allocates a texture for every canvas to be rendered into.
+pub fn prepare_canvas_textures(
+    mut commands: Commands,
+    canvases: Query<(Entity, &VelloCanvas)>,
+    mut texture_cache: ResMut<TextureCache>,
+    device: Res<RenderDevice>,
 ) {
-    let Ok(window) = windows.get_single() else {
-        return;
-    };
-    if window_resize_events.read().last().is_some() {
-        let size = Extent3d {
-            width: window.resolution.physical_width(),
-            height: window.resolution.physical_height(),
-            ..default()
-        };
-        if size.width == 0 || size.height == 0 {
-            return;
-        }
-        for (mut target, target_mat_handle) in query.iter_mut() {
-            let image = setup_image(&mut images, &window.resolution);
-            if let Some(mat) = target_materials.get_mut(target_mat_handle) {
-                target.0 = image.clone();
-                mat.texture = image;
-            }
-            debug!(
-                size = format!(
-                    "Resized Vello render image to {:?}",
-                    (size.width, size.height)
-                )
-            );
-        }
+    for (entity, canvas) in canvases.iter() {
+        let texture = texture_cache.get(
+            &device,
+            TextureDescriptor {
+                label: Some("vello_canvas_texture"),
+                size: Extent3d {
+                    width: canvas.size.x,
+                    height: canvas.size.y,
+                    ..default()
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::STORAGE_BINDING,
+                view_formats: &[],
+            },
+        );
+        commands.entity(entity).insert(VelloCanvasTexture(texture));
     }
 }
 
-pub fn setup_ss_rendertarget(
+/// Creates the bind groups used to composite every canvas texture.
+pub fn prepare_canvas_bind_groups(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut images: ResMut<Assets<Image>>,
-    mut custom_materials: ResMut<Assets<VelloCanvasMaterial>>,
-    windows: Query<&Window>,
-    mut render_target_mesh_handle: Local<Option<Handle<Mesh>>>,
+    canvases: Query<(Entity, &VelloCanvasTexture)>,
+    canvas_pipeline: Res<VelloCanvasPipeline>,
+    device: Res<RenderDevice>,
 ) {
-    let Ok(window) = windows.get_single() else {
-        return;
-    };
-
-    let mesh_handle = render_target_mesh_handle.get_or_insert_with(|| {
-        let mut rendertarget_quad = Mesh::new(
-            PrimitiveTopology::TriangleList,
-            RenderAssetUsages::default(),
+    for (entity, VelloCanvasTexture(texture)) in canvases.iter() {
+        let bind_group = device.create_bind_group(
+            "vello_canvas_bind_group",
+            &canvas_pipeline.texture_layout,
+            &BindGroupEntries::sequential((&texture.default_view, &canvas_pipeline.sampler)),
         );
+        commands
+            .entity(entity)
+            .insert(VelloCanvasBindGroup(bind_group));
+    }
+}
 
-        // Rectangle of the screen
-        let verts = vec![
-            [-1.0, -1.0, 0.0],
-            [1.0, -1.0, 0.0],
-            [1.0, 1.0, 0.0],
-            [-1.0, 1.0, 0.0],
-        ];
-        rendertarget_quad.insert_attribute(Mesh::ATTRIBUTE_POSITION, verts);
+/// Applies the prepared transforms to the respective fragments of every
+/// canvas, and renders each canvas to its texture with WGPU
+#[allow(clippy::complexity)]
+pub fn render_scene(
+    canvases: Query<(&VelloCanvas, &VelloCanvasTexture)>,
+    query_render_vectors: Query<&'static ExtractedRenderAsset>,
+    query_render_scenes: Query<&'static ExtractedRenderScene>,
+    query_render_texts: Query<&'static ExtractedRenderText>,
+    render_assets: Res<RenderAssets<VelloAsset>>,
+    font_render_assets: Res<RenderAssets<VelloFont>>,
+    font_fallback: Res<VelloFontFallback>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+    settings: Option<Res<VelloRenderSettings>>,
+    mut vello_renderer: Local<Option<(Option<VelloRenderer>, VelloRenderSettings)>>,
+    #[cfg(feature = "lottie")] mut lottie_fragments: ResMut<super::cache::LottieFragmentCache>,
+    #[cfg(feature = "svg")] mut svg_strokes: ResMut<super::stroke_cache::SvgStrokeCache>,
+    stats: Option<Res<VelloRenderStats>>,
+) {
+    let settings = settings.map(|settings| *settings).unwrap_or_default();
 
-        let uv_pos = vec![[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [1.0, 1.0]];
-        rendertarget_quad.insert_attribute(Mesh::ATTRIBUTE_UV_0, uv_pos);
+    // (Re)create the renderer when the requested settings change
+    if vello_renderer.as_ref().map(|(_, current)| *current) != Some(settings) {
+        *vello_renderer = Some((VelloRenderer::new(&device, &settings), settings));
+    }
+    let Some((Some(renderer), _)) = vello_renderer.as_mut() else {
+        return;
+    };
 
-        let indices = vec![0, 1, 2, 0, 2, 3];
-        rendertarget_quad.insert_indices(Indices::U32(indices));
+    let mut frame_stats = RenderStats {
+        assets: query_render_vectors.iter().count(),
+        scenes: query_render_scenes.iter().count(),
+        texts: query_render_texts.iter().count(),
+        ..default()
+    };
+    for (canvas, VelloCanvasTexture(texture)) in canvases.iter() {
+        let encode_start = Instant::now();
+        // Encode every item into its own fragment, in parallel
+        let encoder = CanvasEncoder {
+            render_vectors: &query_render_vectors,
+            render_scenes: &query_render_scenes,
+            render_texts: &query_render_texts,
+            render_assets: &render_assets,
+            font_render_assets: &font_render_assets,
+            font_fallback: &font_fallback,
+            #[cfg(feature = "lottie")]
+            lottie_fragments: &lottie_fragments,
+            #[cfg(feature = "svg")]
+            svg_strokes: &svg_strokes,
+            canvas_bounds: Rect::new(0.0, 0.0, canvas.size.x as f64, canvas.size.y as f64),
+            pixel_scale: canvas.pixel_scale,
+        };
+        let fragments = ComputeTaskPool::get().scope(|scope| {
+            for (affine, item, instances) in canvas.items.iter() {
+                let encoder = &encoder;
+                scope.spawn(async move {
+                    match instances {
+                        Some(instances) => encoder.encode_instances(affine, item, instances),
+                        None => encoder.encode(affine, item),
+                    }
+                });
+            }
+        });
+        let mut scene_buffer = Scene::new();
+        for fragment in fragments.iter() {
+            scene_buffer.append(fragment, None);
+        }
+        frame_stats.encode_time += encode_start.elapsed();
+        frame_stats.paths += scene_buffer.encoding().n_paths;
+        frame_stats.path_segments += scene_buffer.encoding().n_path_segments;
 
-        meshes.add(rendertarget_quad)
-    });
-    let texture_image = setup_image(&mut images, &window.resolution);
-    let render_target = SSRenderTarget(texture_image.clone());
-    let mesh = Mesh2dHandle(mesh_handle.clone());
-    let material = custom_materials.add(VelloCanvasMaterial {
-        texture: texture_image,
-    });
+        let render_start = Instant::now();
+        renderer
+            .render_to_texture(
+                device.wgpu_device(),
+                &queue,
+                &scene_buffer,
+                &texture.default_view,
+                &RenderParams {
+                    base_color: vello::peniko::Color::TRANSPARENT,
+                    width: canvas.size.x,
+                    height: canvas.size.y,
+                    antialiasing_method: settings.antialiasing,
+                },
+            )
+            .unwrap();
+        frame_stats.render_time += render_start.elapsed();
+    }
+    #[cfg(feature = "lottie")]
+    lottie_fragments.evict_unused();
+    #[cfg(feature = "svg")]
+    svg_strokes.evict_unused();
+    if let Some(stats) = stats {
+        *stats.0.lock().unwrap() = frame_stats;
+    }
+}
 
-    commands
-        .spawn(MaterialMesh2dBundle {
-            mesh,
-            material,
-            transform: Transform::from_translation(0.001 * Vec3::NEG_Z), // Make sure the vello canvas renders behind Gizmos
-            ..Default::default()
-        })
-        .insert(NoFrustumCulling)
-        .insert(render_target);
+/// The render world data to encode the items of a canvas.
+struct CanvasEncoder<'a, 'w, 's> {
+    render_vectors: &'a Query<'w, 's, &'static ExtractedRenderAsset>,
+    render_scenes: &'a Query<'w, 's, &'static ExtractedRenderScene>,
+    render_texts: &'a Query<'w, 's, &'static ExtractedRenderText>,
+    render_assets: &'a RenderAssets<VelloAsset>,
+    font_render_assets: &'a RenderAssets<VelloFont>,
+    font_fallback: &'a VelloFontFallback,
+    #[cfg(feature = "lottie")]
+    lottie_fragments: &'a super::cache::LottieFragmentCache,
+    #[cfg(feature = "svg")]
+    svg_strokes: &'a super::stroke_cache::SvgStrokeCache,
+    canvas_bounds: Rect,
+    pixel_scale: f32,
 }
 
-/// Hide the render target canvas if there is nothing to render
-pub fn clear_when_empty(
-    mut query_render_target: Query<&mut Visibility, With<SSRenderTarget>>,
-    render_items: Query<(&mut CoordinateSpace, &ViewVisibility)>,
-) {
-    if let Ok(mut visibility) = query_render_target.get_single_mut() {
-        if render_items.is_empty() {
-            *visibility = Visibility::Hidden;
-        } else {
-            *visibility = Visibility::Inherited;
+impl CanvasEncoder<'_, '_, '_> {
+    /// Encode an instanced item once, and append it at every instance.
+    fn encode_instances(
+        &self,
+        affine: &PreparedAffine,
+        item: &PreparedItem,
+        instances: &PreparedInstances,
+    ) -> Scene {
+        let fragment = self.encode(affine, item);
+        // Tint the instances of an asset within its bounds, and of a scene
+        // within the canvas
+        let bounds = match *item {
+            PreparedItem::Asset(entity) => {
+                self.render_vectors.get(entity).ok().and_then(|instance| {
+                    let asset = self.render_assets.get(&instance.asset)?;
+                    let size = ExtractedRenderAssetInstance { instance, asset }.size();
+                    Some(Rect::new(0.0, 0.0, size.x as f64, size.y as f64))
+                })
+            }
+            _ => None,
+        };
+        let mut scene_buffer = Scene::new();
+        for (relative, tint) in instances.0.iter() {
+            let Some(tint) = tint else {
+                scene_buffer.append(&fragment, Some(*relative));
+                continue;
+            };
+            let (transform, bounds) = match bounds {
+                Some(bounds) => (*relative * **affine, bounds),
+                None => (Affine::IDENTITY, self.canvas_bounds),
+            };
+            scene_buffer.push_layer(Mix::Normal, tint.a(), transform, &bounds);
+            scene_buffer.append(&fragment, Some(*relative));
+            // Multiply the colors of the instance, where it is drawn, unless
+            // only its opacity is tinted
+            let color = tint.with_a(1.0);
+            if color.as_rgba_f32() != [1.0; 4] {
+                scene_buffer.push_layer(
+                    BlendMode::new(Mix::Multiply, Compose::SrcAtop),
+                    1.0,
+                    transform,
+                    &bounds,
+                );
+                scene_buffer.fill(
+                    vello::peniko::Fill::NonZero,
+                    transform,
+                    crate::brush::to_peniko_color(color),
+                    None,
+                    &bounds,
+                );
+                scene_buffer.pop_layer();
+            }
+            scene_buffer.pop_layer();
+        }
+        scene_buffer
+    }
+
+    /// Encode an item with its prepared transform, in the pixels of the
+    /// canvas.
+    fn encode(&self, affine: &PreparedAffine, item: &PreparedItem) -> Scene {
+        let mut scene_buffer = Scene::new();
+        let (ui_clip, blend_mode, clip) = match *item {
+            PreparedItem::Asset(entity) => self
+                .render_vectors
+                .get(entity)
+                .map(|asset| (asset.ui_clip, asset.blend_mode, asset.clip.as_ref()))
+                .unwrap_or_default(),
+            PreparedItem::Scene(entity) => self
+                .render_scenes
+                .get(entity)
+                .map(|scene| (scene.ui_clip, scene.blend_mode, scene.clip.as_ref()))
+                .unwrap_or_default(),
+            PreparedItem::Text(entity) => self
+                .render_texts
+                .get(entity)
+                .map(|text| (text.ui_clip, text.blend_mode, text.clip.as_ref()))
+                .unwrap_or_default(),
+        };
+        // Clip the item to the overflow of its UI node, in logical pixels
+        if let Some(ui_clip) = ui_clip {
+            let ui_clip = Rect::new(
+                ui_clip.min.x as f64,
+                ui_clip.min.y as f64,
+                ui_clip.max.x as f64,
+                ui_clip.max.y as f64,
+            );
+            scene_buffer.push_layer(
+                Mix::Clip,
+                1.0,
+                Affine::scale(self.pixel_scale as f64),
+                &ui_clip,
+            );
+        }
+        // Composite the item over the canvas beneath with its blend mode
+        if let Some(VelloBlendMode(blend_mode)) = blend_mode {
+            scene_buffer.push_layer(blend_mode, 1.0, Affine::IDENTITY, &self.canvas_bounds);
+        }
+        match clip {
+            Some(VelloClip::Rect(rect)) => {
+                scene_buffer.push_layer(Mix::Clip, 1.0, **affine, rect);
+            }
+            Some(VelloClip::RoundedRect(rect)) => {
+                scene_buffer.push_layer(Mix::Clip, 1.0, **affine, rect);
+            }
+            Some(VelloClip::Path(path)) => {
+                scene_buffer.push_layer(Mix::Clip, 1.0, **affine, path);
+            }
+            // Isolate the item, to mask it once drawn
+            Some(VelloClip::Svg(_)) => {
+                scene_buffer.push_layer(Mix::Normal, 1.0, Affine::IDENTITY, &self.canvas_bounds);
+            }
+            None => {}
+        }
+        'item: {
+            match *item {
+                PreparedItem::Asset(entity) => {
+                    let Ok(ExtractedRenderAsset {
+                        asset,
+                        #[cfg(feature = "lottie")]
+                        theme,
+                        #[cfg(feature = "lottie")]
+                        playhead,
+                        #[cfg(feature = "svg")]
+                            scene: overridden,
+                        #[cfg(feature = "svg")]
+                        svg_tree,
+                        #[cfg(feature = "lottie")]
+                            composition: slotted,
+                        #[cfg(feature = "lottie")]
+                        text_overrides,
+                        #[cfg(feature = "lottie")]
+                        layer_visibility,
+                        #[cfg(any(feature = "svg", feature = "lottie"))]
+                        alpha,
+                        #[cfg(any(feature = "svg", feature = "lottie"))]
+                        tint,
+                        #[cfg(any(feature = "svg", feature = "lottie"))]
+                        nine_slice,
+                        #[cfg(any(feature = "svg", feature = "lottie"))]
+                        non_scaling_stroke,
+                        ..
+                    }) = self.render_vectors.get(entity)
+                    else {
+                        break 'item;
+                    };
+                    #[cfg(feature = "lottie")]
+                    let asset_id = asset.id();
+                    let Some(asset) = self.render_assets.get(asset) else {
+                        break 'item;
+                    };
+                    #[cfg(any(feature = "svg", feature = "lottie"))]
+                    let stroke_scale = match non_scaling_stroke {
+                        true => super::non_scaling_stroke::stroke_scale(**affine, self.pixel_scale),
+                        false => 1.0,
+                    };
+                    // Tint the asset in a layer, isolated from the canvas beneath
+                    #[cfg(any(feature = "svg", feature = "lottie"))]
+                    let bounds = match nine_slice {
+                        Some(nine_slice) => {
+                            Rect::new(0.0, 0.0, nine_slice.size.x as f64, nine_slice.size.y as f64)
+                        }
+                        None => Rect::new(0.0, 0.0, asset.width as f64, asset.height as f64),
+                    };
+                    #[cfg(any(feature = "svg", feature = "lottie"))]
+                    if tint.is_some() {
+                        scene_buffer.push_layer(vello::peniko::Mix::Normal, 1.0, **affine, &bounds);
+                    }
+                    match &asset.file {
+                        #[cfg(feature = "svg")]
+                        crate::VectorFile::Svg(scene) => {
+                            let scene = overridden.as_ref().unwrap_or(scene);
+                            // Render the tree again, with its strokes scaled
+                            let scene = match svg_tree.as_ref().or(asset.tree.as_ref()) {
+                                Some(tree) if stroke_scale != 1.0 => {
+                                    self.svg_strokes.get_or_render(tree, stroke_scale)
+                                }
+                                _ => scene.clone(),
+                            };
+                            if *alpha < 1.0 {
+                                scene_buffer.push_layer(
+                                    vello::peniko::Mix::Normal,
+                                    *alpha,
+                                    **affine,
+                                    &bounds,
+                                );
+                            }
+                            match nine_slice {
+                                Some(nine_slice) => super::nine_slice::render_nine_slice(
+                                    &mut scene_buffer,
+                                    &scene,
+                                    Vec2::new(asset.width, asset.height),
+                                    nine_slice,
+                                    **affine,
+                                ),
+                                None => scene_buffer.append(&scene, Some(**affine)),
+                            }
+                            if *alpha < 1.0 {
+                                scene_buffer.pop_layer();
+                            }
+                        }
+                        #[cfg(feature = "lottie")]
+                        crate::VectorFile::Lottie(composition) => {
+                            let composition = slotted.as_ref().unwrap_or(composition);
+                            let font = text_overrides
+                                .as_ref()
+                                .and_then(|overrides| self.font_render_assets.get(&overrides.font));
+                            let key = super::cache::LottieFragmentKey {
+                                theme: theme.clone(),
+                                composition: slotted.clone(),
+                                text_overrides: text_overrides.clone(),
+                                layer_visibility: layer_visibility.clone(),
+                                alpha: *alpha,
+                                font_loaded: font.is_some(),
+                                stroke_scale,
+                            };
+                            // Encoded in the local space of the asset, to
+                            // be reused wherever it is drawn
+                            let fragment = self.lottie_fragments.get_or_encode(
+                                asset_id,
+                                *playhead,
+                                key,
+                                |fragment, precomps| {
+                                    let shown = layer_visibility
+                                        .as_ref()
+                                        .and_then(|visibility| visibility.hide_layers(composition));
+                                    let composition = shown.as_ref().unwrap_or(composition);
+                                    // Renderers are cheap, and one is needed
+                                    // by every task
+                                    let recolored =
+                                        theme.as_ref().map(|cs| cs.recolor(composition));
+                                    let rendered = recolored.as_ref().unwrap_or(composition);
+                                    let scaled = (stroke_scale != 1.0).then(|| {
+                                        crate::integrations::lottie::scale_strokes(
+                                            rendered,
+                                            stroke_scale,
+                                        )
+                                    });
+                                    let mut renderer =
+                                        crate::integrations::lottie::PrecompRenderer::new(precomps);
+                                    renderer.render(
+                                        scaled.as_ref().unwrap_or(rendered),
+                                        *playhead,
+                                        Affine::IDENTITY,
+                                        (asset.alpha * alpha) as f64,
+                                        fragment,
+                                    );
+                                    crate::integrations::lottie::render_image_layers(
+                                        fragment,
+                                        composition,
+                                        &asset.image_layers,
+                                        *playhead,
+                                        Affine::IDENTITY,
+                                        asset.alpha * alpha,
+                                    );
+                                    if let (Some(overrides), Some(font)) = (text_overrides, font) {
+                                        crate::integrations::lottie::render_text_overrides(
+                                            fragment,
+                                            composition,
+                                            &asset.text_layers,
+                                            overrides,
+                                            font,
+                                            *playhead,
+                                            Affine::IDENTITY,
+                                            asset.alpha * alpha,
+                                        );
+                                    }
+                                },
+                            );
+                            scene_buffer.append(&fragment, Some(**affine));
+                        }
+                        #[cfg(not(any(feature = "svg", feature = "lottie")))]
+                        _ => unimplemented!(),
+                    }
+                    #[cfg(any(feature = "svg", feature = "lottie"))]
+                    if let Some(tint) = tint {
+                        // Multiply the colors of the asset, where it is drawn
+                        scene_buffer.push_layer(
+                            vello::peniko::BlendMode::new(
+                                vello::peniko::Mix::Multiply,
+                                vello::peniko::Compose::SrcAtop,
+                            ),
+                            1.0,
+                            **affine,
+                            &bounds,
+                        );
+                        scene_buffer.fill(
+                            vello::peniko::Fill::NonZero,
+                            **affine,
+                            crate::brush::to_peniko_color(*tint),
+                            None,
+                            &bounds,
+                        );
+                        scene_buffer.pop_layer();
+                        scene_buffer.pop_layer();
+                    }
+                }
+                PreparedItem::Scene(entity) => {
+                    if let Ok(ExtractedRenderScene { scene, .. }) = self.render_scenes.get(entity) {
+                        scene_buffer.append(scene, Some(**affine));
+                    }
+                }
+                PreparedItem::Text(entity) => {
+                    let Ok(ExtractedRenderText {
+                        font,
+                        text,
+                        alignment,
+                        reveal,
+                        selection,
+                        layout,
+                        ..
+                    }) = self.render_texts.get(entity)
+                    else {
+                        break 'item;
+                    };
+                    if let Some(font) = self.font_render_assets.get(font) {
+                        // Texts are laid out before they are extracted, unless
+                        // their font was just loaded
+                        let uncached;
+                        let layout = match layout {
+                            Some(layout) => &*layout.0,
+                            None => {
+                                uncached = font.layout_text(text, self.font_fallback, |font| {
+                                    self.font_render_assets.get(font)
+                                });
+                                &uncached
+                            }
+                        };
+                        font.render(
+                            &mut scene_buffer,
+                            **affine,
+                            text,
+                            *alignment,
+                            reveal.as_ref(),
+                            selection.as_ref(),
+                            layout,
+                        );
+                    }
+                }
+            }
+        }
+        if let Some(clip) = clip {
+            // Keep the item where the mask is drawn
+            if let VelloClip::Svg(mask) = clip {
+                scene_buffer.push_layer(
+                    BlendMode::new(Mix::Normal, Compose::DestIn),
+                    1.0,
+                    Affine::IDENTITY,
+                    &self.canvas_bounds,
+                );
+                #[cfg(feature = "svg")]
+                if let Some(VelloAsset {
+                    file: crate::VectorFile::Svg(mask),
+                    ..
+                }) = self.render_assets.get(mask)
+                {
+                    scene_buffer.append(mask, Some(**affine));
+                }
+                #[cfg(not(feature = "svg"))]
+                let _ = mask;
+                scene_buffer.pop_layer();
+            }
+            scene_buffer.pop_layer();
+        }
+        if blend_mode.is_some() {
+            scene_buffer.pop_layer();
+        }
+        if ui_clip.is_some() {
+            scene_buffer.pop_layer();
         }
+        scene_buffer
     }
 }
@@ -0,0 +1,17 @@
+use bevy::prelude::*;
+
+/// A color multiplied with the rendered colors of a vello asset instance, like
+/// `Sprite::color`, e.g. to flash an enemy red. The alpha of the tint is
+/// multiplied with the opacity of the instance, as with [`AlphaOverride`].
+/// Instances without this component are not tinted.
+///
+/// [`AlphaOverride`]: super::AlphaOverride
+#[derive(Component, Debug, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct VelloTint(pub Color);
+
+impl Default for VelloTint {
+    fn default() -> Self {
+        Self(Color::WHITE)
+    }
+}
@@ -1,7 +1,13 @@
 use crate::VelloAsset;
 use bevy::prelude::*;
 
+/// How the Z coordinate of an asset is computed for sorting.
+///
+/// This component isn't reflected, as [`ZFunction::Computed`] holds a function.
+/// Entities without it, such as those spawned from a `DynamicScene`, use
+/// [`ZFunction::TransformZ`].
 #[derive(Component, Debug, Default, Clone, Copy, PartialEq)]
+#[allow(unpredictable_function_pointer_comparisons)]
 pub enum ZFunction {
     /// Make no adjustment to the asset's transform Z coordinate.
     #[default]
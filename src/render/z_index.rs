@@ -0,0 +1,9 @@
+use bevy::prelude::*;
+
+/// Orders vello instances which share the same Z-index, the higher value being
+/// rendered on top. The Z-index itself still comes from the transform (or the
+/// [`ZFunction`](super::ZFunction) of an asset), so this only decides the order
+/// of instances which would otherwise be composited in extraction order.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Reflect)]
+#[reflect(Component)]
+pub struct VelloZIndex(pub i32);
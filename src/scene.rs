@@ -0,0 +1,241 @@
+//! Helpers to draw shapes into a [`VelloScene`] with Bevy types.
+//!
+//! Positions are in the units of the scene with Y up, like the transform of
+//! the scene, and angles are counterclockwise from the X axis, in radians.
+
+use crate::brush::to_peniko_color;
+use crate::stroke::VelloStroke;
+use crate::VelloScene;
+use bevy::math::Affine2;
+use bevy::prelude::*;
+use vello::kurbo::{self, Affine, BezPath, Point, Shape, Stroke};
+use vello::peniko::{self, BlendMode, Fill};
+
+/// A point of a scene, flipped so Y is down.
+fn point(position: Vec2) -> Point {
+    Point::new(position.x as f64, -position.y as f64)
+}
+
+/// The lines between consecutive points, flipped so Y is down.
+fn polyline(points: impl IntoIterator<Item = Vec2>) -> BezPath {
+    let mut path = BezPath::new();
+    for (i, position) in points.into_iter().enumerate() {
+        match i {
+            0 => path.move_to(point(position)),
+            _ => path.line_to(point(position)),
+        }
+    }
+    path
+}
+
+impl VelloScene {
+    /// Fill a shape in the coordinates of the scene, with Y down.
+    fn fill_shape(&mut self, color: Color, shape: &impl Shape) -> &mut Self {
+        self.fill(
+            Fill::NonZero,
+            Affine::IDENTITY,
+            to_peniko_color(color),
+            None,
+            shape,
+        );
+        self
+    }
+
+    /// Stroke a shape in the coordinates of the scene, with Y down.
+    fn stroke_shape(&mut self, stroke: &Stroke, color: Color, shape: &impl Shape) -> &mut Self {
+        self.stroke(
+            stroke,
+            Affine::IDENTITY,
+            to_peniko_color(color),
+            None,
+            shape,
+        );
+        self
+    }
+
+    /// Fill a rectangle of the given size around its center.
+    pub fn rect(&mut self, center: Vec2, size: Vec2, color: Color) -> &mut Self {
+        let rect = kurbo::Rect::from_center_size(point(center), (size.x as f64, size.y as f64));
+        self.fill_shape(color, &rect)
+    }
+
+    /// Fill a rectangle of the given size around its center, with rounded
+    /// corners of the given radius.
+    pub fn rounded_rect(
+        &mut self,
+        center: Vec2,
+        size: Vec2,
+        radius: f32,
+        color: Color,
+    ) -> &mut Self {
+        let rect = kurbo::Rect::from_center_size(point(center), (size.x as f64, size.y as f64));
+        self.fill_shape(color, &rect.to_rounded_rect(radius as f64))
+    }
+
+    /// Fill a circle.
+    pub fn circle(&mut self, center: Vec2, radius: f32, color: Color) -> &mut Self {
+        self.fill_shape(color, &kurbo::Circle::new(point(center), radius as f64))
+    }
+
+    /// Stroke a line of the given width.
+    pub fn line(&mut self, from: Vec2, to: Vec2, width: f32, color: Color) -> &mut Self {
+        self.stroke_line(from, to, &VelloStroke::new(width), color)
+    }
+
+    /// Stroke the lines between consecutive points, of the given width.
+    pub fn polyline(
+        &mut self,
+        points: impl IntoIterator<Item = Vec2>,
+        width: f32,
+        color: Color,
+    ) -> &mut Self {
+        self.stroke_polyline(points, &VelloStroke::new(width), color)
+    }
+
+    /// Stroke an arc of a circle of the given width, from the start angle
+    /// counterclockwise by the sweep angle, or clockwise if it is negative.
+    pub fn arc(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+        width: f32,
+        color: Color,
+    ) -> &mut Self {
+        self.stroke_arc(
+            center,
+            radius,
+            start_angle,
+            sweep_angle,
+            &VelloStroke::new(width),
+            color,
+        )
+    }
+
+    /// Stroke a line with a style, e.g. dashed.
+    pub fn stroke_line(
+        &mut self,
+        from: Vec2,
+        to: Vec2,
+        stroke: &VelloStroke,
+        color: Color,
+    ) -> &mut Self {
+        let line = kurbo::Line::new(point(from), point(to));
+        self.stroke_shape(&stroke.into(), color, &line)
+    }
+
+    /// Stroke the lines between consecutive points with a style.
+    pub fn stroke_polyline(
+        &mut self,
+        points: impl IntoIterator<Item = Vec2>,
+        stroke: &VelloStroke,
+        color: Color,
+    ) -> &mut Self {
+        self.stroke_shape(&stroke.into(), color, &polyline(points))
+    }
+
+    /// Stroke the outline of the polygon of the given points with a style.
+    pub fn stroke_polygon(
+        &mut self,
+        points: impl IntoIterator<Item = Vec2>,
+        stroke: &VelloStroke,
+        color: Color,
+    ) -> &mut Self {
+        let mut path = polyline(points);
+        path.close_path();
+        self.stroke_shape(&stroke.into(), color, &path)
+    }
+
+    /// Stroke the outline of a rectangle of the given size around its center
+    /// with a style, e.g. a dashed selection outline.
+    pub fn stroke_rect(
+        &mut self,
+        center: Vec2,
+        size: Vec2,
+        stroke: &VelloStroke,
+        color: Color,
+    ) -> &mut Self {
+        let rect = kurbo::Rect::from_center_size(point(center), (size.x as f64, size.y as f64));
+        self.stroke_shape(&stroke.into(), color, &rect)
+    }
+
+    /// Stroke the outline of a rectangle with rounded corners with a style.
+    pub fn stroke_rounded_rect(
+        &mut self,
+        center: Vec2,
+        size: Vec2,
+        radius: f32,
+        stroke: &VelloStroke,
+        color: Color,
+    ) -> &mut Self {
+        let rect = kurbo::Rect::from_center_size(point(center), (size.x as f64, size.y as f64));
+        self.stroke_shape(&stroke.into(), color, &rect.to_rounded_rect(radius as f64))
+    }
+
+    /// Stroke the outline of a circle with a style.
+    pub fn stroke_circle(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        stroke: &VelloStroke,
+        color: Color,
+    ) -> &mut Self {
+        let circle = kurbo::Circle::new(point(center), radius as f64);
+        self.stroke_shape(&stroke.into(), color, &circle)
+    }
+
+    /// Stroke an arc of a circle with a style, from the start angle
+    /// counterclockwise by the sweep angle, or clockwise if it is negative.
+    pub fn stroke_arc(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+        stroke: &VelloStroke,
+        color: Color,
+    ) -> &mut Self {
+        // Angles are clockwise with Y down
+        let arc = kurbo::Arc {
+            center: point(center),
+            radii: kurbo::Vec2::new(radius as f64, radius as f64),
+            start_angle: -start_angle as f64,
+            sweep_angle: -sweep_angle as f64,
+            x_rotation: 0.0,
+        };
+        self.stroke_shape(&stroke.into(), color, &arc)
+    }
+
+    /// Fill the polygon of the given points, e.g. a triangle.
+    pub fn path(&mut self, points: impl IntoIterator<Item = Vec2>, color: Color) -> &mut Self {
+        let mut path = polyline(points);
+        path.close_path();
+        self.fill_shape(color, &path)
+    }
+
+    /// Draw an image centered on the origin, one unit per pixel, transformed
+    /// with Y up, and composited over the scene beneath with a blend mode,
+    /// e.g. a photo under vector annotations. Convert Bevy images with
+    /// [`to_peniko_image`](crate::brush::to_peniko_image).
+    pub fn image(
+        &mut self,
+        image: &peniko::Image,
+        transform: Affine2,
+        blend_mode: impl Into<BlendMode>,
+    ) -> &mut Self {
+        let (width, height) = (image.width as f64, image.height as f64);
+        let [a, b, c, d] = transform.matrix2.to_cols_array().map(f64::from);
+        let [e, f] = transform.translation.to_array().map(f64::from);
+        // Flip the transform so Y is down, from the top left of the image
+        let transform = Affine::FLIP_Y
+            * Affine::new([a, b, c, d, e, f])
+            * Affine::FLIP_Y
+            * Affine::translate((-width / 2.0, -height / 2.0));
+        let bounds = kurbo::Rect::new(0.0, 0.0, width, height);
+        self.push_layer(blend_mode, 1.0, transform, &bounds);
+        self.draw_image(image, transform);
+        self.pop_layer();
+        self
+    }
+}
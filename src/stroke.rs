@@ -0,0 +1,133 @@
+//! The style of strokes, e.g. for dashed selection outlines, drawn with the
+//! [`VelloScene`](crate::VelloScene) helpers or overriding the strokes of an
+//! SVG element.
+
+use bevy::prelude::*;
+use vello::kurbo::{Cap, Join, Stroke};
+
+/// The shape of the ends of a stroke, and of its dashes.
+#[derive(PartialEq, Eq, Default, Clone, Copy, Debug, Reflect)]
+pub enum VelloStrokeCap {
+    /// Flat, at the end of the path.
+    Butt,
+    /// Flat, half the width of the stroke past the end of the path.
+    Square,
+    #[default]
+    Round,
+}
+
+/// The shape of the corners of a stroke.
+#[derive(PartialEq, Eq, Default, Clone, Copy, Debug, Reflect)]
+pub enum VelloStrokeJoin {
+    /// Cut off.
+    Bevel,
+    /// Sharp, unless longer than the miter limit.
+    Miter,
+    #[default]
+    Round,
+}
+
+/// How a path is stroked. Lengths are in the units the path is drawn in.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_vello::prelude::*;
+/// # let mut scene = VelloScene::new();
+/// let outline = VelloStroke::new(2.0)
+///     .with_cap(VelloStrokeCap::Butt)
+///     .with_dashes(0.0, [8.0, 4.0]);
+/// scene.stroke_rect(Vec2::ZERO, Vec2::splat(100.0), &outline, Color::WHITE);
+/// ```
+#[derive(PartialEq, Clone, Debug, Reflect)]
+pub struct VelloStroke {
+    pub width: f32,
+    pub cap: VelloStrokeCap,
+    pub join: VelloStrokeJoin,
+    /// The longest a miter join may be, as a multiple of the width, before
+    /// it is beveled.
+    pub miter_limit: f32,
+    /// The lengths of alternating dashes and gaps, or empty for a solid
+    /// stroke.
+    pub dash_pattern: Vec<f32>,
+    /// How far into the dash pattern the stroke starts.
+    pub dash_offset: f32,
+}
+
+impl Default for VelloStroke {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl VelloStroke {
+    /// A solid stroke of the given width, with round caps and joins.
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            cap: VelloStrokeCap::Round,
+            join: VelloStrokeJoin::Round,
+            miter_limit: 4.0,
+            dash_pattern: Vec::new(),
+            dash_offset: 0.0,
+        }
+    }
+
+    pub fn with_cap(mut self, cap: VelloStrokeCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    pub fn with_join(mut self, join: VelloStrokeJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    pub fn with_miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    /// Dash the stroke with the lengths of alternating dashes and gaps,
+    /// starting the given length into the pattern.
+    pub fn with_dashes(mut self, offset: f32, pattern: impl IntoIterator<Item = f32>) -> Self {
+        self.dash_offset = offset;
+        self.dash_pattern = pattern.into_iter().collect();
+        self
+    }
+}
+
+impl From<&VelloStroke> for Stroke {
+    fn from(stroke: &VelloStroke) -> Self {
+        let cap = match stroke.cap {
+            VelloStrokeCap::Butt => Cap::Butt,
+            VelloStrokeCap::Square => Cap::Square,
+            VelloStrokeCap::Round => Cap::Round,
+        };
+        let join = match stroke.join {
+            VelloStrokeJoin::Bevel => Join::Bevel,
+            VelloStrokeJoin::Miter => Join::Miter,
+            VelloStrokeJoin::Round => Join::Round,
+        };
+        let mut kurbo_stroke = Stroke::new(stroke.width as f64)
+            .with_caps(cap)
+            .with_join(join)
+            .with_miter_limit(stroke.miter_limit as f64);
+        // Dashing never ends on a pattern without any length
+        if stroke.dash_pattern.iter().any(|length| *length > 0.0) {
+            kurbo_stroke = kurbo_stroke.with_dashes(
+                stroke.dash_offset as f64,
+                stroke
+                    .dash_pattern
+                    .iter()
+                    .map(|length| length.max(0.0) as f64),
+            );
+        }
+        kurbo_stroke
+    }
+}
+
+impl From<VelloStroke> for Stroke {
+    fn from(stroke: VelloStroke) -> Self {
+        Self::from(&stroke)
+    }
+}
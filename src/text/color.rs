@@ -0,0 +1,490 @@
+//! The color glyphs of fonts, e.g. emoji, drawn from their `COLR` layers and
+//! paint graphs, or from their `CBDT` and `sbix` bitmaps.
+
+use super::VelloFont;
+use bevy::utils::HashMap;
+use rustybuzz::ttf_parser;
+use std::sync::{Arc, Mutex};
+use vello::glyph::skrifa::color::{
+    Brush as ColorBrush, ColorGlyphCollection, ColorPainter, ColorStop, CompositeMode, Extend,
+    Transform,
+};
+use vello::glyph::skrifa::instance::LocationRef;
+use vello::glyph::skrifa::outline::{OutlineGlyphCollection, OutlinePen};
+use vello::glyph::skrifa::raw::TableProvider;
+use vello::glyph::skrifa::{FontRef, GlyphId, MetadataProvider};
+use vello::glyph::Glyph;
+use vello::kurbo::{Affine, BezPath, Rect};
+use vello::peniko::{self, BlendMode, Blob, Brush, Color, Compose, Fill, Format, Gradient, Mix};
+use vello::Scene;
+
+/// The area filled by paints which fill their whole clip, in font units.
+const PAINT_AREA: Rect = Rect::new(-16384.0, -16384.0, 16384.0, 16384.0);
+
+/// The palette index of the color of the text.
+const FOREGROUND: u16 = 0xFFFF;
+
+/// The decoded bitmaps of the glyphs of a font, by the glyph and the pixels
+/// per em of its strike. They are dropped with the font, when its asset is
+/// removed or modified.
+#[derive(Clone, Default)]
+pub(crate) struct ColorBitmaps(Arc<Mutex<HashMap<(u32, u16), Option<peniko::Image>>>>);
+
+/// The color glyphs of a font.
+pub(crate) struct ColorGlyphs<'a> {
+    font: &'a VelloFont,
+    colors: ColorGlyphCollection<'a>,
+    outlines: OutlineGlyphCollection<'a>,
+    bitmaps: Option<ttf_parser::Face<'a>>,
+    palette: Vec<Color>,
+}
+
+impl<'a> ColorGlyphs<'a> {
+    /// The color glyphs of a font, or `None` if it has no color tables.
+    pub fn new(font: &'a VelloFont, font_ref: &FontRef<'a>) -> Option<Self> {
+        let data = font.font.data.data();
+        let bitmaps = ttf_parser::Face::parse(data, font.font.index)
+            .ok()
+            .filter(|face| {
+                let tables = face.tables();
+                tables.cbdt.is_some() || tables.sbix.is_some()
+            });
+        let colr = font_ref.colr().is_ok();
+        if !colr && bitmaps.is_none() {
+            return None;
+        }
+        // The first palette of the font
+        let palette = font_ref
+            .cpal()
+            .ok()
+            .and_then(|cpal| {
+                let first = cpal.color_record_indices().first()?.get() as usize;
+                let records = cpal.color_records_array()?.ok()?;
+                let len = cpal.num_palette_entries() as usize;
+                Some(
+                    records
+                        .get(first..first + len)?
+                        .iter()
+                        .map(|record| {
+                            Color::rgba8(record.red, record.green, record.blue, record.alpha)
+                        })
+                        .collect(),
+                )
+            })
+            .unwrap_or_default();
+        Some(Self {
+            font,
+            colors: font_ref.color_glyphs(),
+            outlines: font_ref.outline_glyphs(),
+            bitmaps,
+            palette,
+        })
+    }
+
+    /// Whether a glyph is drawn in its own colors.
+    pub fn contains(&self, id: u32) -> bool {
+        self.colors.get(GlyphId::new(id as u16)).is_some()
+            || self.bitmaps.as_ref().is_some_and(|face| {
+                face.glyph_raster_image(ttf_parser::GlyphId(id as u16), u16::MAX)
+                    .is_some_and(|image| image.format == ttf_parser::RasterImageFormat::PNG)
+            })
+    }
+
+    /// Draw a color glyph of a text. The layers of the glyph which are the
+    /// color of the text are drawn with its brush, and the transform of the
+    /// brush relative to the text.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &self,
+        scene: &mut Scene,
+        transform: Affine,
+        glyph: &Glyph,
+        size: f32,
+        location: LocationRef,
+        foreground: &Brush,
+        foreground_transform: Option<Affine>,
+    ) {
+        let foreground_transform = foreground_transform.map(|brush| transform * brush);
+        let transform = transform * Affine::translate((glyph.x as f64, glyph.y as f64));
+        let id = GlyphId::new(glyph.id as u16);
+        if let Some(color_glyph) = self.colors.get(id) {
+            let upem = self.outlines_upem();
+            // Font units are Y up
+            let scale = size as f64 / upem;
+            let mut painter = ScenePainter {
+                glyphs: self,
+                scene,
+                location,
+                foreground,
+                foreground_transform,
+                transforms: vec![transform * Affine::scale_non_uniform(scale, -scale)],
+            };
+            let _ = color_glyph.paint(location, &mut painter);
+        } else if let Some(face) = &self.bitmaps {
+            let ppem = size.ceil().clamp(1.0, u16::MAX as f32) as u16;
+            let Some(raster) = face.glyph_raster_image(ttf_parser::GlyphId(glyph.id as u16), ppem)
+            else {
+                return;
+            };
+            let key = (glyph.id, raster.pixels_per_em);
+            let image = self
+                .font
+                .bitmaps
+                .0
+                .lock()
+                .unwrap()
+                .entry(key)
+                .or_insert_with(|| decode_png(raster.data))
+                .clone();
+            let Some(image) = image else {
+                return;
+            };
+            // The bitmap is positioned in the pixels of its strike, with Y up
+            let scale = size as f64 / raster.pixels_per_em as f64;
+            let top = (raster.y as f64 + raster.height as f64) * scale;
+            let width_scale = raster.width as f64 / image.width as f64;
+            let height_scale = raster.height as f64 / image.height as f64;
+            scene.draw_image(
+                &image,
+                transform
+                    * Affine::translate((raster.x as f64 * scale, -top))
+                    * Affine::scale_non_uniform(scale * width_scale, scale * height_scale),
+            );
+        }
+    }
+
+    fn outlines_upem(&self) -> f64 {
+        FontRef::from_index(self.font.font.data.data(), self.font.font.index)
+            .ok()
+            .and_then(|font| font.head().ok())
+            .map_or(1000.0, |head| head.units_per_em() as f64)
+    }
+
+    /// The outline of a glyph, in font units.
+    fn outline(&self, id: GlyphId, location: LocationRef) -> BezPath {
+        let mut pen = BezPathPen(BezPath::new());
+        if let Some(outline) = self.outlines.get(id) {
+            let size = vello::skrifa::instance::Size::unscaled();
+            let _ = outline.draw((size, location), &mut pen);
+        }
+        pen.0
+    }
+
+    /// A color of the palette, or the color of the text. The color of a text
+    /// drawn with a gradient is the average of its stops.
+    fn color(&self, palette_index: u16, alpha: f32, foreground: &Brush) -> Color {
+        let color = match (palette_index, foreground) {
+            (FOREGROUND, Brush::Solid(color)) => *color,
+            (FOREGROUND, Brush::Gradient(gradient)) => {
+                average(gradient.stops.iter().map(|stop| stop.color))
+            }
+            (FOREGROUND, Brush::Image(_)) => Color::WHITE,
+            (index, _) => self
+                .palette
+                .get(index as usize)
+                .copied()
+                .unwrap_or(Color::BLACK),
+        };
+        color.with_alpha_factor(alpha)
+    }
+}
+
+/// The average of colors, or transparent without any.
+fn average(colors: impl ExactSizeIterator<Item = Color>) -> Color {
+    let n = colors.len().max(1) as f64;
+    let [r, g, b, a] = colors.fold([0.0; 4], |sum, Color { r, g, b, a }| {
+        [
+            sum[0] + r as f64 / n,
+            sum[1] + g as f64 / n,
+            sum[2] + b as f64 / n,
+            sum[3] + a as f64 / n,
+        ]
+    });
+    Color::rgba8(r as u8, g as u8, b as u8, a as u8)
+}
+
+/// A brush with its opacity multiplied.
+fn with_alpha_factor(brush: &Brush, alpha: f32) -> Brush {
+    match brush {
+        _ if alpha >= 1.0 => brush.clone(),
+        Brush::Solid(color) => color.with_alpha_factor(alpha).into(),
+        Brush::Gradient(gradient) => Brush::Gradient(Gradient {
+            kind: gradient.kind,
+            extend: gradient.extend,
+            stops: gradient
+                .stops
+                .iter()
+                .map(|stop| stop.with_alpha_factor(alpha))
+                .collect(),
+        }),
+        Brush::Image(_) => brush.clone(),
+    }
+}
+
+/// Decode a PNG bitmap into an image.
+fn decode_png(data: &[u8]) -> Option<peniko::Image> {
+    let image = image::load_from_memory_with_format(data, image::ImageFormat::Png)
+        .ok()?
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+    Some(peniko::Image::new(
+        Blob::new(Arc::new(image.into_raw())),
+        Format::Rgba8,
+        width,
+        height,
+    ))
+}
+
+/// Collects the outline of a glyph.
+pub(crate) struct BezPathPen(pub BezPath);
+
+impl OutlinePen for BezPathPen {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.move_to((x as f64, y as f64));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.line_to((x as f64, y as f64));
+    }
+
+    fn quad_to(&mut self, cx0: f32, cy0: f32, x: f32, y: f32) {
+        self.0
+            .quad_to((cx0 as f64, cy0 as f64), (x as f64, y as f64));
+    }
+
+    fn curve_to(&mut self, cx0: f32, cy0: f32, cx1: f32, cy1: f32, x: f32, y: f32) {
+        self.0.curve_to(
+            (cx0 as f64, cy0 as f64),
+            (cx1 as f64, cy1 as f64),
+            (x as f64, y as f64),
+        );
+    }
+
+    fn close(&mut self) {
+        self.0.close_path();
+    }
+}
+
+/// Paints a color glyph into a scene.
+struct ScenePainter<'a, 'b> {
+    glyphs: &'b ColorGlyphs<'a>,
+    scene: &'b mut Scene,
+    location: LocationRef<'b>,
+    foreground: &'b Brush,
+    /// The transform of the brush of the text.
+    foreground_transform: Option<Affine>,
+    /// The transforms pushed by the glyph, from font units.
+    transforms: Vec<Affine>,
+}
+
+impl ScenePainter<'_, '_> {
+    fn transform(&self) -> Affine {
+        self.transforms.last().copied().unwrap_or_default()
+    }
+
+    /// The brush of a paint and its transform, relative to the current
+    /// transform. Layers of the color of the text are painted with its brush.
+    fn paint(&self, brush: ColorBrush, brush_transform: Option<Affine>) -> (Brush, Option<Affine>) {
+        match brush {
+            ColorBrush::Solid {
+                palette_index: FOREGROUND,
+                alpha,
+            } => (
+                with_alpha_factor(self.foreground, alpha),
+                self.foreground_transform
+                    .map(|foreground| self.transform().inverse() * foreground),
+            ),
+            brush => (self.brush(brush), brush_transform),
+        }
+    }
+
+    fn brush(&self, brush: ColorBrush) -> Brush {
+        let stops = |color_stops: &[ColorStop]| -> Vec<peniko::ColorStop> {
+            color_stops
+                .iter()
+                .map(|stop| peniko::ColorStop {
+                    offset: stop.offset,
+                    color: self
+                        .glyphs
+                        .color(stop.palette_index, stop.alpha, self.foreground),
+                })
+                .collect()
+        };
+        // Gradients of less than two stops are solid
+        let gradient = |gradient: Gradient, extend: Extend, color_stops: &[ColorStop]| {
+            let stops = stops(color_stops);
+            match stops[..] {
+                [] => Brush::Solid(Color::TRANSPARENT),
+                [stop] => Brush::Solid(stop.color),
+                _ => gradient
+                    .with_extend(match extend {
+                        Extend::Repeat => peniko::Extend::Repeat,
+                        Extend::Reflect => peniko::Extend::Reflect,
+                        _ => peniko::Extend::Pad,
+                    })
+                    .with_stops(stops.as_slice())
+                    .into(),
+            }
+        };
+        match brush {
+            ColorBrush::Solid {
+                palette_index,
+                alpha,
+            } => Brush::Solid(self.glyphs.color(palette_index, alpha, self.foreground)),
+            ColorBrush::LinearGradient {
+                p0,
+                p1,
+                color_stops,
+                extend,
+            } => gradient(
+                Gradient::new_linear((p0.x as f64, p0.y as f64), (p1.x as f64, p1.y as f64)),
+                extend,
+                color_stops,
+            ),
+            ColorBrush::RadialGradient {
+                c0,
+                r0,
+                c1,
+                r1,
+                color_stops,
+                extend,
+            } => gradient(
+                Gradient::new_two_point_radial(
+                    (c0.x as f64, c0.y as f64),
+                    r0.max(0.0),
+                    (c1.x as f64, c1.y as f64),
+                    r1.max(0.0),
+                ),
+                extend,
+                color_stops,
+            ),
+            // Sweep gradients aren't supported by vello, they are filled with
+            // the average of their stops
+            ColorBrush::SweepGradient { color_stops, .. } => Brush::Solid(average(
+                stops(color_stops).into_iter().map(|stop| stop.color),
+            )),
+        }
+    }
+}
+
+fn to_affine(transform: Transform) -> Affine {
+    let Transform {
+        xx,
+        yx,
+        xy,
+        yy,
+        dx,
+        dy,
+    } = transform;
+    Affine::new([xx, yx, xy, yy, dx, dy].map(f64::from))
+}
+
+fn blend_mode(mode: CompositeMode) -> BlendMode {
+    let compose = |compose| BlendMode::new(Mix::Normal, compose);
+    let mix = |mix| BlendMode::new(mix, Compose::SrcOver);
+    match mode {
+        CompositeMode::Clear => compose(Compose::Clear),
+        CompositeMode::Src => compose(Compose::Copy),
+        CompositeMode::Dest => compose(Compose::Dest),
+        CompositeMode::DestOver => compose(Compose::DestOver),
+        CompositeMode::SrcIn => compose(Compose::SrcIn),
+        CompositeMode::DestIn => compose(Compose::DestIn),
+        CompositeMode::SrcOut => compose(Compose::SrcOut),
+        CompositeMode::DestOut => compose(Compose::DestOut),
+        CompositeMode::SrcAtop => compose(Compose::SrcAtop),
+        CompositeMode::DestAtop => compose(Compose::DestAtop),
+        CompositeMode::Xor => compose(Compose::Xor),
+        CompositeMode::Plus => compose(Compose::PlusLighter),
+        CompositeMode::Screen => mix(Mix::Screen),
+        CompositeMode::Overlay => mix(Mix::Overlay),
+        CompositeMode::Darken => mix(Mix::Darken),
+        CompositeMode::Lighten => mix(Mix::Lighten),
+        CompositeMode::ColorDodge => mix(Mix::ColorDodge),
+        CompositeMode::ColorBurn => mix(Mix::ColorBurn),
+        CompositeMode::HardLight => mix(Mix::HardLight),
+        CompositeMode::SoftLight => mix(Mix::SoftLight),
+        CompositeMode::Difference => mix(Mix::Difference),
+        CompositeMode::Exclusion => mix(Mix::Exclusion),
+        CompositeMode::Multiply => mix(Mix::Multiply),
+        CompositeMode::HslHue => mix(Mix::Hue),
+        CompositeMode::HslSaturation => mix(Mix::Saturation),
+        CompositeMode::HslColor => mix(Mix::Color),
+        CompositeMode::HslLuminosity => mix(Mix::Luminosity),
+        _ => compose(Compose::SrcOver),
+    }
+}
+
+impl ColorPainter for ScenePainter<'_, '_> {
+    fn push_transform(&mut self, transform: Transform) {
+        self.transforms
+            .push(self.transform() * to_affine(transform));
+    }
+
+    fn pop_transform(&mut self) {
+        if self.transforms.len() > 1 {
+            self.transforms.pop();
+        }
+    }
+
+    fn push_clip_glyph(&mut self, glyph_id: GlyphId) {
+        let outline = self.glyphs.outline(glyph_id, self.location);
+        self.scene
+            .push_layer(Mix::Clip, 1.0, self.transform(), &outline);
+    }
+
+    fn push_clip_box(&mut self, clip_box: vello::skrifa::raw::types::BoundingBox<f32>) {
+        let rect = Rect::new(
+            clip_box.x_min as f64,
+            clip_box.y_min as f64,
+            clip_box.x_max as f64,
+            clip_box.y_max as f64,
+        );
+        self.scene
+            .push_layer(Mix::Clip, 1.0, self.transform(), &rect);
+    }
+
+    fn pop_clip(&mut self) {
+        self.scene.pop_layer();
+    }
+
+    fn fill(&mut self, brush: ColorBrush<'_>) {
+        let (brush, brush_transform) = self.paint(brush, None);
+        self.scene.fill(
+            Fill::NonZero,
+            self.transform(),
+            &brush,
+            brush_transform,
+            &PAINT_AREA,
+        );
+    }
+
+    fn fill_glyph(
+        &mut self,
+        glyph_id: GlyphId,
+        brush_transform: Option<Transform>,
+        brush: ColorBrush<'_>,
+    ) {
+        let outline = self.glyphs.outline(glyph_id, self.location);
+        let (brush, brush_transform) = self.paint(brush, brush_transform.map(to_affine));
+        self.scene.fill(
+            Fill::NonZero,
+            self.transform(),
+            &brush,
+            brush_transform,
+            &outline,
+        );
+    }
+
+    fn push_layer(&mut self, composite_mode: CompositeMode) {
+        self.scene.push_layer(
+            blend_mode(composite_mode),
+            1.0,
+            self.transform(),
+            &PAINT_AREA,
+        );
+    }
+
+    fn pop_layer(&mut self) {
+        self.scene.pop_layer();
+    }
+}
@@ -1,20 +1,201 @@
-use super::vello_text::VelloText;
-use super::VelloTextAlignment;
+use super::color::{BezPathPen, ColorBitmaps, ColorGlyphs};
+use super::layout::{VelloTextGlyph, VelloTextLayout, VelloTextLine};
+use super::vello_text::{VelloFontFallback, VelloText, VelloTextReveal, VelloTextSelection};
+use super::{VelloTextAlign, VelloTextAlignment, VelloTextDirection};
 use bevy::prelude::*;
 use bevy::reflect::TypePath;
 use bevy::render::render_asset::RenderAsset;
+use bevy::utils::HashSet;
+use std::ops::Range;
 use std::sync::Arc;
-use vello::glyph::skrifa::{FontRef, MetadataProvider};
+use unicode_bidi::{Level, ParagraphBidiInfo};
+use vello::glyph::skrifa::instance::{Location, LocationRef, Size};
+use vello::glyph::skrifa::metrics::Decoration;
+use vello::glyph::skrifa::outline::OutlineGlyphCollection;
+use vello::glyph::skrifa::{FontRef, GlyphId, MetadataProvider};
 use vello::glyph::Glyph;
-use vello::kurbo::Affine;
-use vello::peniko::{self, Blob, Brush, Color, Font};
+use vello::kurbo::{Affine, BezPath, Rect, Stroke};
+use vello::peniko::{self, Blob, Brush, Color, Fill, Font, StyleRef};
 use vello::Scene;
 
-const VARIATIONS: &[(&str, f32)] = &[];
+/// A shaped glyph of a line of text, before it is positioned.
+struct LineGlyph {
+    /// The first character of the cluster of the glyph.
+    ch: char,
+    /// The range of the cluster of the glyph in the text, in bytes.
+    index: usize,
+    end: usize,
+    id: u32,
+    advance: f32,
+    /// The offset of the glyph from the pen, with Y up.
+    offset: Vec2,
+    /// The index of the span of the glyph.
+    span: usize,
+    /// The index of the font of the glyph, in the fonts of the layout.
+    font: usize,
+    /// Whether the glyph is the first of its cluster, as lines are only
+    /// broken between clusters.
+    cluster_start: bool,
+    /// The bidi embedding level of the glyph, which is right to left if odd.
+    level: Level,
+}
+
+/// A line of glyphs in logical order, whether its paragraph is right to left,
+/// and whether it is the last line of its paragraph.
+struct Line {
+    glyphs: Vec<LineGlyph>,
+    rtl: bool,
+    last: bool,
+    /// The offset of the line in the text, in bytes.
+    index: usize,
+}
+
+impl VelloTextDirection {
+    fn level(self) -> Option<Level> {
+        match self {
+            VelloTextDirection::Auto => None,
+            VelloTextDirection::LeftToRight => Some(Level::ltr()),
+            VelloTextDirection::RightToLeft => Some(Level::rtl()),
+        }
+    }
+}
+
+/// A run of text in one style: the content of a text, or one of its
+/// sections.
+struct TextSpan<'a> {
+    font: &'a VelloFont,
+    content: &'a str,
+    size: f32,
+    brush: Option<&'a Brush>,
+    underline: bool,
+    strikethrough: bool,
+}
+
+/// A glyph of a laid out text.
+struct LayoutGlyph {
+    /// The index of the span of the glyph.
+    span: usize,
+    /// The index of the font of the glyph, in the fonts of the layout.
+    font: usize,
+    /// The range of the cluster of the glyph in the text, in bytes.
+    index: usize,
+    end: usize,
+    ch: char,
+    /// The position of the pen at the glyph, and the advance of the glyph.
+    pen_x: f32,
+    advance: f32,
+    rtl: bool,
+    line: usize,
+    glyph: Glyph,
+}
+
+/// A line of a laid out text.
+struct LayoutLine {
+    /// The offset of the line in the text, in bytes.
+    index: usize,
+    /// The position of the pen at the start and the end of the line.
+    start: f32,
+    end: f32,
+    baseline: f32,
+    /// The range of the glyphs of the line in the layout.
+    glyphs: Range<usize>,
+}
+
+/// An underline or strikethrough of consecutive glyphs of a span on a line.
+struct TextDecoration {
+    span: usize,
+    /// The top of the rule, with Y down, and its thickness.
+    top: f32,
+    thickness: f32,
+    /// The range of the decorated glyphs in the layout.
+    glyphs: Range<usize>,
+}
+
+/// The glyphs of a text laid out in lines, from the top line, and the size of
+/// their bounds.
+pub(crate) struct TextLayout {
+    /// The fonts of the spans, and their fallback fonts.
+    fonts: Vec<VelloFont>,
+    /// The glyphs of the lines, in order.
+    glyphs: Vec<LayoutGlyph>,
+    lines: Vec<LayoutLine>,
+    decorations: Vec<TextDecoration>,
+    /// The ascent of the lines, and their height.
+    ascent: f32,
+    line_height: f32,
+    width: f32,
+    height: f32,
+    /// The offset of the last line from the top line.
+    pen_y: f32,
+    /// The length of the text, in bytes.
+    len: usize,
+}
+
+impl TextLayout {
+    /// The size of the bounds of the text.
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width, self.height)
+    }
+}
+
+/// The layout of a text entity, cached until its text or one of its fonts,
+/// including the fonts of its sections and fallbacks, changes.
+#[derive(Component, Clone)]
+pub struct CachedTextLayout(pub(crate) Arc<TextLayout>);
+
+/// Lay out the texts which changed, or whose fonts did.
+pub(crate) fn cache_text_layouts(
+    mut commands: Commands,
+    texts: Query<(
+        Entity,
+        Ref<Handle<VelloFont>>,
+        Ref<VelloText>,
+        Has<CachedTextLayout>,
+    )>,
+    fonts: Res<Assets<VelloFont>>,
+    mut font_events: EventReader<AssetEvent<VelloFont>>,
+    fallback: Res<VelloFontFallback>,
+) {
+    let changed_fonts: HashSet<AssetId<VelloFont>> = font_events
+        .read()
+        .map(|event| match event {
+            AssetEvent::Added { id }
+            | AssetEvent::Modified { id }
+            | AssetEvent::Removed { id }
+            | AssetEvent::Unused { id }
+            | AssetEvent::LoadedWithDependencies { id } => *id,
+        })
+        .collect();
+    let is_changed = |font: &Handle<VelloFont>| changed_fonts.contains(&font.id());
+    // The global fallback is a font of every text
+    let fallback_changed = fallback.is_changed() || fallback.fonts.iter().any(is_changed);
+    for (entity, handle, text, cached) in texts.iter() {
+        let fonts_changed = fallback_changed
+            || is_changed(&handle)
+            || text.fallback.iter().any(is_changed)
+            || text
+                .sections
+                .iter()
+                .filter_map(|section| section.font.as_ref())
+                .any(is_changed);
+        if cached && !fonts_changed && !handle.is_changed() && !text.is_changed() {
+            continue;
+        }
+        let Some(font) = fonts.get(&*handle) else {
+            commands.entity(entity).remove::<CachedTextLayout>();
+            continue;
+        };
+        let layout = font.layout_text(&text, &fallback, |font| fonts.get(font));
+        commands
+            .entity(entity)
+            .insert(CachedTextLayout(Arc::new(layout)));
+    }
+}
 
 #[derive(Asset, TypePath, Clone)]
 pub struct VelloFont {
     pub font: Arc<peniko::Font>,
+    pub(crate) bitmaps: ColorBitmaps,
 }
 
 impl RenderAsset for VelloFont {
@@ -36,87 +217,496 @@ impl RenderAsset for VelloFont {
 
 impl VelloFont {
     pub fn new(font_data: Vec<u8>) -> Self {
+        Self::new_with_index(font_data, 0)
+    }
+
+    /// A face of a font collection, e.g. a `.ttc` file, by its index in the
+    /// collection. The index of a single font is 0.
+    pub fn new_with_index(font_data: Vec<u8>, index: u32) -> Self {
+        Self::from_blob(Blob::new(Arc::new(font_data)), index)
+    }
+
+    /// A face of font data shared with the other faces of its collection.
+    pub(crate) fn from_blob(data: Blob<u8>, index: u32) -> Self {
         Self {
-            font: Arc::new(Font::new(Blob::new(Arc::new(font_data)), 0)),
+            font: Arc::new(Font::new(data, index)),
+            bitmaps: ColorBitmaps::default(),
         }
     }
 
+    /// The number of faces of font data, 1 unless it is a collection.
+    pub(crate) fn face_count(font_data: &[u8]) -> u32 {
+        rustybuzz::ttf_parser::fonts_in_collection(font_data).unwrap_or(1)
+    }
+
+    /// The size of a text drawn with this font. Sections with another font
+    /// are measured with this font, and fallback fonts aren't used, use
+    /// [`VelloFont::sizeof_with`] to measure them with their own.
     pub fn sizeof(&self, text: &VelloText) -> Vec2 {
-        let font = FontRef::new(self.font.data.data()).expect("Vello font creation error");
-        let font_size = vello::skrifa::instance::Size::new(text.size);
-        let charmap = font.charmap();
-        let axes = font.axes();
-        let var_loc = axes.location(VARIATIONS);
-        let metrics = font.metrics(font_size, &var_loc);
-        let line_height = metrics.ascent - metrics.descent + metrics.leading;
-        let glyph_metrics = font.glyph_metrics(font_size, &var_loc);
-
-        let mut pen_x = 0.0;
-        let mut pen_y: f32 = 0.0;
-        let mut width: f32 = 0.0;
-        for ch in text.content.chars() {
-            if ch == '\n' {
-                pen_y += line_height;
-                pen_x = 0.0;
-                continue;
+        self.sizeof_with(text, &VelloFontFallback::default(), |_| None)
+    }
+
+    /// The size of a text drawn with this font, and its sections and fallback
+    /// fonts with the fonts found by their handle, e.g. in
+    /// `Assets<VelloFont>`.
+    pub fn sizeof_with<'a>(
+        &'a self,
+        text: &'a VelloText,
+        fallback: &'a VelloFontFallback,
+        fonts: impl Fn(&Handle<VelloFont>) -> Option<&'a VelloFont>,
+    ) -> Vec2 {
+        self.layout_text(text, fallback, fonts).size()
+    }
+
+    /// The layout of a text drawn with this font, e.g. to place a caret or hit
+    /// test its characters. Sections with another font are laid out with this
+    /// font, and fallback fonts aren't used, use [`VelloFont::layout_with`]
+    /// to lay them out with their own.
+    pub fn layout(&self, text: &VelloText) -> VelloTextLayout {
+        self.layout_with(text, &VelloFontFallback::default(), |_| None)
+    }
+
+    /// The layout of a text drawn with this font, and its sections and
+    /// fallback fonts with the fonts found by their handle, e.g. in
+    /// `Assets<VelloFont>`.
+    pub fn layout_with<'a>(
+        &'a self,
+        text: &'a VelloText,
+        fallback: &'a VelloFontFallback,
+        fonts: impl Fn(&Handle<VelloFont>) -> Option<&'a VelloFont>,
+    ) -> VelloTextLayout {
+        VelloTextLayout::from(&self.layout_text(text, fallback, fonts))
+    }
+
+    /// The outline of a text drawn with this font, e.g. to animate it as a
+    /// shape, in the coordinates of its [`VelloTextLayout`]. Sections with
+    /// another font are outlined with this font, and fallback fonts aren't
+    /// used, use [`VelloFont::outline_with`] to outline them with their own.
+    pub fn outline(&self, text: &VelloText) -> BezPath {
+        self.outline_with(text, &VelloFontFallback::default(), |_| None)
+    }
+
+    /// The outline of a text drawn with this font, and its sections and
+    /// fallback fonts with the fonts found by their handle.
+    pub fn outline_with<'a>(
+        &'a self,
+        text: &'a VelloText,
+        fallback: &'a VelloFontFallback,
+        fonts: impl Fn(&Handle<VelloFont>) -> Option<&'a VelloFont>,
+    ) -> BezPath {
+        let mut path = BezPath::new();
+        for outline in self.glyph_outlines_with(text, fallback, fonts) {
+            path.extend(outline);
+        }
+        path
+    }
+
+    /// The outlines of the glyphs of a text drawn with this font, e.g. to
+    /// animate them apart, in the order and coordinates of the glyphs of its
+    /// [`VelloTextLayout`].
+    pub fn glyph_outlines(&self, text: &VelloText) -> Vec<BezPath> {
+        self.glyph_outlines_with(text, &VelloFontFallback::default(), |_| None)
+    }
+
+    /// The outlines of the glyphs of a text drawn with this font, and its
+    /// sections and fallback fonts with the fonts found by their handle.
+    pub fn glyph_outlines_with<'a>(
+        &'a self,
+        text: &'a VelloText,
+        fallback: &'a VelloFontFallback,
+        fonts: impl Fn(&Handle<VelloFont>) -> Option<&'a VelloFont>,
+    ) -> Vec<BezPath> {
+        let spans = self.spans(text, &fonts);
+        let layout = self.layout_text(text, fallback, fonts);
+        let font_refs: Vec<FontRef> = layout.fonts.iter().map(font_ref).collect();
+        let outlines: Vec<_> = font_refs.iter().map(|font| font.outline_glyphs()).collect();
+        let locations: Vec<_> = font_refs
+            .iter()
+            .map(|font| font.axes().location(variations(text)))
+            .collect();
+        // The glyphs are laid out from the first baseline
+        let top = Affine::translate((0.0, (layout.height - layout.pen_y) as f64));
+        layout
+            .glyphs
+            .iter()
+            .map(|glyph| {
+                let size = spans[glyph.span].size;
+                let (outlines, location) = (&outlines[glyph.font], &locations[glyph.font]);
+                top * glyph_outline(outlines, size, location, &glyph.glyph)
+            })
+            .collect()
+    }
+
+    /// The spans of a text and its sections, with the font and style of each.
+    fn spans<'a>(
+        &'a self,
+        text: &'a VelloText,
+        fonts: impl Fn(&Handle<VelloFont>) -> Option<&'a VelloFont>,
+    ) -> Vec<TextSpan<'a>> {
+        let mut spans = vec![TextSpan {
+            font: self,
+            content: &text.content,
+            size: text.size,
+            brush: text.brush.as_ref(),
+            underline: text.underline,
+            strikethrough: text.strikethrough,
+        }];
+        spans.extend(text.sections.iter().map(|section| TextSpan {
+            font: section.font.as_ref().and_then(&fonts).unwrap_or(self),
+            content: &section.content,
+            size: section.size.unwrap_or(text.size),
+            brush: section.brush.as_ref().or(text.brush.as_ref()),
+            underline: section.underline.unwrap_or(text.underline),
+            strikethrough: section.strikethrough.unwrap_or(text.strikethrough),
+        }));
+        spans
+    }
+
+    /// Lay out the glyphs of a text and its sections in lines, wrapping words
+    /// which overflow its bounds. Sections whose font isn't found are laid out
+    /// with this font, and the characters missing from the font of a section
+    /// with the first fallback font which has them.
+    pub(crate) fn layout_text<'a>(
+        &'a self,
+        text: &'a VelloText,
+        fallback: &'a VelloFontFallback,
+        fonts: impl Fn(&Handle<VelloFont>) -> Option<&'a VelloFont>,
+    ) -> TextLayout {
+        let spans = self.spans(text, &fonts);
+        let span_fonts: Vec<FontRef> = spans.iter().map(|span| font_ref(span.font)).collect();
+        // Lines are as tall as the tallest span
+        let mut line_height: f32 = 0.0;
+        let mut ascent: f32 = 0.0;
+        let mut cap_height: f32 = 0.0;
+        // The decorations of each span, from the font of the span or, for
+        // fonts without them, from its size
+        let mut span_decorations = Vec::new();
+        for (span, font) in spans.iter().zip(&span_fonts) {
+            let font_size = vello::skrifa::instance::Size::new(span.size);
+            let metrics = font.metrics(font_size, &font.axes().location(variations(text)));
+            let span_line_height = metrics.ascent - metrics.descent + metrics.leading;
+            line_height = line_height.max(span_line_height);
+            ascent = ascent.max(metrics.ascent);
+            cap_height = cap_height.max(metrics.cap_height.unwrap_or(span_line_height));
+            let valid = |decoration: &Decoration| decoration.thickness > 0.0;
+            let thickness = span.size / 16.0;
+            let underline = metrics.underline.filter(valid).unwrap_or(Decoration {
+                offset: -span.size / 10.0,
+                thickness,
+            });
+            let strikethrough = metrics.strikeout.filter(valid).unwrap_or(Decoration {
+                offset: metrics.x_height.unwrap_or(span.size / 2.0) / 2.0 + thickness / 2.0,
+                thickness,
+            });
+            span_decorations.push(
+                [
+                    span.underline.then_some(underline),
+                    span.strikethrough.then_some(strikethrough),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>(),
+            );
+        }
+        line_height *= text.line_height.unwrap_or(1.0);
+        // The fonts of the spans, each followed by the fallback fonts
+        let fallback_fonts: Vec<&VelloFont> = text
+            .fallback
+            .iter()
+            .chain(&fallback.fonts)
+            .filter_map(&fonts)
+            .collect();
+        let mut layout_fonts: Vec<&VelloFont> = Vec::new();
+        let chains: Vec<Vec<usize>> = spans
+            .iter()
+            .map(|span| {
+                let mut chain = Vec::new();
+                for font in std::iter::once(span.font).chain(fallback_fonts.iter().copied()) {
+                    let index = match layout_fonts
+                        .iter()
+                        .position(|other| Arc::ptr_eq(&other.font, &font.font))
+                    {
+                        Some(index) => index,
+                        None => {
+                            layout_fonts.push(font);
+                            layout_fonts.len() - 1
+                        }
+                    };
+                    if !chain.contains(&index) {
+                        chain.push(index);
+                    }
+                }
+                chain
+            })
+            .collect();
+        let faces: Vec<rustybuzz::Face> = layout_fonts
+            .iter()
+            .map(|font| shaping_face(font, text))
+            .collect();
+        // Runs are shaped with the offset of their text in the whole text
+        let shape =
+            |span: usize, run: &str, index: usize, level: Level, glyphs: &mut Vec<LineGlyph>| {
+                let start = glyphs.len();
+                let mut previous_cluster = None;
+                for glyph in shape_run(&faces, &chains[span], spans[span].size, run, level) {
+                    glyphs.push(LineGlyph {
+                        ch: run[glyph.cluster..].chars().next().unwrap_or_default(),
+                        index: index + glyph.cluster,
+                        end: index + run.len(),
+                        id: glyph.id,
+                        advance: glyph.advance,
+                        offset: glyph.offset,
+                        span,
+                        font: glyph.font,
+                        cluster_start: previous_cluster != Some(glyph.cluster),
+                        level,
+                    });
+                    previous_cluster = Some(glyph.cluster);
+                }
+                // Clusters end where the next cluster starts
+                let shaped = &mut glyphs[start..];
+                let mut end = index + run.len();
+                for glyph in shaped.iter_mut().rev() {
+                    glyph.end = end;
+                    if glyph.cluster_start {
+                        end = glyph.index;
+                    }
+                }
+                // Spacing is added after every cluster, and after spaces
+                for i in 0..shaped.len() {
+                    if shaped.get(i + 1).map_or(true, |next| next.cluster_start) {
+                        shaped[i].advance += text.letter_spacing;
+                        if shaped[i].ch == ' ' {
+                            shaped[i].advance += text.word_spacing;
+                        }
+                    }
+                }
+            };
+        // The text of every paragraph, which flows across spans, and the
+        // ranges of its spans, with their offsets in the whole text
+        let mut paragraph_texts: Vec<(String, Vec<(usize, Range<usize>, usize)>)> =
+            vec![Default::default()];
+        let mut index = 0;
+        for (i, span) in spans.iter().enumerate() {
+            for (j, piece) in span.content.split('\n').enumerate() {
+                if j > 0 {
+                    paragraph_texts.push(Default::default());
+                    index += 1;
+                }
+                let (paragraph, ranges) = paragraph_texts.last_mut().unwrap();
+                let start = paragraph.len();
+                paragraph.push_str(piece);
+                ranges.push((i, start..paragraph.len(), index));
+                index += piece.len();
+            }
+        }
+        // The glyphs of every paragraph, shaped in runs of one span and
+        // direction, and whether the paragraph is right to left
+        let mut paragraphs: Vec<(Vec<LineGlyph>, bool, usize)> = Vec::new();
+        for (paragraph, ranges) in &paragraph_texts {
+            let bidi = ParagraphBidiInfo::new(paragraph, text.direction.level());
+            let mut glyphs = Vec::new();
+            for (span, range, index) in ranges {
+                let mut start = range.start;
+                while start < range.end {
+                    let level = bidi.levels[start];
+                    let end = (start..range.end)
+                        .find(|&i| bidi.levels[i] != level)
+                        .unwrap_or(range.end);
+                    let index = index + start - range.start;
+                    shape(*span, &paragraph[start..end], index, level, &mut glyphs);
+                    start = end;
+                }
             }
-            let gid = charmap.map(ch).unwrap_or_default();
-            let advance = glyph_metrics.advance_width(gid).unwrap_or_default();
+            let index = ranges.first().map_or(0, |(_, _, index)| *index);
+            paragraphs.push((glyphs, bidi.paragraph_level.is_rtl(), index));
+        }
+
+        let max_width = text.bounds.map_or(f32::INFINITY, |bounds| bounds.x);
+        let max_lines = text.bounds.map_or(usize::MAX, |bounds| {
+            ((bounds.y - cap_height) / line_height).floor().max(0.0) as usize + 1
+        });
+        let mut lines: Vec<Line> = Vec::new();
+        for (paragraph, rtl, index) in paragraphs {
+            let mut line: Vec<LineGlyph> = Vec::new();
+            let mut line_width = 0.0;
+            let mut wrap = |line: &mut Vec<LineGlyph>, line_width: &mut f32| {
+                // Spaces at the end of wrapped lines are dropped
+                while line.last().is_some_and(|glyph| glyph.ch == ' ') {
+                    line.pop();
+                }
+                lines.push(Line {
+                    index: line.first().map_or(index, |glyph| glyph.index),
+                    glyphs: std::mem::take(line),
+                    rtl,
+                    last: false,
+                });
+                *line_width = 0.0;
+            };
+            let mut glyphs = paragraph.into_iter().peekable();
+            while glyphs.peek().is_some() {
+                // A word and the spaces after it
+                let mut word = Vec::new();
+                while let Some(glyph) = glyphs.next_if(|glyph| glyph.ch != ' ') {
+                    word.push(glyph);
+                }
+                let word_width: f32 = word.iter().map(|glyph| glyph.advance).sum();
+                while let Some(glyph) = glyphs.next_if(|glyph| glyph.ch == ' ') {
+                    word.push(glyph);
+                }
+                if !line.is_empty() && line_width + word_width > max_width {
+                    wrap(&mut line, &mut line_width);
+                }
+                // Words wider than the bounds are broken between characters
+                let break_word = line.is_empty() && word_width > max_width;
+                for glyph in word {
+                    if break_word
+                        && glyph.ch != ' '
+                        && glyph.cluster_start
+                        && !line.is_empty()
+                        && line_width + glyph.advance > max_width
+                    {
+                        wrap(&mut line, &mut line_width);
+                    }
+                    line_width += glyph.advance;
+                    line.push(glyph);
+                }
+            }
+            lines.push(Line {
+                index: line.first().map_or(index, |glyph| glyph.index),
+                glyphs: line,
+                rtl,
+                last: true,
+            });
+        }
+        lines.truncate(max_lines);
 
-            pen_x += advance;
-            width = width.max(pen_x);
+        for line in &mut lines {
+            reorder(&mut line.glyphs);
+        }
+        let line_width = |line: &Line| line.glyphs.iter().map(|glyph| glyph.advance).sum::<f32>();
+        let width = lines.iter().map(line_width).fold(0.0, f32::max);
+        let mut glyphs = Vec::new();
+        let mut layout_lines = Vec::new();
+        let mut decorations = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let free = width - line_width(line);
+            let spaces = line.glyphs.iter().filter(|glyph| glyph.ch == ' ').count();
+            // Lines are aligned to the start of their direction, unless
+            // another alignment is set
+            let start = match line.rtl {
+                true => free,
+                false => 0.0,
+            };
+            let (mut pen_x, justify) = match text.align {
+                VelloTextAlign::Start => (start, 0.0),
+                VelloTextAlign::Left => (0.0, 0.0),
+                VelloTextAlign::Center => (free / 2.0, 0.0),
+                VelloTextAlign::Right => (free, 0.0),
+                // The last lines of paragraphs aren't justified
+                VelloTextAlign::Justify if line.last || spaces == 0 => (start, 0.0),
+                VelloTextAlign::Justify => (0.0, free / spaces as f32),
+            };
+            let baseline = i as f32 * line_height;
+            let line_start = (pen_x, glyphs.len());
+            // Consecutive glyphs of a span are decorated together
+            let mut decorated: Option<(usize, usize)> = None;
+            let mut decorate = |decorated: Option<(usize, usize)>, end: usize| {
+                if let Some((span, start)) = decorated {
+                    for decoration in &span_decorations[span] {
+                        decorations.push(TextDecoration {
+                            span,
+                            top: baseline - decoration.offset,
+                            thickness: decoration.thickness,
+                            glyphs: start..end,
+                        });
+                    }
+                }
+            };
+            for glyph in &line.glyphs {
+                if decorated.is_some_and(|(span, _)| span != glyph.span) {
+                    decorate(decorated.take(), glyphs.len());
+                }
+                if !span_decorations[glyph.span].is_empty() && decorated.is_none() {
+                    decorated = Some((glyph.span, glyphs.len()));
+                }
+                glyphs.push(LayoutGlyph {
+                    span: glyph.span,
+                    font: glyph.font,
+                    index: glyph.index,
+                    end: glyph.end,
+                    ch: glyph.ch,
+                    pen_x,
+                    advance: glyph.advance,
+                    rtl: glyph.level.is_rtl(),
+                    line: i,
+                    glyph: Glyph {
+                        id: glyph.id,
+                        x: pen_x + glyph.offset.x,
+                        y: baseline - glyph.offset.y,
+                    },
+                });
+                pen_x += glyph.advance;
+                if glyph.ch == ' ' {
+                    pen_x += justify;
+                }
+            }
+            decorate(decorated, glyphs.len());
+            layout_lines.push(LayoutLine {
+                index: line.index,
+                start: line_start.0,
+                end: pen_x,
+                baseline,
+                glyphs: line_start.1..glyphs.len(),
+            });
+        }
+        let pen_y = lines.len().saturating_sub(1) as f32 * line_height;
+        TextLayout {
+            fonts: layout_fonts.into_iter().cloned().collect(),
+            glyphs,
+            lines: layout_lines,
+            decorations,
+            ascent,
+            line_height,
+            width,
+            height: cap_height + pen_y,
+            pen_y,
+            len: spans.iter().map(|span| span.content.len()).sum(),
         }
-        let height: f32 = metrics.cap_height.unwrap_or(line_height) + pen_y;
-        Vec2::new(width, height)
     }
 
+    /// Draw a text from its layout by this font.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn render(
         &self,
         scene: &mut Scene,
         mut transform: Affine,
         text: &VelloText,
         alignment: VelloTextAlignment,
+        reveal: Option<&VelloTextReveal>,
+        selection: Option<&VelloTextSelection>,
+        layout: &TextLayout,
     ) {
-        let font = FontRef::new(self.font.data.data()).expect("Vello font creation error");
-
-        let font_size = vello::skrifa::instance::Size::new(text.size);
-        let charmap = font.charmap();
-        let axes = font.axes();
-        let var_loc = axes.location(VARIATIONS);
-        let metrics = font.metrics(font_size, &var_loc);
-        let line_height = metrics.ascent - metrics.descent + metrics.leading;
-        let glyph_metrics = font.glyph_metrics(font_size, &var_loc);
-
-        let mut pen_x = 0f32;
-        let mut pen_y = 0f32;
-        let mut width = 0f32;
-        let glyphs: Vec<Glyph> = text
-            .content
-            .chars()
-            .filter_map(|ch| {
-                if ch == '\n' {
-                    pen_y += line_height;
-                    pen_x = 0.0;
-                    return None;
-                }
-                let gid = charmap.map(ch).unwrap_or_default();
-                let advance = glyph_metrics.advance_width(gid).unwrap_or_default();
-                let x = pen_x;
-                pen_x += advance;
-                width = width.max(pen_x);
-                Some(Glyph {
-                    id: gid.to_u16() as u32,
-                    x,
-                    y: pen_y,
-                })
-            })
-            .collect();
+        // The fonts of the layout are only needed by their size and brush
+        let spans = self.spans(text, |_| None);
+        let selection = selection.map(|selection| (selection, VelloTextLayout::from(layout)));
+        let TextLayout {
+            fonts,
+            glyphs,
+            decorations,
+            width,
+            height,
+            pen_y,
+            ..
+        } = layout;
+        let (width, height, pen_y) = (*width, *height, *pen_y);
+
         // Push up from pen_y
         transform *= vello::kurbo::Affine::translate((0.0, -pen_y as f64));
 
         // Alignment settings
         let width = width as f64;
-        let height = (metrics.cap_height.unwrap_or(line_height) + pen_y) as f64;
+        let height = height as f64;
         match alignment {
             VelloTextAlignment::TopLeft => {
                 transform *= vello::kurbo::Affine::translate((0.0, height));
@@ -147,12 +737,420 @@ impl VelloFont {
             }
         }
 
-        scene
-            .draw_glyphs(&self.font)
-            .font_size(text.size)
-            .transform(transform)
-            .normalized_coords(var_loc.coords())
-            .brush(&text.brush.clone().unwrap_or(Brush::Solid(Color::WHITE)))
-            .draw(vello::peniko::Fill::EvenOdd, glyphs.into_iter());
+        // Only the glyphs of the revealed characters are drawn
+        let revealed = reveal.map_or(usize::MAX, |reveal| {
+            let chars = spans.iter().flat_map(|span| span.content.chars());
+            let count = (chars.clone().count() as f32 * reveal.progress.clamp(0.0, 1.0)) as usize;
+            chars.take(count).map(char::len_utf8).sum()
+        });
+        let is_revealed = |glyph: &&LayoutGlyph| glyph.index < revealed;
+        // The decorations of the revealed glyphs which aren't spaces
+        let decorations: Vec<(usize, Rect)> = decorations
+            .iter()
+            .filter_map(|decoration| {
+                let (start, end) = glyphs[decoration.glyphs.clone()]
+                    .iter()
+                    .filter(is_revealed)
+                    .filter(|glyph| glyph.ch != ' ')
+                    .fold((f32::INFINITY, f32::NEG_INFINITY), |(start, end), glyph| {
+                        (start.min(glyph.pen_x), end.max(glyph.pen_x + glyph.advance))
+                    });
+                let top = decoration.top as f64;
+                let bottom = top + decoration.thickness as f64;
+                (end > start).then(|| {
+                    let rect = Rect::new(start as f64, top, end as f64, bottom);
+                    (decoration.span, rect)
+                })
+            })
+            .collect();
+
+        // The consecutive glyphs of each span and font are drawn together
+        let mut glyphs = glyphs.iter().filter(is_revealed).peekable();
+        let mut runs = Vec::new();
+        while let Some(&&LayoutGlyph {
+            span: span_index,
+            font: font_index,
+            ..
+        }) = glyphs.peek()
+        {
+            let run: Vec<Glyph> = std::iter::from_fn(|| {
+                glyphs.next_if(|glyph| (glyph.span, glyph.font) == (span_index, font_index))
+            })
+            .map(|glyph| glyph.glyph)
+            .collect();
+            let vello_font = &fonts[font_index];
+            let font = font_ref(vello_font);
+            let color_glyphs = ColorGlyphs::new(vello_font, &font);
+            runs.push((&spans[span_index], vello_font, font, color_glyphs, run));
+        }
+        let is_color = |color_glyphs: &Option<ColorGlyphs>, glyph: &Glyph| {
+            color_glyphs
+                .as_ref()
+                .is_some_and(|color| color.contains(glyph.id))
+        };
+
+        // Brushes other than solid colors are relative to the bounds of the
+        // text, from (0, 0) at their top left to (1, 1) at their bottom right
+        let bounds = Affine::translate((0.0, pen_y as f64 - height))
+            * Affine::scale_non_uniform(width, height);
+        let brush_transform = |brush: &Brush| match brush {
+            Brush::Solid(_) => None,
+            Brush::Gradient(_) => Some(bounds),
+            Brush::Image(image) => Some(
+                bounds
+                    * Affine::scale_non_uniform(
+                        1.0 / image.width as f64,
+                        1.0 / image.height as f64,
+                    ),
+            ),
+        };
+        // The brushes of glyph runs are relative to their last glyph, so the
+        // outlines of glyphs are drawn as paths for those of the bounds
+        let draw = |scene: &mut Scene,
+                    span: &TextSpan,
+                    vello_font: &VelloFont,
+                    font: &FontRef,
+                    brush: &Brush,
+                    style: StyleRef,
+                    glyphs: Vec<Glyph>| {
+            let var_loc = font.axes().location(variations(text));
+            let Some(brush_transform) = brush_transform(brush) else {
+                scene
+                    .draw_glyphs(&vello_font.font)
+                    .font_size(span.size)
+                    .transform(transform)
+                    .normalized_coords(var_loc.coords())
+                    .brush(brush)
+                    .draw(style, glyphs.into_iter());
+                return;
+            };
+            let outlines = font.outline_glyphs();
+            let mut path = BezPath::new();
+            for glyph in glyphs {
+                path.extend(glyph_outline(&outlines, span.size, &var_loc, &glyph));
+            }
+            match style {
+                StyleRef::Fill(fill) => {
+                    scene.fill(fill, transform, brush, Some(brush_transform), &path);
+                }
+                StyleRef::Stroke(stroke) => {
+                    scene.stroke(stroke, transform, brush, Some(brush_transform), &path);
+                }
+            }
+        };
+
+        // The selection and caret are positioned from the top of the bounds
+        let layout_transform = transform * Affine::translate((0.0, pen_y as f64 - height));
+        let to_rect = |rect: bevy::math::Rect| {
+            Rect::new(
+                rect.min.x as f64,
+                rect.min.y as f64,
+                rect.max.x as f64,
+                rect.max.y as f64,
+            )
+        };
+        if let Some((selection, layout)) = &selection {
+            let brush_transform = brush_transform(&selection.brush);
+            for rect in layout.selection(selection.range.clone()) {
+                let rect = to_rect(rect);
+                scene.fill(
+                    Fill::NonZero,
+                    layout_transform,
+                    &selection.brush,
+                    brush_transform,
+                    &rect,
+                );
+            }
+        }
+
+        // Outlines are drawn behind the glyphs of every run
+        if let Some(outline) = &text.outline {
+            let stroke = Stroke::new(outline.width as f64);
+            for (span, vello_font, font, color_glyphs, run) in &runs {
+                let outlined = run
+                    .iter()
+                    .filter(|glyph| !is_color(color_glyphs, glyph))
+                    .copied()
+                    .collect();
+                let style = StyleRef::Stroke(&stroke);
+                draw(
+                    scene,
+                    span,
+                    vello_font,
+                    font,
+                    &outline.brush,
+                    style,
+                    outlined,
+                );
+            }
+            let brush_transform = brush_transform(&outline.brush);
+            for (_, rect) in &decorations {
+                scene.stroke(&stroke, transform, &outline.brush, brush_transform, rect);
+            }
+        }
+
+        for (span, vello_font, font, color_glyphs, run) in runs {
+            let var_loc = font.axes().location(variations(text));
+            let brush = span.brush.cloned().unwrap_or(Brush::Solid(Color::WHITE));
+            let mut run = run.into_iter().peekable();
+            while run.peek().is_some() {
+                // Color glyphs are drawn in their own colors, one by one
+                while let Some(glyph) = run.next_if(|glyph| is_color(&color_glyphs, glyph)) {
+                    if let Some(color_glyphs) = &color_glyphs {
+                        let location = LocationRef::new(var_loc.coords());
+                        color_glyphs.draw(
+                            scene,
+                            transform,
+                            &glyph,
+                            span.size,
+                            location,
+                            &brush,
+                            brush_transform(&brush),
+                        );
+                    }
+                }
+                let outlined: Vec<Glyph> =
+                    std::iter::from_fn(|| run.next_if(|glyph| !is_color(&color_glyphs, glyph)))
+                        .collect();
+                if outlined.is_empty() {
+                    continue;
+                }
+                let style = StyleRef::Fill(Fill::EvenOdd);
+                draw(scene, span, vello_font, &font, &brush, style, outlined);
+            }
+        }
+
+        for (span, rect) in decorations {
+            let brush = spans[span]
+                .brush
+                .cloned()
+                .unwrap_or(Brush::Solid(Color::WHITE));
+            scene.fill(
+                Fill::NonZero,
+                transform,
+                &brush,
+                brush_transform(&brush),
+                &rect,
+            );
+        }
+
+        if let Some((selection, layout)) = &selection {
+            if let Some(index) = selection.caret_index.filter(|_| selection.caret_shown) {
+                let caret = layout.caret(index);
+                let half_width = selection.caret_width as f64 / 2.0;
+                let rect = to_rect(caret).inflate(half_width, 0.0);
+                let brush = &selection.caret_brush;
+                scene.fill(
+                    Fill::NonZero,
+                    layout_transform,
+                    brush,
+                    brush_transform(brush),
+                    &rect,
+                );
+            }
+        }
+    }
+}
+
+impl From<&TextLayout> for VelloTextLayout {
+    fn from(layout: &TextLayout) -> Self {
+        // The top of the bounds is the cap height above the first baseline
+        let top = layout.height - layout.pen_y;
+        let glyphs = layout
+            .glyphs
+            .iter()
+            .map(|glyph| VelloTextGlyph {
+                id: glyph.glyph.id,
+                range: glyph.index..glyph.end,
+                position: Vec2::new(glyph.pen_x, top + glyph.glyph.y),
+                advance: glyph.advance,
+                rtl: glyph.rtl,
+                line: glyph.line,
+            })
+            .collect();
+        let lines = layout
+            .lines
+            .iter()
+            .map(|line| {
+                let baseline = top + line.baseline;
+                let line_top = baseline - layout.ascent;
+                VelloTextLine {
+                    index: line.index,
+                    rect: bevy::math::Rect::new(
+                        line.start,
+                        line_top,
+                        line.end,
+                        line_top + layout.line_height,
+                    ),
+                    baseline,
+                    glyphs: line.glyphs.clone(),
+                }
+            })
+            .collect();
+        VelloTextLayout {
+            size: Vec2::new(layout.width, layout.height),
+            lines,
+            glyphs,
+            len: layout.len,
+        }
+    }
+}
+
+/// The outline of a glyph at its position, with Y down.
+fn glyph_outline(
+    outlines: &OutlineGlyphCollection,
+    size: f32,
+    location: &Location,
+    glyph: &Glyph,
+) -> BezPath {
+    let mut pen = BezPathPen(BezPath::new());
+    if let Some(outline) = outlines.get(GlyphId::new(glyph.id as u16)) {
+        let location = LocationRef::new(location.coords());
+        let _ = outline.draw((Size::new(size), location), &mut pen);
     }
+    Affine::new([1.0, 0.0, 0.0, -1.0, glyph.x as f64, glyph.y as f64]) * pen.0
+}
+
+/// A glyph shaped by a font of a chain.
+#[derive(Clone, Copy)]
+struct ShapedGlyph {
+    /// The index of the font of the glyph, in the shaped faces.
+    font: usize,
+    id: u32,
+    advance: f32,
+    offset: Vec2,
+    /// The offset of the cluster of the glyph in the shaped run.
+    cluster: usize,
+}
+
+/// Shape a run of text in one direction with the first font of a chain, so
+/// its glyphs are kerned and form ligatures, and the clusters it has no glyphs
+/// for with the next fonts. The glyphs are returned in logical order.
+fn shape_run(
+    faces: &[rustybuzz::Face],
+    chain: &[usize],
+    size: f32,
+    run: &str,
+    level: Level,
+) -> Vec<ShapedGlyph> {
+    let font = chain[0];
+    let face = &faces[font];
+    let scale = size / face.units_per_em() as f32;
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(run);
+    buffer.guess_segment_properties();
+    buffer.set_direction(match level.is_rtl() {
+        true => rustybuzz::Direction::RightToLeft,
+        false => rustybuzz::Direction::LeftToRight,
+    });
+    let output = rustybuzz::shape(face, &[], buffer);
+    let mut glyphs: Vec<_> = output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, position)| ShapedGlyph {
+            font,
+            id: info.glyph_id,
+            advance: position.x_advance as f32 * scale,
+            offset: Vec2::new(position.x_offset as f32, position.y_offset as f32) * scale,
+            cluster: info.cluster as usize,
+        })
+        .collect();
+    // Right-to-left runs are shaped in visual order
+    if level.is_rtl() {
+        glyphs.reverse();
+    }
+    let fallback = &chain[1..];
+    if fallback.is_empty() || glyphs.iter().all(|glyph| glyph.id != 0) {
+        return glyphs;
+    }
+
+    // The end of the cluster starting at a glyph
+    let cluster_end = |start: usize| {
+        start
+            + glyphs[start..]
+                .iter()
+                .take_while(|glyph| glyph.cluster == glyphs[start].cluster)
+                .count()
+    };
+    let is_missing =
+        |start: usize, end: usize| glyphs[start..end].iter().any(|glyph| glyph.id == 0);
+    let mut shaped = Vec::with_capacity(glyphs.len());
+    let mut start = 0;
+    while start < glyphs.len() {
+        let mut end = cluster_end(start);
+        if !is_missing(start, end) {
+            shaped.extend_from_slice(&glyphs[start..end]);
+            start = end;
+            continue;
+        }
+        // Consecutive missing clusters are shaped together
+        while end < glyphs.len() && is_missing(end, cluster_end(end)) {
+            end = cluster_end(end);
+        }
+        let range = glyphs[start].cluster..glyphs.get(end).map_or(run.len(), |glyph| glyph.cluster);
+        shaped.extend(
+            shape_run(faces, fallback, size, &run[range.clone()], level)
+                .into_iter()
+                .map(|glyph| ShapedGlyph {
+                    cluster: range.start + glyph.cluster,
+                    ..glyph
+                }),
+        );
+        start = end;
+    }
+    shaped
+}
+
+/// Reorder a line of glyphs from logical to visual order, by reversing every
+/// run at or above each odd bidi level, from the highest.
+fn reorder(line: &mut [LineGlyph]) {
+    let levels = line.iter().map(|glyph| glyph.level.number());
+    let (Some(lowest_odd), Some(highest)) = (
+        levels.clone().filter(|level| level % 2 == 1).min(),
+        levels.max(),
+    ) else {
+        return;
+    };
+    for level in (lowest_odd..=highest).rev() {
+        let mut start = 0;
+        while start < line.len() {
+            if line[start].level.number() < level {
+                start += 1;
+                continue;
+            }
+            let end = line[start..]
+                .iter()
+                .position(|glyph| glyph.level.number() < level)
+                .map_or(line.len(), |len| start + len);
+            line[start..end].reverse();
+            start = end;
+        }
+    }
+}
+
+/// The font of a font file, or of a collection at its index.
+fn font_ref(font: &VelloFont) -> FontRef<'_> {
+    FontRef::from_index(font.font.data.data(), font.font.index).expect("Vello font creation error")
+}
+
+/// The variation axes of a text, by tag.
+fn variations(text: &VelloText) -> impl Iterator<Item = (&str, f32)> {
+    text.variations
+        .iter()
+        .map(|(tag, value)| (tag.as_str(), *value))
+}
+
+/// The face of a font for shaping, at the variation axes of a text.
+fn shaping_face<'a>(font: &'a VelloFont, text: &VelloText) -> rustybuzz::Face<'a> {
+    let mut face = rustybuzz::Face::from_slice(font.font.data.data(), font.font.index)
+        .expect("Vello font creation error");
+    let variations: Vec<_> = variations(text)
+        .map(|(tag, value)| rustybuzz::Variation {
+            tag: rustybuzz::ttf_parser::Tag::from_bytes_lossy(tag.as_bytes()),
+            value,
+        })
+        .collect();
+    face.set_variations(&variations);
+    face
 }
@@ -3,7 +3,14 @@ use crate::integrations::VectorLoaderError;
 use bevy::asset::io::Reader;
 use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
 use bevy::utils::BoxedFuture;
+use std::sync::Arc;
+use vello::peniko::Blob;
 
+/// Loads `.ttf` fonts and `.ttc` font collections.
+///
+/// Every face of a collection is a labeled sub-asset, `faces/<index>`, e.g.
+/// `asset_server.load("fonts/NotoSansCJK.ttc#faces/2")`, and the asset of
+/// the collection is its first face.
 #[derive(Default)]
 pub struct VelloFontLoader;
 
@@ -18,18 +25,29 @@ impl AssetLoader for VelloFontLoader {
         &'a self,
         reader: &'a mut Reader,
         _settings: &'a Self::Settings,
-        _load_context: &'a mut LoadContext,
+        load_context: &'a mut LoadContext,
     ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
         Box::pin(async move {
             let mut bytes = Vec::new();
             reader.read_to_end(&mut bytes).await?;
-            let vello_font = VelloFont::new(bytes.to_vec());
+            let face_count = VelloFont::face_count(&bytes);
+            // The faces share the data of the collection
+            let data = Blob::new(Arc::new(bytes));
+            if face_count > 1 {
+                for index in 0..face_count {
+                    load_context.add_labeled_asset(
+                        format!("faces/{index}"),
+                        VelloFont::from_blob(data.clone(), index),
+                    );
+                }
+            }
+            let vello_font = VelloFont::from_blob(data, 0);
 
             Ok(vello_font)
         })
     }
 
     fn extensions(&self) -> &[&str] {
-        &["ttf"]
+        &["ttf", "ttc"]
     }
 }
@@ -0,0 +1,156 @@
+//! The layout of texts in lines and glyphs, e.g. to place a caret or hit test
+//! the characters of an editable text.
+
+use bevy::prelude::*;
+use std::ops::Range;
+
+/// The layout of a [`VelloText`](super::VelloText), from
+/// [`VelloFont::layout`](super::VelloFont::layout).
+///
+/// Positions are relative to the top left corner of the bounds of the text,
+/// with Y down, so a position is at
+/// `alignment.offset(layout.size) + Vec2::new(x, layout.size.y - y)` in the
+/// local space of a text entity. Indices are offsets in bytes into the text:
+/// its content followed by the contents of its sections.
+#[derive(Clone, Debug, Default)]
+pub struct VelloTextLayout {
+    /// The size of the bounds of the text.
+    pub size: Vec2,
+    pub lines: Vec<VelloTextLine>,
+    /// The glyphs of the lines, from the top line, and from the left of each
+    /// line.
+    pub glyphs: Vec<VelloTextGlyph>,
+    /// The length of the text, in bytes.
+    pub len: usize,
+}
+
+/// A line of a [`VelloTextLayout`].
+#[derive(Clone, Debug)]
+pub struct VelloTextLine {
+    /// The index of the first character of the line.
+    pub index: usize,
+    /// The box of the line, from the left of its first glyph to the right of
+    /// its last, and from its ascent down by the height of a line.
+    pub rect: Rect,
+    pub baseline: f32,
+    /// The range of the glyphs of the line in [`VelloTextLayout::glyphs`].
+    pub glyphs: Range<usize>,
+}
+
+/// A glyph of a [`VelloTextLayout`].
+#[derive(Clone, Debug)]
+pub struct VelloTextGlyph {
+    pub id: u32,
+    /// The range of the characters the glyph is shaped from, its cluster. The
+    /// glyphs of a cluster, e.g. a letter and its accent, share the range.
+    pub range: Range<usize>,
+    /// The position of the pen at the left of the glyph, on its baseline.
+    pub position: Vec2,
+    pub advance: f32,
+    /// Whether the glyph is right to left, so its cluster starts at its right.
+    pub rtl: bool,
+    /// The index of the line of the glyph.
+    pub line: usize,
+}
+
+impl VelloTextLayout {
+    /// The caret at an index, as a box of no width from the top to the
+    /// bottom of its line. Carets are at the start of the character at the
+    /// index, or at the end of the character before it.
+    pub fn caret(&self, index: usize) -> Rect {
+        let edge = self
+            .edge(|glyph| glyph.range.start == index, true)
+            .or_else(|| {
+                // Empty lines have no glyphs to place the caret by
+                let line = self
+                    .lines
+                    .iter()
+                    .position(|line| line.index == index && line.glyphs.is_empty())?;
+                Some((line, self.lines[line].rect.min.x))
+            })
+            .or_else(|| self.edge(|glyph| glyph.range.end == index, false))
+            .or_else(|| {
+                let before = self
+                    .glyphs
+                    .iter()
+                    .map(|glyph| glyph.range.start)
+                    .filter(|start| *start < index)
+                    .max()?;
+                self.edge(|glyph| glyph.range.start == before, false)
+            });
+        let Some((line, x)) = edge.or_else(|| Some((0, self.lines.first()?.rect.min.x))) else {
+            return Rect::default();
+        };
+        let rect = self.lines[line].rect;
+        Rect::new(x, rect.min.y, x, rect.max.y)
+    }
+
+    /// The boxes of the selected characters of a range, one for each run of
+    /// consecutive selected glyphs of a line, from the top to the bottom of
+    /// the line.
+    pub fn selection(&self, range: Range<usize>) -> Vec<Rect> {
+        let mut rects = Vec::new();
+        for line in &self.lines {
+            let mut rect: Option<Rect> = None;
+            for glyph in &self.glyphs[line.glyphs.clone()] {
+                if !range.contains(&glyph.range.start) {
+                    rects.extend(rect.take());
+                    continue;
+                }
+                let (left, right) = (glyph.position.x, glyph.position.x + glyph.advance);
+                let rect =
+                    rect.get_or_insert(Rect::new(left, line.rect.min.y, left, line.rect.max.y));
+                rect.max.x = rect.max.x.max(right);
+            }
+            rects.extend(rect);
+        }
+        rects
+    }
+
+    /// The index of the caret nearest a point, e.g. where a text is clicked.
+    pub fn hit_test(&self, point: Vec2) -> usize {
+        let distance = |min: f32, max: f32, x: f32| (min - x).max(x - max).max(0.0);
+        let Some(line) = self.lines.iter().min_by(|a, b| {
+            let a = distance(a.rect.min.y, a.rect.max.y, point.y);
+            let b = distance(b.rect.min.y, b.rect.max.y, point.y);
+            a.total_cmp(&b)
+        }) else {
+            return 0;
+        };
+        let Some(glyph) = self.glyphs[line.glyphs.clone()].iter().min_by(|a, b| {
+            let a = distance(a.position.x, a.position.x + a.advance, point.x);
+            let b = distance(b.position.x, b.position.x + b.advance, point.x);
+            a.total_cmp(&b)
+        }) else {
+            return line.index;
+        };
+        // Points over the half of a glyph at the start of its cluster are
+        // before it
+        let left = point.x < glyph.position.x + glyph.advance / 2.0;
+        match left != glyph.rtl {
+            true => glyph.range.start,
+            false => glyph.range.end,
+        }
+    }
+
+    /// The line and position of the start or end of the cluster of the
+    /// matching glyphs, or `None` if no glyph matches.
+    fn edge(&self, matches: impl Fn(&VelloTextGlyph) -> bool, start: bool) -> Option<(usize, f32)> {
+        self.glyphs
+            .iter()
+            .filter(|glyph| matches(glyph))
+            .fold(None, |edge, glyph| {
+                // Left to right clusters start at their left
+                let left = start != glyph.rtl;
+                let x = match left {
+                    true => glyph.position.x,
+                    false => glyph.position.x + glyph.advance,
+                };
+                Some(match edge {
+                    None => (glyph.line, x),
+                    Some((line, other)) if left => (line, x.min(other)),
+                    Some((line, other)) => (line, x.max(other)),
+                })
+            })
+    }
+}
@@ -1,9 +1,21 @@
 //! Components and text logic.
 
+mod color;
 mod font;
 mod font_loader;
+mod layout;
+#[cfg(feature = "system-fonts")]
+mod system_fonts;
 mod vello_text;
 
 pub use font::VelloFont;
+pub(crate) use font::{cache_text_layouts, CachedTextLayout};
 pub(crate) use font_loader::VelloFontLoader;
-pub use vello_text::{VelloText, VelloTextAlignment};
+pub use layout::{VelloTextGlyph, VelloTextLayout, VelloTextLine};
+#[cfg(feature = "system-fonts")]
+pub use system_fonts::{VelloSystemFonts, VelloSystemFontsPlugin};
+pub(crate) use vello_text::{blink_text_carets, fit_texts_to_nodes};
+pub use vello_text::{
+    VelloFontFallback, VelloText, VelloTextAlign, VelloTextAlignment, VelloTextDirection,
+    VelloTextNodeBounds, VelloTextOutline, VelloTextReveal, VelloTextSection, VelloTextSelection,
+};
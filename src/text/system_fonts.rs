@@ -0,0 +1,110 @@
+//! The fonts installed on the system, loaded as [`VelloFont`]s by their
+//! family name.
+
+use super::VelloFont;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+
+/// Enumerates the fonts installed on the system into [`VelloSystemFonts`].
+pub struct VelloSystemFontsPlugin;
+
+impl Plugin for VelloSystemFontsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VelloSystemFonts>();
+    }
+}
+
+/// The fonts installed on the system. Fonts are enumerated when the resource
+/// is created, and loaded into `Assets<VelloFont>` the first time they are
+/// requested.
+///
+/// ```no_run
+/// # use bevy::prelude::*;
+/// # use bevy_vello::prelude::*;
+/// fn setup(
+///     mut commands: Commands,
+///     mut system_fonts: ResMut<VelloSystemFonts>,
+///     mut fonts: ResMut<Assets<VelloFont>>,
+/// ) {
+///     let font = system_fonts.get("sans-serif", &mut fonts).unwrap_or_default();
+///     commands.spawn(VelloTextBundle { font, ..default() });
+/// }
+/// ```
+#[derive(Resource)]
+pub struct VelloSystemFonts {
+    database: fontdb::Database,
+    loaded: HashMap<fontdb::ID, Handle<VelloFont>>,
+}
+
+impl Default for VelloSystemFonts {
+    fn default() -> Self {
+        let mut database = fontdb::Database::new();
+        database.load_system_fonts();
+        Self {
+            database,
+            loaded: HashMap::default(),
+        }
+    }
+}
+
+impl VelloSystemFonts {
+    /// The family names of the installed fonts, sorted.
+    pub fn families(&self) -> Vec<&str> {
+        let mut families: Vec<&str> = self
+            .database
+            .faces()
+            .flat_map(|face| face.families.iter().map(|(family, _)| family.as_str()))
+            .collect();
+        families.sort_unstable();
+        families.dedup();
+        families
+    }
+
+    /// The regular font of a family, or `None` if it isn't installed. The
+    /// generic families `serif`, `sans-serif`, `monospace`, `cursive` and
+    /// `fantasy` are the families the system prefers.
+    pub fn get(
+        &mut self,
+        family: &str,
+        fonts: &mut Assets<VelloFont>,
+    ) -> Option<Handle<VelloFont>> {
+        self.get_styled(family, 400, false, fonts)
+    }
+
+    /// The font of a family nearest to a weight, from 100 (thin) to 900
+    /// (black), and style, or `None` if the family isn't installed.
+    pub fn get_styled(
+        &mut self,
+        family: &str,
+        weight: u16,
+        italic: bool,
+        fonts: &mut Assets<VelloFont>,
+    ) -> Option<Handle<VelloFont>> {
+        let family = match family {
+            "serif" => fontdb::Family::Serif,
+            "sans-serif" => fontdb::Family::SansSerif,
+            "monospace" => fontdb::Family::Monospace,
+            "cursive" => fontdb::Family::Cursive,
+            "fantasy" => fontdb::Family::Fantasy,
+            name => fontdb::Family::Name(name),
+        };
+        let id = self.database.query(&fontdb::Query {
+            families: &[family],
+            weight: fontdb::Weight(weight),
+            style: match italic {
+                true => fontdb::Style::Italic,
+                false => fontdb::Style::Normal,
+            },
+            ..default()
+        })?;
+        if let Some(handle) = self.loaded.get(&id) {
+            return Some(handle.clone());
+        }
+        let font = self.database.with_face_data(id, |data, index| {
+            VelloFont::new_with_index(data.to_vec(), index)
+        })?;
+        let handle = fonts.add(font);
+        self.loaded.insert(id, handle.clone());
+        Some(handle)
+    }
+}
@@ -1,9 +1,12 @@
 use crate::VelloFont;
 use bevy::prelude::*;
+use bevy::render::extract_resource::ExtractResource;
+use std::ops::Range;
 use vello::peniko::Brush;
 
 /// Describes how to position text from the origin
-#[derive(Component, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Component, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
 pub enum VelloTextAlignment {
     /// Bounds start from the render position and advance up and to the right.
     #[default]
@@ -28,11 +31,283 @@ pub enum VelloTextAlignment {
     TopRight,
 }
 
-#[derive(Component, Default, Clone)]
+impl VelloTextAlignment {
+    /// The bottom left corner of text of the given size, from the render
+    /// position, with Y up.
+    pub(crate) fn offset(&self, size: Vec2) -> Vec2 {
+        match self {
+            VelloTextAlignment::TopLeft => Vec2::new(0.0, -size.y),
+            VelloTextAlignment::Left => Vec2::new(0.0, -size.y / 2.0),
+            VelloTextAlignment::BottomLeft => Vec2::ZERO,
+            VelloTextAlignment::Top => Vec2::new(-size.x / 2.0, -size.y),
+            VelloTextAlignment::Center => -size / 2.0,
+            VelloTextAlignment::Bottom => Vec2::new(-size.x / 2.0, 0.0),
+            VelloTextAlignment::TopRight => Vec2::new(-size.x, -size.y),
+            VelloTextAlignment::Right => Vec2::new(-size.x, -size.y / 2.0),
+            VelloTextAlignment::BottomRight => Vec2::new(-size.x, 0.0),
+        }
+    }
+
+    /// The point of a box of the given size which text is aligned from, from
+    /// the center of the box, with Y down.
+    pub(crate) fn anchor(&self, size: Vec2) -> Vec2 {
+        let anchor = -(self.offset(size) + size / 2.0);
+        Vec2::new(anchor.x, -anchor.y)
+    }
+}
+
+/// The base direction of the paragraphs of a text, which orders their runs of
+/// left-to-right and right-to-left text, and aligns their lines.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+pub enum VelloTextDirection {
+    /// The direction of the first strongly directional character of each
+    /// paragraph, e.g. right to left for a paragraph starting in Arabic.
+    #[default]
+    Auto,
+    /// Lines are ordered and aligned from left to right.
+    LeftToRight,
+    /// Lines are ordered and aligned from right to left.
+    RightToLeft,
+}
+
+/// How the lines of a text are aligned within the width of its widest line.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+pub enum VelloTextAlign {
+    /// Lines are aligned to the left of left-to-right paragraphs, and to the
+    /// right of right-to-left paragraphs.
+    #[default]
+    Start,
+    /// Lines are aligned to the left.
+    Left,
+    /// Lines are centered.
+    Center,
+    /// Lines are aligned to the right.
+    Right,
+    /// Lines are stretched to the width of the text by widening their spaces,
+    /// except the last line of each paragraph, which is aligned to its start.
+    Justify,
+}
+
+#[derive(Component, Default, Clone, Reflect)]
+#[reflect(Component)]
 pub struct VelloText {
     pub content: String,
     pub size: f32,
+    /// The brush of the text, which isn't reflected. Gradients and images
+    /// are relative to the bounds of the text, from (0, 0) at its top left to
+    /// (1, 1) at its bottom right, so an image is stretched over the text.
+    #[reflect(ignore)]
     pub brush: Option<Brush>,
+    /// The bounds the text is laid out in. Words which overflow the width are
+    /// wrapped onto the next line, or broken if they are wider than the
+    /// bounds, and lines which overflow the height aren't drawn.
+    pub bounds: Option<Vec2>,
+    /// Sections of text after the content, with their own style, which flow
+    /// with the content as one text, e.g. to highlight a word.
+    pub sections: Vec<VelloTextSection>,
+    /// The base direction of the paragraphs of the text.
+    pub direction: VelloTextDirection,
+    /// How the lines of the text are aligned. The text as a whole is
+    /// positioned by its [`VelloTextAlignment`].
+    pub align: VelloTextAlign,
+    /// The fonts which draw the characters missing from the fonts of the text,
+    /// in order of priority, before those of [`VelloFontFallback`].
+    pub fallback: Vec<Handle<VelloFont>>,
+    /// The values of the variation axes of variable fonts, by their tag, e.g.
+    /// `("wght", 700.0)` for a bold weight, `"wdth"` for the width or
+    /// `"slnt"` for the slant. Axes which aren't set are the defaults of the
+    /// font, and values are clamped to the range of their axis.
+    pub variations: Vec<(String, f32)>,
+    /// The space added after every character, which may be negative to
+    /// tighten the text.
+    pub letter_spacing: f32,
+    /// The space added after every space, on top of the letter spacing.
+    pub word_spacing: f32,
+    /// The height of lines, as a multiple of the line height of the fonts.
+    /// Defaults to `1.0`.
+    pub line_height: Option<f32>,
+    /// An outline drawn around the glyphs of the text, e.g. to keep it
+    /// readable over busy backgrounds.
+    pub outline: Option<VelloTextOutline>,
+    /// Whether the text is underlined.
+    pub underline: bool,
+    /// Whether the text is struck through.
+    pub strikethrough: bool,
+}
+
+/// An outline of the glyphs of a [`VelloText`]. The outline is centered on
+/// the edges of glyphs and drawn behind them, so half of its width shows
+/// around them. Glyphs drawn in their own colors, e.g. emoji, aren't
+/// outlined.
+#[derive(Default, Clone, Reflect)]
+pub struct VelloTextOutline {
+    pub width: f32,
+    /// The brush of the outline, which isn't reflected.
+    #[reflect(ignore)]
+    pub brush: Brush,
+}
+
+impl VelloTextOutline {
+    pub fn new(width: f32, brush: impl Into<Brush>) -> Self {
+        Self {
+            width,
+            brush: brush.into(),
+        }
+    }
+}
+
+/// The fonts which draw the characters missing from the fonts of every
+/// [`VelloText`], e.g. CJK characters or symbols, in order of priority.
+#[derive(Resource, Default, Clone, Reflect, ExtractResource)]
+#[reflect(Resource)]
+pub struct VelloFontFallback {
+    pub fonts: Vec<Handle<VelloFont>>,
+}
+
+/// Reveals the characters of a [`VelloText`] progressively, e.g. to type
+/// dialogue on character by character. The text is laid out whole, so its
+/// lines don't move as it is revealed.
+#[derive(Component, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct VelloTextReveal {
+    /// The fraction of the characters which are drawn, from `0.0` for none to
+    /// `1.0` for all of them, in the order of the text.
+    pub progress: f32,
+}
+
+/// The selection and caret of an editable [`VelloText`]. The selection is
+/// drawn behind the glyphs of the text, and the caret over them. Indices are
+/// those of its [`VelloTextLayout`](super::VelloTextLayout).
+#[derive(Component, Clone, Reflect)]
+#[reflect(Component)]
+pub struct VelloTextSelection {
+    /// The selected characters, or an empty range for none.
+    pub range: Range<usize>,
+    /// The index of the caret, or `None` to hide it.
+    pub caret_index: Option<usize>,
+    /// The brush of the selection, which isn't reflected.
+    #[reflect(ignore)]
+    pub brush: Brush,
+    /// The brush of the caret, which isn't reflected.
+    #[reflect(ignore)]
+    pub caret_brush: Brush,
+    pub caret_width: f32,
+    /// The time the caret is shown, then hidden, in seconds, or zero for a
+    /// caret which doesn't blink. The caret is shown whenever the selection
+    /// changes, e.g. as the caret moves.
+    pub blink: f32,
+    #[reflect(ignore)]
+    blink_start: f32,
+    #[reflect(ignore)]
+    pub(crate) caret_shown: bool,
+}
+
+impl Default for VelloTextSelection {
+    fn default() -> Self {
+        Self {
+            range: 0..0,
+            caret_index: None,
+            brush: vello::peniko::Color::rgba8(51, 153, 255, 102).into(),
+            caret_brush: vello::peniko::Color::WHITE.into(),
+            caret_width: 2.0,
+            blink: 0.5,
+            blink_start: 0.0,
+            caret_shown: true,
+        }
+    }
+}
+
+impl VelloTextSelection {
+    pub fn new(range: Range<usize>, caret_index: Option<usize>) -> Self {
+        Self {
+            range,
+            caret_index,
+            ..default()
+        }
+    }
+}
+
+/// Blink the carets of text selections.
+pub(crate) fn blink_text_carets(time: Res<Time>, mut selections: Query<&mut VelloTextSelection>) {
+    let elapsed = time.elapsed_seconds();
+    for mut selection in selections.iter_mut() {
+        let changed = selection.is_changed();
+        let selection = selection.bypass_change_detection();
+        if changed {
+            selection.blink_start = elapsed;
+        }
+        let phase = (elapsed - selection.blink_start) / selection.blink;
+        selection.caret_shown = selection.blink <= 0.0 || phase % 2.0 < 1.0;
+    }
+}
+
+/// Lays out a screen-space [`VelloText`] of a UI node in the computed size of
+/// its [`Node`], so the text wraps within the node, and aligns it within the
+/// node by its [`VelloTextAlignment`], e.g. [`VelloTextAlignment::TopLeft`]
+/// in the top left corner of the node rather than from its center.
+#[derive(Component, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct VelloTextNodeBounds;
+
+/// Set the bounds of texts to the size of their UI nodes.
+pub(crate) fn fit_texts_to_nodes(
+    mut texts: Query<(&Node, &mut VelloText), With<VelloTextNodeBounds>>,
+) {
+    for (node, mut text) in texts.iter_mut() {
+        // Unchanged bounds don't lay out the text again
+        let bounds = Some(node.size());
+        if text.bounds != bounds {
+            text.bounds = bounds;
+        }
+    }
+}
+
+/// A section of a [`VelloText`] with its own style. Unset styles are those of
+/// the text.
+#[derive(Default, Clone, Reflect)]
+pub struct VelloTextSection {
+    pub content: String,
+    pub size: Option<f32>,
+    /// The brush of the section, which isn't reflected.
+    #[reflect(ignore)]
+    pub brush: Option<Brush>,
+    pub font: Option<Handle<VelloFont>>,
+    pub underline: Option<bool>,
+    pub strikethrough: Option<bool>,
+}
+
+impl VelloTextSection {
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            ..default()
+        }
+    }
+
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn with_brush(mut self, brush: impl Into<Brush>) -> Self {
+        self.brush = Some(brush.into());
+        self
+    }
+
+    pub fn with_font(mut self, font: Handle<VelloFont>) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    pub fn with_underline(mut self, underline: bool) -> Self {
+        self.underline = Some(underline);
+        self
+    }
+
+    pub fn with_strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = Some(strikethrough);
+        self
+    }
 }
 
 impl VelloText {
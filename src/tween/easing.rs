@@ -0,0 +1,34 @@
+use bevy::reflect::Reflect;
+
+/// How a tween eases between its start and end values.
+#[derive(PartialEq, Eq, Default, Clone, Copy, Debug, Reflect)]
+pub enum TweenEasing {
+    /// Change at a constant rate.
+    #[default]
+    Linear,
+    /// Start slowly and speed up.
+    EaseIn,
+    /// Start quickly and slow down.
+    EaseOut,
+    /// Start and end slowly.
+    EaseInOut,
+}
+
+impl TweenEasing {
+    /// Ease a ratio from `0.0` to `1.0`.
+    pub fn ease(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            TweenEasing::Linear => t,
+            TweenEasing::EaseIn => t * t,
+            TweenEasing::EaseOut => t * (2.0 - t),
+            TweenEasing::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    -1.0 + (4.0 - 2.0 * t) * t
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use crate::AlphaOverride;
+use bevy::prelude::*;
+
+/// Interpolates a field of a component for a [`VelloTween`](super::VelloTween).
+///
+/// Tweens of custom lenses are animated by adding the
+/// [`animate_tweens`](super::animate_tweens) system for that lens.
+pub trait VelloLens: Send + Sync + 'static {
+    /// The component being tweened.
+    type Component: Component;
+
+    /// Set the value of the component at a ratio between the start (`0.0`)
+    /// and end (`1.0`) of the tween.
+    fn lerp(&self, target: &mut Self::Component, ratio: f32);
+}
+
+/// Tweens the opacity of an [`AlphaOverride`].
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct AlphaLens {
+    pub start: f32,
+    pub end: f32,
+}
+
+impl VelloLens for AlphaLens {
+    type Component = AlphaOverride;
+
+    fn lerp(&self, target: &mut AlphaOverride, ratio: f32) {
+        target.0 = self.start + (self.end - self.start) * ratio;
+    }
+}
+
+/// Tweens the ratio of a [`VelloPathMorph`](crate::morph::VelloPathMorph).
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct PathMorphLens {
+    pub start: f32,
+    pub end: f32,
+}
+
+impl VelloLens for PathMorphLens {
+    type Component = crate::morph::VelloPathMorph;
+
+    fn lerp(&self, target: &mut crate::morph::VelloPathMorph, ratio: f32) {
+        target.t = self.start + (self.end - self.start) * ratio;
+    }
+}
+
+/// Tweens the color of a layer in a lottie [`Theme`](crate::Theme).
+#[cfg(feature = "lottie")]
+#[derive(PartialEq, Clone, Debug)]
+pub struct ThemeColorLens {
+    /// The name of the layer to recolor.
+    pub layer: String,
+    pub start: Color,
+    pub end: Color,
+}
+
+#[cfg(feature = "lottie")]
+impl VelloLens for ThemeColorLens {
+    type Component = crate::Theme;
+
+    fn lerp(&self, target: &mut crate::Theme, ratio: f32) {
+        let start = Vec4::from(self.start.as_rgba_f32());
+        let end = Vec4::from(self.end.as_rgba_f32());
+        target.edit(&self.layer, Color::rgba_from_array(start.lerp(end, ratio)));
+    }
+}
+
+/// The color space in which a [`ThemeLerp`] interpolates colors.
+#[cfg(feature = "lottie")]
+#[derive(PartialEq, Eq, Default, Clone, Copy, Debug)]
+pub enum LerpColorSpace {
+    /// Gamma-encoded sRGB, as colors are authored.
+    #[default]
+    Srgb,
+    /// Linear RGB, which keeps the perceived brightness of blends.
+    LinearRgb,
+    /// Hue, saturation and lightness, along the shortest hue arc.
+    Hsl,
+    /// Lightness, chroma and hue, along the shortest hue arc.
+    Lch,
+}
+
+#[cfg(feature = "lottie")]
+impl LerpColorSpace {
+    /// Interpolate between two colors in this color space.
+    pub fn lerp(&self, start: Color, end: Color, ratio: f32) -> Color {
+        // Interpolate the hue (in degrees) along the shortest arc
+        let lerp_hue = |start: Vec4, end: Vec4, hue: usize| {
+            let mut value = start.lerp(end, ratio);
+            let delta = (end[hue] - start[hue] + 540.0) % 360.0 - 180.0;
+            value[hue] = (start[hue] + delta * ratio).rem_euclid(360.0);
+            value
+        };
+        match self {
+            LerpColorSpace::Srgb => {
+                let start = Vec4::from(start.as_rgba_f32());
+                let end = Vec4::from(end.as_rgba_f32());
+                Color::rgba_from_array(start.lerp(end, ratio))
+            }
+            LerpColorSpace::LinearRgb => {
+                let start = Vec4::from(start.as_linear_rgba_f32());
+                let end = Vec4::from(end.as_linear_rgba_f32());
+                Color::rgba_linear_from_array(start.lerp(end, ratio))
+            }
+            LerpColorSpace::Hsl => {
+                let start = Vec4::from(start.as_hsla_f32());
+                let end = Vec4::from(end.as_hsla_f32());
+                Color::hsla_from_array(lerp_hue(start, end, 0))
+            }
+            LerpColorSpace::Lch => {
+                let start = Vec4::from(start.as_lcha_f32());
+                let end = Vec4::from(end.as_lcha_f32());
+                Color::lcha_from_array(lerp_hue(start, end, 2))
+            }
+        }
+    }
+
+    fn lerp_paint(
+        &self,
+        start: &crate::ThemePaint,
+        end: &crate::ThemePaint,
+        ratio: f32,
+    ) -> crate::ThemePaint {
+        use crate::ThemePaint;
+        match (start, end) {
+            (ThemePaint::Solid(start), ThemePaint::Solid(end)) => {
+                ThemePaint::Solid(self.lerp(*start, *end, ratio))
+            }
+            // Interpolate every stop, a solid color being the same color for
+            // every stop
+            (start, end) => {
+                let stops = |paint: &ThemePaint, index: usize| match paint {
+                    ThemePaint::Solid(color) => Some(*color),
+                    ThemePaint::Gradient(colors) => colors.get(index).copied(),
+                };
+                let count = match (start, end) {
+                    (ThemePaint::Gradient(start), ThemePaint::Gradient(end)) => {
+                        start.len().max(end.len())
+                    }
+                    (ThemePaint::Gradient(colors), _) | (_, ThemePaint::Gradient(colors)) => {
+                        colors.len()
+                    }
+                    _ => 1,
+                };
+                let colors = (0..count)
+                    .filter_map(|index| match (stops(start, index), stops(end, index)) {
+                        (Some(start), Some(end)) => Some(self.lerp(start, end, ratio)),
+                        (start, end) => start.or(end),
+                    })
+                    .collect();
+                ThemePaint::Gradient(colors)
+            }
+        }
+    }
+}
+
+/// Tweens between two lottie [`Theme`](crate::Theme)s, e.g. to fade from a
+/// day palette to a night palette.
+///
+/// The colors of layers swapped by both themes are interpolated. Layers only
+/// swapped by the end theme have its color for the whole tween, and layers
+/// only swapped by the start theme keep its color until the end.
+#[cfg(feature = "lottie")]
+#[derive(PartialEq, Clone, Debug)]
+pub struct ThemeLerp {
+    pub start: crate::Theme,
+    pub end: crate::Theme,
+    /// The color space in which colors are interpolated.
+    pub color_space: LerpColorSpace,
+}
+
+#[cfg(feature = "lottie")]
+impl ThemeLerp {
+    /// Interpolate between two themes in sRGB.
+    pub fn new(start: crate::Theme, end: crate::Theme) -> Self {
+        Self {
+            start,
+            end,
+            color_space: LerpColorSpace::default(),
+        }
+    }
+
+    pub fn with_color_space(mut self, color_space: LerpColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Interpolate the values of two maps of a theme.
+    fn lerp_map<T: Clone>(
+        start: &bevy::utils::HashMap<String, T>,
+        end: &bevy::utils::HashMap<String, T>,
+        ratio: f32,
+        lerp: impl Fn(&T, &T) -> T,
+    ) -> bevy::utils::HashMap<String, T> {
+        let mut values: bevy::utils::HashMap<String, T> = end
+            .iter()
+            .map(|(layer, end)| {
+                let value = match start.get(layer) {
+                    Some(start) => lerp(start, end),
+                    None => end.clone(),
+                };
+                (layer.clone(), value)
+            })
+            .collect();
+        if ratio < 1.0 {
+            for (layer, start) in start.iter() {
+                values.entry(layer.clone()).or_insert_with(|| start.clone());
+            }
+        }
+        values
+    }
+}
+
+#[cfg(feature = "lottie")]
+impl VelloLens for ThemeLerp {
+    type Component = crate::Theme;
+
+    fn lerp(&self, target: &mut crate::Theme, ratio: f32) {
+        let space = self.color_space;
+        target.colors = Self::lerp_map(&self.start.colors, &self.end.colors, ratio, |a, b| {
+            space.lerp(*a, *b, ratio)
+        });
+        target.fills = Self::lerp_map(&self.start.fills, &self.end.fills, ratio, |a, b| {
+            space.lerp_paint(a, b, ratio)
+        });
+        target.strokes = Self::lerp_map(&self.start.strokes, &self.end.strokes, ratio, |a, b| {
+            space.lerp_paint(a, b, ratio)
+        });
+    }
+}
+
+/// Tweens the speed of lottie [`PlaybackOptions`](crate::PlaybackOptions).
+#[cfg(feature = "lottie")]
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct PlaybackSpeedLens {
+    pub start: f64,
+    pub end: f64,
+}
+
+#[cfg(feature = "lottie")]
+impl VelloLens for PlaybackSpeedLens {
+    type Component = crate::PlaybackOptions;
+
+    fn lerp(&self, target: &mut crate::PlaybackOptions, ratio: f32) {
+        target.speed = self.start + (self.end - self.start) * ratio as f64;
+    }
+}
@@ -0,0 +1,19 @@
+//! Tweens which animate the components of vello entities over time, e.g. to
+//! fade in an asset or pulse the colors of a lottie theme.
+
+mod easing;
+pub use easing::TweenEasing;
+
+mod lens;
+pub use lens::{AlphaLens, PathMorphLens, VelloLens};
+#[cfg(feature = "lottie")]
+pub use lens::{LerpColorSpace, PlaybackSpeedLens, ThemeColorLens, ThemeLerp};
+
+mod plugin;
+pub(crate) use plugin::TweenPlugin;
+
+mod systems;
+pub use systems::animate_tweens;
+
+mod vello_tween;
+pub use vello_tween::{TweenRepeat, VelloTween};
@@ -0,0 +1,22 @@
+use super::{animate_tweens, AlphaLens, PathMorphLens};
+use bevy::prelude::*;
+
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (animate_tweens::<AlphaLens>, animate_tweens::<PathMorphLens>),
+        );
+        #[cfg(feature = "lottie")]
+        app.add_systems(
+            Update,
+            (
+                animate_tweens::<super::ThemeColorLens>,
+                animate_tweens::<super::ThemeLerp>,
+                animate_tweens::<super::PlaybackSpeedLens>,
+            ),
+        );
+    }
+}
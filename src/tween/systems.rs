@@ -0,0 +1,17 @@
+use super::{VelloLens, VelloTween};
+use bevy::prelude::*;
+
+/// Advance the tweens of a lens, and set the values of their components.
+pub fn animate_tweens<L: VelloLens>(
+    mut query: Query<(&mut VelloTween<L>, &mut L::Component)>,
+    time: Res<Time>,
+) {
+    for (mut tween, mut target) in query.iter_mut() {
+        if tween.is_finished() {
+            continue;
+        }
+        tween.elapsed += time.delta();
+        let ratio = tween.easing.ease(tween.ratio());
+        tween.lens.lerp(&mut target, ratio);
+    }
+}
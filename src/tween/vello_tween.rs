@@ -0,0 +1,84 @@
+use super::{TweenEasing, VelloLens};
+use bevy::prelude::*;
+use std::time::Duration;
+
+/// What a tween does after reaching its end.
+#[derive(PartialEq, Eq, Default, Clone, Copy, Debug)]
+pub enum TweenRepeat {
+    /// Stop at the end.
+    #[default]
+    Once,
+    /// Restart from the start.
+    Loop,
+    /// Reverse back to the start, and repeat.
+    PingPong,
+}
+
+/// Animates a component of this entity with a [`VelloLens`].
+#[derive(Component, Clone, Debug)]
+pub struct VelloTween<L: VelloLens> {
+    /// The lens which sets the tweened value.
+    pub lens: L,
+    /// The time taken to tween from the start to the end.
+    pub duration: Duration,
+    /// How the value eases between the start and end.
+    pub easing: TweenEasing,
+    /// What the tween does after reaching its end.
+    pub repeat: TweenRepeat,
+    /// The time since the tween started.
+    pub(crate) elapsed: Duration,
+}
+
+impl<L: VelloLens> VelloTween<L> {
+    /// Create a linear tween which plays once.
+    pub fn new(lens: L, duration: Duration) -> Self {
+        Self {
+            lens,
+            duration,
+            easing: TweenEasing::default(),
+            repeat: TweenRepeat::default(),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: TweenEasing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_repeat(mut self, repeat: TweenRepeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    /// The time since the tween started.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Whether the tween has stopped at its end.
+    pub fn is_finished(&self) -> bool {
+        self.repeat == TweenRepeat::Once && self.elapsed >= self.duration
+    }
+
+    /// The ratio between the start (`0.0`) and end (`1.0`) of the tween,
+    /// before easing.
+    pub fn ratio(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        let t = self.elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        match self.repeat {
+            TweenRepeat::Once => t.min(1.0),
+            TweenRepeat::Loop => t.fract(),
+            TweenRepeat::PingPong => {
+                let t = t % 2.0;
+                if t > 1.0 {
+                    2.0 - t
+                } else {
+                    t
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Runs the schedules of an app with sockets, which fail on their first
+//! update if the systems of the plugins are ordered in a cycle.
+#![cfg(feature = "lottie")]
+
+use bevy::prelude::*;
+use bevy::render::view::VisibilityPlugin;
+use bevy_vello::integrations::lottie::load_lottie_from_str;
+use bevy_vello::prelude::*;
+use bevy_vello::VelloPlugin;
+
+#[test]
+fn sockets_follow_layers() {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        TransformPlugin,
+        HierarchyPlugin,
+        bevy::input::InputPlugin,
+        WindowPlugin {
+            primary_window: None,
+            ..default()
+        },
+        VisibilityPlugin,
+    ))
+    // Added by the render plugin, which needs a GPU
+    .init_asset::<Image>()
+    .init_asset::<Shader>()
+    .init_asset::<Mesh>()
+    .init_resource::<bevy::render::deterministic::DeterministicRenderingConfig>()
+    .add_plugins((bevy::gizmos::GizmoPlugin, VelloPlugin));
+    let asset = load_lottie_from_str(LOTTIE).unwrap();
+    let asset = app.world.resource_mut::<Assets<VelloAsset>>().add(asset);
+    let mut socket = Entity::PLACEHOLDER;
+    app.world
+        .spawn(VelloAssetBundle {
+            vector: asset,
+            ..default()
+        })
+        .with_children(|parent| {
+            socket = parent
+                .spawn((LottieSocket::new("layer"), SpatialBundle::default()))
+                .id();
+        });
+    // The playheads are spawned at the end of the first update
+    app.update();
+    app.update();
+    let transform = app.world.get::<GlobalTransform>(socket).unwrap();
+    assert_eq!(transform.translation(), Vec3::new(10.0, 0.0, 0.0));
+}
+
+/// A null layer at (60, 50) of a 100x100 animation.
+const LOTTIE: &str = r#"{"v":"5.7","fr":30,"ip":0,"op":60,"w":100,"h":100,"assets":[],"layers":[
+    {"ty":3,"nm":"layer","ind":1,"ip":0,"op":60,"st":0,"sr":1,"ks":{
+        "a":{"a":0,"k":[0,0]},"p":{"a":0,"k":[60,50]},"s":{"a":0,"k":[100,100]},
+        "r":{"a":0,"k":0},"o":{"a":0,"k":100}}}]}"#;